@@ -1,15 +1,23 @@
 //! This crate provides types for fetching and unpacking compressed
 //! archives in tarball or zip format.
-use std::fs::File;
-use std::path::Path;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
-use attohttpc::header::HeaderMap;
-use headers::{ContentLength, Header, HeaderMapExt};
+use attohttpc::header::{HeaderMap, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use attohttpc::StatusCode;
+use fs_utils::ensure_containing_dir_exists;
+use headers::{ContentLength, ContentRange, Header, HeaderMapExt};
+use log::debug;
+use progress_read::ProgressRead;
+use tee::TeeReader;
 use thiserror::Error;
 
+pub mod request;
 mod tarball;
 mod zip;
 
+pub use crate::request::RequestKind;
 pub use crate::tarball::Tarball;
 pub use crate::zip::Zip;
 
@@ -95,6 +103,23 @@ cfg_if::cfg_if! {
     }
 }
 
+/// Downloads the specified URL into the cache file in full, without unpacking it, resuming a
+/// previous partial download if possible.
+///
+/// This allows a download to be verified before anything is unpacked from it, after which it can
+/// be loaded from the cache file. Returns the total size of the archive.
+pub fn download(
+    url: &str,
+    cache_file: &Path,
+    progress: &mut dyn FnMut(usize),
+) -> Result<u64, ArchiveError> {
+    let (compressed_size, data) = fetch_resumable(url, cache_file)?;
+    let mut data = ProgressRead::new(data, (), |_, read| progress(read));
+    io::copy(&mut data, &mut io::sink())?;
+    remove_validator(cache_file)?;
+    Ok(compressed_size)
+}
+
 /// Determines the length of an HTTP response's content in bytes, using
 /// the HTTP `"Content-Length"` header.
 fn content_length(headers: &HeaderMap) -> Result<u64, ArchiveError> {
@@ -103,3 +128,229 @@ fn content_length(headers: &HeaderMap) -> Result<u64, ArchiveError> {
         .map(|ContentLength(v)| v)
         .ok_or_else(|| ArchiveError::MissingHeaderError(ContentLength::name()))
 }
+
+/// Starts downloading the specified URL into the cache file, resuming a previous partial
+/// download if possible.
+///
+/// If the cache file already contains data, only the remaining bytes are requested with an HTTP
+/// `Range` header, made conditional with `If-Range` on the validator (`ETag` or `Last-Modified`)
+/// of the response the data came from. If the archive has changed since then, or the server
+/// doesn't honor the range, the download starts over from the beginning. Partial data without a
+/// recorded validator is never resumed. Returns the total size of the archive along with a reader
+/// over its full contents, which writes any newly downloaded data through to the cache file.
+///
+/// Note: A resumed download isn't verified until the archive is unpacked, so callers should
+/// discard the cache file if unpacking fails, and remove the validator with [`remove_validator`]
+/// once the cache file is no longer needed for resuming.
+fn fetch_resumable(url: &str, cache_file: &Path) -> Result<(u64, Box<dyn Read>), ArchiveError> {
+    ensure_containing_dir_exists(&cache_file)?;
+    let partial_len = fs::metadata(cache_file).map_or(0, |metadata| metadata.len());
+    let validator = match partial_len {
+        0 => None,
+        _ => fs::read_to_string(validator_file(cache_file)).ok(),
+    };
+
+    let (status, headers, response) = if let Some(validator) = validator {
+        let (status, headers, response) = request::get(url, RequestKind::Download)
+            .header(RANGE, format!("bytes={}-", partial_len))
+            .header(IF_RANGE, validator.trim())
+            .send()?
+            .split();
+
+        if status == StatusCode::PARTIAL_CONTENT {
+            if let Some(total) = resumed_length(&headers, partial_len) {
+                debug!("Resuming download of {} at byte {}", url, partial_len);
+                let existing = File::open(cache_file)?.take(partial_len);
+                let file = OpenOptions::new().append(true).open(cache_file)?;
+                let data = existing.chain(TeeReader::new(response, file));
+                return Ok((total, Box::new(data)));
+            }
+        }
+
+        if status.is_success() && status != StatusCode::PARTIAL_CONTENT {
+            debug!(
+                "{} has changed or the server ignored the range request, downloading in full",
+                url
+            );
+            (status, headers, response)
+        } else {
+            debug!("Unable to resume download of {}, downloading in full", url);
+            request::get(url, RequestKind::Download).send()?.split()
+        }
+    } else {
+        if partial_len > 0 {
+            debug!(
+                "No validator was recorded for the partial download of {}, downloading in full",
+                url
+            );
+        }
+        request::get(url, RequestKind::Download).send()?.split()
+    };
+
+    if !status.is_success() {
+        return Err(ArchiveError::HttpError(status));
+    }
+
+    let compressed_size = content_length(&headers)?;
+    // Record the validator before any data is written, so an interrupted download can be resumed
+    match response_validator(&headers) {
+        Some(validator) => fs::write(validator_file(cache_file), validator)?,
+        None => remove_validator(cache_file)?,
+    }
+    let file = File::create(cache_file)?;
+    Ok((compressed_size, Box::new(TeeReader::new(response, file))))
+}
+
+/// Removes the validator recorded for resuming a download into the cache file, if there is one
+pub fn remove_validator(cache_file: &Path) -> io::Result<()> {
+    match fs::remove_file(validator_file(cache_file)) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// The file in which the validator for resuming a download into the cache file is recorded
+fn validator_file(cache_file: &Path) -> PathBuf {
+    let mut file = cache_file.as_os_str().to_owned();
+    file.push(".validator");
+    PathBuf::from(file)
+}
+
+/// Determines the validator of a response, which identifies the version of the resource it
+/// contains, for use with `If-Range`
+///
+/// Weak `ETag`s can't be used with `If-Range`, so `Last-Modified` is used for those instead.
+fn response_validator(headers: &HeaderMap) -> Option<String> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+        .map(str::to_string)
+}
+
+/// Determines the total length of a resumed download, using the HTTP `"Content-Range"` header.
+///
+/// Returns `None` if the range doesn't start at the expected offset, since the partial data
+/// can't be reused in that case.
+fn resumed_length(headers: &HeaderMap, offset: u64) -> Option<u64> {
+    let range: ContentRange = headers.typed_get()?;
+
+    match (range.bytes_range(), range.bytes_len()) {
+        (Some((start, _)), Some(total)) if start == offset => Some(total),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{download, fetch_resumable, response_validator, resumed_length, validator_file};
+    use attohttpc::header::{HeaderMap, HeaderValue, ETAG, LAST_MODIFIED};
+    use headers::{ContentRange, HeaderMapExt};
+    use std::fs;
+    use std::io::Read;
+
+    #[test]
+    fn test_resumed_length() {
+        let mut headers = HeaderMap::new();
+        headers.typed_insert(ContentRange::bytes(100..400, 400).unwrap());
+
+        assert_eq!(resumed_length(&headers, 100), Some(400));
+        assert_eq!(resumed_length(&headers, 50), None);
+    }
+
+    #[test]
+    fn test_resumed_length_unknown_total() {
+        let mut headers = HeaderMap::new();
+        headers.typed_insert(ContentRange::bytes(100..400, None).unwrap());
+
+        assert_eq!(resumed_length(&headers, 100), None);
+    }
+
+    #[test]
+    fn test_resumed_length_missing_header() {
+        assert_eq!(resumed_length(&HeaderMap::new(), 100), None);
+    }
+
+    #[test]
+    fn test_response_validator() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(response_validator(&headers), None);
+
+        let modified = "Wed, 21 Oct 2015 07:28:00 GMT";
+        headers.insert(LAST_MODIFIED, HeaderValue::from_static(modified));
+        assert_eq!(response_validator(&headers).as_deref(), Some(modified));
+
+        // Weak ETags can't be used with If-Range
+        headers.insert(ETAG, HeaderValue::from_static("W/\"v1\""));
+        assert_eq!(response_validator(&headers).as_deref(), Some(modified));
+
+        headers.insert(ETAG, HeaderValue::from_static("\"v1\""));
+        assert_eq!(response_validator(&headers).as_deref(), Some("\"v1\""));
+    }
+
+    #[test]
+    fn test_resume_unchanged_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_file = dir.path().join("unchanged.tgz.partial");
+        fs::write(&cache_file, "hello ").unwrap();
+        fs::write(validator_file(&cache_file), "\"v1\"").unwrap();
+
+        let _mock = mockito::mock("GET", "/resume/unchanged.tgz")
+            .match_header("range", "bytes=6-")
+            .match_header("if-range", "\"v1\"")
+            .with_status(206)
+            .with_header("content-range", "bytes 6-10/11")
+            .with_body("world")
+            .create();
+
+        let url = format!("{}/resume/unchanged.tgz", mockito::server_url());
+        assert_eq!(download(&url, &cache_file, &mut |_| {}).unwrap(), 11);
+        assert_eq!(fs::read_to_string(&cache_file).unwrap(), "hello world");
+        assert!(!validator_file(&cache_file).exists());
+    }
+
+    #[test]
+    fn test_resume_changed_archive_starts_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_file = dir.path().join("changed.tgz.partial");
+        fs::write(&cache_file, "stale ").unwrap();
+        fs::write(validator_file(&cache_file), "\"v1\"").unwrap();
+
+        // The server sends the whole archive when it no longer matches the validator
+        let _mock = mockito::mock("GET", "/resume/changed.tgz")
+            .match_header("if-range", "\"v1\"")
+            .with_header("etag", "\"v2\"")
+            .with_body("hello world")
+            .create();
+
+        let url = format!("{}/resume/changed.tgz", mockito::server_url());
+        let (size, mut data) = fetch_resumable(&url, &cache_file).unwrap();
+        assert_eq!(size, 11);
+        assert_eq!(
+            fs::read_to_string(validator_file(&cache_file)).unwrap(),
+            "\"v2\""
+        );
+
+        let mut contents = String::new();
+        data.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "hello world");
+        assert_eq!(fs::read_to_string(&cache_file).unwrap(), "hello world");
+    }
+
+    #[test]
+    fn test_partial_without_validator_starts_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_file = dir.path().join("unvalidated.tgz.partial");
+        fs::write(&cache_file, "stale ").unwrap();
+
+        let _mock = mockito::mock("GET", "/resume/unvalidated.tgz")
+            .match_header("range", mockito::Matcher::Missing)
+            .with_body("hello world")
+            .create();
+
+        let url = format!("{}/resume/unvalidated.tgz", mockito::server_url());
+        assert_eq!(download(&url, &cache_file, &mut |_| {}).unwrap(), 11);
+        assert_eq!(fs::read_to_string(&cache_file).unwrap(), "hello world");
+    }
+}
@@ -0,0 +1,440 @@
+//! Provides a central builder for the HTTP requests made while fetching tools, so that each kind
+//! of request gets appropriate timeouts, proxies, and redirects are handled consistently.
+
+use std::env;
+use std::ffi::OsString;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use attohttpc::header::{HeaderMap, HeaderValue, IntoHeaderName, AUTHORIZATION, LOCATION};
+use attohttpc::{InvalidResponseKind, ProxySettings, RequestBuilder, Response, StatusCode};
+use log::debug;
+use url::Url;
+
+/// Environment variable to override the timeout for registry metadata requests, in seconds
+pub const VOLTA_REGISTRY_TIMEOUT: &str = "VOLTA_REGISTRY_TIMEOUT";
+
+/// Environment variable to override the timeout for archive downloads, in seconds
+pub const VOLTA_DOWNLOAD_TIMEOUT: &str = "VOLTA_DOWNLOAD_TIMEOUT";
+
+const DEFAULT_REGISTRY_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+const METADATA_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DOWNLOAD_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The most redirects a single request will follow before giving up
+const MAX_REDIRECTS: u32 = 5;
+
+/// The environment variables that configure a proxy, which take precedence over any fallback
+const PROXY_ENV_VARS: [&str; 3] = ["all_proxy", "http_proxy", "https_proxy"];
+
+static PROXY_FALLBACK: OnceLock<fn() -> ProxyConfig> = OnceLock::new();
+static FALLBACK_PROXY_SETTINGS: OnceLock<Option<ProxySettings>> = OnceLock::new();
+static AUTH_TOKEN_LOOKUP: OnceLock<fn(&str) -> Option<String>> = OnceLock::new();
+
+/// Proxies configured outside of the environment, e.g. in npm's configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// The proxy for `http` URLs
+    pub http: Option<String>,
+    /// The proxy for `https` URLs
+    pub https: Option<String>,
+}
+
+/// Sets where to find the proxies to use when none are set in the environment
+///
+/// The fallback is only called once, when the first request is made without a proxy in the
+/// environment. `NO_PROXY` from the environment still applies to the fallback proxies.
+pub fn set_proxy_fallback(fallback: fn() -> ProxyConfig) {
+    let _ = PROXY_FALLBACK.set(fallback);
+}
+
+/// Sets where to find the token to authenticate requests with, e.g. in npm's configuration
+///
+/// The lookup is given the URL of each request, and should only return a token for URLs that it
+/// is configured for. The token is sent as a bearer token, unless the request sets its own
+/// `Authorization` header.
+pub fn set_auth_token_lookup(lookup: fn(&str) -> Option<String>) {
+    let _ = AUTH_TOKEN_LOOKUP.set(lookup);
+}
+
+/// The kind of request being made, which determines the timeouts that apply to it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestKind {
+    /// A small request for registry or index metadata, which should fail fast
+    Metadata,
+
+    /// A download of a (potentially large) tool archive
+    Download,
+}
+
+impl RequestKind {
+    fn connect_timeout(self) -> Duration {
+        match self {
+            RequestKind::Metadata => METADATA_CONNECT_TIMEOUT,
+            RequestKind::Download => DOWNLOAD_CONNECT_TIMEOUT,
+        }
+    }
+
+    /// The timeout for the request, taking any environment override into account
+    ///
+    /// For metadata requests, this bounds the entire request. For downloads, it bounds the time
+    /// spent waiting for more data, so that slow but steady downloads aren't interrupted.
+    pub fn timeout(self) -> Duration {
+        match self {
+            RequestKind::Metadata => parse_timeout(
+                env::var_os(VOLTA_REGISTRY_TIMEOUT),
+                DEFAULT_REGISTRY_TIMEOUT,
+            ),
+            RequestKind::Download => parse_timeout(
+                env::var_os(VOLTA_DOWNLOAD_TIMEOUT),
+                DEFAULT_DOWNLOAD_TIMEOUT,
+            ),
+        }
+    }
+}
+
+/// Creates a GET request for the given URL, configured with the timeouts for `kind`
+pub fn get(url: &str, kind: RequestKind) -> Request {
+    let mut builder = new_builder(url, kind);
+    if let Some(token) = AUTH_TOKEN_LOOKUP.get().and_then(|lookup| lookup(url)) {
+        match HeaderValue::try_from(format!("Bearer {}", token)) {
+            Ok(value) => builder = builder.header(AUTHORIZATION, value),
+            Err(_) => debug!("Ignoring invalid auth token for {}", url),
+        }
+    }
+
+    Request { builder, kind }
+}
+
+/// A GET request created by `get`, which follows redirects itself
+///
+/// `attohttpc` forwards every header when it follows a redirect. Mirrors commonly redirect to a
+/// CDN on a different host, so redirects are followed here instead, dropping any `Authorization`
+/// header once the request leaves the original host, so that credentials are never leaked.
+pub struct Request {
+    builder: RequestBuilder,
+    kind: RequestKind,
+}
+
+impl Request {
+    /// Sets a header on the request, replacing any existing value
+    ///
+    /// # Panics
+    /// As with `attohttpc`, this panics if the value is invalid.
+    pub fn header<H, V>(self, header: H, value: V) -> Self
+    where
+        H: IntoHeaderName,
+        V: TryInto<HeaderValue>,
+        attohttpc::Error: From<V::Error>,
+    {
+        Request {
+            builder: self.builder.header(header, value),
+            kind: self.kind,
+        }
+    }
+
+    /// Sends the request, following up to `MAX_REDIRECTS` redirects
+    pub fn send(self) -> attohttpc::Result<Response> {
+        let Request { mut builder, kind } = self;
+        let mut redirects = 0;
+
+        loop {
+            let url = builder.inspect().url().clone();
+            let headers = builder.inspect().headers().clone();
+            let response = builder.send()?;
+
+            if !is_redirect(response.status()) {
+                return Ok(response);
+            }
+
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Err(attohttpc::ErrorKind::TooManyRedirections.into());
+            }
+
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .ok_or(InvalidResponseKind::LocationHeader)?;
+            let next = url
+                .join(location)
+                .map_err(|_| InvalidResponseKind::RedirectionUrl)?;
+            debug!("Following redirect from {} to {}", url, next);
+
+            let same_origin = next.origin() == url.origin();
+            builder = new_builder(next.as_str(), kind);
+            *builder.headers_mut() = redirect_headers(headers, same_origin);
+        }
+    }
+}
+
+fn new_builder(url: &str, kind: RequestKind) -> RequestBuilder {
+    let mut builder = attohttpc::get(url)
+        .connect_timeout(kind.connect_timeout())
+        .follow_redirects(false);
+
+    if !proxy_in_env() {
+        if let Some(settings) = fallback_proxy_settings() {
+            builder = builder.proxy_settings(settings.clone());
+        }
+    }
+
+    match kind {
+        RequestKind::Metadata => builder.timeout(kind.timeout()),
+        RequestKind::Download => builder.read_timeout(kind.timeout()),
+    }
+}
+
+fn proxy_in_env() -> bool {
+    PROXY_ENV_VARS
+        .iter()
+        .any(|name| env::var_os(name).is_some() || env::var_os(name.to_ascii_uppercase()).is_some())
+}
+
+fn fallback_proxy_settings() -> Option<&'static ProxySettings> {
+    FALLBACK_PROXY_SETTINGS
+        .get_or_init(|| {
+            let config = PROXY_FALLBACK.get()?();
+            proxy_settings(
+                config,
+                env::var("no_proxy").or_else(|_| env::var("NO_PROXY")).ok(),
+            )
+        })
+        .as_ref()
+}
+
+/// Builds the settings for the configured proxies, skipping any that aren't valid URLs
+///
+/// Returns `None` if no proxy is configured, so that requests are made directly.
+fn proxy_settings(config: ProxyConfig, no_proxy: Option<String>) -> Option<ProxySettings> {
+    let parse = |proxy: Option<String>| {
+        let proxy = proxy?;
+        match Url::parse(&proxy) {
+            Ok(url) => Some(url),
+            Err(error) => {
+                debug!("Ignoring invalid proxy '{}': {}", proxy, error);
+                None
+            }
+        }
+    };
+    let http = parse(config.http);
+    let https = parse(config.https);
+    if http.is_none() && https.is_none() {
+        return None;
+    }
+
+    debug!("Using proxies http: {:?}, https: {:?}", http, https);
+    let mut builder = ProxySettings::builder().http_proxy(http).https_proxy(https);
+    if let Some(no_proxy) = no_proxy {
+        for host in no_proxy
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+        {
+            builder = builder.add_no_proxy_host(host.trim_start_matches('.'));
+        }
+    }
+    Some(builder.build())
+}
+
+fn is_redirect(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    )
+}
+
+/// The headers to send to the target of a redirect
+fn redirect_headers(mut headers: HeaderMap, same_origin: bool) -> HeaderMap {
+    if !same_origin && headers.remove(AUTHORIZATION).is_some() {
+        debug!("Not forwarding credentials to a different host");
+    }
+    headers
+}
+
+/// Parses a timeout in seconds, falling back to the default if it is missing or invalid
+fn parse_timeout(value: Option<OsString>, default: Duration) -> Duration {
+    let Some(value) = value else {
+        return default;
+    };
+
+    match value
+        .to_str()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+    {
+        Some(secs) if secs > 0 => Duration::from_secs(secs),
+        _ => {
+            debug!(
+                "Ignoring invalid timeout '{}', using {} seconds",
+                value.to_string_lossy(),
+                default.as_secs()
+            );
+            default
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeout() {
+        let default = Duration::from_secs(30);
+
+        assert_eq!(parse_timeout(None, default), default);
+        assert_eq!(
+            parse_timeout(Some("5".into()), default),
+            Duration::from_secs(5)
+        );
+        assert_eq!(parse_timeout(Some("0".into()), default), default);
+        assert_eq!(parse_timeout(Some("soon".into()), default), default);
+    }
+
+    #[test]
+    fn test_auth_token_is_sent_to_configured_urls() {
+        set_auth_token_lookup(|url| url.contains("/authed/").then(|| "secret".into()));
+        let authed = mockito::mock("GET", "/authed/cowsay")
+            .match_header("authorization", "Bearer secret")
+            .with_body("metadata")
+            .create();
+        let public = mockito::mock("GET", "/public/cowsay")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_body("metadata")
+            .create();
+
+        for path in ["/authed/cowsay", "/public/cowsay"] {
+            let response = get(
+                &format!("{}{}", mockito::server_url(), path),
+                RequestKind::Metadata,
+            )
+            .send()
+            .unwrap();
+            assert_eq!(response.text().unwrap(), "metadata");
+        }
+
+        authed.assert();
+        public.assert();
+    }
+
+    #[test]
+    fn test_redirect_to_other_host_drops_authorization() {
+        // `localhost` and `127.0.0.1` reach the same mock server, but are different hosts
+        let cdn_url = mockito::server_url().replace("127.0.0.1", "localhost");
+        let cdn = mockito::mock("GET", "/cdn/cross-host.tgz")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_body("archive")
+            .create();
+        let mirror = mockito::mock("GET", "/mirror/cross-host.tgz")
+            .match_header("authorization", "Bearer secret")
+            .with_status(302)
+            .with_header("location", &format!("{}/cdn/cross-host.tgz", cdn_url))
+            .create();
+
+        let response = get(
+            &format!("{}/mirror/cross-host.tgz", mockito::server_url()),
+            RequestKind::Download,
+        )
+        .header(AUTHORIZATION, "Bearer secret")
+        .send()
+        .unwrap();
+
+        assert_eq!(response.text().unwrap(), "archive");
+        mirror.assert();
+        cdn.assert();
+    }
+
+    #[test]
+    fn test_redirect_on_same_host_keeps_authorization() {
+        let target = mockito::mock("GET", "/same-host/target")
+            .match_header("authorization", "Bearer secret")
+            .with_body("metadata")
+            .create();
+        let _redirect = mockito::mock("GET", "/same-host/source")
+            .with_status(301)
+            .with_header("location", "/same-host/target")
+            .create();
+
+        let response = get(
+            &format!("{}/same-host/source", mockito::server_url()),
+            RequestKind::Metadata,
+        )
+        .header(AUTHORIZATION, "Bearer secret")
+        .send()
+        .unwrap();
+
+        assert_eq!(response.text().unwrap(), "metadata");
+        target.assert();
+    }
+
+    #[test]
+    fn test_redirect_loop_is_bounded() {
+        let redirect = mockito::mock("GET", "/loop")
+            .with_status(307)
+            .with_header("location", "/loop")
+            .expect(MAX_REDIRECTS as usize + 1)
+            .create();
+
+        let error = get(
+            &format!("{}/loop", mockito::server_url()),
+            RequestKind::Metadata,
+        )
+        .send()
+        .unwrap_err();
+
+        assert!(matches!(
+            error.kind(),
+            attohttpc::ErrorKind::TooManyRedirections
+        ));
+        redirect.assert();
+    }
+
+    #[test]
+    fn test_proxy_settings() {
+        let config = ProxyConfig {
+            http: Some("http://proxy.example.com:8080".into()),
+            https: Some("http://secure-proxy.example.com:3128".into()),
+        };
+        let settings = proxy_settings(config, Some("internal.example.com, .local".into())).unwrap();
+        let proxy_for = |url: &str| {
+            settings
+                .for_url(&Url::parse(url).unwrap())
+                .map(Url::to_string)
+        };
+
+        assert_eq!(
+            proxy_for("http://registry.example.com/npm").as_deref(),
+            Some("http://proxy.example.com:8080/")
+        );
+        assert_eq!(
+            proxy_for("https://registry.example.com/npm").as_deref(),
+            Some("http://secure-proxy.example.com:3128/")
+        );
+        assert_eq!(proxy_for("https://internal.example.com/npm"), None);
+        assert_eq!(proxy_for("https://mirror.local/npm"), None);
+    }
+
+    #[test]
+    fn test_proxy_settings_without_valid_proxy() {
+        assert!(proxy_settings(ProxyConfig::default(), None).is_none());
+
+        let config = ProxyConfig {
+            http: Some("not a url".into()),
+            https: None,
+        };
+        assert!(proxy_settings(config, None).is_none());
+    }
+
+    #[test]
+    fn test_metadata_fails_faster_than_downloads() {
+        assert!(RequestKind::Metadata.connect_timeout() < RequestKind::Download.connect_timeout());
+        assert!(DEFAULT_REGISTRY_TIMEOUT < DEFAULT_DOWNLOAD_TIMEOUT);
+    }
+}
@@ -5,10 +5,8 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-use super::{content_length, ArchiveError};
-use fs_utils::ensure_containing_dir_exists;
+use super::{fetch_resumable, ArchiveError};
 use progress_read::ProgressRead;
-use tee::TeeReader;
 use verbatim::PathExt;
 use zip_rs::unstable::stream::ZipStreamReader;
 
@@ -34,19 +32,10 @@ impl Zip {
     }
 
     /// Initiate fetching of a Node zip archive from the given URL, returning
-    /// a `Remote` data source.
+    /// a `Remote` data source. If the local file already holds a partial
+    /// download, the remaining data is requested instead.
     pub fn fetch(url: &str, cache_file: &Path) -> Result<Box<dyn Archive>, ArchiveError> {
-        let (status, headers, response) = attohttpc::get(url).send()?.split();
-
-        if !status.is_success() {
-            return Err(ArchiveError::HttpError(status));
-        }
-
-        let compressed_size = content_length(&headers)?;
-
-        ensure_containing_dir_exists(&cache_file)?;
-        let file = File::create(cache_file)?;
-        let data = Box::new(TeeReader::new(response, file));
+        let (compressed_size, data) = fetch_resumable(url, cache_file)?;
 
         Ok(Box::new(Zip {
             compressed_size,
@@ -5,6 +5,14 @@ use cfg_if::cfg_if;
 
 cfg_if! {
     if #[cfg(windows)] {
+        use std::env;
+        use std::path::PathBuf;
+
+        use log::debug;
+
+        /// The longest command line that `cmd.exe` accepts
+        const CMD_LINE_LIMIT: usize = 8191;
+
         pub fn create_command<E>(exe: E) -> Command
         where
             E: AsRef<OsStr>
@@ -19,6 +27,66 @@ cfg_if! {
             command.arg(exe);
             command
         }
+
+        /// Prepares a command from `create_command` to be run with the given `PATH`
+        ///
+        /// If the command line is too long for `cmd.exe` and the executable on `PATH` is an
+        /// `.exe`, the executable is run directly instead, which allows a command line of up to
+        /// 32767 characters. Batch files can only be run through `cmd.exe`, so those commands are
+        /// left as-is.
+        pub fn prepare_command(command: Command, path: &OsStr) -> Command {
+            // Each argument may need to be quoted and is separated by a space
+            let length = command
+                .get_args()
+                .fold("cmd.exe".len(), |length, arg| length + arg.len() + 3);
+            if length <= CMD_LINE_LIMIT {
+                return command;
+            }
+
+            // The first argument is `/C`, followed by the executable
+            let Some(exe) = command.get_args().nth(1) else {
+                return command;
+            };
+            let cwd = command
+                .get_current_dir()
+                .map(PathBuf::from)
+                .or_else(|| env::current_dir().ok())
+                .unwrap_or_default();
+            let is_exe = |resolved: &PathBuf| {
+                resolved
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+            };
+            let resolved = match which::which_in(exe, Some(path), cwd) {
+                Ok(resolved) if is_exe(&resolved) => resolved,
+                _ => {
+                    debug!(
+                        "Command line is longer than {} characters, but {:?} must be run with cmd.exe",
+                        CMD_LINE_LIMIT,
+                        exe
+                    );
+                    return command;
+                }
+            };
+
+            debug!(
+                "Command line is longer than {} characters, running {} directly",
+                CMD_LINE_LIMIT,
+                resolved.display()
+            );
+            let mut direct = Command::new(resolved);
+            direct.args(command.get_args().skip(2));
+            for (key, value) in command.get_envs() {
+                match value {
+                    Some(value) => direct.env(key, value),
+                    None => direct.env_remove(key),
+                };
+            }
+            if let Some(dir) = command.get_current_dir() {
+                direct.current_dir(dir);
+            }
+            direct
+        }
     } else {
         pub fn create_command<E>(exe: E) -> Command
         where
@@ -26,5 +94,62 @@ cfg_if! {
         {
             Command::new(exe)
         }
+
+        /// Prepares a command from `create_command` to be run with the given `PATH`
+        ///
+        /// Commands are run directly on this platform, so nothing needs to change.
+        pub fn prepare_command(command: Command, _path: &OsStr) -> Command {
+            command
+        }
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    fn system_path() -> OsString {
+        env::var_os("PATH").unwrap()
+    }
+
+    #[test]
+    fn short_command_uses_cmd() {
+        let mut command = create_command("where");
+        command.arg("cmd");
+
+        let command = prepare_command(command, &system_path());
+
+        assert_eq!(command.get_program(), "cmd.exe");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["/C", "where", "cmd"]
+        );
+    }
+
+    #[test]
+    fn long_command_runs_exe_directly() {
+        let args: Vec<String> = (0..2000).map(|n| format!("argument-{}", n)).collect();
+        let mut command = create_command("where");
+        command.args(&args);
+        command.env("VOLTA_TEST_ENV", "1");
+
+        let command = prepare_command(command, &system_path());
+
+        let program = PathBuf::from(command.get_program());
+        assert!(program.is_absolute());
+        assert!(program
+            .file_name()
+            .unwrap()
+            .eq_ignore_ascii_case("where.exe"));
+
+        // None of the arguments are lost, and the environment is kept
+        let passed: Vec<_> = command.get_args().collect();
+        assert_eq!(passed, args.iter().map(OsStr::new).collect::<Vec<_>>());
+        assert!(command
+            .get_envs()
+            .any(|(key, value)| key == "VOLTA_TEST_ENV" && value == Some(OsStr::new("1"))));
     }
 }
@@ -5,6 +5,7 @@ use super::ExitCode;
 use crate::style::{text_width, tool_version};
 use crate::tool;
 use crate::tool::package::PackageManager;
+use node_semver::Version;
 use textwrap::{fill, indent};
 
 const REPORT_BUG_CTA: &str =
@@ -14,6 +15,9 @@ https://github.com/volta-cli/volta/issues with the details!";
 
 const PERMISSIONS_CTA: &str = "Please ensure you have correct permissions to the Volta directory.";
 
+/// The number of bytes in a mebibyte, for displaying sizes
+const MIB: u64 = 1024 * 1024;
+
 #[derive(Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum ErrorKind {
@@ -32,6 +36,10 @@ pub enum ErrorKind {
         name: String,
     },
 
+    /// Thrown when executables are selected with `--bin` while installing anything other than
+    /// a single package
+    BinarySelectionRequiresPackage,
+
     /// Thrown when building the virtual environment path fails
     BuildPathError,
 
@@ -50,6 +58,11 @@ pub enum ErrorKind {
         package: String,
     },
 
+    /// Thrown when a file for the output captured by `volta run --capture` can't be created
+    CaptureFileError {
+        file: PathBuf,
+    },
+
     /// Thrown when the Completions out-dir is not a directory
     CompletionsOutFileError {
         path: PathBuf,
@@ -111,6 +124,13 @@ pub enum ErrorKind {
         from_url: String,
     },
 
+    /// Thrown when more than one version of the same tool is given to a command that sets a
+    /// single version, e.g. `volta install node@18 node@20`
+    DuplicateToolSpec {
+        action: String,
+        tool: String,
+    },
+
     /// Thrown when unable to execute a hook command
     ExecuteHookError {
         command: String,
@@ -127,6 +147,17 @@ pub enum ErrorKind {
         path: PathBuf,
     },
 
+    /// Thrown when the lockfile is frozen but would be changed, or doesn't match the project
+    FrozenLockfileError {
+        file: PathBuf,
+        reason: String,
+    },
+
+    /// Thrown when a git source is given for a tool that can only be installed from a release
+    GitSourceNotSupported {
+        tool: String,
+    },
+
     /// Thrown when a hook command returns a non-zero exit code
     HookCommandFailed {
         command: String,
@@ -146,6 +177,18 @@ pub enum ErrorKind {
     /// Thrown when determining the name of a newly-installed package fails
     InstalledPackageNameError,
 
+    /// Thrown when there isn't enough disk space to download and unpack a tool
+    InsufficientDiskSpace {
+        needed: u64,
+        available: u64,
+    },
+
+    /// Thrown when a downloaded archive doesn't match the integrity published by the registry
+    IntegrityMismatch {
+        tool: String,
+        from_url: String,
+    },
+
     InvalidHookCommand {
         command: String,
     },
@@ -170,6 +213,11 @@ pub enum ErrorKind {
         version: String,
     },
 
+    /// Thrown when a target platform for fetching Node can't be parsed
+    InvalidNodeTarget {
+        target: String,
+    },
+
     /// Thrown when a format other than "npm" or "github" is given for yarn.index in the hooks
     InvalidRegistryFormat {
         format: String,
@@ -181,9 +229,31 @@ pub enum ErrorKind {
         errors: Vec<String>,
     },
 
+    /// Thrown when a tool name contains control characters or leading or trailing whitespace
+    InvalidToolNameCharacters {
+        name: String,
+    },
+
+    /// Thrown when the Volta directory uses an older layout that hasn't been migrated
+    LayoutMigrationRequired {
+        version: String,
+    },
+
+    /// Thrown when a local Node archive isn't named like an official distribution for this platform
+    LocalArchiveMismatch {
+        file: PathBuf,
+        expected: String,
+    },
+
     /// Thrown when unable to acquire a lock on the Volta directory
     LockAcquireError,
 
+    /// Thrown when a package config was written by a newer version of Volta
+    NewerPackageConfig {
+        package: String,
+        schema_version: u32,
+    },
+
     /// Thrown when pinning or installing npm@bundled and couldn't detect the bundled version
     NoBundledNpm {
         command: String,
@@ -200,9 +270,15 @@ pub enum ErrorKind {
         tool: String,
     },
 
+    /// Thrown when there are no URLs to download from, e.g. a custom resolver returned none
+    NoDownloadUrls,
+
     /// Thrown when there is no Node version matching a requested semver specifier.
     NodeVersionNotFound {
         matching: String,
+        suggestions: Vec<Version>,
+        /// The index that was searched, if any
+        index_url: Option<String>,
     },
 
     NoHomeEnvironmentVar,
@@ -220,6 +296,12 @@ pub enum ErrorKind {
     /// Thrown when the platform (Node version) could not be determined
     NoPlatform,
 
+    /// Thrown when parsing the project manifest and there is a `"volta"` key that pins npm, pnpm,
+    /// or Yarn without pinning Node
+    NoProjectNodeForPinnedTool {
+        tool: String,
+    },
+
     /// Thrown when parsing the project manifest and there is a `"volta"` key without Node
     NoProjectNodeInManifest,
 
@@ -231,10 +313,13 @@ pub enum ErrorKind {
 
     /// Thrown when no shell profiles could be found
     NoShellProfile {
-        env_profile: String,
+        profiles: Vec<PathBuf>,
         bin_dir: PathBuf,
     },
 
+    /// Thrown when Node is deferred to the system but there is no Node install outside of Volta
+    NoSystemNode,
+
     /// Thrown when the user tries to pin Node or Yarn versions outside of a package.
     NotInPackage,
 
@@ -244,6 +329,12 @@ pub enum ErrorKind {
     /// Thrown when default pnpm is not set
     NoDefaultPnpm,
 
+    /// Thrown when `npm exec` is run with a version of npm that doesn't provide it
+    NpmExecNotAvailable {
+        version: String,
+        bundled_with: Option<String>,
+    },
+
     /// Thrown when `npm link` is called with a package that isn't available
     NpmLinkMissingPackage {
         package: String,
@@ -257,10 +348,19 @@ pub enum ErrorKind {
     /// Thrown when there is no npm version matching the requested Semver/Tag
     NpmVersionNotFound {
         matching: String,
+        /// The registry that was searched, if any
+        registry_url: Option<String>,
     },
 
     NpxNotAvailable {
         version: String,
+        bundled_with: Option<String>,
+    },
+
+    /// Thrown when an executable selected with `--bin` isn't provided by the package
+    PackageBinaryNotFound {
+        package: String,
+        bin: String,
     },
 
     /// Thrown when the command to install a global package is not successful
@@ -296,6 +396,12 @@ pub enum ErrorKind {
     /// Thrown when a package has been unpacked but is not formed correctly.
     PackageUnpackError,
 
+    /// Thrown when there is no version of a package matching a requested specifier
+    PackageVersionNotFound {
+        name: String,
+        matching: String,
+    },
+
     /// Thrown when writing a package manifest fails
     PackageWriteError {
         file: PathBuf,
@@ -309,6 +415,11 @@ pub enum ErrorKind {
         file: PathBuf,
     },
 
+    /// Thrown when unable to parse a project lockfile
+    ParseLockfileError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to parse the node index cache
     ParseNodeIndexCacheError,
 
@@ -329,6 +440,9 @@ pub enum ErrorKind {
     /// Thrown when unable to parse the platform.json file
     ParsePlatformError,
 
+    /// Thrown when unable to parse an exported toolchain
+    ParseToolchainExportError,
+
     /// Thrown when unable to parse a tool spec (`<tool>[@<version>]`)
     ParseToolSpecError {
         tool_spec: String,
@@ -342,6 +456,8 @@ pub enum ErrorKind {
     /// Thrown when there is no pnpm version matching a requested semver specifier.
     PnpmVersionNotFound {
         matching: String,
+        /// The registry that was searched, if any
+        registry_url: Option<String>,
     },
 
     /// Thrown when executing a project-local binary fails
@@ -385,6 +501,16 @@ pub enum ErrorKind {
         file: PathBuf,
     },
 
+    /// Thrown when a local Node archive could not be opened or read
+    ReadLocalArchiveError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error opening a project lockfile
+    ReadLockfileError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error reading the Node Index Cache
     ReadNodeIndexCacheError {
         file: PathBuf,
@@ -395,9 +521,19 @@ pub enum ErrorKind {
         file: PathBuf,
     },
 
+    /// Thrown when there was an error reading a `.node-version` file
+    ReadNodeVersionFileError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error reading the npm manifest file
     ReadNpmManifestError,
 
+    /// Thrown when an operation needs to modify the Volta directory, but it is read-only
+    ReadOnlyVoltaHome {
+        home: PathBuf,
+    },
+
     /// Thrown when there was an error reading a package configuration file
     ReadPackageConfigError {
         file: PathBuf,
@@ -408,6 +544,16 @@ pub enum ErrorKind {
         file: PathBuf,
     },
 
+    /// Thrown when there was an error reading the provenance recorded for a tool image
+    ReadProvenanceError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error reading a shell profile script
+    ReadShellProfileError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to read the user Path environment variable from the registry
     #[cfg(windows)]
     ReadUserPathError,
@@ -452,6 +598,14 @@ pub enum ErrorKind {
     /// Thrown when serializing the platform to JSON fails
     StringifyPlatformError,
 
+    /// Thrown when serializing an exported toolchain fails
+    StringifyToolchainExportError,
+
+    /// Thrown when a tool was fetched, but the default platform couldn't be updated to use it
+    ToolNotActivated {
+        tool: String,
+    },
+
     /// Thrown when a given feature has not yet been implemented
     Unimplemented {
         feature: String,
@@ -489,11 +643,21 @@ pub enum ErrorKind {
         file: PathBuf,
     },
 
+    /// Thrown when recording the relocated image directory fails
+    WriteImageRootError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error writing the npm launcher
     WriteLauncherError {
         tool: String,
     },
 
+    /// Thrown when writing a project lockfile fails
+    WriteLockfileError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error writing the node index cache
     WriteNodeIndexCacheError {
         file: PathBuf,
@@ -514,6 +678,16 @@ pub enum ErrorKind {
         file: PathBuf,
     },
 
+    /// Thrown when there was an error recording the provenance of a tool image
+    WriteProvenanceError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error writing a shell profile script
+    WriteShellProfileError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to write the user PATH environment variable
     #[cfg(windows)]
     WriteUserPathError,
@@ -529,6 +703,8 @@ pub enum ErrorKind {
     /// Thrown when there is no Yarn version matching a requested semver specifier.
     YarnVersionNotFound {
         matching: String,
+        /// The registry that was searched, if any
+        registry_url: Option<String>,
     },
 }
 
@@ -559,6 +735,12 @@ See `volta help install` and `volta help pin` for info about making tools availa
 Use `volta install` to add a package to your toolchain (see `volta help install` for more info)."#,
                 name
             ),
+            ErrorKind::BinarySelectionRequiresPackage => write!(
+                f,
+                "Executables can only be selected when installing a single package
+
+Please install other tools with a separate `volta install` command."
+            ),
             ErrorKind::BuildPathError => write!(
                 f,
                 "Could not create execution environment.
@@ -586,6 +768,14 @@ Use `volta install {}` to update the default version.",
 Use `npm install` or `yarn add` to select a version of {} for this project.",
                 package
             ),
+            ErrorKind::CaptureFileError { file } => write!(
+                f,
+                "Could not create file to capture output at {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorKind::CompletionsOutFileError { path } => write!(
                 f,
                 "Completions file `{}` already exists.
@@ -688,6 +878,14 @@ from {}
 Please verify your internet connection and ensure the correct version is specified.",
                 tool, from_url
             ),
+            ErrorKind::DuplicateToolSpec { action, tool } => write!(
+                f,
+                "Could not {} more than one version of {} at once.
+
+Please choose a single version, or use `volta fetch` to download several versions without
+changing the default.",
+                action, tool
+            ),
             ErrorKind::ExecuteHookError { command } => write!(
                 f,
                 "Could not execute hook command: '{}'
@@ -727,6 +925,22 @@ Please ensure that the correct command is specified.",
 Please ensure that the file exists and is accessible.",
                 path.display(),
             ),
+            ErrorKind::FrozenLockfileError { file, reason } => write!(
+                f,
+                "Could not use the frozen project lockfile
+at {}
+
+{}. Run the command without `--frozen` (or with `VOLTA_FROZEN` unset) to update it.",
+                file.display(),
+                reason
+            ),
+            ErrorKind::GitSourceNotSupported { tool } => write!(
+                f,
+                "Could not install {} from a git repository.
+
+Please specify a released version of {} instead.",
+                tool, tool
+            ),
             ErrorKind::HookCommandFailed { command } => write!(
                 f,
                 "Hook command '{}' indicated a failure.
@@ -760,6 +974,23 @@ Please ensure that the correct command is specified.",
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorKind::InsufficientDiskSpace { needed, available } => write!(
+                f,
+                "Not enough disk space to fetch the tool: about {} MiB is needed, but only {} MiB is available.
+
+Please free up some disk space and try again.",
+                needed / MIB,
+                available / MIB
+            ),
+            ErrorKind::IntegrityMismatch { tool, from_url } => write!(
+                f,
+                "Integrity check failed for {}
+downloaded from {}
+
+The archive does not match the checksum published by the registry. Please try again, and
+contact the registry maintainers if the problem persists.",
+                tool, from_url
+            ),
             ErrorKind::InvalidHookCommand { command } => write!(
                 f,
                 "Invalid hook command: '{}'
@@ -830,6 +1061,13 @@ To {action} the package '{version}', please use an explicit version such as '{ve
                 write!(f, "{}\n\n{}", error, wrapped_cta)
             }
 
+            ErrorKind::InvalidNodeTarget { target } => write!(
+                f,
+                "Invalid target platform '{}'
+
+Please use a target of the form `<os>-<arch>`, like `linux-x64`, `darwin-arm64`, or `win-x64`.",
+                target
+            ),
             ErrorKind::InvalidRegistryFormat { format } => write!(
                 f,
                 "Unrecognized index registry format: '{}'
@@ -858,11 +1096,43 @@ format
                     name, call_to_action, formatted_errs
                 )
             }
+            ErrorKind::InvalidToolNameCharacters { name } => write!(
+                f,
+                "Invalid tool name {:?}
+
+Tool names cannot contain control characters or leading or trailing whitespace.",
+                name
+            ),
+            ErrorKind::LayoutMigrationRequired { version } => write!(
+                f,
+                "Your Volta directory uses an outdated layout ({}).
+
+Please run 'volta-migrate' to update it, then try again.",
+                version
+            ),
+            ErrorKind::LocalArchiveMismatch { file, expected } => write!(
+                f,
+                "Archive '{}' does not match the expected Node distribution.
+
+Please provide the archive for this platform, named '{}'.",
+                file.display(),
+                expected
+            ),
             // Note: No CTA as this error is purely informational and shouldn't be exposed to the user
             ErrorKind::LockAcquireError => write!(
                 f,
                 "Unable to acquire lock on Volta directory"
             ),
+            ErrorKind::NewerPackageConfig {
+                package,
+                schema_version,
+            } => write!(
+                f,
+                "The config for {} was written by a newer version of Volta (config version {}).
+
+Please update Volta, or uninstall the package before installing it again.",
+                package, schema_version
+            ),
             ErrorKind::NoBundledNpm { command } => write!(
                 f,
                 "Could not detect bundled npm version.
@@ -889,13 +1159,38 @@ Use `volta run --yarn` to select a version (see `volta help run` for more info).
 Use `volta install node` to select a default Node first, then install a {0} version.",
                                 tool
             ),
-            ErrorKind::NodeVersionNotFound { matching } => write!(
+            ErrorKind::NoDownloadUrls => write!(
                 f,
-                r#"Could not find Node version matching "{}" in the version registry.
+                "There are no locations to download from.
 
-Please verify that the version is correct."#,
-                matching
+Please verify your mirror settings."
             ),
+            ErrorKind::NodeVersionNotFound {
+                matching,
+                suggestions,
+                index_url,
+            } => {
+                write!(
+                    f,
+                    r#"Could not find Node version matching "{}" in the version registry{}.
+
+Please verify that the version is correct."#,
+                    matching,
+                    searched(index_url)
+                )?;
+
+                if !suggestions.is_empty() {
+                    let suggestions: Vec<String> =
+                        suggestions.iter().map(Version::to_string).collect();
+                    write!(
+                        f,
+                        " The closest available versions are: {}",
+                        suggestions.join(", ")
+                    )?;
+                }
+
+                Ok(())
+            }
             ErrorKind::NoHomeEnvironmentVar => write!(
                 f,
                 "Could not determine home directory.
@@ -927,6 +1222,13 @@ Use `volta pin node` to pin Node first, then pin a {0} version.",
 
 To run any Node command, first set a default version using `volta install node`"
             ),
+            ErrorKind::NoProjectNodeForPinnedTool { tool } => write!(
+                f,
+                "This project pins {} but not Node.
+
+Use `volta pin node` to pin Node first (see `volta help pin` for more info).",
+                tool
+            ),
             ErrorKind::NoProjectNodeInManifest => write!(
                 f,
                 "No Node version found in this project.
@@ -945,13 +1247,26 @@ Use `volta pin pnpm` to select a version (see `volta help pin` for more info)."
 
 Use `volta pin yarn` to select a version (see `volta help pin` for more info)."
             ),
-            ErrorKind::NoShellProfile { env_profile, bin_dir } => write!(
-                f,
-                "Could not locate user profile.
-Tried $PROFILE ({}), ~/.bashrc, ~/.bash_profile, ~/.zshenv ~/.zshrc, ~/.profile, and ~/.config/fish/config.fish
+            ErrorKind::NoShellProfile { profiles, bin_dir } => {
+                let tried: Vec<_> = profiles
+                    .iter()
+                    .map(|profile| profile.display().to_string())
+                    .collect();
+                write!(
+                    f,
+                    "Could not locate user profile.
+Tried {}
 
 Please create one of these and try again; or you can edit your profile manually to add '{}' to your PATH",
-                env_profile, bin_dir.display()
+                    tried.join(", "),
+                    bin_dir.display()
+                )
+            }
+            ErrorKind::NoSystemNode => write!(
+                f,
+                "Could not find a system Node install.
+
+Install Node outside of Volta, or select a Volta-managed version with `volta install node`."
             ),
             ErrorKind::NotInPackage => write!(
                 f,
@@ -971,6 +1286,18 @@ Use `volta install pnpm` to select a default version (see `volta help install` f
 
 Use `volta install yarn` to select a default version (see `volta help install` for more info)."
             ),
+            ErrorKind::NpmExecNotAvailable {
+                version,
+                bundled_with,
+            } => write!(
+                f,
+                "'npm exec' is only available with npm >= 7.0.0
+
+{}
+
+Use `volta pin npm` or `volta install npm` to select a newer version.",
+                active_npm(version, bundled_with)
+            ),
             ErrorKind::NpmLinkMissingPackage { package } => write!(
                 f,
                 "Could not locate the package '{}'
@@ -985,19 +1312,35 @@ Please ensure it is available by running `npm link` in its source directory.",
 Please ensure it is linked with `npm link` or installed with `npm i -g {0}`.",
                 package
             ),
-            ErrorKind::NpmVersionNotFound { matching } => write!(
+            ErrorKind::NpmVersionNotFound {
+                matching,
+                registry_url,
+            } => write!(
                 f,
-                r#"Could not find Node version matching "{}" in the version registry.
+                r#"Could not find Node version matching "{}" in the version registry{}.
 
 Please verify that the version is correct."#,
-                matching
+                matching,
+                searched(registry_url)
             ),
-            ErrorKind::NpxNotAvailable { version } => write!(
+            ErrorKind::NpxNotAvailable {
+                version,
+                bundled_with,
+            } => write!(
                 f,
                 "'npx' is only available with npm >= 5.2.0
 
-This project is configured to use version {} of npm.",
-                version
+{}
+
+Use `volta pin npm` or `volta install npm` to select a newer version.",
+                active_npm(version, bundled_with)
+            ),
+            ErrorKind::PackageBinaryNotFound { package, bin } => write!(
+                f,
+                "Package '{}' does not provide an executable named '{}'
+
+Please check the package's `bin` field for the executables it provides.",
+                package, bin
             ),
             ErrorKind::PackageInstallFailed { package } => write!(
                 f,
@@ -1049,6 +1392,13 @@ Please ensure that the file exists.",
 
 Please ensure the package is correctly formatted."
             ),
+            ErrorKind::PackageVersionNotFound { name, matching } => write!(
+                f,
+                r#"Could not find {} version matching "{}" in the package registry.
+
+Please verify that the version is correct."#,
+                name, matching
+            ),
             ErrorKind::PackageWriteError { file } => write!(
                 f,
                 "Could not write project manifest
@@ -1072,6 +1422,14 @@ from {}
 Please ensure the file is correctly formatted.",
                 file.display()
             ),
+            ErrorKind::ParseLockfileError { file } => write!(
+                f,
+                "Could not parse project lockfile
+at {}
+
+Please ensure the file is correctly formatted, or remove it and run `volta pin --update-lock`.",
+                file.display()
+            ),
             ErrorKind::ParseNodeIndexCacheError => write!(
                 f,
                 "Could not parse Node index cache file.
@@ -1114,6 +1472,12 @@ Please ensure the version of Node is correct."
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorKind::ParseToolchainExportError => write!(
+                f,
+                "Could not parse the exported toolchain.
+
+Please ensure the document was created by exporting a Volta toolchain."
+            ),
             ErrorKind::ParseToolSpecError { tool_spec } => write!(
                 f,
                 "Could not parse tool spec `{}`
@@ -1128,12 +1492,16 @@ Please supply a spec in the format `<tool name>[@<version>]`.",
 {}",
                 tool, PERMISSIONS_CTA
             ),
-            ErrorKind::PnpmVersionNotFound { matching } => write!(
+            ErrorKind::PnpmVersionNotFound {
+                matching,
+                registry_url,
+            } => write!(
                 f,
-                r#"Could not find pnpm version matching "{}" in the version registry.
+                r#"Could not find pnpm version matching "{}" in the version registry{}.
 
 Please verify that the version is correct."#,
-                matching
+                matching,
+                searched(registry_url)
             ),
             ErrorKind::ProjectLocalBinaryExecError { command } => write!(
                 f,
@@ -1200,6 +1568,23 @@ from {}
                 "Could not read hooks file
 from {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorKind::ReadLocalArchiveError { file } => write!(
+                f,
+                "Could not read Node archive
+from {}
+
+Please ensure the file exists and is a valid Node distribution archive.",
+                file.display()
+            ),
+            ErrorKind::ReadLockfileError { file } => write!(
+                f,
+                "Could not read project lockfile
+from {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1218,6 +1603,15 @@ from {}
                 "Could not read Node index cache expiration
 from {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorKind::ReadNodeVersionFileError { file } => write!(
+                f,
+                "Could not read Node version file
+from {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1228,6 +1622,13 @@ from {}
 
 Please ensure the version of Node is correct."
             ),
+            ErrorKind::ReadOnlyVoltaHome { home } => write!(
+                f,
+                "Could not modify the Volta directory at {}, because it is read-only.
+
+Please ensure you have write access to the directory, or set VOLTA_HOME to a writable location.",
+                home.display()
+            ),
             ErrorKind::ReadPackageConfigError { file } => write!(
                 f,
                 "Could not read package configuration file
@@ -1242,6 +1643,24 @@ from {}
                 "Could not read default platform file
 from {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorKind::ReadProvenanceError { file } => write!(
+                f,
+                "Could not read provenance file
+from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorKind::ReadShellProfileError { file } => write!(
+                f,
+                "Could not read shell profile script
+from {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1320,6 +1739,20 @@ at {}
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorKind::StringifyToolchainExportError => write!(
+                f,
+                "Could not serialize the exported toolchain.
+
+{}",
+                REPORT_BUG_CTA
+            ),
+            ErrorKind::ToolNotActivated { tool } => write!(
+                f,
+                "{} was fetched, but could not be set as the default.
+
+The previous default is unchanged. Run `volta install {}` again to finish setting it.",
+                tool, tool
+            ),
             ErrorKind::Unimplemented { feature } => {
                 write!(f, "{} is not supported yet.", feature)
             }
@@ -1377,6 +1810,15 @@ to {}
                 "Could not write bundled npm version
 to {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorKind::WriteImageRootError { file } => write!(
+                f,
+                "Could not record the image directory
+to {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1388,6 +1830,15 @@ to {}
 This is most likely an intermittent failure, please try again.",
                 tool
             ),
+            ErrorKind::WriteLockfileError { file } => write!(
+                f,
+                "Could not write project lockfile
+to {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorKind::WriteNodeIndexCacheError { file } => write!(
                 f,
                 "Could not write Node index cache
@@ -1420,6 +1871,24 @@ to {}
                 "Could not save platform settings
 to {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorKind::WriteProvenanceError { file } => write!(
+                f,
+                "Could not record provenance
+for {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorKind::WriteShellProfileError { file } => write!(
+                f,
+                "Could not write shell profile script
+to {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1445,12 +1914,16 @@ from {}
 Please verify your internet connection.",
                 from_url
             ),
-            ErrorKind::YarnVersionNotFound { matching } => write!(
+            ErrorKind::YarnVersionNotFound {
+                matching,
+                registry_url,
+            } => write!(
                 f,
-                r#"Could not find Yarn version matching "{}" in the version registry.
+                r#"Could not find Yarn version matching "{}" in the version registry{}.
 
 Please verify that the version is correct."#,
-                matching
+                matching,
+                searched(registry_url)
             ),
         }
     }
@@ -1462,10 +1935,12 @@ impl ErrorKind {
             ErrorKind::BinaryAlreadyInstalled { .. } => ExitCode::FileSystemError,
             ErrorKind::BinaryExecError => ExitCode::ExecutionFailure,
             ErrorKind::BinaryNotFound { .. } => ExitCode::ExecutableNotFound,
+            ErrorKind::BinarySelectionRequiresPackage => ExitCode::InvalidArguments,
             ErrorKind::BuildPathError => ExitCode::EnvironmentError,
             ErrorKind::BypassError { .. } => ExitCode::ExecutionFailure,
             ErrorKind::CannotFetchPackage { .. } => ExitCode::InvalidArguments,
             ErrorKind::CannotPinPackage { .. } => ExitCode::InvalidArguments,
+            ErrorKind::CaptureFileError { .. } => ExitCode::FileSystemError,
             ErrorKind::CompletionsOutFileError { .. } => ExitCode::InvalidArguments,
             ErrorKind::ContainingDirError { .. } => ExitCode::FileSystemError,
             ErrorKind::CouldNotDetermineTool => ExitCode::UnknownError,
@@ -1480,42 +1955,57 @@ impl ErrorKind {
             ErrorKind::DeleteFileError { .. } => ExitCode::FileSystemError,
             ErrorKind::DeprecatedCommandError { .. } => ExitCode::InvalidArguments,
             ErrorKind::DownloadToolNetworkError { .. } => ExitCode::NetworkError,
+            ErrorKind::DuplicateToolSpec { .. } => ExitCode::InvalidArguments,
             ErrorKind::ExecuteHookError { .. } => ExitCode::ExecutionFailure,
             ErrorKind::ExtensionCycleError { .. } => ExitCode::ConfigurationError,
             ErrorKind::ExtensionPathError { .. } => ExitCode::FileSystemError,
+            ErrorKind::FrozenLockfileError { .. } => ExitCode::ConfigurationError,
+            ErrorKind::GitSourceNotSupported { .. } => ExitCode::InvalidArguments,
             ErrorKind::HookCommandFailed { .. } => ExitCode::ConfigurationError,
             ErrorKind::HookMultipleFieldsSpecified => ExitCode::ConfigurationError,
             ErrorKind::HookNoFieldsSpecified => ExitCode::ConfigurationError,
             ErrorKind::HookPathError { .. } => ExitCode::ConfigurationError,
             ErrorKind::InstalledPackageNameError => ExitCode::UnknownError,
+            ErrorKind::InsufficientDiskSpace { .. } => ExitCode::FileSystemError,
+            ErrorKind::IntegrityMismatch { .. } => ExitCode::NetworkError,
             ErrorKind::InvalidHookCommand { .. } => ExitCode::ExecutableNotFound,
             ErrorKind::InvalidHookOutput { .. } => ExitCode::ExecutionFailure,
             ErrorKind::InvalidInvocation { .. } => ExitCode::InvalidArguments,
             ErrorKind::InvalidInvocationOfBareVersion { .. } => ExitCode::InvalidArguments,
+            ErrorKind::InvalidNodeTarget { .. } => ExitCode::InvalidArguments,
             ErrorKind::InvalidRegistryFormat { .. } => ExitCode::ConfigurationError,
             ErrorKind::InvalidToolName { .. } => ExitCode::InvalidArguments,
+            ErrorKind::InvalidToolNameCharacters { .. } => ExitCode::InvalidArguments,
+            ErrorKind::LayoutMigrationRequired { .. } => ExitCode::EnvironmentError,
+            ErrorKind::LocalArchiveMismatch { .. } => ExitCode::InvalidArguments,
             ErrorKind::LockAcquireError => ExitCode::FileSystemError,
+            ErrorKind::NewerPackageConfig { .. } => ExitCode::ConfigurationError,
             ErrorKind::NoBundledNpm { .. } => ExitCode::ConfigurationError,
             ErrorKind::NoCommandLinePnpm => ExitCode::ConfigurationError,
             ErrorKind::NoCommandLineYarn => ExitCode::ConfigurationError,
             ErrorKind::NoDefaultNodeVersion { .. } => ExitCode::ConfigurationError,
+            ErrorKind::NoDownloadUrls => ExitCode::ConfigurationError,
             ErrorKind::NodeVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorKind::NoHomeEnvironmentVar => ExitCode::EnvironmentError,
             ErrorKind::NoInstallDir => ExitCode::EnvironmentError,
             ErrorKind::NoLocalDataDir => ExitCode::EnvironmentError,
             ErrorKind::NoPinnedNodeVersion { .. } => ExitCode::ConfigurationError,
             ErrorKind::NoPlatform => ExitCode::ConfigurationError,
+            ErrorKind::NoProjectNodeForPinnedTool { .. } => ExitCode::ConfigurationError,
             ErrorKind::NoProjectNodeInManifest => ExitCode::ConfigurationError,
             ErrorKind::NoProjectPnpm => ExitCode::ConfigurationError,
             ErrorKind::NoProjectYarn => ExitCode::ConfigurationError,
             ErrorKind::NoShellProfile { .. } => ExitCode::EnvironmentError,
+            ErrorKind::NoSystemNode => ExitCode::ExecutableNotFound,
             ErrorKind::NotInPackage => ExitCode::ConfigurationError,
             ErrorKind::NoDefaultPnpm => ExitCode::ConfigurationError,
             ErrorKind::NoDefaultYarn => ExitCode::ConfigurationError,
+            ErrorKind::NpmExecNotAvailable { .. } => ExitCode::ExecutableNotFound,
             ErrorKind::NpmLinkMissingPackage { .. } => ExitCode::ConfigurationError,
             ErrorKind::NpmLinkWrongManager { .. } => ExitCode::ConfigurationError,
             ErrorKind::NpmVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorKind::NpxNotAvailable { .. } => ExitCode::ExecutableNotFound,
+            ErrorKind::PackageBinaryNotFound { .. } => ExitCode::InvalidArguments,
             ErrorKind::PackageInstallFailed { .. } => ExitCode::UnknownError,
             ErrorKind::PackageManifestParseError { .. } => ExitCode::ConfigurationError,
             ErrorKind::PackageManifestReadError { .. } => ExitCode::FileSystemError,
@@ -1523,9 +2013,11 @@ impl ErrorKind {
             ErrorKind::PackageParseError { .. } => ExitCode::ConfigurationError,
             ErrorKind::PackageReadError { .. } => ExitCode::FileSystemError,
             ErrorKind::PackageUnpackError => ExitCode::ConfigurationError,
+            ErrorKind::PackageVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorKind::PackageWriteError { .. } => ExitCode::FileSystemError,
             ErrorKind::ParseBinConfigError => ExitCode::UnknownError,
             ErrorKind::ParseHooksError { .. } => ExitCode::ConfigurationError,
+            ErrorKind::ParseLockfileError { .. } => ExitCode::ConfigurationError,
             ErrorKind::ParseToolSpecError { .. } => ExitCode::InvalidArguments,
             ErrorKind::ParseNodeIndexCacheError => ExitCode::UnknownError,
             ErrorKind::ParseNodeIndexError { .. } => ExitCode::NetworkError,
@@ -1533,6 +2025,7 @@ impl ErrorKind {
             ErrorKind::ParseNpmManifestError => ExitCode::UnknownError,
             ErrorKind::ParsePackageConfigError => ExitCode::UnknownError,
             ErrorKind::ParsePlatformError => ExitCode::ConfigurationError,
+            ErrorKind::ParseToolchainExportError => ExitCode::ConfigurationError,
             ErrorKind::PersistInventoryError { .. } => ExitCode::FileSystemError,
             ErrorKind::PnpmVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorKind::ProjectLocalBinaryExecError { .. } => ExitCode::ExecutionFailure,
@@ -1544,11 +2037,17 @@ impl ErrorKind {
             ErrorKind::ReadDefaultNpmError { .. } => ExitCode::FileSystemError,
             ErrorKind::ReadDirError { .. } => ExitCode::FileSystemError,
             ErrorKind::ReadHooksError { .. } => ExitCode::FileSystemError,
+            ErrorKind::ReadLocalArchiveError { .. } => ExitCode::FileSystemError,
+            ErrorKind::ReadLockfileError { .. } => ExitCode::FileSystemError,
             ErrorKind::ReadNodeIndexCacheError { .. } => ExitCode::FileSystemError,
             ErrorKind::ReadNodeIndexExpiryError { .. } => ExitCode::FileSystemError,
+            ErrorKind::ReadNodeVersionFileError { .. } => ExitCode::FileSystemError,
             ErrorKind::ReadNpmManifestError => ExitCode::UnknownError,
+            ErrorKind::ReadOnlyVoltaHome { .. } => ExitCode::FileSystemError,
             ErrorKind::ReadPackageConfigError { .. } => ExitCode::FileSystemError,
             ErrorKind::ReadPlatformError { .. } => ExitCode::FileSystemError,
+            ErrorKind::ReadProvenanceError { .. } => ExitCode::FileSystemError,
+            ErrorKind::ReadShellProfileError { .. } => ExitCode::FileSystemError,
             #[cfg(windows)]
             ErrorKind::ReadUserPathError => ExitCode::EnvironmentError,
             ErrorKind::RegistryFetchError { .. } => ExitCode::NetworkError,
@@ -1560,6 +2059,8 @@ impl ErrorKind {
             ErrorKind::StringifyBinConfigError => ExitCode::UnknownError,
             ErrorKind::StringifyPackageConfigError => ExitCode::UnknownError,
             ErrorKind::StringifyPlatformError => ExitCode::UnknownError,
+            ErrorKind::StringifyToolchainExportError => ExitCode::UnknownError,
+            ErrorKind::ToolNotActivated { .. } => ExitCode::FileSystemError,
             ErrorKind::Unimplemented { .. } => ExitCode::UnknownError,
             ErrorKind::UnpackArchiveError { .. } => ExitCode::UnknownError,
             ErrorKind::UpgradePackageNotFound { .. } => ExitCode::ConfigurationError,
@@ -1567,11 +2068,15 @@ impl ErrorKind {
             ErrorKind::VersionParseError { .. } => ExitCode::NoVersionMatch,
             ErrorKind::WriteBinConfigError { .. } => ExitCode::FileSystemError,
             ErrorKind::WriteDefaultNpmError { .. } => ExitCode::FileSystemError,
+            ErrorKind::WriteImageRootError { .. } => ExitCode::FileSystemError,
             ErrorKind::WriteLauncherError { .. } => ExitCode::FileSystemError,
+            ErrorKind::WriteLockfileError { .. } => ExitCode::FileSystemError,
             ErrorKind::WriteNodeIndexCacheError { .. } => ExitCode::FileSystemError,
             ErrorKind::WriteNodeIndexExpiryError { .. } => ExitCode::FileSystemError,
             ErrorKind::WritePackageConfigError { .. } => ExitCode::FileSystemError,
             ErrorKind::WritePlatformError { .. } => ExitCode::FileSystemError,
+            ErrorKind::WriteProvenanceError { .. } => ExitCode::FileSystemError,
+            ErrorKind::WriteShellProfileError { .. } => ExitCode::FileSystemError,
             #[cfg(windows)]
             ErrorKind::WriteUserPathError => ExitCode::EnvironmentError,
             ErrorKind::Yarn2NotSupported => ExitCode::NoVersionMatch,
@@ -1580,3 +2085,22 @@ impl ErrorKind {
         }
     }
 }
+
+/// Describes the active version of npm, and the Node version it is bundled with if it isn't custom
+fn active_npm(version: &str, bundled_with: &Option<String>) -> String {
+    match bundled_with {
+        Some(node) => format!(
+            "The active version of npm is {}, which is bundled with Node {}.",
+            version, node
+        ),
+        None => format!("The active version of npm is {}.", version),
+    }
+}
+
+/// Describes the index or registry that was searched for a version, if it is known
+fn searched(url: &Option<String>) -> String {
+    match url {
+        Some(url) => format!(" (searched {})", url),
+        None => String::new(),
+    }
+}
@@ -1,6 +1,7 @@
 //! Events for the sessions in executables and shims and everything
 
 use std::env;
+use std::process::Child;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
@@ -10,6 +11,14 @@ use crate::hook::Publish;
 use crate::monitor::send_events;
 use crate::session::ActivityKind;
 
+/// Environment variable that turns off publishing events, regardless of the configured hooks
+const VOLTA_NO_EVENTS: &str = "VOLTA_NO_EVENTS";
+
+/// Determines whether publishing events has been turned off with `VOLTA_NO_EVENTS`
+pub fn events_disabled() -> bool {
+    env::var_os(VOLTA_NO_EVENTS).is_some()
+}
+
 // the Event data that is serialized to JSON and sent the plugin
 #[derive(Deserialize, Serialize)]
 pub struct Event {
@@ -143,14 +152,15 @@ impl EventLog {
         self.events.push(event);
     }
 
-    pub fn publish(&self, plugin: Option<&Publish>) {
+    /// Publishes the events with the given hook, returning the spawned hook process, if any
+    ///
+    /// Without a hook, this is a no-op: The events are not serialized and no process is spawned.
+    pub fn publish(&self, plugin: Option<&Publish>) -> Option<Child> {
         match plugin {
             // Note: This call to unimplemented is left in, as it's not a Fallible operation that can use ErrorKind::Unimplemented
             Some(Publish::Url(_)) => unimplemented!(),
-            Some(Publish::Bin(command)) => {
-                send_events(command, &self.events);
-            }
-            None => {}
+            Some(Publish::Bin(command)) => send_events(command, &self.events),
+            None => None,
         }
     }
 }
@@ -163,6 +173,15 @@ pub mod tests {
     use crate::session::ActivityKind;
     use regex::Regex;
 
+    #[test]
+    fn test_publish_without_hook() {
+        let mut event_log = EventLog::init();
+        event_log.add_event_start(ActivityKind::Install);
+        event_log.add_event_end(ActivityKind::Install, ExitCode::Success);
+
+        assert!(event_log.publish(None).is_none());
+    }
+
     #[test]
     fn test_adding_events() {
         let mut event_log = EventLog::init();
@@ -1,17 +1,43 @@
 //! Provides utilities for operating on the filesystem.
 
+use std::borrow::Cow;
 use std::fs::{self, create_dir_all, read_dir, DirEntry, File, Metadata};
 use std::io;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::error::{Context, ErrorKind, Fallible};
-use crate::layout::volta_home;
+use crate::layout::{tmp_dir_override, volta_home};
 use retry::delay::Fibonacci;
 use retry::{retry, OperationResult};
 use tempfile::{tempdir_in, NamedTempFile, TempDir};
 
+/// Whether file names that differ only in case refer to the same file, as on the default
+/// filesystems of macOS and Windows
+pub const CASE_INSENSITIVE: bool = cfg!(any(target_os = "macos", windows));
+
+/// Normalizes a file name for comparison, ignoring case on case-insensitive filesystems, so that
+/// names which refer to the same file compare as equal
+pub fn file_name_key(name: &str) -> Cow<'_, str> {
+    if CASE_INSENSITIVE {
+        Cow::Owned(name.to_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Determines whether `path` is `base` or inside of it, ignoring case on case-insensitive
+/// filesystems
+pub fn path_starts_with(path: &Path, base: &Path) -> bool {
+    if CASE_INSENSITIVE {
+        let key = |path: &Path| PathBuf::from(path.to_string_lossy().to_lowercase());
+        key(path).starts_with(key(base))
+    } else {
+        path.starts_with(base)
+    }
+}
+
 /// Opens a file, creating it if it doesn't exist
 pub fn touch(path: &Path) -> io::Result<File> {
     if !path.is_file() {
@@ -75,7 +101,8 @@ pub fn read_file<P: AsRef<Path>>(path: P) -> io::Result<Option<String>> {
 ///
 /// Note that this function allocates an intermediate vector of directory entries to
 /// construct the iterator from, so if a directory is expected to be very large, it
-/// will allocate temporary data proportional to the number of entries.
+/// will allocate temporary data proportional to the number of entries. In that case,
+/// prefer `read_dir_streaming`.
 pub fn read_dir_eager(dir: &Path) -> io::Result<impl Iterator<Item = (DirEntry, Metadata)>> {
     let entries = read_dir(dir)?;
     let vec = entries
@@ -89,6 +116,22 @@ pub fn read_dir_eager(dir: &Path) -> io::Result<impl Iterator<Item = (DirEntry,
     Ok(vec.into_iter())
 }
 
+/// Reads a directory and returns an iterator that lazily yields each entry along with its
+/// metadata.
+///
+/// Unlike `read_dir_eager`, entries are read one at a time as the iterator is consumed, so memory
+/// use doesn't grow with the size of the directory. As a result, an error reading an individual
+/// entry is yielded in place of that entry, rather than failing the whole call.
+pub fn read_dir_streaming(
+    dir: &Path,
+) -> io::Result<impl Iterator<Item = io::Result<(DirEntry, Metadata)>>> {
+    Ok(read_dir(dir)?.map(|entry| {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        Ok((entry, metadata))
+    }))
+}
+
 /// Reads the contents of a directory and returns a Vec of the matched results
 /// from the input function
 pub fn dir_entry_match<T, F>(dir: &Path, mut f: F) -> io::Result<Vec<T>>
@@ -102,9 +145,23 @@ where
         .collect::<Vec<T>>())
 }
 
+/// Returns the Volta tmp directory
+///
+/// The tmp directory in the Volta home is created by `volta setup`, but one relocated with
+/// `VOLTA_TMP` (e.g. for a single CI job) is created when it is first needed.
+fn staging_root<'a>() -> Fallible<&'a Path> {
+    let tmp_dir = volta_home()?.tmp_dir();
+    if tmp_dir_override().is_some() {
+        create_dir_all(tmp_dir).with_context(|| ErrorKind::CreateTempDirError {
+            in_dir: tmp_dir.to_owned(),
+        })?;
+    }
+    Ok(tmp_dir)
+}
+
 /// Creates a NamedTempFile in the Volta tmp directory
 pub fn create_staging_file() -> Fallible<NamedTempFile> {
-    let tmp_dir = volta_home()?.tmp_dir();
+    let tmp_dir = staging_root()?;
     NamedTempFile::new_in(tmp_dir).with_context(|| ErrorKind::CreateTempFileError {
         in_dir: tmp_dir.to_owned(),
     })
@@ -112,12 +169,21 @@ pub fn create_staging_file() -> Fallible<NamedTempFile> {
 
 /// Creates a staging directory in the Volta tmp directory
 pub fn create_staging_dir() -> Fallible<TempDir> {
-    let tmp_root = volta_home()?.tmp_dir();
+    let tmp_root = staging_root()?;
     tempdir_in(tmp_root).with_context(|| ErrorKind::CreateTempDirError {
         in_dir: tmp_root.to_owned(),
     })
 }
 
+/// Creates a staging directory in the given directory, creating that directory if needed
+pub fn create_staging_dir_in(dir: &Path) -> Fallible<TempDir> {
+    create_dir_all(dir)
+        .and_then(|_| tempdir_in(dir))
+        .with_context(|| ErrorKind::CreateTempDirError {
+            in_dir: dir.to_owned(),
+        })
+}
+
 /// Create a file symlink. The `dst` path will be a symbolic link pointing to the `src` path.
 pub fn symlink_file<S, D>(src: S, dest: D) -> io::Result<()>
 where
@@ -44,6 +44,11 @@ impl LazyHookConfig {
         self.settings
             .get_or_try_init(|| HookConfig::current(project))
     }
+
+    /// Discards the loaded configuration, so that it is read again the next time it is needed
+    pub fn reset(&mut self) {
+        self.settings.take();
+    }
 }
 
 /// Volta hook configuration
@@ -251,6 +256,8 @@ impl RegistryFormat {
 pub struct EventHooks {
     /// The hook for publishing events, if any.
     pub publish: Option<Publish>,
+    /// Whether events are enabled, if set explicitly with `"events": true|false`
+    pub enabled: Option<bool>,
 }
 
 impl EventHooks {
@@ -258,6 +265,15 @@ impl EventHooks {
     fn merge(self, other: Self) -> Self {
         Self {
             publish: self.publish.or(other.publish),
+            enabled: self.enabled.or(other.enabled),
+        }
+    }
+
+    /// The hook to publish events with, unless events have been disabled
+    pub fn publisher(&self) -> Option<&Publish> {
+        match self.enabled {
+            Some(false) => None,
+            _ => self.publish.as_ref(),
         }
     }
 }
@@ -287,6 +303,17 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_from_str_events_disabled() {
+        let fixture_dir = fixture_path("hooks");
+        let disabled_file = fixture_dir.join("events_disabled.json");
+        let hooks = HookConfig::from_file(&disabled_file).unwrap().unwrap();
+        let events = hooks.events.unwrap();
+
+        assert_eq!(events.enabled, Some(false));
+        assert_eq!(events.publisher(), None);
+    }
+
     #[test]
     fn test_from_str_bins() {
         let fixture_dir = fixture_path("hooks");
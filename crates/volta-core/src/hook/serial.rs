@@ -130,7 +130,15 @@ pub struct RawHookConfig {
     pub npm: Option<RawToolHooks<Npm>>,
     pub pnpm: Option<RawToolHooks<Pnpm>>,
     pub yarn: Option<RawYarnHooks>,
-    pub events: Option<RawEventHooks>,
+    pub events: Option<RawEventsConfig>,
+}
+
+/// The `events` config, which is either the event hooks or a boolean to turn events on or off
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RawEventsConfig {
+    Enabled(bool),
+    Hooks(RawEventHooks),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -139,13 +147,24 @@ pub struct RawEventHooks {
     pub publish: Option<RawPublishHook>,
 }
 
-impl TryFrom<RawEventHooks> for super::EventHooks {
+impl TryFrom<RawEventsConfig> for super::EventHooks {
     type Error = VoltaError;
 
-    fn try_from(raw: RawEventHooks) -> Fallible<super::EventHooks> {
-        let publish = raw.publish.map(|p| p.try_into()).transpose()?;
-
-        Ok(super::EventHooks { publish })
+    fn try_from(raw: RawEventsConfig) -> Fallible<super::EventHooks> {
+        match raw {
+            RawEventsConfig::Enabled(enabled) => Ok(super::EventHooks {
+                publish: None,
+                enabled: Some(enabled),
+            }),
+            RawEventsConfig::Hooks(hooks) => {
+                let publish = hooks.publish.map(|p| p.try_into()).transpose()?;
+
+                Ok(super::EventHooks {
+                    publish,
+                    enabled: None,
+                })
+            }
+        }
     }
 }
 
@@ -34,13 +34,24 @@ pub enum DistroHook {
 impl DistroHook {
     /// Performs resolution of the distro URL based on the given version and file name
     pub fn resolve(&self, version: &Version, filename: &str) -> Fallible<String> {
+        self.resolve_for_target(version, filename, NODE_DISTRO_OS, NODE_DISTRO_ARCH)
+    }
+
+    /// Performs resolution of the distro URL for a platform other than the current one
+    pub fn resolve_for_target(
+        &self,
+        version: &Version,
+        filename: &str,
+        os: &str,
+        arch: &str,
+    ) -> Fallible<String> {
         let extension = calculate_extension(filename).unwrap_or("");
 
         match &self {
             DistroHook::Prefix(prefix) => Ok(format!("{}{}", prefix, filename)),
             DistroHook::Template(template) => Ok(template
-                .replace(ARCH_TEMPLATE, NODE_DISTRO_ARCH)
-                .replace(OS_TEMPLATE, NODE_DISTRO_OS)
+                .replace(ARCH_TEMPLATE, arch)
+                .replace(OS_TEMPLATE, os)
                 .replace(EXTENSION_TEMPLATE, extension)
                 .replace(FILENAME_TEMPLATE, filename)
                 .replace(VERSION_TEMPLATE, &version.to_string())),
@@ -226,6 +237,20 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_distro_template_resolve_for_target() {
+        let hook = DistroHook::Template(
+            "http://localhost/node/{{os}}/{{arch}}/{{version}}/{{ext}}/{{filename}}".to_string(),
+        );
+        let version = Version::parse("1.0.0").unwrap();
+
+        assert_eq!(
+            hook.resolve_for_target(&version, "node-v1.0.0-win-arm64.zip", "win", "arm64")
+                .expect("Could not resolve URL"),
+            "http://localhost/node/win/arm64/1.0.0/zip/node-v1.0.0-win-arm64.zip"
+        );
+    }
+
     #[test]
     fn test_metadata_prefix_resolve() {
         let prefix = "http://localhost/node/index/";
@@ -5,11 +5,9 @@ use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::path::Path;
 
-use crate::error::{Context, ErrorKind, Fallible};
-use crate::fs::read_dir_eager;
-use crate::layout::volta_home;
+use crate::error::Fallible;
+use crate::layout::{installed_images, volta_home};
 use crate::tool::PackageConfig;
-use crate::version::parse_version;
 use log::debug;
 use node_semver::Version;
 use walkdir::WalkDir;
@@ -94,12 +92,32 @@ pub fn package_configs() -> Fallible<BTreeSet<PackageConfig>> {
 /// Reads the contents of a directory and returns the set of all versions found
 /// in the directory's listing by parsing the directory names as semantic versions
 fn read_versions(dir: &Path) -> Fallible<BTreeSet<Version>> {
-    let contents = read_dir_eager(dir).with_context(|| ErrorKind::ReadDirError {
-        dir: dir.to_owned(),
-    })?;
-
-    Ok(contents
-        .filter(|(_, metadata)| metadata.is_dir())
-        .filter_map(|(entry, _)| parse_version(entry.file_name().to_string_lossy()).ok())
+    Ok(installed_images(dir)?
+        .into_iter()
+        .map(|(version, _)| version)
         .collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_read_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("12.14.0")).unwrap();
+        fs::create_dir(dir.path().join("20.5.1")).unwrap();
+        fs::create_dir(dir.path().join("not-a-version")).unwrap();
+        fs::write(dir.path().join("16.0.0"), "").unwrap();
+
+        let versions = read_versions(dir.path()).unwrap();
+        assert_eq!(
+            versions.into_iter().collect::<Vec<_>>(),
+            vec![
+                Version::parse("12.14.0").unwrap(),
+                Version::parse("20.5.1").unwrap()
+            ]
+        );
+    }
+}
@@ -1,11 +1,18 @@
+use std::cell::Cell;
 use std::env;
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
 
 use crate::error::{Context, ErrorKind, Fallible};
+use crate::fs::read_dir_streaming;
+use crate::version::parse_version;
 use cfg_if::cfg_if;
 use dunce::canonicalize;
+use node_semver::Version;
 use once_cell::sync::OnceCell;
 use volta_layout::v4::{VoltaHome, VoltaInstall};
+use volta_layout::{v1, v2, v3, v4};
 
 cfg_if! {
     if #[cfg(unix)] {
@@ -17,20 +24,185 @@ cfg_if! {
     }
 }
 
+/// Environment variable that moves the Node images out of the Volta home directory
+const VOLTA_IMAGE_ROOT: &str = "VOLTA_IMAGE_ROOT";
+
+/// Environment variable that moves the staging directory out of the Volta home directory
+const VOLTA_TMP: &str = "VOLTA_TMP";
+
+/// Environment variable that moves the inventory of downloaded archives out of the Volta home
+const VOLTA_INVENTORY: &str = "VOLTA_INVENTORY";
+
 static VOLTA_HOME: OnceCell<VoltaHome> = OnceCell::new();
 static VOLTA_INSTALL: OnceCell<VoltaInstall> = OnceCell::new();
 
+thread_local! {
+    static VOLTA_HOME_OVERRIDE: Cell<Option<&'static VoltaHome>> = Cell::new(None);
+}
+
 pub fn volta_home<'a>() -> Fallible<&'a VoltaHome> {
+    if let Some(home) = VOLTA_HOME_OVERRIDE.with(Cell::get) {
+        return Ok(home);
+    }
+
     VOLTA_HOME.get_or_try_init(|| {
         let home_dir = match env::var_os("VOLTA_HOME") {
             Some(home) => PathBuf::from(home),
             None => default_home_dir()?,
         };
 
-        Ok(VoltaHome::new(home_dir))
+        Ok(with_env_overrides(VoltaHome::new(home_dir)))
+    })
+}
+
+/// Relocates the directories that are overridden by environment variables
+///
+/// `VOLTA_TMP` and `VOLTA_INVENTORY` let parallel jobs that share a Volta home use their own
+/// staging directory and archive cache, while the images, the default platform, and the shims
+/// remain shared. Staged files are moved into the Volta home (or the image root) with a
+/// rename, so `VOLTA_TMP` must be on the same file system. Archives in a job's own inventory
+/// aren't visible to other jobs, so each job downloads the tools that aren't already unpacked.
+fn with_env_overrides(mut home: VoltaHome) -> VoltaHome {
+    if let Some(image_root) = image_root(&home) {
+        home = home.with_node_image_root(image_root.join("node"));
+    }
+    if let Some(tmp_dir) = tmp_dir_override() {
+        home = home.with_tmp_dir(tmp_dir);
+    }
+    if let Some(inventory_dir) = dir_override(VOLTA_INVENTORY) {
+        home = home.with_inventory_dir(inventory_dir);
+    }
+    home
+}
+
+/// Guard for a temporary Volta home directory, created by `override_volta_home`
+///
+/// The previous Volta home is restored when the guard is dropped.
+#[must_use = "the override is removed as soon as the guard is dropped"]
+pub struct VoltaHomeOverride {
+    previous: Option<&'static VoltaHome>,
+}
+
+impl Drop for VoltaHomeOverride {
+    fn drop(&mut self) {
+        VOLTA_HOME_OVERRIDE.with(|home| home.set(self.previous));
+    }
+}
+
+/// Temporarily points `volta_home()` at the given directory, for the current thread only
+///
+/// This allows tests and embedders to sandbox a `Session` in a throwaway directory without
+/// changing the `VOLTA_HOME` environment variable for the whole process. Overrides can be
+/// nested, and each one lasts until its guard is dropped.
+pub fn override_volta_home(root: PathBuf) -> VoltaHomeOverride {
+    let home = with_env_overrides(VoltaHome::new(root));
+
+    // The layout is small and overrides are rare, so leaking it is an acceptable price for
+    // handing out the same `'static` references as the global Volta home
+    let home: &'static VoltaHome = Box::leak(Box::new(home));
+    let previous = VOLTA_HOME_OVERRIDE.with(|current| current.replace(Some(home)));
+
+    VoltaHomeOverride { previous }
+}
+
+/// The directory that Node images are kept in instead of the Volta home directory, if any
+///
+/// When set, Node images are unpacked into and run from `<root>/node/<version>`, so that large
+/// images can be kept off of a small home partition. The directory is set with `VOLTA_IMAGE_ROOT`
+/// and recorded in the Volta home by `record_image_root`, so later commands (including the
+/// shims) still find the images without the environment variable.
+pub fn image_root_override() -> Fallible<Option<PathBuf>> {
+    volta_home().map(image_root)
+}
+
+/// Records the directory set in `VOLTA_IMAGE_ROOT`, if any, as the image root of the Volta home
+pub fn record_image_root() -> Fallible<()> {
+    let Some(root) = dir_override(VOLTA_IMAGE_ROOT) else {
+        return Ok(());
+    };
+
+    let home = volta_home()?;
+    if recorded_image_root(home).as_ref() == Some(&root) {
+        return Ok(());
+    }
+
+    let file = home.image_root_file();
+    write(file, root.to_string_lossy().as_bytes()).with_context(|| ErrorKind::WriteImageRootError {
+        file: file.to_owned(),
     })
 }
 
+/// The image root of the given layout: `VOLTA_IMAGE_ROOT` if set, otherwise the recorded one
+fn image_root(home: &VoltaHome) -> Option<PathBuf> {
+    dir_override(VOLTA_IMAGE_ROOT).or_else(|| recorded_image_root(home))
+}
+
+/// The image root recorded in the Volta home directory, if any
+fn recorded_image_root(home: &VoltaHome) -> Option<PathBuf> {
+    let contents = read_to_string(home.image_root_file()).ok()?;
+    let root = contents.trim();
+    (!root.is_empty()).then(|| PathBuf::from(root))
+}
+
+/// The directory set in `VOLTA_TMP`, if any
+pub fn tmp_dir_override() -> Option<PathBuf> {
+    dir_override(VOLTA_TMP)
+}
+
+/// The directory set in an environment variable, ignoring an empty value
+fn dir_override(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Lists the unpacked Node images, along with the directory of each, from oldest to newest
+pub fn installed_node_images() -> Fallible<Vec<(Version, PathBuf)>> {
+    installed_images(volta_home()?.node_image_root_dir())
+}
+
+/// Lists the unpacked npm images, along with the directory of each, from oldest to newest
+pub fn installed_npm_images() -> Fallible<Vec<(Version, PathBuf)>> {
+    installed_images(volta_home()?.npm_image_root_dir())
+}
+
+/// Lists the unpacked pnpm images, along with the directory of each, from oldest to newest
+pub fn installed_pnpm_images() -> Fallible<Vec<(Version, PathBuf)>> {
+    installed_images(volta_home()?.pnpm_image_root_dir())
+}
+
+/// Lists the unpacked Yarn images, along with the directory of each, from oldest to newest
+pub fn installed_yarn_images() -> Fallible<Vec<(Version, PathBuf)>> {
+    installed_images(volta_home()?.yarn_image_root_dir())
+}
+
+/// Lists the image directories in `root`, which are named by version
+///
+/// Entries that aren't directories or whose names aren't versions (e.g. OS-generated files) are
+/// skipped.
+pub(crate) fn installed_images(root: &Path) -> Fallible<Vec<(Version, PathBuf)>> {
+    let read_dir_error = || ErrorKind::ReadDirError {
+        dir: root.to_owned(),
+    };
+
+    let mut images = read_dir_streaming(root)
+        .with_context(read_dir_error)?
+        .filter_map(|entry| match entry {
+            Ok((entry, metadata)) if metadata.is_dir() => {
+                parse_version(entry.file_name().to_string_lossy())
+                    .ok()
+                    .map(|version| Ok((version, entry.path())))
+            }
+            Ok(_) => None,
+            Err(error) => Some(Err(error)),
+        })
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(read_dir_error)?;
+
+    images.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(images)
+}
+
 pub fn volta_install<'a>() -> Fallible<&'a VoltaInstall> {
     VOLTA_INSTALL.get_or_try_init(|| {
         let install_dir = match env::var_os("VOLTA_INSTALL_DIR") {
@@ -42,6 +214,75 @@ pub fn volta_install<'a>() -> Fallible<&'a VoltaInstall> {
     })
 }
 
+/// The version of the on-disk layout of the Volta home directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LayoutVersion {
+    /// The Volta home directory doesn't exist yet
+    Missing,
+    /// The Volta home directory exists, but has no layout file (pre-v1 or not yet set up)
+    Untagged,
+    V1,
+    V2,
+    V3,
+    V4,
+}
+
+impl LayoutVersion {
+    /// The layout version expected by this version of Volta
+    pub const CURRENT: LayoutVersion = LayoutVersion::V4;
+}
+
+impl fmt::Display for LayoutVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LayoutVersion::Missing => "missing",
+            LayoutVersion::Untagged => "untagged",
+            LayoutVersion::V1 => "v1",
+            LayoutVersion::V2 => "v2",
+            LayoutVersion::V3 => "v3",
+            LayoutVersion::V4 => "v4",
+        })
+    }
+}
+
+/// Detects the layout version of the Volta home directory, using the layout marker files
+pub fn detect_version() -> Fallible<LayoutVersion> {
+    Ok(detect_version_in(volta_home()?.root()))
+}
+
+fn detect_version_in(home: &Path) -> LayoutVersion {
+    let home = home.to_owned();
+
+    // Check the newest layouts first, since an interrupted migration may leave older marker files
+    if v4::VoltaHome::new(home.clone()).layout_file().exists() {
+        LayoutVersion::V4
+    } else if v3::VoltaHome::new(home.clone()).layout_file().exists() {
+        LayoutVersion::V3
+    } else if v2::VoltaHome::new(home.clone()).layout_file().exists() {
+        LayoutVersion::V2
+    } else if v1::VoltaHome::new(home.clone()).layout_file().exists() {
+        LayoutVersion::V1
+    } else if home.exists() {
+        LayoutVersion::Untagged
+    } else {
+        LayoutVersion::Missing
+    }
+}
+
+/// Ensures that the Volta home directory uses the current layout
+///
+/// Returns an error pointing to the migration if the directory uses an older layout, so that we
+/// don't fail later on with a confusing error while reading from the wrong location.
+pub fn ensure_current_layout() -> Fallible<()> {
+    match detect_version()? {
+        LayoutVersion::CURRENT => Ok(()),
+        version => Err(ErrorKind::LayoutMigrationRequired {
+            version: version.to_string(),
+        }
+        .into()),
+    }
+}
+
 /// Determine the binary install directory from the currently running executable
 ///
 /// The volta-shim and volta binaries will be installed in the same location, so we can use the
@@ -57,3 +298,150 @@ fn default_install_dir() -> Fallible<PathBuf> {
         })
         .with_context(|| ErrorKind::NoInstallDir)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_detect_version_missing() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            detect_version_in(&dir.path().join("volta")),
+            LayoutVersion::Missing
+        );
+    }
+
+    #[test]
+    fn test_detect_version_untagged() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(detect_version_in(dir.path()), LayoutVersion::Untagged);
+    }
+
+    #[test]
+    fn test_detect_version_tagged() {
+        let dir = tempfile::tempdir().unwrap();
+
+        File::create(dir.path().join("layout.v2")).unwrap();
+        assert_eq!(detect_version_in(dir.path()), LayoutVersion::V2);
+
+        File::create(dir.path().join("layout.v3")).unwrap();
+        assert_eq!(detect_version_in(dir.path()), LayoutVersion::V3);
+
+        File::create(dir.path().join("layout.v4")).unwrap();
+        assert_eq!(detect_version_in(dir.path()), LayoutVersion::CURRENT);
+    }
+
+    #[test]
+    fn test_installed_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = override_volta_home(dir.path().to_owned());
+        let home = volta_home().unwrap();
+
+        for version in ["20.5.1", "8.9.4", "16.20.2"] {
+            std::fs::create_dir_all(home.node_image_dir(version)).unwrap();
+        }
+        std::fs::create_dir_all(home.node_image_root_dir().join("not-a-version")).unwrap();
+        File::create(home.node_image_root_dir().join(".DS_Store")).unwrap();
+        std::fs::create_dir_all(home.yarn_image_dir("1.22.19")).unwrap();
+
+        let images = installed_node_images().unwrap();
+        let versions: Vec<String> = images.iter().map(|(v, _)| v.to_string()).collect();
+        assert_eq!(versions, ["8.9.4", "16.20.2", "20.5.1"]);
+        assert_eq!(images[0].1, home.node_image_dir("8.9.4"));
+
+        assert_eq!(
+            installed_yarn_images().unwrap(),
+            [(
+                Version::parse("1.22.19").unwrap(),
+                home.yarn_image_dir("1.22.19")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_node_image_root_override() {
+        let home = VoltaHome::new(PathBuf::from("/home/user/.volta"))
+            .with_node_image_root(PathBuf::from("/mnt/images/node"));
+
+        assert_eq!(
+            home.node_image_dir("20.5.1"),
+            PathBuf::from("/mnt/images/node/20.5.1")
+        );
+        // Everything else stays in the Volta home directory
+        assert_eq!(
+            home.default_platform_file(),
+            Path::new("/home/user/.volta/tools/user/platform.json")
+        );
+    }
+
+    #[test]
+    fn test_recorded_image_root() {
+        let home_dir = tempfile::tempdir().unwrap();
+        std::fs::write(home_dir.path().join("image-root"), "/mnt/images\n").unwrap();
+
+        let _guard = override_volta_home(home_dir.path().to_owned());
+        assert_eq!(
+            image_root_override().unwrap(),
+            Some(PathBuf::from("/mnt/images"))
+        );
+        assert_eq!(
+            volta_home().unwrap().node_image_dir("20.5.1"),
+            PathBuf::from("/mnt/images/node/20.5.1")
+        );
+    }
+
+    #[test]
+    fn test_tmp_and_inventory_overrides() {
+        let home = VoltaHome::new(PathBuf::from("/home/user/.volta"))
+            .with_tmp_dir(PathBuf::from("/ci/job-1/tmp"))
+            .with_inventory_dir(PathBuf::from("/ci/job-1/inventory"));
+
+        assert_eq!(home.tmp_dir(), Path::new("/ci/job-1/tmp"));
+        assert_eq!(
+            home.node_inventory_dir(),
+            Path::new("/ci/job-1/inventory/node")
+        );
+        assert_eq!(
+            home.yarn_inventory_dir(),
+            Path::new("/ci/job-1/inventory/yarn")
+        );
+        assert_eq!(
+            home.node_npm_version_file("20.5.1"),
+            PathBuf::from("/ci/job-1/inventory/node/node-v20.5.1-npm")
+        );
+        // The images are still shared
+        assert_eq!(
+            home.node_image_dir("20.5.1"),
+            PathBuf::from("/home/user/.volta/tools/image/node/20.5.1")
+        );
+    }
+
+    #[test]
+    fn test_override_volta_home() {
+        let outer = tempfile::tempdir().unwrap();
+        let inner = tempfile::tempdir().unwrap();
+
+        let outer_guard = override_volta_home(outer.path().to_owned());
+        assert_eq!(volta_home().unwrap().root(), outer.path());
+
+        {
+            let _inner_guard = override_volta_home(inner.path().to_owned());
+            assert_eq!(volta_home().unwrap().root(), inner.path());
+        }
+
+        // Dropping the nested guard restores the outer override
+        assert_eq!(volta_home().unwrap().root(), outer.path());
+        drop(outer_guard);
+        assert_ne!(volta_home().unwrap().root(), outer.path());
+    }
+
+    #[test]
+    fn test_older_layouts_sort_first() {
+        assert!(LayoutVersion::Untagged < LayoutVersion::V1);
+        assert!(LayoutVersion::V3 < LayoutVersion::CURRENT);
+    }
+}
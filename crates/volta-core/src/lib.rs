@@ -9,10 +9,13 @@ pub mod inventory;
 pub mod layout;
 pub mod log;
 pub mod monitor;
+mod npmrc;
 pub mod platform;
+pub mod profile;
 pub mod project;
 pub mod run;
 pub mod session;
+pub mod shell;
 pub mod shim;
 pub mod signal;
 pub mod style;
@@ -20,5 +23,6 @@ pub mod sync;
 pub mod tool;
 pub mod toolchain;
 pub mod version;
+pub mod warning;
 
 const VOLTA_FEATURE_PNPM: &str = "VOLTA_FEATURE_PNPM";
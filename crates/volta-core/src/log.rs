@@ -1,12 +1,17 @@
 //! This module provides a custom Logger implementation for use with the `log` crate
-use console::style;
-use log::{trace, Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use chrono::Local;
+use console::{strip_ansi_codes, style};
+use fs_utils::ensure_containing_dir_exists;
+use log::{trace, warn, Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
 use std::env;
 use std::fmt::Display;
-use std::io::IsTerminal;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use textwrap::{fill, Options, WordSplitter};
 
-use crate::style::text_width;
+use crate::style::{is_terminal, text_width};
 
 const ERROR_PREFIX: &str = "error:";
 const WARNING_PREFIX: &str = "warning:";
@@ -15,6 +20,10 @@ const SHIM_WARNING_PREFIX: &str = "Volta warning:";
 const MIGRATION_ERROR_PREFIX: &str = "Volta update error:";
 const MIGRATION_WARNING_PREFIX: &str = "Volta update warning:";
 const VOLTA_LOGLEVEL: &str = "VOLTA_LOGLEVEL";
+/// Environment variable with the path of a file to append log records to
+const VOLTA_LOG_FILE: &str = "VOLTA_LOG_FILE";
+/// Environment variable with the level of records written to the log file (default: debug)
+const VOLTA_LOG_FILE_LEVEL: &str = "VOLTA_LOG_FILE_LEVEL";
 const ALLOWED_PREFIXES: [&str; 5] = [
     "volta",
     "archive",
@@ -48,21 +57,63 @@ pub enum LogVerbosity {
 pub struct Logger {
     context: LogContext,
     level: LevelFilter,
+    file: Option<LogFile>,
+}
+
+/// A file that log records are appended to, in addition to the console
+///
+/// The file has its own level, so that it can capture more detail than is shown on the console.
+struct LogFile {
+    file: Mutex<File>,
+    level: LevelFilter,
+}
+
+impl LogFile {
+    fn open(path: &Path, level: LevelFilter) -> io::Result<Self> {
+        ensure_containing_dir_exists(&path)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(LogFile {
+            file: Mutex::new(file),
+            level,
+        })
+    }
+
+    fn write(&self, record: &Record) {
+        if let Ok(mut file) = self.file.lock() {
+            // Failing to write to the log file shouldn't interrupt the command being logged
+            let _ = writeln!(
+                file,
+                "{} [{}] {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                record.level(),
+                strip_ansi_codes(&record.args().to_string())
+            );
+        }
+    }
 }
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.level
+        metadata.level() <= self.max_level()
     }
 
     fn log(&self, record: &Record) {
-        let level_allowed = self.enabled(record.metadata());
-
         let is_valid_target = ALLOWED_PREFIXES
             .iter()
             .any(|prefix| record.target().starts_with(prefix));
 
-        if level_allowed && is_valid_target {
+        if !is_valid_target {
+            return;
+        }
+
+        if let Some(file) = &self.file {
+            if record.level() <= file.level {
+                file.write(record);
+            }
+        }
+
+        if record.level() <= self.level {
             match record.level() {
                 Level::Error => self.log_error(record.args()),
                 Level::Warn => self.log_warning(record.args()),
@@ -75,17 +126,45 @@ impl Log for Logger {
         }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.file.lock() {
+                let _ = file.flush();
+            }
+        }
+    }
 }
 
 impl Logger {
     /// Initialize the global logger with a Logger instance
     /// Will use the requested level of Verbosity
     /// If set to Default, will use the environment to determine the level of verbosity
+    ///
+    /// If `VOLTA_LOG_FILE` is set, records are also appended to that file, at the level given by
+    /// `VOLTA_LOG_FILE_LEVEL` (debug by default) regardless of the console verbosity.
     pub fn init(context: LogContext, verbosity: LogVerbosity) -> Result<(), SetLoggerError> {
-        let logger = Logger::new(context, verbosity);
-        log::set_max_level(logger.level);
+        let mut logger = Logger::new(context, verbosity);
+
+        let file_error = match env::var_os(VOLTA_LOG_FILE) {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                match LogFile::open(&path, file_level_from_env()) {
+                    Ok(file) => {
+                        logger.file = Some(file);
+                        None
+                    }
+                    Err(error) => Some((path, error)),
+                }
+            }
+            None => None,
+        };
+
+        log::set_max_level(logger.max_level());
         log::set_boxed_logger(Box::new(logger))?;
+
+        if let Some((path, error)) = file_error {
+            warn!("Could not open log file '{}': {}", path.display(), error);
+        }
         Ok(())
     }
 
@@ -97,7 +176,19 @@ impl Logger {
             LogVerbosity::VeryVerbose => LevelFilter::Trace,
         };
 
-        Logger { context, level }
+        Logger {
+            context,
+            level,
+            file: None,
+        }
+    }
+
+    /// The most verbose level that is written anywhere, either to the console or the log file
+    fn max_level(&self) -> LevelFilter {
+        match &self.file {
+            Some(file) => self.level.max(file.level),
+            None => self.level,
+        }
     }
 
     fn log_error<D>(&self, message: &D)
@@ -163,7 +254,7 @@ fn level_from_env() -> LevelFilter {
         .ok()
         .and_then(|level| level.to_uppercase().parse().ok())
         .unwrap_or_else(|| {
-            if std::io::stdout().is_terminal() {
+            if is_terminal() {
                 trace!("using fallback log level (info)");
                 LevelFilter::Info
             } else {
@@ -172,5 +263,46 @@ fn level_from_env() -> LevelFilter {
         })
 }
 
+/// Determines the logging level for the log file based on the environment
+/// If VOLTA_LOG_FILE_LEVEL is set to a valid level, we use that, otherwise Debug
+fn file_level_from_env() -> LevelFilter {
+    env::var(VOLTA_LOG_FILE_LEVEL)
+        .ok()
+        .and_then(|level| level.to_uppercase().parse().ok())
+        .unwrap_or(LevelFilter::Debug)
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_file_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("volta.log");
+
+        let mut logger = Logger::new(LogContext::Volta, LogVerbosity::Quiet);
+        logger.file = Some(LogFile::open(&path, LevelFilter::Debug).unwrap());
+        assert_eq!(logger.max_level(), LevelFilter::Debug);
+
+        let log = |target: &str, level: Level, message: &str| {
+            logger.log(
+                &Record::builder()
+                    .target(target)
+                    .level(level)
+                    .args(format_args!("{}", message))
+                    .build(),
+            )
+        };
+        log("volta_core::tool", Level::Debug, "debug detail");
+        log("volta_core::tool", Level::Trace, "too verbose");
+        log("attohttpc", Level::Debug, "not from volta");
+        logger.flush();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("[DEBUG] debug detail"));
+        assert!(!contents.contains("too verbose"));
+        assert!(!contents.contains("not from volta"));
+        assert_eq!(contents.lines().count(), 1);
+    }
+}
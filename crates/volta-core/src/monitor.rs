@@ -11,21 +11,22 @@ use crate::event::Event;
 
 /// Send event to the spawned command process
 // if hook command is not configured, this is not called
-pub fn send_events(command: &str, events: &[Event]) {
+pub fn send_events(command: &str, events: &[Event]) -> Option<Child> {
     match serde_json::to_string_pretty(&events) {
         Ok(events_json) => {
             let tempfile_path = env::var_os("VOLTA_WRITE_EVENTS_FILE")
                 .and_then(|_| write_events_file(events_json.clone()));
-            if let Some(ref mut child_process) = spawn_process(command, tempfile_path) {
-                if let Some(ref mut p_stdin) = child_process.stdin.as_mut() {
-                    if let Err(error) = writeln!(p_stdin, "{}", events_json) {
-                        debug!("Could not write events to executable stdin: {:?}", error);
-                    }
+            let mut child_process = spawn_process(command, tempfile_path)?;
+            if let Some(ref mut p_stdin) = child_process.stdin.as_mut() {
+                if let Err(error) = writeln!(p_stdin, "{}", events_json) {
+                    debug!("Could not write events to executable stdin: {:?}", error);
                 }
             }
+            Some(child_process)
         }
         Err(error) => {
             debug!("Could not serialize events data to JSON: {:?}", error);
+            None
         }
     }
 }
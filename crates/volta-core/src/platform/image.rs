@@ -1,16 +1,21 @@
+use std::env;
 use std::ffi::OsString;
 use std::path::PathBuf;
 
-use super::{build_path_error, Sourced};
+use super::{build_path_error, PlatformNode, Sourced, System};
 use crate::error::{Context, Fallible};
-use crate::layout::volta_home;
+use crate::layout::{env_paths, volta_home};
 use crate::tool::load_default_npm_version;
 use node_semver::Version;
 
+/// Environment variable that, when set, runs tools with a clean `PATH` containing only the
+/// Volta-managed tools and the system essentials, rather than the inherited `PATH`
+pub const VOLTA_CLEAN_ENV: &str = "VOLTA_CLEAN_ENV";
+
 /// A platform image.
 pub struct Image {
     /// The pinned version of Node.
-    pub node: Sourced<Version>,
+    pub node: Sourced<PlatformNode>,
     /// The custom version of npm, if any. `None` represents using the npm that is bundled with Node
     pub npm: Option<Sourced<Version>>,
     /// The pinned version of pnpm, if any.
@@ -40,32 +45,73 @@ impl Image {
         }
 
         // Add Node path to the bins last, so that any custom version of npm will be earlier in the PATH
-        let node_str = self.node.value.to_string();
-        bins.push(home.node_image_bin_dir(&node_str));
+        // The system Node isn't in an image, it is found on the PATH outside of Volta instead
+        if let PlatformNode::Version(node) = &self.node.value {
+            bins.push(home.node_image_bin_dir(&node.to_string()));
+        }
         Ok(bins)
     }
 
     /// Produces a modified version of the current `PATH` environment variable that
     /// will find toolchain executables (Node, npm, pnpm, Yarn) in the installation directories
     /// for the given versions instead of in the Volta shim directory.
+    ///
+    /// When the image uses the system Node, the Volta directories are removed from the PATH, so
+    /// that `node` resolves to the system install rather than back to the shim.
+    ///
+    /// When `VOLTA_CLEAN_ENV` is set, the inherited `PATH` is discarded entirely, see
+    /// `Image::clean_path`.
     pub fn path(&self) -> Fallible<OsString> {
+        if env::var_os(VOLTA_CLEAN_ENV).is_some() {
+            return self.clean_path();
+        }
+
         let old_path = envoy::path().unwrap_or_else(|| envoy::Var::from(""));
+        let mut new_path = old_path.split();
+
+        if self.node.value.is_system() {
+            for remove_path in env_paths()? {
+                new_path = new_path.remove(remove_path);
+            }
+        }
 
-        old_path
-            .split()
+        new_path
             .prefix(self.bins()?)
             .join()
             .with_context(build_path_error)
     }
 
+    /// Produces a `PATH` containing only the toolchain executables for the given versions and the
+    /// essential system directories, ignoring the current `PATH` environment variable.
+    ///
+    /// When the image uses the system Node, the directory containing it is included as well.
+    fn clean_path(&self) -> Fallible<OsString> {
+        let mut paths = self.bins()?;
+
+        if self.node.value.is_system() {
+            if let Some(dir) = System::node_path()?.parent() {
+                paths.push(dir.to_owned());
+            }
+        }
+
+        paths.extend(System::essential_paths());
+
+        env::join_paths(paths).with_context(build_path_error)
+    }
+
     /// Determines the sourced version of npm that will be available, resolving the version bundled with Node, if needed
-    pub fn resolve_npm(&self) -> Fallible<Sourced<Version>> {
-        match &self.npm {
-            Some(npm) => Ok(npm.clone()),
-            None => load_default_npm_version(&self.node.value).map(|npm| Sourced {
-                value: npm,
-                source: self.node.source,
+    ///
+    /// Returns `None` if the npm bundled with the system Node is used, since its version isn't known.
+    pub fn resolve_npm(&self) -> Fallible<Option<Sourced<Version>>> {
+        match (&self.npm, &self.node.value) {
+            (Some(npm), _) => Ok(Some(npm.clone())),
+            (None, PlatformNode::Version(node)) => load_default_npm_version(node).map(|npm| {
+                Some(Sourced {
+                    value: npm,
+                    source: self.node.source,
+                })
             }),
+            (None, PlatformNode::System) => Ok(None),
         }
     }
 }
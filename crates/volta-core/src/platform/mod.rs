@@ -1,11 +1,17 @@
 use std::env;
 use std::fmt;
+use std::str::FromStr;
 
-use crate::error::{ErrorKind, Fallible};
+use crate::error::{ErrorKind, Fallible, VoltaError};
 use crate::session::Session;
-use crate::tool::{Node, Npm, Pnpm, Yarn};
+use crate::style::tool_version;
+use crate::tool::{load_default_npm_version, Node, Npm, Pnpm, Yarn};
+use crate::version::parse_version;
+use crate::warning::Warning;
 use crate::VOLTA_FEATURE_PNPM;
+use log::debug;
 use node_semver::Version;
+use serde::{Deserialize, Serialize};
 
 mod image;
 mod system;
@@ -14,8 +20,12 @@ mod system;
 #[cfg(test)]
 mod tests;
 
-pub use image::Image;
-pub use system::System;
+pub use image::{Image, VOLTA_CLEAN_ENV};
+pub use system::{System, SYSTEM_NODE};
+
+/// Set to `0` to fall back to the default platform when a project's pinned Node can't be fetched,
+/// rather than failing
+const VOLTA_STRICT_PIN: &str = "VOLTA_STRICT_PIN";
 
 /// The source with which a version is associated
 #[derive(Clone, Copy)]
@@ -152,11 +162,87 @@ impl<T> From<InheritOption<T>> for Option<T> {
     }
 }
 
+/// The Node of a platform, either a version managed by Volta or the system Node
+#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PlatformNode {
+    /// A version of Node that Volta fetches and runs from its image directory
+    Version(Version),
+    /// Whichever Node is found on the PATH outside of Volta (`node@system`)
+    ///
+    /// Volta doesn't manage or inspect the system Node, so there are some caveats to using it:
+    ///
+    /// - Whichever `node` comes first on the PATH (outside of Volta) is used at run time, so the
+    ///   effective version can change without Volta knowing about it.
+    /// - The npm bundled with the system Node is used unless a custom npm is set, and its version
+    ///   isn't known ahead of time, so checks that depend on it (e.g. for `npx`) are skipped.
+    /// - Global packages installed while using the system Node run with whatever the system Node
+    ///   is at the time, rather than the version they were installed with.
+    System,
+}
+
+impl PlatformNode {
+    /// The version of Node, if it is managed by Volta
+    pub fn version(&self) -> Option<&Version> {
+        match self {
+            PlatformNode::Version(version) => Some(version),
+            PlatformNode::System => None,
+        }
+    }
+
+    /// Whether this is the system Node
+    pub fn is_system(&self) -> bool {
+        matches!(self, PlatformNode::System)
+    }
+}
+
+impl FromStr for PlatformNode {
+    type Err = VoltaError;
+
+    /// Parses a Node version, accepting `system` for the system Node
+    fn from_str(version: &str) -> Fallible<Self> {
+        if version.trim() == SYSTEM_NODE {
+            Ok(PlatformNode::System)
+        } else {
+            parse_version(version).map(PlatformNode::Version)
+        }
+    }
+}
+
+impl From<Version> for PlatformNode {
+    fn from(version: Version) -> Self {
+        PlatformNode::Version(version)
+    }
+}
+
+impl TryFrom<String> for PlatformNode {
+    type Error = VoltaError;
+
+    fn try_from(version: String) -> Fallible<Self> {
+        version.parse()
+    }
+}
+
+impl From<PlatformNode> for String {
+    fn from(node: PlatformNode) -> String {
+        node.to_string()
+    }
+}
+
+impl fmt::Display for PlatformNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlatformNode::Version(version) => version.fmt(f),
+            PlatformNode::System => f.write_str(SYSTEM_NODE),
+        }
+    }
+}
+
 #[derive(Clone, PartialOrd, Ord, PartialEq, Eq)]
 #[cfg_attr(test, derive(Debug))]
 /// Represents the specification of a single Platform, regardless of the source
 pub struct PlatformSpec {
-    pub node: Version,
+    pub node: PlatformNode,
     pub npm: Option<Version>,
     pub pnpm: Option<Version>,
     pub yarn: Option<Version>,
@@ -192,12 +278,98 @@ impl PlatformSpec {
             yarn: self.yarn.clone().map(Sourced::with_binary),
         }
     }
+
+    /// Merge this PlatformSpec over a default PlatformSpec
+    ///
+    /// Node is always taken from this instance, while npm, pnpm, and Yarn fall back to the
+    /// value from `default` if they aren't set here.
+    pub fn merged_with(&self, default: &PlatformSpec) -> PlatformSpec {
+        PlatformSpec {
+            node: self.node.clone(),
+            npm: self.npm.clone().or_else(|| default.npm.clone()),
+            pnpm: self.pnpm.clone().or_else(|| default.pnpm.clone()),
+            yarn: self.yarn.clone().or_else(|| default.yarn.clone()),
+        }
+    }
+
+    /// Lists the tools that differ between this PlatformSpec and `other`
+    ///
+    /// Each change describes how to get from this platform to `other`, in the order node, npm,
+    /// pnpm, Yarn. Tools that are the same in both are omitted.
+    pub fn diff(&self, other: &PlatformSpec) -> Vec<FieldChange> {
+        let display = |version: &Option<Version>| version.as_ref().map(Version::to_string);
+        let fields = [
+            (
+                "node",
+                Some(self.node.to_string()),
+                Some(other.node.to_string()),
+            ),
+            ("npm", display(&self.npm), display(&other.npm)),
+            ("pnpm", display(&self.pnpm), display(&other.pnpm)),
+            ("yarn", display(&self.yarn), display(&other.yarn)),
+        ];
+
+        fields
+            .into_iter()
+            .filter_map(|(tool, from, to)| match (from, to) {
+                (None, Some(version)) => Some(FieldChange::Added { tool, version }),
+                (Some(version), None) => Some(FieldChange::Removed { tool, version }),
+                (Some(from), Some(to)) if from != to => {
+                    Some(FieldChange::Changed { tool, from, to })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A difference in a single tool between two `PlatformSpec`s
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldChange {
+    /// The tool is only set in the second platform
+    Added { tool: &'static str, version: String },
+    /// The tool is only set in the first platform
+    Removed { tool: &'static str, version: String },
+    /// The tool is set to different versions in the two platforms
+    Changed {
+        tool: &'static str,
+        from: String,
+        to: String,
+    },
+}
+
+impl FieldChange {
+    /// The name of the tool that changed
+    pub fn tool(&self) -> &'static str {
+        match self {
+            FieldChange::Added { tool, .. }
+            | FieldChange::Removed { tool, .. }
+            | FieldChange::Changed { tool, .. } => tool,
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    /// Describes the change, e.g. `added yarn@1.22.19` or `changed node from 18.17.0 to 20.11.1`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldChange::Added { tool, version } => {
+                write!(f, "added {}", tool_version(tool, version))
+            }
+            FieldChange::Removed { tool, version } => {
+                write!(f, "removed {}", tool_version(tool, version))
+            }
+            FieldChange::Changed { tool, from, to } => {
+                write!(f, "changed {} from {} to {}", tool, from, to)
+            }
+        }
+    }
 }
 
 /// Represents a (maybe) platform with values from the command line
 #[derive(Clone)]
 pub struct CliPlatform {
-    pub node: Option<Version>,
+    pub node: Option<PlatformNode>,
     pub npm: InheritOption<Version>,
     pub pnpm: InheritOption<Version>,
     pub yarn: InheritOption<Version>,
@@ -233,7 +405,7 @@ impl From<CliPlatform> for Option<Platform> {
 /// Represents a real Platform, with Versions pulled from one or more `PlatformSpec`s
 #[derive(Clone)]
 pub struct Platform {
-    pub node: Sourced<Version>,
+    pub node: Sourced<PlatformNode>,
     pub npm: Option<Sourced<Version>>,
     pub pnpm: Option<Sourced<Version>>,
     pub yarn: Option<Sourced<Version>>,
@@ -274,8 +446,37 @@ impl Platform {
     /// Check out a `Platform` into a fully-realized `Image`
     ///
     /// This will ensure that all necessary tools are fetched and available for execution
+    ///
+    /// If the project's pinned Node can't be downloaded (e.g. the version hasn't been released)
+    /// and `VOLTA_STRICT_PIN` is `0`, this warns and checks out the default platform instead.
     pub fn checkout(self, session: &mut Session) -> Fallible<Image> {
-        Node::new(self.node.value.clone()).ensure_fetched(session)?;
+        match &self.node.value {
+            PlatformNode::System => {
+                System::node_path()?;
+            }
+            PlatformNode::Version(version) => {
+                if let Err(error) = Node::new(version.clone()).ensure_available(session) {
+                    let lenient = matches!(self.node.source, Source::Project)
+                        && matches!(error.kind(), ErrorKind::DownloadToolNetworkError { .. })
+                        && !is_strict_pin();
+                    let fallback = if lenient {
+                        session.default_platform()?.map(PlatformSpec::as_default)
+                    } else {
+                        None
+                    };
+                    let Some(fallback) = fallback else {
+                        return Err(error);
+                    };
+
+                    debug!("Could not fetch the pinned Node version: {}", error);
+                    session.warn(Warning::PinnedNodeUnavailable {
+                        pinned: version.clone(),
+                        default: fallback.node.value.clone(),
+                    });
+                    return fallback.checkout(session);
+                }
+            }
+        }
 
         if let Some(Sourced { value: version, .. }) = &self.npm {
             Npm::new(version.clone()).ensure_fetched(session)?;
@@ -303,6 +504,74 @@ impl Platform {
     }
 }
 
+impl fmt::Display for Platform {
+    /// Describes the platform as each tool's version and where it came from, e.g.
+    /// `node@20.5.1 (default), yarn@3.6.0 (project)`, omitting any tools that aren't set
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |name: &str, value: &dyn fmt::Display, source: Source| {
+            format!("{} ({})", tool_version(name, value), source)
+        };
+
+        let mut described = vec![describe("node", &self.node.value, self.node.source)];
+        let tools = [
+            ("npm", self.npm.as_ref()),
+            ("pnpm", self.pnpm.as_ref()),
+            ("yarn", self.yarn.as_ref()),
+        ];
+        described.extend(tools.into_iter().filter_map(|(name, version)| {
+            version.map(|Sourced { value, source }| describe(name, value, *source))
+        }));
+
+        f.write_str(&described.join(", "))
+    }
+}
+
+/// The tool versions in effect for the current directory, along with where each came from
+///
+/// This is determined from the project and default platforms alone, without fetching or running
+/// any of the tools.
+#[derive(Clone, Default)]
+pub struct CurrentTools {
+    pub node: Option<Sourced<PlatformNode>>,
+    /// The npm version, which is the version bundled with Node if no other version is set
+    ///
+    /// The bundled version is only known once that version of Node has been fetched.
+    pub npm: Option<Sourced<Version>>,
+    pub pnpm: Option<Sourced<Version>>,
+    pub yarn: Option<Sourced<Version>>,
+}
+
+impl From<Option<Platform>> for CurrentTools {
+    fn from(platform: Option<Platform>) -> Self {
+        let Some(platform) = platform else {
+            return CurrentTools::default();
+        };
+
+        // The npm bundled with the system Node isn't known
+        let npm = platform.npm.or_else(|| {
+            let node = platform.node.value.version()?;
+            load_default_npm_version(node)
+                .map(|npm| Sourced {
+                    value: npm,
+                    source: platform.node.source,
+                })
+                .ok()
+        });
+
+        CurrentTools {
+            node: Some(platform.node),
+            npm,
+            pnpm: platform.pnpm,
+            yarn: platform.yarn,
+        }
+    }
+}
+
+/// Whether a project's pinned Node must be used, rather than falling back to the default platform
+fn is_strict_pin() -> bool {
+    env::var_os(VOLTA_STRICT_PIN).map_or(true, |value| value != "0")
+}
+
 fn build_path_error() -> ErrorKind {
     ErrorKind::BuildPathError
 }
@@ -1,9 +1,14 @@
+use std::env;
 use std::ffi::OsString;
+use std::path::PathBuf;
 
 use super::build_path_error;
-use crate::error::{Context, Fallible};
+use crate::error::{Context, ErrorKind, Fallible};
 use crate::layout::env_paths;
 
+/// The pseudo-version that is written in place of a Node version to defer Node to the system
+pub const SYSTEM_NODE: &str = "system";
+
 /// A lightweight namespace type representing the system environment, i.e. the environment
 /// with Volta removed.
 pub struct System;
@@ -22,4 +27,38 @@ impl System {
 
         new_path.join().with_context(build_path_error)
     }
+
+    /// The minimal set of system directories needed for basic commands to work, used in place of
+    /// the inherited `PATH` when running tools in a clean environment
+    #[cfg(unix)]
+    pub fn essential_paths() -> Vec<PathBuf> {
+        ["/usr/bin", "/bin", "/usr/sbin", "/sbin"]
+            .iter()
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    /// The minimal set of system directories needed for basic commands to work, used in place of
+    /// the inherited `PATH` when running tools in a clean environment
+    #[cfg(windows)]
+    pub fn essential_paths() -> Vec<PathBuf> {
+        let root = env::var_os("SystemRoot").map_or_else(|| "C:\\Windows".into(), PathBuf::from);
+        let system32 = root.join("System32");
+
+        vec![
+            system32.clone(),
+            root,
+            system32.join("Wbem"),
+            system32.join("WindowsPowerShell").join("v1.0"),
+        ]
+    }
+
+    /// Locates the `node` executable outside of Volta
+    pub fn node_path() -> Fallible<PathBuf> {
+        let path = System::path()?;
+        let cwd = env::current_dir().ok();
+
+        which::which_in("node", Some(path), cwd.unwrap_or_default())
+            .with_context(|| ErrorKind::NoSystemNode)
+    }
 }
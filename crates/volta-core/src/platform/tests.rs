@@ -3,7 +3,6 @@ use crate::layout::volta_home;
 #[cfg(windows)]
 use crate::layout::volta_install;
 use node_semver::Version;
-#[cfg(windows)]
 use std::path::PathBuf;
 
 // Since unit tests are run in parallel, tests that modify the PATH environment variable are subject to race conditions
@@ -12,6 +11,8 @@ use std::path::PathBuf;
 fn test_paths() {
     test_image_path();
     test_system_path();
+    test_system_node_image_path();
+    test_clean_image_path();
 }
 
 #[cfg(unix)]
@@ -62,7 +63,7 @@ fn test_image_path() {
     let v771 = Version::parse("7.7.1").unwrap();
 
     let only_node = Image {
-        node: Sourced::with_default(v123.clone()),
+        node: Sourced::with_default(v123.clone().into()),
         npm: None,
         pnpm: None,
         yarn: None,
@@ -74,7 +75,7 @@ fn test_image_path() {
     );
 
     let node_npm = Image {
-        node: Sourced::with_default(v123.clone()),
+        node: Sourced::with_default(v123.clone().into()),
         npm: Some(Sourced::with_default(v643.clone())),
         pnpm: None,
         yarn: None,
@@ -86,7 +87,7 @@ fn test_image_path() {
     );
 
     let node_pnpm = Image {
-        node: Sourced::with_default(v123.clone()),
+        node: Sourced::with_default(v123.clone().into()),
         npm: None,
         pnpm: Some(Sourced::with_default(v771.clone())),
         yarn: None,
@@ -98,7 +99,7 @@ fn test_image_path() {
     );
 
     let node_yarn = Image {
-        node: Sourced::with_default(v123.clone()),
+        node: Sourced::with_default(v123.clone().into()),
         npm: None,
         pnpm: None,
         yarn: Some(Sourced::with_default(v457.clone())),
@@ -110,7 +111,7 @@ fn test_image_path() {
     );
 
     let node_npm_pnpm = Image {
-        node: Sourced::with_default(v123.clone()),
+        node: Sourced::with_default(v123.clone().into()),
         npm: Some(Sourced::with_default(v643.clone())),
         pnpm: Some(Sourced::with_default(v771)),
         yarn: None,
@@ -128,7 +129,7 @@ fn test_image_path() {
     );
 
     let node_npm_yarn = Image {
-        node: Sourced::with_default(v123),
+        node: Sourced::with_default(v123.into()),
         npm: Some(Sourced::with_default(v643)),
         pnpm: None,
         yarn: Some(Sourced::with_default(v457)),
@@ -161,6 +162,79 @@ fn test_system_path() {
     );
 }
 
+fn test_system_node_image_path() {
+    #[cfg(unix)]
+    let path_delimiter = ":";
+    #[cfg(windows)]
+    let path_delimiter = ";";
+    let path = build_test_path();
+    std::env::set_var("PATH", path);
+
+    let yarn_bin = volta_home().unwrap().yarn_image_bin_dir("4.5.7");
+    let expected_yarn_bin = yarn_bin.to_str().unwrap();
+    #[cfg(unix)]
+    let expected_system_path = "/usr/bin:/bin";
+    #[cfg(windows)]
+    let expected_system_path = "C:\\\\somebin;D:\\\\ProbramFlies";
+
+    // The system Node is found on the PATH without Volta, so neither the shims nor a Node image
+    // are included, but other tools still come from their images
+    let system_yarn = Image {
+        node: Sourced::with_default(PlatformNode::System),
+        npm: None,
+        pnpm: None,
+        yarn: Some(Sourced::with_default(Version::parse("4.5.7").unwrap())),
+    };
+
+    assert_eq!(
+        system_yarn.path().unwrap().into_string().unwrap(),
+        [expected_yarn_bin, expected_system_path].join(path_delimiter)
+    );
+}
+
+fn test_clean_image_path() {
+    let path = build_test_path();
+    std::env::set_var("PATH", path);
+    std::env::set_var(VOLTA_CLEAN_ENV, "1");
+
+    let node_bin = volta_home().unwrap().node_image_bin_dir("1.2.3");
+    let yarn_bin = volta_home().unwrap().yarn_image_bin_dir("4.5.7");
+
+    let node_yarn = Image {
+        node: Sourced::with_default(Version::parse("1.2.3").unwrap().into()),
+        npm: None,
+        pnpm: None,
+        yarn: Some(Sourced::with_default(Version::parse("4.5.7").unwrap())),
+    };
+
+    // Only the image directories and the system essentials are on the PATH, none of the
+    // inherited entries (including the shims) are kept
+    let clean_path = node_yarn.path().unwrap();
+    let entries: Vec<PathBuf> = std::env::split_paths(&clean_path).collect();
+    let mut expected = vec![yarn_bin, node_bin];
+    expected.extend(System::essential_paths());
+    assert_eq!(entries, expected);
+
+    assert!(!entries.contains(&volta_home().unwrap().shim_dir().to_owned()));
+    #[cfg(windows)]
+    assert!(!entries.contains(&PathBuf::from("D:\\\\ProbramFlies")));
+
+    std::env::remove_var(VOLTA_CLEAN_ENV);
+}
+
+#[test]
+fn test_platform_node() {
+    let system = "system".parse::<PlatformNode>().unwrap();
+    assert!(system.is_system());
+    assert_eq!(system.version(), None);
+    assert_eq!(system.to_string(), "system");
+
+    let version = "v20.5.1".parse::<PlatformNode>().unwrap();
+    assert!(!version.is_system());
+    assert_eq!(version.version(), Some(&Version::from((20, 5, 1))));
+    assert_eq!(version.to_string(), "20.5.1");
+}
+
 mod inherit_option {
     mod map {
         use super::super::super::*;
@@ -213,16 +287,190 @@ mod inherit_option {
     }
 }
 
+mod platform_spec {
+    mod merged_with {
+        use super::super::super::*;
+
+        #[test]
+        fn uses_own_node() {
+            let project = PlatformSpec {
+                node: Version::from((12, 14, 1)).into(),
+                npm: None,
+                pnpm: None,
+                yarn: None,
+            };
+            let default = PlatformSpec {
+                node: Version::from((10, 10, 10)).into(),
+                npm: None,
+                pnpm: None,
+                yarn: None,
+            };
+
+            let merged = project.merged_with(&default);
+
+            assert_eq!(merged.node, PlatformNode::from(Version::from((12, 14, 1))));
+        }
+
+        #[test]
+        fn prefers_own_tools() {
+            let project = PlatformSpec {
+                node: Version::from((12, 14, 1)).into(),
+                npm: Some(Version::from((6, 13, 2))),
+                pnpm: Some(Version::from((7, 7, 1))),
+                yarn: Some(Version::from((1, 17, 0))),
+            };
+            let default = PlatformSpec {
+                node: Version::from((10, 10, 10)).into(),
+                npm: Some(Version::from((5, 6, 3))),
+                pnpm: Some(Version::from((6, 34, 0))),
+                yarn: Some(Version::from((1, 10, 0))),
+            };
+
+            let merged = project.merged_with(&default);
+
+            assert_eq!(merged, project);
+        }
+
+        #[test]
+        fn falls_back_to_default_tools() {
+            let project = PlatformSpec {
+                node: Version::from((12, 14, 1)).into(),
+                npm: None,
+                pnpm: None,
+                yarn: Some(Version::from((1, 17, 0))),
+            };
+            let default = PlatformSpec {
+                node: Version::from((10, 10, 10)).into(),
+                npm: Some(Version::from((5, 6, 3))),
+                pnpm: Some(Version::from((6, 34, 0))),
+                yarn: Some(Version::from((1, 10, 0))),
+            };
+
+            let merged = project.merged_with(&default);
+
+            assert_eq!(merged.node, PlatformNode::from(Version::from((12, 14, 1))));
+            assert_eq!(merged.npm, Some(Version::from((5, 6, 3))));
+            assert_eq!(merged.pnpm, Some(Version::from((6, 34, 0))));
+            assert_eq!(merged.yarn, Some(Version::from((1, 17, 0))));
+        }
+
+        #[test]
+        fn leaves_missing_tools_unset() {
+            let project = PlatformSpec {
+                node: Version::from((12, 14, 1)).into(),
+                npm: None,
+                pnpm: None,
+                yarn: None,
+            };
+            let default = PlatformSpec {
+                node: Version::from((10, 10, 10)).into(),
+                npm: None,
+                pnpm: None,
+                yarn: None,
+            };
+
+            let merged = project.merged_with(&default);
+
+            assert!(merged.npm.is_none());
+            assert!(merged.pnpm.is_none());
+            assert!(merged.yarn.is_none());
+        }
+    }
+}
+
+mod platform_spec_diff {
+    use super::super::*;
+
+    fn spec(node: &str, npm: Option<&str>, pnpm: Option<&str>, yarn: Option<&str>) -> PlatformSpec {
+        let parse = |version: &str| version.parse::<Version>().unwrap();
+        PlatformSpec {
+            node: parse(node).into(),
+            npm: npm.map(parse),
+            pnpm: pnpm.map(parse),
+            yarn: yarn.map(parse),
+        }
+    }
+
+    #[test]
+    fn same_platform_has_no_changes() {
+        let platform = spec("20.11.1", Some("10.4.0"), None, Some("1.22.19"));
+
+        assert!(platform.diff(&platform.clone()).is_empty());
+    }
+
+    #[test]
+    fn node_major_change() {
+        let before = spec("18.17.0", None, None, None);
+        let after = spec("20.11.1", None, None, None);
+
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes,
+            vec![FieldChange::Changed {
+                tool: "node",
+                from: "18.17.0".parse().unwrap(),
+                to: "20.11.1".parse().unwrap(),
+            }]
+        );
+        assert_eq!(
+            changes[0].to_string(),
+            "changed node from 18.17.0 to 20.11.1"
+        );
+    }
+
+    #[test]
+    fn yarn_added() {
+        let before = spec("20.11.1", None, None, None);
+        let after = spec("20.11.1", None, None, Some("1.22.19"));
+
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes,
+            vec![FieldChange::Added {
+                tool: "yarn",
+                version: "1.22.19".parse().unwrap(),
+            }]
+        );
+        assert_eq!(changes[0].to_string(), "added yarn@1.22.19");
+    }
+
+    #[test]
+    fn npm_removed() {
+        let before = spec("20.11.1", Some("10.4.0"), None, None);
+        let after = spec("20.11.1", None, None, None);
+
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes,
+            vec![FieldChange::Removed {
+                tool: "npm",
+                version: "10.4.0".parse().unwrap(),
+            }]
+        );
+        assert_eq!(changes[0].to_string(), "removed npm@10.4.0");
+    }
+
+    #[test]
+    fn lists_changes_in_tool_order() {
+        let before = spec("18.17.0", Some("9.8.1"), Some("8.6.0"), None);
+        let after = spec("20.11.1", None, Some("8.6.0"), Some("1.22.19"));
+
+        let tools: Vec<_> = before.diff(&after).iter().map(FieldChange::tool).collect();
+        assert_eq!(tools, ["node", "npm", "yarn"]);
+    }
+}
+
 mod cli_platform {
+    use super::super::PlatformNode;
     use node_semver::Version;
 
-    const NODE_VERSION: Version = Version {
+    const NODE_VERSION: PlatformNode = PlatformNode::Version(Version {
         major: 12,
         minor: 14,
         patch: 1,
         build: Vec::new(),
         pre_release: Vec::new(),
-    };
+    });
     const NPM_VERSION: Version = Version {
         major: 6,
         minor: 13,
@@ -252,7 +500,7 @@ mod cli_platform {
             };
 
             let base = Platform {
-                node: Sourced::with_default(Version::from((10, 10, 10))),
+                node: Sourced::with_default(Version::from((10, 10, 10)).into()),
                 npm: None,
                 pnpm: None,
                 yarn: None,
@@ -296,7 +544,7 @@ mod cli_platform {
             };
 
             let base = Platform {
-                node: Sourced::with_default(Version::from((10, 10, 10))),
+                node: Sourced::with_default(Version::from((10, 10, 10)).into()),
                 npm: Some(Sourced::with_default(Version::from((5, 6, 3)))),
                 pnpm: None,
                 yarn: None,
@@ -319,7 +567,7 @@ mod cli_platform {
             };
 
             let base = Platform {
-                node: Sourced::with_default(Version::from((10, 10, 10))),
+                node: Sourced::with_default(Version::from((10, 10, 10)).into()),
                 npm: Some(Sourced::with_default(NPM_VERSION)),
                 pnpm: None,
                 yarn: None,
@@ -342,7 +590,7 @@ mod cli_platform {
             };
 
             let base = Platform {
-                node: Sourced::with_default(Version::from((10, 10, 10))),
+                node: Sourced::with_default(Version::from((10, 10, 10)).into()),
                 npm: Some(Sourced::with_default(NPM_VERSION)),
                 pnpm: None,
                 yarn: None,
@@ -363,7 +611,7 @@ mod cli_platform {
             };
 
             let base = Platform {
-                node: Sourced::with_default(Version::from((10, 10, 10))),
+                node: Sourced::with_default(Version::from((10, 10, 10)).into()),
                 npm: None,
                 pnpm: None,
                 yarn: Some(Sourced::with_default(Version::from((1, 10, 3)))),
@@ -386,7 +634,7 @@ mod cli_platform {
             };
 
             let base = Platform {
-                node: Sourced::with_default(Version::from((10, 10, 10))),
+                node: Sourced::with_default(Version::from((10, 10, 10)).into()),
                 npm: None,
                 pnpm: None,
                 yarn: Some(Sourced::with_default(YARN_VERSION)),
@@ -409,7 +657,7 @@ mod cli_platform {
             };
 
             let base = Platform {
-                node: Sourced::with_default(Version::from((10, 10, 10))),
+                node: Sourced::with_default(Version::from((10, 10, 10)).into()),
                 npm: None,
                 pnpm: None,
                 yarn: Some(Sourced::with_default(YARN_VERSION)),
@@ -544,3 +792,36 @@ mod cli_platform {
         }
     }
 }
+
+mod platform {
+    mod display {
+        use super::super::super::*;
+
+        #[test]
+        fn describes_tools_with_sources() {
+            let platform = Platform {
+                node: Sourced::with_project("20.5.1".parse().unwrap()),
+                npm: Some(Sourced::with_default("9.8.0".parse().unwrap())),
+                pnpm: None,
+                yarn: Some(Sourced::with_command_line("3.6.0".parse().unwrap())),
+            };
+
+            assert_eq!(
+                platform.to_string(),
+                "node@20.5.1 (project), npm@9.8.0 (default), yarn@3.6.0 (command-line)"
+            );
+        }
+
+        #[test]
+        fn omits_unset_tools() {
+            let platform = Platform {
+                node: Sourced::with_default("20.5.1".parse().unwrap()),
+                npm: None,
+                pnpm: None,
+                yarn: None,
+            };
+
+            assert_eq!(platform.to_string(), "node@20.5.1 (default)");
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Provides a timing mode, enabled with `VOLTA_PROFILE=1`, which records how long the major
+//! phases of a command take and prints a breakdown when Volta exits.
+
+use std::env;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+const VOLTA_PROFILE: &str = "VOLTA_PROFILE";
+
+static ENABLED: Lazy<bool> =
+    Lazy::new(|| env::var_os(VOLTA_PROFILE).is_some_and(|value| !value.is_empty() && value != "0"));
+static TIMINGS: Lazy<Mutex<Vec<(Phase, Duration)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A phase of a command that is timed when profiling is enabled
+///
+/// Phases may be nested, e.g. unpacking happens while fetching, so their durations can add up to
+/// more than the total time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// Reading the default platform
+    LoadToolchain,
+    /// Finding and reading the current project
+    LoadProject,
+    /// Resolving a version specifier, e.g. from the Node index
+    Resolve,
+    /// Downloading (or loading from the cache) and unpacking a tool that isn't installed yet
+    Fetch,
+    /// Unpacking a tool archive into its image directory
+    Unpack,
+    /// Running the tool, from spawning the process until it exits
+    Spawn,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::LoadToolchain => "toolchain load",
+            Phase::LoadProject => "project load",
+            Phase::Resolve => "resolve",
+            Phase::Fetch => "fetch",
+            Phase::Unpack => "unpack",
+            Phase::Spawn => "spawn",
+        })
+    }
+}
+
+/// Runs `f`, recording how long it took as part of `phase` if profiling is enabled
+pub fn time<T, F>(phase: Phase, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    if !*ENABLED {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    if let Ok(mut timings) = TIMINGS.lock() {
+        timings.push((phase, start.elapsed()));
+    }
+    result
+}
+
+/// Prints the time spent in each phase to stderr, if profiling is enabled
+pub(crate) fn print_report() {
+    if !*ENABLED {
+        return;
+    }
+
+    if let Ok(timings) = TIMINGS.lock() {
+        eprintln!("{}", format_report(&timings));
+    }
+}
+
+/// Formats the total time spent in each phase, in the order that the phases happen
+fn format_report(timings: &[(Phase, Duration)]) -> String {
+    let mut totals: Vec<(Phase, Duration, usize)> = Vec::new();
+    for &(phase, duration) in timings {
+        match totals.iter_mut().find(|(p, _, _)| *p == phase) {
+            Some((_, total, count)) => {
+                *total += duration;
+                *count += 1;
+            }
+            None => totals.push((phase, duration, 1)),
+        }
+    }
+    totals.sort_by_key(|(phase, _, _)| *phase);
+
+    let mut report = String::from("Volta profile:");
+    if totals.is_empty() {
+        report.push_str("\n    (no phases recorded)");
+    }
+    for (phase, total, count) in totals {
+        let ms = total.as_secs_f64() * 1000.0;
+        report.push_str(&format!("\n    {:<16}{:>10.1}ms", phase.to_string(), ms));
+        if count > 1 {
+            report.push_str(&format!(" ({} times)", count));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_report() {
+        let timings = [
+            (Phase::Spawn, Duration::from_millis(120)),
+            (Phase::LoadProject, Duration::from_micros(1500)),
+            (Phase::Resolve, Duration::from_millis(20)),
+            (Phase::Resolve, Duration::from_millis(5)),
+        ];
+
+        assert_eq!(
+            format_report(&timings),
+            "Volta profile:
+    project load           1.5ms
+    resolve               25.0ms (2 times)
+    spawn                120.0ms"
+        );
+        assert_eq!(
+            format_report(&[]),
+            "Volta profile:\n    (no phases recorded)"
+        );
+    }
+}
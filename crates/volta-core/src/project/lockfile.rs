@@ -0,0 +1,222 @@
+//! Provides the `Lockfile` type, which records the exact tool versions resolved for a project
+//! so that every checkout of the project uses an identical toolchain.
+
+use std::env;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::error::{Context, ErrorKind, Fallible, VoltaError};
+use crate::platform::PlatformSpec;
+use crate::style::tool_version;
+use crate::version::option_version_serde;
+use node_semver::Version;
+use serde::{Deserialize, Serialize};
+
+/// The name of the lockfile, stored alongside the project's `package.json`
+pub const LOCKFILE_NAME: &str = "volta.lock.json";
+
+/// Environment variable that freezes the lockfile, making any change to it an error
+///
+/// This is also set by the `--frozen` flag, so that the tools run by Volta see it as well.
+pub const VOLTA_FROZEN: &str = "VOLTA_FROZEN";
+
+/// Whether the lockfile is frozen, so that the locked versions must be used as they are
+pub fn is_frozen() -> bool {
+    env::var_os(VOLTA_FROZEN).is_some()
+}
+
+/// The exact tool versions recorded for a project
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Lockfile {
+    #[serde(
+        default,
+        with = "option_version_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub node: Option<Version>,
+    #[serde(
+        default,
+        with = "option_version_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub npm: Option<Version>,
+    #[serde(
+        default,
+        with = "option_version_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pnpm: Option<Version>,
+    #[serde(
+        default,
+        with = "option_version_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub yarn: Option<Version>,
+}
+
+impl Lockfile {
+    /// Reads the lockfile at the given path, returning `None` if it doesn't exist
+    pub fn from_file_if_exists(file: &Path) -> Fallible<Option<Self>> {
+        match File::open(file) {
+            Err(error) => {
+                if error.kind() == io::ErrorKind::NotFound {
+                    Ok(None)
+                } else {
+                    Err(VoltaError::from_source(
+                        error,
+                        ErrorKind::ReadLockfileError {
+                            file: file.to_owned(),
+                        },
+                    ))
+                }
+            }
+            Ok(lockfile) => serde_json::from_reader(lockfile)
+                .with_context(|| ErrorKind::ParseLockfileError {
+                    file: file.to_owned(),
+                })
+                .map(Some),
+        }
+    }
+
+    /// Writes the lockfile to the given path
+    pub fn write(&self, file: &Path) -> Fallible<()> {
+        let mut contents =
+            serde_json::to_string_pretty(self).with_context(|| ErrorKind::WriteLockfileError {
+                file: file.to_owned(),
+            })?;
+        contents.push('\n');
+
+        std::fs::write(file, contents).with_context(|| ErrorKind::WriteLockfileError {
+            file: file.to_owned(),
+        })
+    }
+
+    /// Describes the first tool whose version differs from another lockfile, if there is one
+    pub fn difference(&self, other: &Lockfile) -> Option<String> {
+        let tools = [
+            ("node", &self.node, &other.node),
+            ("npm", &self.npm, &other.npm),
+            ("pnpm", &self.pnpm, &other.pnpm),
+            ("yarn", &self.yarn, &other.yarn),
+        ];
+
+        tools
+            .into_iter()
+            .find_map(|(tool, locked, other)| match (locked, other) {
+                _ if locked == other => None,
+                (Some(locked), Some(other)) => Some(format!(
+                    "The lockfile has {}, but {} is needed",
+                    tool_version(tool, locked),
+                    tool_version(tool, other)
+                )),
+                (Some(locked), None) => Some(format!(
+                    "The lockfile has {}, but it is no longer needed",
+                    tool_version(tool, locked)
+                )),
+                (None, Some(other)) => Some(format!(
+                    "The lockfile doesn't have {}",
+                    tool_version(tool, other)
+                )),
+                (None, None) => None,
+            })
+    }
+}
+
+impl From<&PlatformSpec> for Lockfile {
+    fn from(platform: &PlatformSpec) -> Self {
+        Lockfile {
+            // The system Node isn't managed by Volta, so there is no version to lock
+            node: platform.node.version().cloned(),
+            npm: platform.npm.clone(),
+            pnpm: platform.pnpm.clone(),
+            yarn: platform.yarn.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let lockfile = Lockfile {
+            node: Some(Version::from((16, 20, 2))),
+            npm: Some(Version::from((8, 19, 4))),
+            pnpm: None,
+            yarn: Some(Version::from((1, 22, 19))),
+        };
+
+        let json = serde_json::to_string(&lockfile).unwrap();
+        assert_eq!(
+            json,
+            r#"{"node":"16.20.2","npm":"8.19.4","yarn":"1.22.19"}"#
+        );
+
+        let parsed: Lockfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, lockfile);
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let parsed: Lockfile = serde_json::from_str(r#"{"node":"v18.17.1"}"#).unwrap();
+
+        assert_eq!(
+            parsed,
+            Lockfile {
+                node: Some(Version::from((18, 17, 1))),
+                npm: None,
+                pnpm: None,
+                yarn: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_difference() {
+        let locked = Lockfile {
+            node: Some(Version::from((16, 20, 2))),
+            npm: None,
+            pnpm: None,
+            yarn: Some(Version::from((1, 22, 19))),
+        };
+
+        assert_eq!(locked.difference(&locked.clone()), None);
+        assert_eq!(
+            locked.difference(&Lockfile {
+                node: Some(Version::from((18, 17, 1))),
+                ..locked.clone()
+            }),
+            Some("The lockfile has node@16.20.2, but node@18.17.1 is needed".into())
+        );
+        assert_eq!(
+            locked.difference(&Lockfile {
+                yarn: None,
+                ..locked.clone()
+            }),
+            Some("The lockfile has yarn@1.22.19, but it is no longer needed".into())
+        );
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+
+        assert_eq!(Lockfile::from_file_if_exists(&path).unwrap(), None);
+
+        let lockfile = Lockfile {
+            node: Some(Version::from((20, 5, 0))),
+            npm: None,
+            pnpm: Some(Version::from((8, 6, 12))),
+            yarn: None,
+        };
+        lockfile.write(&path).unwrap();
+
+        assert_eq!(
+            Lockfile::from_file_if_exists(&path).unwrap(),
+            Some(lockfile)
+        );
+    }
+}
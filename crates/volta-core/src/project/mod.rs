@@ -1,6 +1,7 @@
 //! Provides the `Project` type, which represents a Node project tree in
 //! the filesystem.
 
+use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
 use std::iter::once;
@@ -9,18 +10,28 @@ use std::path::{Path, PathBuf};
 use node_semver::Version;
 use once_cell::unsync::OnceCell;
 
-use crate::error::{Context, ErrorKind, Fallible, VoltaError};
+use crate::error::{Context, ErrorKind, Fallible};
+use crate::fs::read_file;
 use crate::layout::volta_home;
-use crate::platform::PlatformSpec;
+use crate::platform::{PlatformNode, PlatformSpec, SYSTEM_NODE};
+use crate::profile::{time, Phase};
 use crate::tool::BinConfig;
+use crate::version::{VersionSpec, VersionTag};
 use chain_map::ChainMap;
 use indexmap::IndexSet;
 
+mod lockfile;
 mod serial;
 #[cfg(test)]
 mod tests;
+mod validate;
 
+pub use lockfile::{is_frozen, Lockfile, LOCKFILE_NAME, VOLTA_FROZEN};
 use serial::{update_manifest, Manifest, ManifestKey};
+pub use validate::{validate_manifest, ManifestIssue};
+
+/// The file used by nodenv and other version managers to select a project's Node version
+const NODE_VERSION_FILE: &str = ".node-version";
 
 /// A lazily loaded Project
 pub struct LazyProject {
@@ -34,15 +45,36 @@ impl LazyProject {
         }
     }
 
+    /// Loads the project for the specified directory, rather than the current directory
+    #[cfg(test)]
+    pub fn for_dir(dir: PathBuf) -> Fallible<Self> {
+        Ok(LazyProject {
+            project: OnceCell::with_value(Project::for_dir(dir)?),
+        })
+    }
+
     pub fn get(&self) -> Fallible<Option<&Project>> {
-        let project = self.project.get_or_try_init(Project::for_current_dir)?;
+        let project = self
+            .project
+            .get_or_try_init(|| time(Phase::LoadProject, Project::for_current_dir))?;
         Ok(project.as_ref())
     }
 
     pub fn get_mut(&mut self) -> Fallible<Option<&mut Project>> {
-        let _ = self.project.get_or_try_init(Project::for_current_dir)?;
+        let _ = self
+            .project
+            .get_or_try_init(|| time(Phase::LoadProject, Project::for_current_dir))?;
         Ok(self.project.get_mut().unwrap().as_mut())
     }
+
+    pub fn is_loaded(&self) -> bool {
+        self.project.get().is_some()
+    }
+
+    /// Discards the loaded project, so that it is read again the next time it is needed
+    pub fn reset(&mut self) {
+        self.project.take();
+    }
 }
 
 /// A Node project workspace in the filesystem
@@ -52,6 +84,9 @@ pub struct Project {
     workspace_manifests: IndexSet<PathBuf>,
     dependencies: ChainMap<String, String>,
     platform: Option<PlatformSpec>,
+    pinned: PinnedSpecs,
+    env: HashMap<String, String>,
+    lockfile: Option<Lockfile>,
 }
 
 impl Project {
@@ -80,6 +115,7 @@ impl Project {
         let mut dependencies: ChainMap<String, String> = manifest.dependency_maps.collect();
         let mut workspace_manifests = IndexSet::new();
         let mut platform = manifest.platform;
+        let mut env = manifest.env;
         let mut extends = manifest.extends;
 
         // Iterate the `volta.extends` chain, parsing each file in turn
@@ -106,19 +142,54 @@ impl Project {
                 (None, None) => None,
             };
 
+            // As with the platform, values from the extending manifest take precedence
+            for (name, value) in manifest.env {
+                env.entry(name).or_insert(value);
+            }
+
             extends = manifest.extends;
         }
 
-        let platform = platform.map(TryInto::try_into).transpose()?;
+        let mut pinned = platform
+            .as_ref()
+            .map(PartialPlatform::pinned_specs)
+            .transpose()?
+            .unwrap_or_default();
+
+        // A `.node-version` file is only used when none of the manifests pin Node
+        if pinned.node.is_none() {
+            let file = sibling_path(&manifest_file, NODE_VERSION_FILE);
+            if let Some(raw) = read_node_version_file(&file)? {
+                pinned.node = Some(raw.parse()?);
+            }
+        }
+
+        // Pinned versions that aren't exact are resolved when the project's tools are first used
+        let platform = match platform {
+            Some(partial) if pinned.node.is_none() => {
+                return Err(partial.missing_node_error().into())
+            }
+            _ => pinned.exact_platform(),
+        };
+        let lockfile = Lockfile::from_file_if_exists(&sibling_path(&manifest_file, LOCKFILE_NAME))?;
 
         Ok(Project {
             manifest_file,
             workspace_manifests,
             dependencies,
             platform,
+            pinned,
+            env,
+            lockfile,
         })
     }
 
+    /// Returns the environment variables set in the `volta.env` key of the project, which are
+    /// added to the environment of the project's tools when they run
+    pub fn env(&self) -> &HashMap<String, String> {
+        &self.env
+    }
+
     /// Returns a reference to the manifest file for the current project
     pub fn manifest_file(&self) -> &Path {
         &self.manifest_file
@@ -133,10 +204,63 @@ impl Project {
     }
 
     /// Returns a reference to the Project's `PlatformSpec`, if available
+    ///
+    /// This is `None` until any ranges or tags that are pinned have been resolved, which is done
+    /// by `Session::project_platform`.
     pub fn platform(&self) -> Option<&PlatformSpec> {
         self.platform.as_ref()
     }
 
+    /// Whether the pinned versions include ranges or tags that haven't been resolved yet
+    pub(crate) fn needs_resolution(&self) -> bool {
+        self.platform.is_none() && self.pinned.node.is_some()
+    }
+
+    /// Records the exact platform that the pinned versions were resolved to
+    pub(crate) fn set_resolved_platform(&mut self, platform: PlatformSpec) {
+        self.platform = Some(platform);
+    }
+
+    /// Returns the tool versions pinned in the project's manifests, as they are written
+    pub fn pinned_specs(&self) -> &PinnedSpecs {
+        &self.pinned
+    }
+
+    /// Returns a reference to the Project's lockfile, if one exists
+    pub fn lockfile(&self) -> Option<&Lockfile> {
+        self.lockfile.as_ref()
+    }
+
+    /// Returns the path to the Project's lockfile, whether or not it exists
+    pub fn lockfile_file(&self) -> PathBuf {
+        sibling_path(&self.manifest_file, LOCKFILE_NAME)
+    }
+
+    /// Discards any versions recorded in the lockfile, so that they will be resolved again
+    ///
+    /// The lockfile will be created if it doesn't already exist, and will be written the next
+    /// time a tool is pinned in this project. This is an error if the lockfile is frozen.
+    pub fn reset_lockfile(&mut self) -> Fallible<()> {
+        if is_frozen() {
+            return Err(ErrorKind::FrozenLockfileError {
+                file: self.lockfile_file(),
+                reason: "Updating the lockfile would resolve its versions again".into(),
+            }
+            .into());
+        }
+
+        self.lockfile = Some(Lockfile::default());
+        Ok(())
+    }
+
+    /// Ensures that the lockfile matches the pinned platform, if the lockfile is frozen
+    pub fn check_frozen_lockfile(&self) -> Fallible<()> {
+        match &self.platform {
+            Some(platform) => self.check_lockfile(platform),
+            None => Ok(()),
+        }
+    }
+
     /// Returns true if the project dependency map contains the specified dependency
     pub fn has_direct_dependency(&self, dependency: &str) -> bool {
         self.dependencies.contains_key(dependency)
@@ -173,8 +297,7 @@ impl Project {
     /// Yarn projects that are using PnP or pnpm linker need to use yarn run.
     // (project uses Yarn berry if 'yarnrc.yml' exists, uses PnP if '.pnp.js' or '.pnp.cjs' exist)
     pub fn needs_yarn_run(&self) -> bool {
-        self.platform()
-            .map_or(false, |platform| platform.yarn.is_some())
+        self.pinned.yarn.is_some()
             && self.workspace_roots().any(|x| {
                 x.join(".yarnrc.yml").exists()
                     || x.join(".pnp.cjs").exists()
@@ -183,31 +306,47 @@ impl Project {
     }
 
     /// Pins the Node version in this project's manifest file
-    pub fn pin_node(&mut self, version: Version) -> Fallible<()> {
-        update_manifest(&self.manifest_file, ManifestKey::Node, Some(&version))?;
-
-        if let Some(platform) = self.platform.as_mut() {
-            platform.node = version;
-        } else {
-            self.platform = Some(PlatformSpec {
-                node: version,
+    pub fn pin_node(&mut self, version: PlatformNode) -> Fallible<()> {
+        let pinned = match &self.platform {
+            Some(platform) => PlatformSpec {
+                node: version.clone(),
+                ..platform.clone()
+            },
+            None => PlatformSpec {
+                node: version.clone(),
                 npm: None,
                 pnpm: None,
                 yarn: None,
-            });
-        }
+            },
+        };
+        self.check_lockfile(&pinned)?;
 
-        Ok(())
+        update_manifest(&self.manifest_file, ManifestKey::Node, Some(&version))?;
+
+        self.pinned.node = Some(match version {
+            PlatformNode::Version(version) => VersionSpec::Exact(version),
+            PlatformNode::System => VersionSpec::Tag(VersionTag::Custom(SYSTEM_NODE.into())),
+        });
+        self.platform = Some(pinned);
+
+        self.update_lockfile()
     }
 
     /// Pins the npm version in this project's manifest file
     pub fn pin_npm(&mut self, version: Option<Version>) -> Fallible<()> {
-        if let Some(platform) = self.platform.as_mut() {
+        if let Some(platform) = &self.platform {
+            let pinned = PlatformSpec {
+                npm: version.clone(),
+                ..platform.clone()
+            };
+            self.check_lockfile(&pinned)?;
+
             update_manifest(&self.manifest_file, ManifestKey::Npm, version.as_ref())?;
 
-            platform.npm = version;
+            self.pinned.npm = version.map(VersionSpec::Exact);
+            self.platform = Some(pinned);
 
-            Ok(())
+            self.update_lockfile()
         } else {
             Err(ErrorKind::NoPinnedNodeVersion { tool: "npm".into() }.into())
         }
@@ -215,12 +354,19 @@ impl Project {
 
     /// Pins the pnpm version in this project's manifest file
     pub fn pin_pnpm(&mut self, version: Option<Version>) -> Fallible<()> {
-        if let Some(platform) = self.platform.as_mut() {
+        if let Some(platform) = &self.platform {
+            let pinned = PlatformSpec {
+                pnpm: version.clone(),
+                ..platform.clone()
+            };
+            self.check_lockfile(&pinned)?;
+
             update_manifest(&self.manifest_file, ManifestKey::Pnpm, version.as_ref())?;
 
-            platform.pnpm = version;
+            self.pinned.pnpm = version.map(VersionSpec::Exact);
+            self.platform = Some(pinned);
 
-            Ok(())
+            self.update_lockfile()
         } else {
             Err(ErrorKind::NoPinnedNodeVersion {
                 tool: "pnpm".into(),
@@ -231,12 +377,19 @@ impl Project {
 
     /// Pins the Yarn version in this project's manifest file
     pub fn pin_yarn(&mut self, version: Option<Version>) -> Fallible<()> {
-        if let Some(platform) = self.platform.as_mut() {
+        if let Some(platform) = &self.platform {
+            let pinned = PlatformSpec {
+                yarn: version.clone(),
+                ..platform.clone()
+            };
+            self.check_lockfile(&pinned)?;
+
             update_manifest(&self.manifest_file, ManifestKey::Yarn, version.as_ref())?;
 
-            platform.yarn = version;
+            self.pinned.yarn = version.map(VersionSpec::Exact);
+            self.platform = Some(pinned);
 
-            Ok(())
+            self.update_lockfile()
         } else {
             Err(ErrorKind::NoPinnedNodeVersion {
                 tool: "Yarn".into(),
@@ -244,6 +397,62 @@ impl Project {
             .into())
         }
     }
+
+    /// Ensures that a frozen lockfile exists and matches a platform, since the lockfile can't be
+    /// created or changed
+    fn check_lockfile(&self, platform: &PlatformSpec) -> Fallible<()> {
+        match &self.lockfile {
+            None if is_frozen() => Err(ErrorKind::FrozenLockfileError {
+                file: self.lockfile_file(),
+                reason: "The project doesn't have a lockfile".into(),
+            }
+            .into()),
+            Some(lockfile) if is_frozen() => match lockfile.difference(&Lockfile::from(platform)) {
+                Some(reason) => Err(ErrorKind::FrozenLockfileError {
+                    file: self.lockfile_file(),
+                    reason,
+                }
+                .into()),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the currently pinned platform in the project lockfile, creating the lockfile on the
+    /// first pin
+    fn update_lockfile(&mut self) -> Fallible<()> {
+        if let Some(platform) = &self.platform {
+            let lockfile = Lockfile::from(platform);
+            lockfile.write(&self.lockfile_file())?;
+            self.lockfile = Some(lockfile);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the path to a file that sits alongside the given manifest file
+fn sibling_path(manifest_file: &Path, name: &str) -> PathBuf {
+    // Invariant: The manifest file was successfully loaded, so it has a parent directory
+    manifest_file
+        .parent()
+        .expect("File paths always have a parent")
+        .join(name)
+}
+
+/// Reads the first line of a `.node-version` file, if the file exists and isn't empty
+fn read_node_version_file(file: &Path) -> Fallible<Option<String>> {
+    let contents = read_file(file).with_context(|| ErrorKind::ReadNodeVersionFileError {
+        file: file.to_owned(),
+    })?;
+
+    Ok(contents
+        .as_deref()
+        .and_then(|contents| contents.lines().next())
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .map(String::from))
 }
 
 fn is_node_root(dir: &Path) -> bool {
@@ -273,14 +482,84 @@ pub(crate) fn find_closest_root(mut dir: PathBuf) -> Option<PathBuf> {
     Some(dir)
 }
 
+/// The tool versions pinned in a project manifest, exactly as they are written
+///
+/// Unlike the project's `PlatformSpec`, these are the raw specs before any resolution, so they
+/// can be used to show e.g. "pinned ^20 (resolved 20.5.1)".
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct PinnedSpecs {
+    pub node: Option<VersionSpec>,
+    pub npm: Option<VersionSpec>,
+    pub pnpm: Option<VersionSpec>,
+    pub yarn: Option<VersionSpec>,
+}
+
+impl PinnedSpecs {
+    /// The platform for the pinned versions, if every one of them is already exact
+    fn exact_platform(&self) -> Option<PlatformSpec> {
+        fn exact(spec: &Option<VersionSpec>) -> Option<Option<Version>> {
+            match spec {
+                None => Some(None),
+                Some(VersionSpec::Exact(version)) => Some(Some(version.clone())),
+                Some(_) => None,
+            }
+        }
+
+        let node = match self.node.as_ref()? {
+            VersionSpec::Tag(VersionTag::Custom(tag)) if tag == SYSTEM_NODE => PlatformNode::System,
+            VersionSpec::Exact(version) => PlatformNode::Version(version.clone()),
+            _ => return None,
+        };
+
+        Some(PlatformSpec {
+            node,
+            npm: exact(&self.npm)?,
+            pnpm: exact(&self.pnpm)?,
+            yarn: exact(&self.yarn)?,
+        })
+    }
+}
+
+/// The raw tool versions from the `volta` key of one or more manifests
+#[derive(Default)]
 struct PartialPlatform {
-    node: Option<Version>,
-    npm: Option<Version>,
-    pnpm: Option<Version>,
-    yarn: Option<Version>,
+    node: Option<String>,
+    npm: Option<String>,
+    pnpm: Option<String>,
+    yarn: Option<String>,
 }
 
 impl PartialPlatform {
+    fn pinned_specs(&self) -> Fallible<PinnedSpecs> {
+        fn parse(spec: &Option<String>) -> Fallible<Option<VersionSpec>> {
+            spec.as_deref().map(str::parse).transpose()
+        }
+
+        Ok(PinnedSpecs {
+            node: parse(&self.node)?,
+            npm: parse(&self.npm)?,
+            pnpm: parse(&self.pnpm)?,
+            yarn: parse(&self.yarn)?,
+        })
+    }
+
+    /// The error for a platform without Node, naming the first other tool that is pinned, if any
+    fn missing_node_error(&self) -> ErrorKind {
+        let pinned = [
+            ("npm", &self.npm),
+            ("pnpm", &self.pnpm),
+            ("Yarn", &self.yarn),
+        ]
+        .into_iter()
+        .find_map(|(tool, version)| version.as_ref().map(|_| tool));
+
+        match pinned {
+            Some(tool) => ErrorKind::NoProjectNodeForPinnedTool { tool: tool.into() },
+            None => ErrorKind::NoProjectNodeInManifest,
+        }
+    }
+
     fn merge(self, other: PartialPlatform) -> PartialPlatform {
         PartialPlatform {
             node: self.node.or(other.node),
@@ -290,18 +569,3 @@ impl PartialPlatform {
         }
     }
 }
-
-impl TryFrom<PartialPlatform> for PlatformSpec {
-    type Error = VoltaError;
-
-    fn try_from(partial: PartialPlatform) -> Fallible<PlatformSpec> {
-        let node = partial.node.ok_or(ErrorKind::NoProjectNodeInManifest)?;
-
-        Ok(PlatformSpec {
-            node,
-            npm: partial.npm,
-            pnpm: partial.pnpm,
-            yarn: partial.yarn,
-        })
-    }
-}
@@ -2,13 +2,11 @@ use std::collections::HashMap;
 use std::fmt;
 use std::fs::{read_to_string, File};
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use super::PartialPlatform;
 use crate::error::{Context, ErrorKind, Fallible};
-use crate::version::parse_version;
 use dunce::canonicalize;
-use node_semver::Version;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
@@ -20,6 +18,7 @@ pub type DependencyMapIterator = std::iter::Chain<
 pub(super) struct Manifest {
     pub dependency_maps: DependencyMapIterator,
     pub platform: Option<PartialPlatform>,
+    pub env: HashMap<String, String>,
     pub extends: Option<PathBuf>,
 }
 
@@ -29,34 +28,78 @@ impl Manifest {
 
         let dependency_maps = raw.dependencies.into_iter().chain(raw.dev_dependencies);
 
-        let (platform, extends) = match raw.volta {
+        let (platform, env, extends) = match raw.volta {
             Some(toolchain) => {
-                let (partial, extends) = toolchain.parse_split()?;
+                let (partial, env, extends) = toolchain.split();
 
                 let next = extends
-                    .map(|path| {
-                        // Invariant: Since we successfully parsed it, we know we have a path to a file
-                        let unresolved = file
-                            .parent()
-                            .expect("File paths always have a parent")
-                            .join(&path);
-                        canonicalize(unresolved)
-                            .with_context(|| ErrorKind::ExtensionPathError { path })
-                    })
+                    .map(|path| resolve_extends(file, path))
                     .transpose()?;
-                (Some(partial), next)
+                (Some(partial), env, next)
             }
-            None => (None, None),
+            None => (None, HashMap::new(), None),
         };
 
         Ok(Manifest {
             dependency_maps,
             platform,
+            env,
             extends,
         })
     }
 }
 
+/// Resolves a `volta.extends` value from the manifest `file` to the path of the extended file
+///
+/// The value is a path relative to the manifest. If no such file exists and the value is a bare
+/// package name, it is instead resolved to that package's `package.json` in `node_modules`, using
+/// the same lookup as Node: each ancestor directory's `node_modules` is searched in turn.
+fn resolve_extends(file: &Path, path: PathBuf) -> Fallible<PathBuf> {
+    // Invariant: Since we successfully parsed it, we know we have a path to a file
+    let dir = file.parent().expect("File paths always have a parent");
+    let unresolved = dir.join(&path);
+
+    let resolved = if !unresolved.exists() && is_package_name(&path) {
+        dir.ancestors()
+            .filter(|ancestor| ancestor.file_name() != Some("node_modules".as_ref()))
+            .map(|ancestor| {
+                ancestor
+                    .join("node_modules")
+                    .join(&path)
+                    .join("package.json")
+            })
+            .find(|manifest| manifest.is_file())
+            .unwrap_or(unresolved)
+    } else {
+        unresolved
+    };
+
+    canonicalize(resolved).with_context(|| ErrorKind::ExtensionPathError { path })
+}
+
+/// Determines whether an `extends` value is a bare package name, e.g. `shared-config` or
+/// `@scope/shared-config`, rather than a path to a file
+fn is_package_name(path: &Path) -> bool {
+    let names = match path
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(names) => names,
+        None => return false,
+    };
+
+    let valid_name = |name: &&str| !name.starts_with('.') && !name.ends_with(".json");
+    match names.as_slice() {
+        [name] => !name.starts_with('@') && valid_name(name),
+        [scope, name] => scope.starts_with('@') && valid_name(name),
+        _ => false,
+    }
+}
+
 pub(super) enum ManifestKey {
     Node,
     Npm,
@@ -75,6 +118,9 @@ impl fmt::Display for ManifestKey {
     }
 }
 
+/// The canonical order of the tool keys in the `volta` hash
+const TOOL_KEY_ORDER: [&str; 4] = ["node", "npm", "pnpm", "yarn"];
+
 /// Updates the `volta` hash in the specified manifest with the given key and value
 ///
 /// Will create the `volta` hash if it isn't already present
@@ -83,7 +129,7 @@ impl fmt::Display for ManifestKey {
 pub(super) fn update_manifest(
     file: &Path,
     key: ManifestKey,
-    value: Option<&Version>,
+    value: Option<&impl fmt::Display>,
 ) -> Fallible<()> {
     let contents = read_to_string(file).with_context(|| ErrorKind::PackageReadError {
         file: file.to_owned(),
@@ -100,18 +146,19 @@ pub(super) fn update_manifest(
             file: file.to_owned(),
         })?;
 
+    let value = value.map(ToString::to_string);
     let key = key.to_string();
 
     match (value, root.get_mut("volta").and_then(|v| v.as_object_mut())) {
         (Some(v), Some(hash)) => {
-            hash.insert(key, Value::String(v.to_string()));
+            insert_tool_key(hash, key, Value::String(v));
         }
         (None, Some(hash)) => {
             hash.remove(&key);
         }
         (Some(v), None) => {
             let mut map = Map::new();
-            map.insert(key, Value::String(v.to_string()));
+            map.insert(key, Value::String(v));
             root.insert("volta".into(), Value::Object(map));
         }
         (None, None) => {}
@@ -138,6 +185,35 @@ pub(super) fn update_manifest(
     Ok(())
 }
 
+/// Sets a tool key in the `volta` hash, keeping the order of the existing keys unchanged
+///
+/// An existing key is updated in place. A new key is inserted ahead of the first tool that comes
+/// after it in the canonical order (node, npm, pnpm, yarn), or at the end if there is none, so
+/// that pinning a new tool results in a minimal diff.
+fn insert_tool_key(hash: &mut Map<String, Value>, key: String, value: Value) {
+    if let Some(existing) = hash.get_mut(&key) {
+        *existing = value;
+        return;
+    }
+
+    let rank = |k: &str| TOOL_KEY_ORDER.iter().position(|tool| *tool == k);
+    let new_rank = rank(&key);
+    let mut pending = Some((key, value));
+
+    for (k, v) in std::mem::take(hash) {
+        if rank(&k) > new_rank {
+            if let Some((key, value)) = pending.take() {
+                hash.insert(key, value);
+            }
+        }
+        hash.insert(k, v);
+    }
+
+    if let Some((key, value)) = pending {
+        hash.insert(key, value);
+    }
+}
+
 #[derive(Deserialize)]
 struct RawManifest {
     dependencies: Option<HashMap<String, String>>,
@@ -172,23 +248,22 @@ struct ToolchainSpec {
     yarn: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     extends: Option<PathBuf>,
+    /// Environment variables to set when running the project's tools
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    env: HashMap<String, String>,
 }
 
 impl ToolchainSpec {
-    /// Moves the tool versions into a `PartialPlatform` and returns that along with the `extends` value
-    fn parse_split(self) -> Fallible<(PartialPlatform, Option<PathBuf>)> {
-        let node = self.node.map(parse_version).transpose()?;
-        let npm = self.npm.map(parse_version).transpose()?;
-        let pnpm = self.pnpm.map(parse_version).transpose()?;
-        let yarn = self.yarn.map(parse_version).transpose()?;
-
+    /// Moves the tool versions into a `PartialPlatform` and returns that along with the `env` and
+    /// `extends` values
+    fn split(self) -> (PartialPlatform, HashMap<String, String>, Option<PathBuf>) {
         let platform = PartialPlatform {
-            node,
-            npm,
-            pnpm,
-            yarn,
+            node: self.node,
+            npm: self.npm,
+            pnpm: self.pnpm,
+            yarn: self.yarn,
         };
 
-        Ok((platform, self.extends))
+        (platform, self.env, self.extends)
     }
 }
@@ -94,6 +94,220 @@ mod project {
         assert_eq!(platform.yarn, Some("1.22.4".parse().unwrap()));
     }
 
+    #[test]
+    fn platform_extends_package() {
+        let project_path = fixture_path(&["extends-package"]);
+        let test_project = Project::for_dir(project_path).unwrap().unwrap();
+        let platform = test_project.platform().unwrap();
+
+        // From the project `extends-package/package.json`
+        assert_eq!(platform.node, "14.17.0".parse().unwrap());
+        // From the shared config `node_modules/@team/volta-config/package.json`
+        assert_eq!(platform.yarn, Some("1.22.4".parse().unwrap()));
+    }
+
+    #[test]
+    fn pinned_specs_exact() {
+        let project_path = fixture_path(&["basic"]);
+        let test_project = Project::for_dir(project_path).unwrap().unwrap();
+
+        assert_eq!(
+            test_project.pinned_specs(),
+            &PinnedSpecs {
+                node: Some(VersionSpec::Exact("6.11.1".parse().unwrap())),
+                npm: Some(VersionSpec::Exact("3.10.10".parse().unwrap())),
+                pnpm: None,
+                yarn: Some(VersionSpec::Exact("1.2.0".parse().unwrap())),
+            }
+        );
+    }
+
+    #[test]
+    fn pinned_specs_workspace() {
+        let project_path = fixture_path(&["nested", "subproject", "inner_project"]);
+        let test_project = Project::for_dir(project_path).unwrap().unwrap();
+        let pinned = test_project.pinned_specs();
+
+        assert_eq!(
+            pinned.node,
+            Some(VersionSpec::Exact("12.14.0".parse().unwrap()))
+        );
+        assert_eq!(
+            pinned.npm,
+            Some(VersionSpec::Exact("6.9.0".parse().unwrap()))
+        );
+        assert_eq!(
+            pinned.yarn,
+            Some(VersionSpec::Exact("1.22.4".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn pinned_specs_ranges() {
+        let manifest_file = fixture_path(&["ranges", "package.json"]);
+        let manifest = serial::Manifest::from_file(&manifest_file).unwrap();
+        let pinned = manifest.platform.unwrap().pinned_specs().unwrap();
+
+        assert_eq!(
+            pinned,
+            PinnedSpecs {
+                node: Some(VersionSpec::Semver("^20".parse().unwrap())),
+                npm: Some(VersionSpec::Exact("10.2.4".parse().unwrap())),
+                pnpm: None,
+                yarn: Some(VersionSpec::Semver("~1.22".parse().unwrap())),
+            }
+        );
+
+        // The ranges are resolved when the project's tools are used, rather than when loading it
+        let project = Project::from_file(manifest_file).unwrap();
+        assert!(project.needs_resolution());
+    }
+
+    #[test]
+    fn ranged_project_loads() {
+        let project = Project::for_dir(fixture_path(&["ranges"]))
+            .unwrap()
+            .unwrap();
+
+        // The ranges are kept as they are written, until the project's tools are used
+        assert!(project.platform().is_none());
+        assert!(project.needs_resolution());
+        assert_eq!(
+            project.pinned_specs().node,
+            Some(VersionSpec::Semver("^20".parse().unwrap()))
+        );
+        assert_eq!(
+            project.pinned_specs().yarn,
+            Some(VersionSpec::Semver("~1.22".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn pinned_specs_follow_pin() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let manifest_file = project_dir.path().join("package.json");
+        std::fs::write(&manifest_file, r#"{ "volta": { "node": "16.20.2" } }"#).unwrap();
+
+        let mut test_project = Project::from_file(manifest_file).unwrap();
+        test_project.pin_node("18.17.1".parse().unwrap()).unwrap();
+        test_project
+            .pin_yarn(Some("1.22.19".parse().unwrap()))
+            .unwrap();
+
+        let pinned = test_project.pinned_specs();
+        assert_eq!(
+            pinned.node,
+            Some(VersionSpec::Exact("18.17.1".parse().unwrap()))
+        );
+        assert_eq!(
+            pinned.yarn,
+            Some(VersionSpec::Exact("1.22.19".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn env_from_manifest_and_extends() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let manifest_file = project_dir.path().join("package.json");
+        std::fs::write(
+            &manifest_file,
+            r#"{
+                "volta": {
+                    "node": "18.17.1",
+                    "env": { "NODE_OPTIONS": "--max-old-space-size=4096" },
+                    "extends": "./shared.json"
+                }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            project_dir.path().join("shared.json"),
+            r#"{
+                "volta": {
+                    "env": { "NODE_OPTIONS": "--inspect", "TZ": "UTC" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let test_project = Project::from_file(manifest_file).unwrap();
+        let env = test_project.env();
+
+        assert_eq!(env.len(), 2);
+        // The project's own value takes precedence over the extended one
+        assert_eq!(env["NODE_OPTIONS"], "--max-old-space-size=4096");
+        assert_eq!(env["TZ"], "UTC");
+    }
+
+    #[test]
+    fn lockfile_missing() {
+        let project_path = fixture_path(&["basic"]);
+        let test_project = Project::for_dir(project_path).unwrap().unwrap();
+
+        assert!(test_project.lockfile().is_none());
+    }
+
+    #[test]
+    fn lockfile_present() {
+        let project_path = fixture_path(&["locked"]);
+        let test_project = Project::for_dir(project_path).unwrap().unwrap();
+        let lockfile = test_project.lockfile().unwrap();
+
+        assert_eq!(lockfile.node, Some("16.20.2".parse().unwrap()));
+        assert_eq!(lockfile.npm, None);
+        assert_eq!(lockfile.yarn, Some("1.22.19".parse().unwrap()));
+    }
+
+    #[test]
+    fn pin_updates_lockfile() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let manifest_file = project_dir.path().join("package.json");
+        std::fs::write(&manifest_file, r#"{ "volta": { "node": "16.20.2" } }"#).unwrap();
+
+        let mut test_project = Project::from_file(manifest_file).unwrap();
+        assert!(test_project.lockfile().is_none());
+
+        // The first pin creates the lockfile
+        let lockfile_file = project_dir.path().join(LOCKFILE_NAME);
+        test_project.pin_node("18.17.1".parse().unwrap()).unwrap();
+        let written = Lockfile::from_file_if_exists(&lockfile_file)
+            .unwrap()
+            .unwrap();
+        assert_eq!(written.node, Some("18.17.1".parse().unwrap()));
+        assert_eq!(test_project.lockfile(), Some(&written));
+
+        test_project.pin_node("20.5.0".parse().unwrap()).unwrap();
+
+        let written = Lockfile::from_file_if_exists(&lockfile_file)
+            .unwrap()
+            .unwrap();
+        assert_eq!(written.node, Some("20.5.0".parse().unwrap()));
+        assert_eq!(test_project.lockfile(), Some(&written));
+    }
+
+    #[test]
+    fn pin_preserves_formatting() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let manifest_file = project_dir.path().join("package.json");
+        std::fs::write(
+            &manifest_file,
+            "{\n\t\"version\": \"1.0.0\",\n\t\"name\": \"formatted\",\n\t\"volta\": {\n\t\t\"yarn\": \"1.22.19\",\n\t\t\"node\": \"16.20.2\"\n\t},\n\t\"private\": true\n}\n",
+        )
+        .unwrap();
+
+        let mut test_project = Project::from_file(manifest_file.clone()).unwrap();
+        test_project.pin_node("20.5.0".parse().unwrap()).unwrap();
+        test_project
+            .pin_npm(Some("9.8.1".parse().unwrap()))
+            .unwrap();
+
+        // Existing keys keep their order and indentation, and npm is inserted ahead of yarn
+        assert_eq!(
+            std::fs::read_to_string(&manifest_file).unwrap(),
+            "{\n\t\"version\": \"1.0.0\",\n\t\"name\": \"formatted\",\n\t\"volta\": {\n\t\t\"npm\": \"9.8.1\",\n\t\t\"yarn\": \"1.22.19\",\n\t\t\"node\": \"20.5.0\"\n\t},\n\t\"private\": true\n}\n"
+        );
+    }
+
     #[test]
     fn direct_dependencies_single() {
         let project_path = fixture_path(&["basic"]);
@@ -216,6 +430,39 @@ mod project {
             kind => panic!("Wrong error kind: {:?}", kind),
         }
     }
+
+    #[test]
+    fn detects_package_cycles() {
+        // cycle-package extends config-a, which has a cycle with config-b
+        let cycle_path = fixture_path(&["cycle-package"]);
+        let project_error = Project::for_dir(cycle_path).unwrap_err();
+
+        match project_error.kind() {
+            ErrorKind::ExtensionCycleError { paths, duplicate } => {
+                let expected_paths = vec![
+                    fixture_path(&["cycle-package", "package.json"]),
+                    fixture_path(&["cycle-package", "node_modules", "config-a", "package.json"]),
+                    fixture_path(&["cycle-package", "node_modules", "config-b", "package.json"]),
+                ];
+                assert_eq!(&expected_paths, paths);
+                assert_eq!(&expected_paths[1], duplicate);
+            }
+            kind => panic!("Wrong error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn pinned_tool_without_node() {
+        let project_path = fixture_path(&["yarn-without-node"]);
+        let project_error = Project::for_dir(project_path).unwrap_err();
+
+        assert_eq!(
+            project_error.kind(),
+            &ErrorKind::NoProjectNodeForPinnedTool {
+                tool: "Yarn".into()
+            }
+        );
+    }
 }
 
 mod needs_yarn_run {
@@ -249,3 +496,88 @@ mod needs_yarn_run {
         assert!(test_project.needs_yarn_run());
     }
 }
+
+mod node_version_file {
+    use super::*;
+    use std::fs;
+
+    fn project_with_node_version(manifest: &str, node_version: &str) -> tempfile::TempDir {
+        let project_dir = tempfile::tempdir().unwrap();
+        fs::write(project_dir.path().join("package.json"), manifest).unwrap();
+        fs::write(project_dir.path().join(".node-version"), node_version).unwrap();
+        project_dir
+    }
+
+    #[test]
+    fn exact_version() {
+        let project_dir = project_with_node_version("{}", "18.17.0\n");
+        let project = Project::for_dir(project_dir.path().to_owned())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(project.platform().unwrap().node, "18.17.0".parse().unwrap());
+        assert_eq!(
+            project.pinned_specs().node,
+            Some(VersionSpec::Exact("18.17.0".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn partial_version_is_resolved_later() {
+        // Loading the project doesn't depend on a matching version having been fetched
+        let project_dir = project_with_node_version("{}", "v20\n");
+        let project = Project::for_dir(project_dir.path().to_owned())
+            .unwrap()
+            .unwrap();
+
+        assert!(project.platform().is_none());
+        assert!(project.needs_resolution());
+        assert!(matches!(
+            project.pinned_specs().node,
+            Some(VersionSpec::Semver(_))
+        ));
+    }
+
+    #[test]
+    fn tag_is_resolved_later() {
+        let project_dir = project_with_node_version("{}", "lts");
+        let project = Project::for_dir(project_dir.path().to_owned())
+            .unwrap()
+            .unwrap();
+
+        assert!(project.platform().is_none());
+        assert!(project.needs_resolution());
+        assert_eq!(
+            project.pinned_specs().node,
+            Some(VersionSpec::Tag(VersionTag::Lts))
+        );
+    }
+
+    #[test]
+    fn manifest_takes_precedence() {
+        let project_dir = project_with_node_version(
+            r#"{ "volta": { "node": "16.20.2", "yarn": "1.22.19" } }"#,
+            "18.17.0",
+        );
+        let project = Project::for_dir(project_dir.path().to_owned())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(project.platform().unwrap().node, "16.20.2".parse().unwrap());
+    }
+
+    #[test]
+    fn fills_in_node_for_manifest_without_it() {
+        let project_dir =
+            project_with_node_version(r#"{ "volta": { "yarn": "1.22.19" } }"#, "18.17.0");
+        let platform = Project::for_dir(project_dir.path().to_owned())
+            .unwrap()
+            .unwrap()
+            .platform()
+            .cloned()
+            .unwrap();
+
+        assert_eq!(platform.node, "18.17.0".parse().unwrap());
+        assert_eq!(platform.yarn, Some("1.22.19".parse().unwrap()));
+    }
+}
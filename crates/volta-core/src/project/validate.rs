@@ -0,0 +1,257 @@
+//! Provides validation of the `volta` key in a package manifest, without applying it
+
+use std::fmt;
+use std::fs::read_to_string;
+use std::path::Path;
+
+use super::{sibling_path, NODE_VERSION_FILE};
+use crate::error::{Context, ErrorKind, Fallible};
+use crate::platform::SYSTEM_NODE;
+use crate::version::{parse_requirements, parse_version};
+use serde_json::Value;
+
+/// A problem with the `volta` key of a package manifest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ManifestIssue {
+    /// A key has a value of the wrong type, e.g. a version that isn't a string
+    InvalidValue { key: String, expected: &'static str },
+
+    /// A tool is pinned to a value that isn't a valid version or version range
+    InvalidVersion { tool: String, version: String },
+
+    /// npm, pnpm, or Yarn is pinned, but Node isn't
+    MissingNode { tool: String },
+
+    /// The `volta` key contains a key that Volta doesn't use
+    UnknownKey { key: String },
+}
+
+impl fmt::Display for ManifestIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestIssue::InvalidValue { key, expected } => {
+                write!(f, "`{}` must be {}", key, expected)
+            }
+            ManifestIssue::InvalidVersion { tool, version } => {
+                write!(
+                    f,
+                    "`volta.{}` is not a valid version or range: {}",
+                    tool, version
+                )
+            }
+            ManifestIssue::MissingNode { tool } => {
+                write!(f, "`volta.{}` is set, but `volta.node` is not", tool)
+            }
+            ManifestIssue::UnknownKey { key } => write!(f, "`volta.{}` is not a known key", key),
+        }
+    }
+}
+
+/// Checks the `volta` key of a package manifest, returning any problems that were found
+///
+/// This only reads the manifest, without fetching or resolving any tools, so it is suitable for
+/// linters and pre-commit hooks. A manifest without a `volta` key has no issues, while one that
+/// can't be read or isn't a JSON object is an error.
+///
+/// Node may be set by a `.node-version` file or by the manifest that is extended with
+/// `volta.extends`, so npm, pnpm, and Yarn are only reported as missing Node when neither is used.
+pub fn validate_manifest(path: &Path) -> Fallible<Vec<ManifestIssue>> {
+    let contents = read_to_string(path).with_context(|| ErrorKind::PackageReadError {
+        file: path.to_owned(),
+    })?;
+    let manifest: Value =
+        serde_json::from_str(&contents).with_context(|| ErrorKind::PackageParseError {
+            file: path.to_owned(),
+        })?;
+    let root = manifest
+        .as_object()
+        .ok_or_else(|| ErrorKind::PackageParseError {
+            file: path.to_owned(),
+        })?;
+
+    let volta = match root.get("volta") {
+        None => return Ok(Vec::new()),
+        Some(Value::Object(volta)) => volta,
+        Some(_) => {
+            return Ok(vec![ManifestIssue::InvalidValue {
+                key: "volta".into(),
+                expected: "an object",
+            }])
+        }
+    };
+
+    let mut issues = Vec::new();
+    for (key, value) in volta {
+        match key.as_str() {
+            "node" | "npm" | "pnpm" | "yarn" => {
+                if let Some(issue) = check_version(key, value) {
+                    issues.push(issue);
+                }
+            }
+            "extends" if !value.is_string() => issues.push(ManifestIssue::InvalidValue {
+                key: "volta.extends".into(),
+                expected: "a path",
+            }),
+            "env" if !is_string_map(value) => issues.push(ManifestIssue::InvalidValue {
+                key: "volta.env".into(),
+                expected: "an object with string values",
+            }),
+            "extends" | "env" => {}
+            _ => issues.push(ManifestIssue::UnknownKey { key: key.clone() }),
+        }
+    }
+
+    let node_elsewhere =
+        volta.contains_key("extends") || sibling_path(path, NODE_VERSION_FILE).is_file();
+    if !volta.contains_key("node") && !node_elsewhere {
+        let pinned = ["npm", "pnpm", "yarn"]
+            .into_iter()
+            .find(|tool| volta.contains_key(*tool));
+        if let Some(tool) = pinned {
+            issues.push(ManifestIssue::MissingNode { tool: tool.into() });
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Checks the version of a single tool in the `volta` key
+fn check_version(tool: &str, value: &Value) -> Option<ManifestIssue> {
+    let Some(version) = value.as_str() else {
+        return Some(ManifestIssue::InvalidValue {
+            key: format!("volta.{}", tool),
+            expected: "a version string",
+        });
+    };
+
+    // Ranges are resolved when the project's tools are used, like `.node-version` files
+    let valid = (tool == "node" && version.trim() == SYSTEM_NODE)
+        || parse_version(version).is_ok()
+        || parse_requirements(version).is_ok();
+    (!valid).then(|| ManifestIssue::InvalidVersion {
+        tool: tool.into(),
+        version: version.into(),
+    })
+}
+
+fn is_string_map(value: &Value) -> bool {
+    value
+        .as_object()
+        .is_some_and(|map| map.values().all(Value::is_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn manifest(dir: &Path, contents: &str) -> PathBuf {
+        let path = dir.join("package.json");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn valid_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for contents in [
+            r#"{ "name": "no-volta" }"#,
+            r#"{ "volta": { "node": "20.11.1", "npm": "10.4.0", "yarn": "1.22.19" } }"#,
+            r#"{ "volta": { "node": "system", "env": { "NODE_ENV": "test" } } }"#,
+            r#"{ "volta": { "node": "^20", "npm": "10.x", "yarn": "~1.22" } }"#,
+            r#"{ "volta": { "extends": "../package.json", "yarn": "1.22.19" } }"#,
+        ] {
+            let path = manifest(dir.path(), contents);
+            assert_eq!(validate_manifest(&path).unwrap(), [], "{}", contents);
+        }
+    }
+
+    #[test]
+    fn invalid_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = manifest(
+            dir.path(),
+            r#"{ "volta": { "node": "twenty", "pnpm": 8, "yarn": "^one" } }"#,
+        );
+
+        assert_eq!(
+            validate_manifest(&path).unwrap(),
+            [
+                ManifestIssue::InvalidVersion {
+                    tool: "node".into(),
+                    version: "twenty".into(),
+                },
+                ManifestIssue::InvalidValue {
+                    key: "volta.pnpm".into(),
+                    expected: "a version string",
+                },
+                ManifestIssue::InvalidVersion {
+                    tool: "yarn".into(),
+                    version: "^one".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn yarn_without_node() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = manifest(dir.path(), r#"{ "volta": { "yarn": "1.22.19" } }"#);
+
+        let issues = validate_manifest(&path).unwrap();
+        assert_eq!(
+            issues,
+            [ManifestIssue::MissingNode {
+                tool: "yarn".into()
+            }]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "`volta.yarn` is set, but `volta.node` is not"
+        );
+
+        // Node can also be set by a `.node-version` file
+        fs::write(dir.path().join(".node-version"), "20.11.1").unwrap();
+        assert_eq!(validate_manifest(&path).unwrap(), []);
+    }
+
+    #[test]
+    fn unknown_keys_and_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = manifest(
+            dir.path(),
+            r#"{ "volta": { "node": "20.11.1", "bun": "1.0.0", "env": { "DEBUG": true } } }"#,
+        );
+
+        assert_eq!(
+            validate_manifest(&path).unwrap(),
+            [
+                ManifestIssue::UnknownKey { key: "bun".into() },
+                ManifestIssue::InvalidValue {
+                    key: "volta.env".into(),
+                    expected: "an object with string values",
+                },
+            ]
+        );
+
+        let path = manifest(dir.path(), r#"{ "volta": "20.11.1" }"#);
+        assert_eq!(
+            validate_manifest(&path).unwrap(),
+            [ManifestIssue::InvalidValue {
+                key: "volta".into(),
+                expected: "an object",
+            }]
+        );
+    }
+
+    #[test]
+    fn unparseable_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = manifest(dir.path(), r#"{ "volta": { "node": "#);
+
+        let error = validate_manifest(&path).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::PackageParseError { file: path });
+    }
+}
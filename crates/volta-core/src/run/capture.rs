@@ -0,0 +1,213 @@
+//! Provides the capture mode of `volta run`, which copies the output of a tool into files while
+//! still streaming it to the terminal
+
+use std::fs::{create_dir_all, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{Context, ErrorKind, Fallible};
+use crate::layout::volta_home;
+use chrono::Local;
+use log::debug;
+
+const STDOUT_FILE: &str = "stdout.log";
+const STDERR_FILE: &str = "stderr.log";
+
+/// How long to keep copying output after the tool exits
+///
+/// A background process started by the tool may inherit its output pipes and keep them open, so
+/// we can't wait for them to be closed.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The files that the output of a tool is captured in, in a directory specific to the run
+pub struct Capture {
+    dir: PathBuf,
+    stdout: File,
+    stderr: File,
+}
+
+impl Capture {
+    /// Creates a new directory for the captured output in the Volta log directory
+    pub fn create() -> Fallible<Self> {
+        let name = Local::now()
+            .format("volta-run-%Y-%m-%d_%H_%M_%S%.3f")
+            .to_string();
+        Capture::create_in(volta_home()?.log_dir().join(name))
+    }
+
+    fn create_in(dir: PathBuf) -> Fallible<Self> {
+        create_dir_all(&dir).with_context(|| ErrorKind::CreateDirError { dir: dir.clone() })?;
+        let create = |name: &str| {
+            let file = dir.join(name);
+            File::create(&file).with_context(|| ErrorKind::CaptureFileError { file })
+        };
+
+        Ok(Capture {
+            stdout: create(STDOUT_FILE)?,
+            stderr: create(STDERR_FILE)?,
+            dir,
+        })
+    }
+
+    /// The directory containing the captured output
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Runs a command to completion, copying its stdout and stderr to the terminal and to the
+    /// capture files as they are written
+    ///
+    /// The command's output is piped rather than inherited, so tools that check for a terminal
+    /// (e.g. to decide whether to use colors) will see that they aren't writing to one. Input is
+    /// still inherited, and the exit status is returned unchanged.
+    pub(super) fn status(self, command: &mut Command) -> io::Result<ExitStatus> {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Nothing is sent on the channel: It is disconnected once every copy has finished
+        let (copying, finished) = channel::<()>();
+        if let Some(out) = child.stdout.take() {
+            tee(out, io::stdout, self.stdout, copying.clone());
+        }
+        if let Some(err) = child.stderr.take() {
+            tee(err, io::stderr, self.stderr, copying);
+        }
+        let status = child.wait();
+
+        // The output is only complete once both pipes have been read to the end
+        if let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(DRAIN_TIMEOUT) {
+            debug!("Output pipes are still open after the tool exited, not capturing any more");
+        }
+        status
+    }
+}
+
+/// Copies everything read from `source` to both the terminal and a file, on a separate thread
+/// that holds `copying` until the copy is finished
+///
+/// If the terminal can't be written to (e.g. it is a pipe that was closed), the copy stops and
+/// `source` is closed, so that the tool sees the same error it would if it were writing to the
+/// terminal itself. Failing to write to the file doesn't stop the copy.
+fn tee<R, T, W>(mut source: R, terminal: T, mut file: File, copying: Sender<()>)
+where
+    R: Read + Send + 'static,
+    T: FnOnce() -> W + Send + 'static,
+    W: Write,
+{
+    thread::spawn(move || {
+        let _copying = copying;
+        let mut terminal = terminal();
+        let mut buffer = [0; 8192];
+        loop {
+            match source.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => {
+                    let _ = file.write_all(&buffer[..read]);
+                    if terminal
+                        .write_all(&buffer[..read])
+                        .and_then(|()| terminal.flush())
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_capture_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let capture = Capture::create_in(dir.path().join("run")).unwrap();
+        let capture_dir = capture.dir().to_owned();
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo out; echo err >&2; exit 3"]);
+        let status = capture.status(&mut command).unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(
+            read_to_string(capture_dir.join(STDOUT_FILE)).unwrap(),
+            "out\n"
+        );
+        assert_eq!(
+            read_to_string(capture_dir.join(STDERR_FILE)).unwrap(),
+            "err\n"
+        );
+    }
+    #[test]
+    #[cfg(unix)]
+    fn test_closed_terminal_closes_pipe() {
+        let dir = tempfile::tempdir().unwrap();
+        let (copying, finished) = channel();
+
+        // The terminal is closed, so the copy stops after the first write and the tool is unable
+        // to write any more
+        let mut child = Command::new("sh")
+            .args(["-c", "echo first; sleep 1; echo second || exit 7"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let file = File::create(dir.path().join(STDOUT_FILE)).unwrap();
+        tee(
+            child.stdout.take().unwrap(),
+            || ClosedTerminal,
+            file,
+            copying,
+        );
+
+        assert_eq!(
+            finished.recv_timeout(Duration::from_secs(5)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        assert_ne!(child.wait().unwrap().code(), Some(0));
+        assert_eq!(
+            read_to_string(dir.path().join(STDOUT_FILE)).unwrap(),
+            "first\n"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_background_process_does_not_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let capture = Capture::create_in(dir.path().join("run")).unwrap();
+
+        // The background process inherits the output pipes and keeps them open
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 3 & echo done"]);
+        let start = std::time::Instant::now();
+        let status = capture.status(&mut command).unwrap();
+
+        assert!(status.success());
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+
+    /// A terminal that can't be written to, like a pipe whose reader has gone away
+    struct ClosedTerminal;
+
+    impl Write for ClosedTerminal {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::env;
 use std::ffi::OsStr;
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
@@ -6,18 +7,19 @@ use std::os::unix::process::ExitStatusExt;
 use std::os::windows::process::ExitStatusExt;
 use std::process::{Command, ExitStatus};
 
-use super::RECURSION_ENV_VAR;
-use crate::command::create_command;
+use super::{Capture, RECURSION_ENV_VAR};
+use crate::command::{create_command, prepare_command};
 use crate::error::{Context, ErrorKind, Fallible};
 use crate::layout::volta_home;
-use crate::platform::{CliPlatform, Platform, System};
+use crate::platform::{CliPlatform, Platform, PlatformNode, System};
+use crate::profile::{time, Phase};
 use crate::session::Session;
 use crate::signal::pass_control_to_shim;
 use crate::style::{note_prefix, tool_version};
 use crate::sync::VoltaLock;
 use crate::tool::package::{DirectInstall, InPlaceUpgrade, PackageConfig, PackageManager};
 use crate::tool::Spec;
-use log::{info, warn};
+use log::{debug, info, warn};
 
 pub enum Executor {
     Tool(Box<ToolCommand>),
@@ -70,6 +72,14 @@ impl Executor {
         }
     }
 
+    /// Captures the output of the command into files, which is only supported when running a tool
+    pub fn capture(&mut self, capture: Capture) {
+        match self {
+            Executor::Tool(cmd) => cmd.capture = Some(capture),
+            _ => warn!("Output is only captured when running a tool, not when managing packages"),
+        }
+    }
+
     pub fn execute(self, session: &mut Session) -> Fallible<ExitStatus> {
         match self {
             Executor::Tool(cmd) => cmd.execute(session),
@@ -83,12 +93,14 @@ impl Executor {
                     "{} Volta is processing each package separately",
                     note_prefix()
                 );
-                for exe in executors {
-                    let status = exe.execute(session)?;
+                let labels: Vec<String> = executors.iter().map(Executor::describe).collect();
+                for (index, exe) in executors.into_iter().enumerate() {
+                    let result = exe.execute(session);
                     // If any of the sub-commands fail, then we should stop installing and return
-                    // that failure.
-                    if !status.success() {
-                        return Ok(status);
+                    // that failure, after reporting what did and didn't run.
+                    if !matches!(&result, Ok(status) if status.success()) {
+                        warn!("{}", partial_failure_summary(&labels, index));
+                        return result;
                     }
                 }
                 // If we get here, then all of the sub-commands succeeded, so we should report success
@@ -96,6 +108,54 @@ impl Executor {
             }
         }
     }
+
+    /// A short description of what this executor runs, used when reporting on multiple executors
+    fn describe(&self) -> String {
+        match self {
+            Executor::Tool(cmd) => cmd.command.get_program().to_string_lossy().into_owned(),
+            // The tool being installed or upgraded is the last argument to the command
+            Executor::PackageInstall(cmd) => last_arg(&cmd.command),
+            Executor::PackageLink(cmd) => cmd.tool.clone(),
+            Executor::PackageUpgrade(cmd) => last_arg(&cmd.command),
+            Executor::InternalInstall(cmd) => cmd.tool.to_string(),
+            Executor::Uninstall(cmd) => cmd.tool.to_string(),
+            Executor::Multiple(executors) => executors
+                .iter()
+                .map(Executor::describe)
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+fn last_arg(command: &Command) -> String {
+    command
+        .get_args()
+        .last()
+        .unwrap_or_else(|| command.get_program())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Summarizes which of several executors succeeded, failed, or were never run, given the index
+/// of the executor that failed
+fn partial_failure_summary(labels: &[String], failed: usize) -> String {
+    let list = |labels: &[String]| match labels {
+        [] => String::from("none"),
+        labels => labels.join(", "),
+    };
+
+    format!(
+        "Stopped after {} failed.
+
+    Succeeded: {}
+    Failed: {}
+    Not attempted: {}",
+        labels[failed],
+        list(&labels[..failed]),
+        labels[failed],
+        list(&labels[failed + 1..])
+    )
 }
 
 impl From<Vec<Executor>> for Executor {
@@ -116,12 +176,14 @@ pub struct ToolCommand {
     command: Command,
     platform: Option<Platform>,
     kind: ToolKind,
+    capture: Option<Capture>,
 }
 
 /// The kind of tool being executed, used to determine the correct execution context
 pub enum ToolKind {
     Node,
     Npm,
+    NpmExec,
     Npx,
     Pnpm,
     Yarn,
@@ -144,6 +206,7 @@ impl ToolCommand {
             command,
             platform,
             kind,
+            capture: None,
         }
     }
 
@@ -176,9 +239,11 @@ impl ToolCommand {
 
     /// Runs the command, returning the `ExitStatus` if it successfully launches
     pub fn execute(mut self, session: &mut Session) -> Fallible<ExitStatus> {
+        let bypass = matches!(self.kind, ToolKind::Bypass(_));
         let (path, on_failure) = match self.kind {
             ToolKind::Node => super::node::execution_context(self.platform, session)?,
             ToolKind::Npm => super::npm::execution_context(self.platform, session)?,
+            ToolKind::NpmExec => super::npm::exec_execution_context(self.platform, session)?,
             ToolKind::Npx => super::npx::execution_context(self.platform, session)?,
             ToolKind::Pnpm => super::pnpm::execution_context(self.platform, session)?,
             ToolKind::Yarn => super::yarn::execution_context(self.platform, session)?,
@@ -188,15 +253,45 @@ impl ToolCommand {
             ToolKind::ProjectLocalBinary(bin) => {
                 super::binary::local_execution_context(bin, self.platform, session)?
             }
+            // Bypassed commands always run against the system PATH, so there's no need to load
+            // the project, toolchain, or platform from the session
             ToolKind::Bypass(command) => (System::path()?, ErrorKind::BypassError { command }),
         };
 
+        if !bypass {
+            add_project_envs(&mut self.command, session)?;
+        }
         self.command.env(RECURSION_ENV_VAR, "1");
-        self.command.env("PATH", path);
+        self.command.env("PATH", &path);
 
+        let mut command = prepare_command(self.command, &path);
         pass_control_to_shim();
-        self.command.status().with_context(|| on_failure)
+        time(Phase::Spawn, || match self.capture {
+            Some(capture) => capture.status(&mut command),
+            None => command.status(),
+        })
+        .with_context(|| on_failure)
+    }
+}
+
+/// Adds the environment variables set by the current project (in `volta.env`) to a command
+///
+/// Variables that are already set, either in the environment or explicitly for the command
+/// (e.g. `volta run --env`), are left unchanged.
+fn add_project_envs(command: &mut Command, session: &mut Session) -> Fallible<()> {
+    if let Some(project) = session.project()? {
+        for (name, value) in project.env() {
+            let name = OsStr::new(name);
+            let explicit = command.get_envs().any(|(key, _)| key == name);
+
+            if !explicit && env::var_os(name).is_none() {
+                debug!("Setting {:?} from the project", name);
+                command.env(name, value);
+            }
+        }
     }
+
+    Ok(())
 }
 
 impl From<ToolCommand> for Executor {
@@ -385,7 +480,8 @@ impl PackageLinkCommand {
         }
 
         if let Some(platform) = session.project_platform()? {
-            if platform.node.major != config.platform.node.major {
+            let major = |node: &PlatformNode| node.version().map(|version| version.major);
+            if major(&platform.node) != major(&config.platform.node) {
                 warn!(
                     "the current project is using {}, but package '{}' was linked using {}. These might not interact correctly.",
                     tool_version("node", &platform.node),
@@ -561,3 +657,103 @@ impl From<UninstallCommand> for Executor {
         Executor::Uninstall(Box::new(cmd))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_bypass_skips_platform() {
+        let mut session = Session::init_unchecked();
+        let command = ToolCommand::new(
+            "true",
+            Vec::<&str>::new(),
+            None,
+            ToolKind::Bypass("true".into()),
+        );
+
+        let status = command.execute(&mut session).unwrap();
+
+        assert!(status.success());
+        assert!(!session.is_platform_loaded());
+    }
+
+    /// The command and execution context that a Yarn shim named `exe` resolves to
+    fn yarn_invocation(exe: &str, session: &mut Session) -> (Vec<OsString>, (OsString, ErrorKind)) {
+        match super::super::get_executor(OsStr::new(exe), &[], session).unwrap() {
+            Executor::Tool(command) => {
+                assert!(matches!(command.kind, ToolKind::Yarn));
+                let invocation = std::iter::once(command.command.get_program())
+                    .chain(command.command.get_args())
+                    .map(OsStr::to_owned)
+                    .collect();
+                let context = super::super::yarn::execution_context(command.platform, session);
+                (invocation, context.unwrap())
+            }
+            _ => panic!("{} should run as a tool", exe),
+        }
+    }
+
+    #[test]
+    fn test_yarnpkg_runs_as_yarn() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = crate::layout::override_volta_home(home.path().to_owned());
+        let mut session = Session::init_unchecked();
+
+        assert_eq!(
+            yarn_invocation("yarnpkg", &mut session),
+            yarn_invocation("yarn", &mut session)
+        );
+    }
+
+    /// The kind of tool that running `exe` with `args` resolves to
+    fn tool_kind(exe: &str, args: &[&str], session: &mut Session) -> ToolKind {
+        let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+        match super::super::get_executor(OsStr::new(exe), &args, session).unwrap() {
+            Executor::Tool(command) => command.kind,
+            _ => panic!("{} should run as a tool", exe),
+        }
+    }
+
+    #[test]
+    fn test_npm_exec_is_detected() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = crate::layout::override_volta_home(home.path().to_owned());
+        let mut session = Session::init_unchecked();
+
+        assert!(matches!(
+            tool_kind("npx", &["cowsay"], &mut session),
+            ToolKind::Npx
+        ));
+        for args in [&["exec", "cowsay"][..], &["--yes", "x", "cowsay"]] {
+            assert!(
+                matches!(tool_kind("npm", args, &mut session), ToolKind::NpmExec),
+                "npm {:?}",
+                args
+            );
+        }
+
+        assert!(matches!(
+            tool_kind("npm", &["run", "exec"], &mut session),
+            ToolKind::Npm
+        ));
+    }
+
+    #[test]
+    fn test_partial_failure_summary() {
+        let labels = ["typescript", "yarn@9.99.99", "cowsay", "eslint"].map(String::from);
+
+        assert_eq!(
+            partial_failure_summary(&labels, 1),
+            "Stopped after yarn@9.99.99 failed.
+
+    Succeeded: typescript
+    Failed: yarn@9.99.99
+    Not attempted: cowsay, eslint"
+        );
+        assert!(partial_failure_summary(&labels, 0).contains("Succeeded: none"));
+        assert!(partial_failure_summary(&labels, 3).contains("Not attempted: none"));
+    }
+}
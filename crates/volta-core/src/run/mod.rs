@@ -12,6 +12,7 @@ use log::debug;
 use node_semver::Version;
 
 pub mod binary;
+mod capture;
 mod executor;
 mod node;
 mod npm;
@@ -20,6 +21,8 @@ mod parser;
 mod pnpm;
 mod yarn;
 
+pub use capture::Capture;
+
 /// Environment variable set internally when a shim has been executed and the context evaluated
 ///
 /// This is set when executing a shim command. If this is already, then the built-in shims (Node,
@@ -44,11 +47,14 @@ pub fn execute_shim(session: &mut Session) -> Fallible<ExitStatus> {
 }
 
 /// Execute a tool with the provided arguments
+///
+/// If `capture` is provided, the output of the tool is also copied into its files.
 pub fn execute_tool<K, V, S>(
     exe: &OsStr,
     args: &[OsString],
     envs: &HashMap<K, V, S>,
     cli: CliPlatform,
+    capture: Option<Capture>,
     session: &mut Session,
 ) -> Fallible<ExitStatus>
 where
@@ -62,6 +68,9 @@ where
     let mut runner = get_executor(exe, args, session)?;
     runner.cli_platform(cli);
     runner.envs(envs);
+    if let Some(capture) = capture {
+        runner.capture(capture);
+    }
 
     runner.execute(session)
 }
@@ -140,10 +149,14 @@ fn debug_active_image(image: &Image) {
     npm: {}
     pnpm: {}
     Yarn: {}",
-        format_tool_version(&image.node),
+        format!(
+            "{} from {} configuration",
+            image.node.value, image.node.source
+        ),
         image
             .resolve_npm()
             .ok()
+            .flatten()
             .as_ref()
             .map(format_tool_version)
             .unwrap_or_else(|| "Bundled with Node".into()),
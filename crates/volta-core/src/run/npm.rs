@@ -3,13 +3,23 @@ use std::ffi::OsString;
 use std::fs::File;
 
 use super::executor::{Executor, ToolCommand, ToolKind, UninstallCommand};
-use super::parser::{CommandArg, InterceptedCommand};
+use super::parser::{is_npm_exec, CommandArg, InterceptedCommand};
 use super::{debug_active_image, debug_no_platform, RECURSION_ENV_VAR};
 use crate::error::{ErrorKind, Fallible};
-use crate::platform::{Platform, System};
+use crate::platform::{Image, Platform, System};
 use crate::session::{ActivityKind, Session};
 use crate::tool::{PackageManifest, Spec};
 use crate::version::VersionSpec;
+use node_semver::Version;
+use once_cell::sync::Lazy;
+
+static REQUIRED_EXEC_NPM_VERSION: Lazy<Version> = Lazy::new(|| Version {
+    major: 7,
+    minor: 0,
+    patch: 0,
+    build: vec![],
+    pre_release: vec![],
+});
 
 /// Build an `Executor` for npm
 ///
@@ -18,7 +28,8 @@ use crate::version::VersionSpec;
 /// Volta directory.
 ///
 /// If the command is _not_ a global install / uninstall or we don't have a default platform, then
-/// we will allow npm to execute the command as usual.
+/// we will allow npm to execute the command as usual. An `npm exec` command is only available
+/// with newer versions of npm, so it gets its own execution context that checks for that.
 pub(super) fn command(args: &[OsString], session: &mut Session) -> Fallible<Executor> {
     session.add_event_start(ActivityKind::Npm);
     // Don't re-evaluate the context or global install interception if this is a recursive call
@@ -59,7 +70,13 @@ pub(super) fn command(args: &[OsString], session: &mut Session) -> Fallible<Exec
         }
     };
 
-    Ok(ToolCommand::new("npm", args, platform, ToolKind::Npm).into())
+    let kind = if is_npm_exec(args) {
+        ToolKind::NpmExec
+    } else {
+        ToolKind::Npm
+    };
+
+    Ok(ToolCommand::new("npm", args, platform, kind).into())
 }
 
 /// Determine the execution context (PATH and failure error message) for npm
@@ -83,6 +100,62 @@ pub(super) fn execution_context(
     }
 }
 
+/// Determine the execution context (PATH and failure error message) for `npm exec`
+pub(super) fn exec_execution_context(
+    platform: Option<Platform>,
+    session: &mut Session,
+) -> Fallible<(OsString, ErrorKind)> {
+    match platform {
+        Some(plat) => {
+            let image = plat.checkout(session)?;
+
+            // If the npm version is lower than the minimum required, we can show a helpful error
+            // message instead of npm's 'Unknown command' error.
+            if let Some((npm, bundled_with)) = active_npm(&image)? {
+                check_npm_supports_exec(&npm, bundled_with)?;
+            }
+
+            let path = image.path()?;
+            debug_active_image(&image);
+
+            Ok((path, ErrorKind::BinaryExecError))
+        }
+        None => {
+            let path = System::path()?;
+            debug_no_platform();
+            Ok((path, ErrorKind::NoPlatform))
+        }
+    }
+}
+
+/// Determine the npm version in effect for the image, along with the Node version it is bundled
+/// with if it isn't a custom npm
+///
+/// The npm bundled with the system Node isn't known ahead of time, so there is none for it.
+pub(super) fn active_npm(image: &Image) -> Fallible<Option<(Version, Option<&Version>)>> {
+    let Some(active_npm) = image.resolve_npm()? else {
+        return Ok(None);
+    };
+    let bundled_with = match image.npm {
+        Some(_) => None,
+        None => image.node.value.version(),
+    };
+
+    Ok(Some((active_npm.value, bundled_with)))
+}
+
+fn check_npm_supports_exec(npm: &Version, bundled_with: Option<&Version>) -> Fallible<()> {
+    if *npm < *REQUIRED_EXEC_NPM_VERSION {
+        return Err(ErrorKind::NpmExecNotAvailable {
+            version: npm.to_string(),
+            bundled_with: bundled_with.map(ToString::to_string),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
 /// Determine the name of the current project, if possible
 fn current_project_name(session: &mut Session) -> Option<String> {
     let project = session.project().ok()??;
@@ -91,3 +164,33 @@ fn current_project_name(session: &mut Session) -> Option<String> {
 
     Some(manifest.name)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npm_with_exec() {
+        let npm = Version::from((7, 0, 0));
+
+        assert!(check_npm_supports_exec(&npm, None).is_ok());
+    }
+
+    #[test]
+    fn npm_with_npx_but_not_exec() {
+        let node = Version::from((14, 21, 3));
+        let npm = Version::from((6, 14, 18));
+
+        let error = check_npm_supports_exec(&npm, Some(&node)).unwrap_err();
+        match error.kind() {
+            ErrorKind::NpmExecNotAvailable {
+                version,
+                bundled_with,
+            } => {
+                assert_eq!(version, "6.14.18");
+                assert_eq!(bundled_with.as_deref(), Some("14.21.3"));
+            }
+            kind => panic!("Wrong error kind: {:?}", kind),
+        }
+    }
+}
@@ -2,9 +2,10 @@ use std::env;
 use std::ffi::OsString;
 
 use super::executor::{Executor, ToolCommand, ToolKind};
+use super::npm::active_npm;
 use super::{debug_active_image, debug_no_platform, RECURSION_ENV_VAR};
 use crate::error::{ErrorKind, Fallible};
-use crate::platform::{Platform, System};
+use crate::platform::{Image, Platform, System};
 use crate::session::{ActivityKind, Session};
 use node_semver::Version;
 use once_cell::sync::Lazy;
@@ -40,13 +41,7 @@ pub(super) fn execution_context(
 
             // If the npm version is lower than the minimum required, we can show a helpful error
             // message instead of a 'command not found' error.
-            let active_npm = image.resolve_npm()?;
-            if active_npm.value < *REQUIRED_NPM_VERSION {
-                return Err(ErrorKind::NpxNotAvailable {
-                    version: active_npm.value.to_string(),
-                }
-                .into());
-            }
+            check_npx_available(&image)?;
 
             let path = image.path()?;
             debug_active_image(&image);
@@ -60,3 +55,69 @@ pub(super) fn execution_context(
         }
     }
 }
+
+/// Ensure that the npm version in effect for the image provides npx
+///
+/// The npm bundled with the system Node isn't known ahead of time, so it is assumed to work.
+fn check_npx_available(image: &Image) -> Fallible<()> {
+    match active_npm(image)? {
+        Some((npm, bundled_with)) => check_npm_supports_npx(&npm, bundled_with),
+        None => Ok(()),
+    }
+}
+
+fn check_npm_supports_npx(npm: &Version, bundled_with: Option<&Version>) -> Fallible<()> {
+    if *npm < *REQUIRED_NPM_VERSION {
+        return Err(ErrorKind::NpxNotAvailable {
+            version: npm.to_string(),
+            bundled_with: bundled_with.map(ToString::to_string),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_npm_with_npx() {
+        let node = Version::from((8, 2, 0));
+        let npm = Version::from((5, 3, 0));
+
+        assert!(check_npm_supports_npx(&npm, Some(&node)).is_ok());
+    }
+
+    #[test]
+    fn bundled_npm_without_npx() {
+        let node = Version::from((6, 11, 1));
+        let npm = Version::from((3, 10, 10));
+
+        let error = check_npm_supports_npx(&npm, Some(&node)).unwrap_err();
+        match error.kind() {
+            ErrorKind::NpxNotAvailable {
+                version,
+                bundled_with,
+            } => {
+                assert_eq!(version, "3.10.10");
+                assert_eq!(bundled_with.as_deref(), Some("6.11.1"));
+            }
+            kind => panic!("Wrong error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn custom_npm_overrides_bundled() {
+        let npm = Version::from((5, 1, 0));
+
+        let error = check_npm_supports_npx(&npm, None).unwrap_err();
+        match error.kind() {
+            ErrorKind::NpxNotAvailable { bundled_with, .. } => assert!(bundled_with.is_none()),
+            kind => panic!("Wrong error kind: {:?}", kind),
+        }
+
+        assert!(check_npm_supports_npx(&REQUIRED_NPM_VERSION, None).is_ok());
+    }
+}
@@ -25,6 +25,8 @@ const NPM_UNINSTALL_ALIASES: [&str; 5] = ["un", "uninstall", "remove", "rm", "r"
 const NPM_LINK_ALIASES: [&str; 2] = ["link", "ln"];
 /// Aliases that npm supports for the `update` command
 const NPM_UPDATE_ALIASES: [&str; 4] = ["update", "udpate", "upgrade", "up"];
+/// Aliases that npm supports for the `exec` command
+const NPM_EXEC_ALIASES: [&str; 2] = ["exec", "x"];
 /// Aliases that pnpm supports for the 'remove' command,
 /// see: https://pnpm.io/cli/remove
 const PNPM_UNINSTALL_ALIASES: [&str; 4] = ["remove", "uninstall", "rm", "un"];
@@ -463,6 +465,17 @@ impl<'a> LinkArgs<'a> {
     }
 }
 
+/// Check if the provided argument list is an `npm exec` command, which runs a package binary the
+/// same way as `npx`
+pub fn is_npm_exec<A>(args: &[A]) -> bool
+where
+    A: AsRef<OsStr>,
+{
+    args.iter()
+        .find(is_positional)
+        .is_some_and(|cmd| NPM_EXEC_ALIASES.iter().any(|a| a == &cmd.as_ref()))
+}
+
 /// Check if the provided argument list includes a global flag and _doesn't_ have a prefix setting
 ///
 /// For our interception, we only want to intercept global commands. Additionally, if the user
@@ -630,6 +643,17 @@ mod tests {
             }
         }
 
+        #[test]
+        fn detects_exec() {
+            assert!(is_npm_exec(&arg_list(["exec", "cowsay"])));
+            assert!(is_npm_exec(&arg_list(["x", "--yes", "cowsay"])));
+            assert!(is_npm_exec(&arg_list(["--silent", "exec", "--", "tsc"])));
+
+            assert!(!is_npm_exec(&arg_list(["run", "exec"])));
+            assert!(!is_npm_exec(&arg_list(["install", "--global", "exec"])));
+            assert!(!is_npm_exec(&arg_list(Vec::<&str>::new())));
+        }
+
         #[test]
         fn handles_global_aliases() {
             match CommandArg::for_npm(&arg_list(["install", "--global", "typescript"])) {
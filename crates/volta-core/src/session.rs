@@ -2,16 +2,27 @@
 //! execution of a Volta tool, including their current directory, Volta
 //! hook configuration, and the state of the local inventory.
 
+use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
 use std::process::exit;
 
 use crate::error::{ExitCode, Fallible, VoltaError};
-use crate::event::EventLog;
-use crate::hook::{HookConfig, LazyHookConfig};
-use crate::platform::PlatformSpec;
+use crate::event::{events_disabled, EventLog};
+use crate::hook::{EventHooks, HookConfig, LazyHookConfig};
+use crate::layout::ensure_current_layout;
+use crate::npmrc;
+use crate::platform::{CurrentTools, Platform, PlatformSpec};
+use crate::profile::{print_report, time, Phase};
 use crate::project::{LazyProject, Project};
+use crate::style::note_prefix;
+use crate::tool::node::VersionResolver;
+use crate::tool::{resolve_pinned, ToolKindName};
 use crate::toolchain::{LazyToolchain, Toolchain};
-use log::debug;
+use crate::version::VersionSpec;
+use crate::warning::Warning;
+use archive::request;
+use log::{debug, info};
+use node_semver::Version;
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
 pub enum ActivityKind {
@@ -84,16 +95,37 @@ pub struct Session {
     toolchain: LazyToolchain,
     project: LazyProject,
     event_log: EventLog,
+    warnings: Vec<Warning>,
+    resolved: HashMap<(ToolKindName, String), Version>,
+    node_resolver: Option<Box<dyn VersionResolver>>,
 }
 
 impl Session {
     /// Constructs a new `Session`.
-    pub fn init() -> Session {
+    ///
+    /// Fails with an error pointing to the migration if the Volta directory uses an older layout,
+    /// rather than failing later on while reading from the wrong location.
+    pub fn init() -> Fallible<Session> {
+        ensure_current_layout()?;
+        Ok(Session::init_unchecked())
+    }
+
+    /// Constructs a new `Session` without checking the layout of the Volta directory, for use
+    /// while the directory is being migrated.
+    pub fn init_unchecked() -> Session {
+        // Requests use the proxies from npm's configuration if none are set in the environment,
+        // along with the credentials npm is configured with for the registries they are made to
+        request::set_proxy_fallback(npmrc::proxy_config);
+        request::set_auth_token_lookup(npmrc::auth_token);
+
         Session {
             hooks: LazyHookConfig::init(),
             toolchain: LazyToolchain::init(),
             project: LazyProject::init(),
             event_log: EventLog::init(),
+            warnings: Vec::new(),
+            resolved: HashMap::new(),
+            node_resolver: None,
         }
     }
 
@@ -113,11 +145,49 @@ impl Session {
     }
 
     /// Returns the current project's pinned platform image, if any.
-    pub fn project_platform(&self) -> Fallible<Option<&PlatformSpec>> {
-        if let Some(project) = self.project()? {
-            return Ok(project.platform());
+    ///
+    /// Versions pinned as ranges or tags (e.g. `^20` or `lts`) are resolved the first time this
+    /// is called, which may need network access. If the project lockfile is frozen, it must
+    /// exist and match the pinned platform, and nothing is resolved remotely.
+    pub fn project_platform(&mut self) -> Fallible<Option<&PlatformSpec>> {
+        let unresolved = self
+            .project()?
+            .filter(|project| project.needs_resolution())
+            .map(|project| {
+                (
+                    project.pinned_specs().clone(),
+                    project.lockfile().cloned(),
+                    project.lockfile_file(),
+                )
+            });
+
+        if let Some((pinned, lockfile, lockfile_file)) = unresolved {
+            let platform = resolve_pinned(&pinned, lockfile.as_ref(), &lockfile_file, self)?;
+            if let Some(project) = self.project_mut()? {
+                project.set_resolved_platform(platform);
+            }
         }
-        Ok(None)
+
+        match self.project()? {
+            Some(project) => {
+                project.check_frozen_lockfile()?;
+                Ok(project.platform())
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the tool versions in effect for the current directory, with where each came from
+    ///
+    /// The project platform takes precedence, with pnpm and Yarn taken from the default platform
+    /// if the project doesn't pin them, as when running the tools.
+    pub fn current_tools(&mut self) -> Fallible<CurrentTools> {
+        Platform::current(self).map(CurrentTools::from)
+    }
+
+    /// Returns whether the default toolchain or the current project have been loaded
+    pub fn is_platform_loaded(&self) -> bool {
+        self.toolchain.is_loaded() || self.project.is_loaded()
     }
 
     /// Produces a reference to the current toolchain (default platform specification)
@@ -130,11 +200,77 @@ impl Session {
         self.toolchain.get_mut()
     }
 
+    /// Discards the loaded default toolchain, so that it is read from disk again when next used
+    ///
+    /// Changes made through this session are already reflected in memory, so this is only needed
+    /// by long-running embedders when another process may have changed the default platform.
+    pub fn reload_toolchain(&mut self) {
+        self.toolchain.reset();
+    }
+
+    /// Discards the loaded project, so that it is read from disk again when next used
+    ///
+    /// The hook configuration includes the project's hooks, so it is discarded as well.
+    pub fn reload_project(&mut self) {
+        self.project.reset();
+        self.hooks.reset();
+    }
+
     /// Produces a reference to the hook configuration
     pub fn hooks(&self) -> Fallible<&HookConfig> {
         self.hooks.get(self.project()?)
     }
 
+    /// Uses a custom source for Node versions and distributions, instead of the Node index
+    #[cfg(test)]
+    pub(crate) fn set_node_resolver(&mut self, resolver: Box<dyn VersionResolver>) {
+        // Versions resolved from the previous source may not be available from the new one
+        self.resolved
+            .retain(|(tool, _), _| *tool != ToolKindName::Node);
+        self.node_resolver = Some(resolver);
+    }
+
+    /// Returns the custom source for Node versions, if one has been set
+    pub(crate) fn node_resolver(&self) -> Option<&dyn VersionResolver> {
+        self.node_resolver.as_deref()
+    }
+
+    /// Logs a warning and records it, so that it can be inspected once the operation completes
+    pub fn warn(&mut self, warning: Warning) {
+        info!("{} {}", note_prefix(), warning);
+        self.warnings.push(warning);
+    }
+
+    /// Returns the warnings produced by operations on this session so far
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Resolves a version spec for a tool, reusing the result if the same spec has already been
+    /// resolved during this session
+    pub fn resolve_cached<F>(
+        &mut self,
+        tool: ToolKindName,
+        spec: VersionSpec,
+        resolve: F,
+    ) -> Fallible<Version>
+    where
+        F: FnOnce(VersionSpec, &mut Session) -> Fallible<Version>,
+    {
+        let key = (tool, spec.to_string());
+        if let Some(version) = self.resolved.get(&key) {
+            debug!(
+                "Using previously resolved {}@{} for '{}'",
+                tool, version, key.1
+            );
+            return Ok(version.clone());
+        }
+
+        let version = time(Phase::Resolve, || resolve(spec, self))?;
+        self.resolved.insert(key, version.clone());
+        Ok(version)
+    }
+
     pub fn add_event_start(&mut self, activity_kind: ActivityKind) {
         self.event_log.add_event_start(activity_kind)
     }
@@ -149,6 +285,11 @@ impl Session {
     }
 
     fn publish_to_event_log(self) {
+        // Don't even load the hooks if events have been turned off entirely
+        if events_disabled() {
+            return;
+        }
+
         let Self {
             project,
             hooks,
@@ -158,8 +299,10 @@ impl Session {
         let plugin_res = project
             .get()
             .and_then(|p| hooks.get(p))
-            .map(|hooks| hooks.events().and_then(|e| e.publish.as_ref()));
+            .map(|hooks| hooks.events().and_then(EventHooks::publisher));
         match plugin_res {
+            // Without a publish hook, there is nothing to do
+            Ok(None) => {}
             Ok(plugin) => {
                 event_log.add_event_args();
                 event_log.publish(plugin);
@@ -172,11 +315,13 @@ impl Session {
 
     pub fn exit(self, code: ExitCode) -> ! {
         self.publish_to_event_log();
+        print_report();
         code.exit();
     }
 
     pub fn exit_tool(self, code: i32) -> ! {
         self.publish_to_event_log();
+        print_report();
         exit(code);
     }
 }
@@ -184,8 +329,16 @@ impl Session {
 #[cfg(test)]
 pub mod tests {
 
+    use crate::error::ErrorKind;
+    use crate::layout::{override_volta_home, volta_home};
+    use crate::platform::Source;
+    use crate::project::LazyProject;
     use crate::session::Session;
+    use crate::tool::ToolKindName;
+    use crate::warning::Warning;
+    use std::cell::Cell;
     use std::env;
+    use std::fs;
     use std::path::PathBuf;
 
     fn fixture_path(fixture_dir: &str) -> PathBuf {
@@ -195,11 +348,108 @@ pub mod tests {
         cargo_manifest_dir
     }
 
+    #[test]
+    fn test_resolve_cached() {
+        let mut session = Session::init_unchecked();
+        let index_reads = Cell::new(0);
+        let resolve = |session: &mut Session, spec: &str| {
+            session
+                .resolve_cached(ToolKindName::Node, spec.parse().unwrap(), |_, _| {
+                    index_reads.set(index_reads.get() + 1);
+                    Ok("20.5.1".parse().unwrap())
+                })
+                .unwrap()
+        };
+
+        let first = resolve(&mut session, "^20");
+        let second = resolve(&mut session, "^20");
+        assert_eq!(first, second);
+        assert_eq!(index_reads.get(), 1);
+
+        resolve(&mut session, "20.5");
+        assert_eq!(index_reads.get(), 2);
+    }
+
+    #[test]
+    fn test_warnings_are_collected() {
+        let mut session = Session::init_unchecked();
+        assert!(session.warnings().is_empty());
+
+        let warning = Warning::ProjectVersionDiffers {
+            project_version: "node@16.20.2".into(),
+            default_version: "node@20.5.1".into(),
+        };
+        session.warn(warning.clone());
+
+        assert_eq!(session.warnings(), [warning]);
+    }
+
+    #[test]
+    fn test_init_requires_current_layout() {
+        let home = tempfile::tempdir().unwrap();
+        let _guard = override_volta_home(home.path().to_owned());
+
+        fs::write(home.path().join("layout.v3"), "").unwrap();
+        let error = Session::init().err().expect("outdated layout is an error");
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::LayoutMigrationRequired { version } if version == "v3"
+        ));
+
+        fs::write(volta_home().unwrap().layout_file(), "").unwrap();
+        assert!(Session::init().is_ok());
+    }
+
+    #[test]
+    fn test_default_platform_in_sandboxed_home() {
+        let home = tempfile::tempdir().unwrap();
+        let _guard = override_volta_home(home.path().to_owned());
+
+        let platform_file = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(platform_file.parent().unwrap()).unwrap();
+        fs::write(
+            platform_file,
+            r#"{"node":{"runtime":"18.17.1","npm":null}}"#,
+        )
+        .unwrap();
+
+        let session = Session::init_unchecked();
+        let platform = session.default_platform().unwrap().unwrap();
+        assert_eq!(platform.node, "18.17.1".parse().unwrap());
+    }
+
+    #[test]
+    fn test_reload_toolchain_reads_modified_platform() {
+        let home = tempfile::tempdir().unwrap();
+        let _guard = override_volta_home(home.path().to_owned());
+
+        let platform_file = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(platform_file.parent().unwrap()).unwrap();
+        let write_default_node = |version: &str| {
+            let platform = format!(r#"{{"node":{{"runtime":"{}","npm":null}}}}"#, version);
+            fs::write(platform_file, platform).unwrap();
+        };
+
+        write_default_node("18.17.1");
+        let mut session = Session::init_unchecked();
+        let platform = session.default_platform().unwrap().unwrap();
+        assert_eq!(platform.node, "18.17.1".parse().unwrap());
+
+        // Another process changes the default platform
+        write_default_node("20.11.1");
+        let platform = session.default_platform().unwrap().unwrap();
+        assert_eq!(platform.node, "18.17.1".parse().unwrap());
+
+        session.reload_toolchain();
+        let platform = session.default_platform().unwrap().unwrap();
+        assert_eq!(platform.node, "20.11.1".parse().unwrap());
+    }
+
     #[test]
     fn test_in_pinned_project() {
         let project_pinned = fixture_path("basic");
         env::set_current_dir(project_pinned).expect("Could not set current directory");
-        let pinned_session = Session::init();
+        let mut pinned_session = Session::init_unchecked();
         let pinned_platform = pinned_session
             .project_platform()
             .expect("Couldn't create Project");
@@ -207,10 +457,90 @@ pub mod tests {
 
         let project_unpinned = fixture_path("no_toolchain");
         env::set_current_dir(project_unpinned).expect("Could not set current directory");
-        let unpinned_session = Session::init();
+        let mut unpinned_session = Session::init_unchecked();
         let unpinned_platform = unpinned_session
             .project_platform()
             .expect("Couldn't create Project");
         assert!(unpinned_platform.is_none());
     }
+
+    /// Writes a default platform to an overridden Volta home
+    fn write_default_platform(contents: &str) {
+        let platform_file = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(platform_file.parent().unwrap()).unwrap();
+        fs::write(platform_file, contents).unwrap();
+    }
+
+    #[test]
+    fn test_current_tools_in_pinned_project() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        write_default_platform(r#"{"node":{"runtime":"20.11.1","npm":null},"pnpm":"8.6.0"}"#);
+
+        let mut session = Session::init_unchecked();
+        session.project = LazyProject::for_dir(fixture_path("basic")).unwrap();
+        let current = session.current_tools().unwrap();
+
+        let node = current.node.unwrap();
+        assert_eq!(node.value, "6.11.1".parse().unwrap());
+        assert_eq!(node.source, Source::Project);
+        let npm = current.npm.unwrap();
+        assert_eq!(npm.value, "3.10.10".parse().unwrap());
+        assert_eq!(npm.source, Source::Project);
+        let yarn = current.yarn.unwrap();
+        assert_eq!(yarn.value, "1.2.0".parse().unwrap());
+        assert_eq!(yarn.source, Source::Project);
+        // The project doesn't pin pnpm, so the default is used
+        let pnpm = current.pnpm.unwrap();
+        assert_eq!(pnpm.value, "8.6.0".parse().unwrap());
+        assert_eq!(pnpm.source, Source::Default);
+    }
+
+    #[test]
+    fn test_current_tools_default_only() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        write_default_platform(r#"{"node":{"runtime":"20.11.1","npm":null},"yarn":"1.22.19"}"#);
+        let npm_version_file = volta_home().unwrap().node_npm_version_file("20.11.1");
+        fs::create_dir_all(npm_version_file.parent().unwrap()).unwrap();
+        fs::write(npm_version_file, "10.2.4").unwrap();
+
+        let mut session = Session::init_unchecked();
+        session.project = LazyProject::for_dir(home.path().to_owned()).unwrap();
+        let current = session.current_tools().unwrap();
+
+        let node = current.node.unwrap();
+        assert_eq!(node.value, "20.11.1".parse().unwrap());
+        assert_eq!(node.source, Source::Default);
+        // Without a default npm, the version bundled with Node is used
+        let npm = current.npm.unwrap();
+        assert_eq!(npm.value, "10.2.4".parse().unwrap());
+        assert_eq!(npm.source, Source::Default);
+        let yarn = current.yarn.unwrap();
+        assert_eq!(yarn.value, "1.22.19".parse().unwrap());
+        assert_eq!(yarn.source, Source::Default);
+        assert!(current.pnpm.is_none());
+    }
+
+    #[test]
+    fn test_project_platform_resolves_ranges() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let layout = volta_home().unwrap();
+        for version in ["18.17.0", "20.5.1", "20.11.1"] {
+            fs::create_dir_all(layout.node_image_root_dir().join(version)).unwrap();
+        }
+        for version in ["1.22.4", "1.22.19"] {
+            fs::create_dir_all(layout.yarn_image_dir(version)).unwrap();
+        }
+
+        let mut session = Session::init_unchecked();
+        session.project = LazyProject::for_dir(fixture_path("ranges")).unwrap();
+        let platform = session.project_platform().unwrap().unwrap();
+
+        // The newest fetched versions satisfying the ranges are used, without any network access
+        assert_eq!(platform.node, "20.11.1".parse().unwrap());
+        assert_eq!(platform.npm, Some("10.2.4".parse().unwrap()));
+        assert_eq!(platform.yarn, Some("1.22.19".parse().unwrap()));
+    }
 }
@@ -0,0 +1,371 @@
+//! Provides types for working with the user's shell and its profile scripts.
+
+use std::env;
+use std::fs::write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Context, ErrorKind, Fallible};
+use crate::fs::read_file;
+use crate::layout::volta_home;
+use fs_utils::ensure_containing_dir_exists;
+
+/// A shell whose profile script can be updated to include Volta
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    /// Every shell whose profile script can be updated
+    pub const ALL: [Shell; 4] = [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell];
+
+    /// Determines the user's shell from the `SHELL` environment variable, if possible
+    pub fn from_env() -> Option<Self> {
+        env::var("SHELL")
+            .ok()
+            .and_then(|shell| Self::from_path(&shell))
+    }
+
+    /// Determines the shell from the path to its executable (e.g. `/bin/zsh`)
+    pub fn from_path(shell: &str) -> Option<Self> {
+        let name = Path::new(shell).file_stem()?.to_str()?.to_ascii_lowercase();
+
+        match name.as_str() {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "pwsh" | "powershell" => Some(Shell::PowerShell),
+            _ => None,
+        }
+    }
+
+    /// Returns the profile scripts for this shell, in order of preference
+    pub fn profile_candidates(&self, home_dir: &Path) -> Vec<PathBuf> {
+        match self {
+            Shell::Bash => {
+                // macOS Terminal opens login shells, which don't read `.bashrc`
+                if cfg!(target_os = "macos") {
+                    vec![home_dir.join(".bash_profile"), home_dir.join(".bashrc")]
+                } else {
+                    vec![home_dir.join(".bashrc"), home_dir.join(".bash_profile")]
+                }
+            }
+            Shell::Zsh => {
+                let zdotdir = env::var_os("ZDOTDIR")
+                    .filter(|dir| !dir.is_empty())
+                    .map_or_else(|| home_dir.to_owned(), PathBuf::from);
+
+                vec![zdotdir.join(".zshenv"), zdotdir.join(".zshrc")]
+            }
+            Shell::Fish => vec![home_dir.join(".config/fish/config.fish")],
+            Shell::PowerShell => {
+                if cfg!(windows) {
+                    vec![home_dir.join("Documents/PowerShell/Microsoft.PowerShell_profile.ps1")]
+                } else {
+                    vec![home_dir.join(".config/powershell/Microsoft.PowerShell_profile.ps1")]
+                }
+            }
+        }
+    }
+
+    /// Returns the first of the profile scripts for this shell that exists, if any
+    pub fn existing_profile(&self, home_dir: &Path) -> Option<PathBuf> {
+        self.profile_candidates(home_dir)
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Returns the statement that sets an environment variable in this shell
+    pub fn export_var(&self, name: &str, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export {}=\"{}\"", name, value),
+            Shell::Fish => format!("set -gx {} \"{}\"", name, value),
+            Shell::PowerShell => format!("$env:{} = \"{}\"", name, value),
+        }
+    }
+
+    /// Returns the statement that adds a directory to the front of the PATH in this shell
+    pub fn prepend_path(&self, dir: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export PATH=\"{}:$PATH\"", dir),
+            Shell::Fish => format!("set -gx PATH \"{}\" $PATH", dir),
+            Shell::PowerShell => format!(
+                "$env:PATH = \"{}\" + [IO.Path]::PathSeparator + $env:PATH",
+                dir
+            ),
+        }
+    }
+}
+
+/// Determines the shells whose profile scripts should be updated
+///
+/// This is the user's current shell, along with any other shell that already has a profile
+/// script, so that Volta is available no matter which of them is opened.
+pub fn shells_to_update(home_dir: &Path, current: Option<Shell>) -> Vec<Shell> {
+    Shell::ALL
+        .into_iter()
+        .filter(|shell| Some(*shell) == current || shell.existing_profile(home_dir).is_some())
+        .collect()
+}
+
+/// Appended to each line that Volta writes to a profile script, so that the lines can be found
+/// and replaced later without touching any others
+const PROFILE_MARKER: &str = "# Added by Volta";
+
+/// Sets `VOLTA_HOME` and adds the given bin directory to the PATH in the profile script for the
+/// specified shell
+///
+/// The first existing profile script for the shell is updated, falling back to creating the most
+/// preferred one. Any lines previously added by this function are replaced. Returns the path to
+/// the profile script that was edited.
+pub fn add_to_profile(shell: Shell, bin_dir: &Path) -> Fallible<PathBuf> {
+    let home_dir = dirs::home_dir().ok_or(ErrorKind::NoHomeEnvironmentVar)?;
+    update_profile(shell, &home_dir, volta_home()?.root(), bin_dir)
+}
+
+fn update_profile(
+    shell: Shell,
+    home_dir: &Path,
+    volta_home: &Path,
+    bin_dir: &Path,
+) -> Fallible<PathBuf> {
+    let profile = shell.existing_profile(home_dir).unwrap_or_else(|| {
+        // Invariant: Every shell has at least one candidate profile script
+        shell.profile_candidates(home_dir).swap_remove(0)
+    });
+
+    let existing = read_file(&profile)
+        .with_context(|| ErrorKind::ReadShellProfileError {
+            file: profile.clone(),
+        })?
+        .unwrap_or_default();
+    let mut contents: String = existing
+        .lines()
+        .filter(|line| !is_volta_line(line))
+        .flat_map(|line| [line, "\n"])
+        .collect();
+    for line in [
+        shell.export_var("VOLTA_HOME", &volta_home.display().to_string()),
+        shell.prepend_path(&bin_dir.display().to_string()),
+    ] {
+        contents.push_str(&format!("{} {}\n", line, PROFILE_MARKER));
+    }
+
+    let write_error = || ErrorKind::WriteShellProfileError {
+        file: profile.clone(),
+    };
+    ensure_containing_dir_exists(&profile).with_context(write_error)?;
+    write(&profile, contents).with_context(write_error)?;
+
+    Ok(profile)
+}
+
+/// Determines whether a line of a profile script was added by Volta
+///
+/// Before lines were marked, `volta setup` wrote the same statements without the marker, so those
+/// are recognized as well to avoid adding them a second time.
+fn is_volta_line(line: &str) -> bool {
+    line.ends_with(PROFILE_MARKER)
+        || line.starts_with("export VOLTA_HOME=")
+        || line.starts_with("set -gx VOLTA_HOME ")
+        || [Shell::Bash, Shell::Fish]
+            .iter()
+            .any(|shell| line == shell.prepend_path("$VOLTA_HOME/bin"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Shell::from_path("/bin/bash"), Some(Shell::Bash));
+        assert_eq!(Shell::from_path("/usr/local/bin/zsh"), Some(Shell::Zsh));
+        assert_eq!(
+            Shell::from_path("/opt/homebrew/bin/fish"),
+            Some(Shell::Fish)
+        );
+        assert_eq!(Shell::from_path("pwsh"), Some(Shell::PowerShell));
+        assert_eq!(Shell::from_path("PowerShell.exe"), Some(Shell::PowerShell));
+        assert_eq!(Shell::from_path("/bin/tcsh"), None);
+        assert_eq!(Shell::from_path(""), None);
+    }
+
+    #[test]
+    fn test_export_syntax() {
+        assert_eq!(
+            Shell::Bash.export_var("VOLTA_HOME", "$HOME/.volta"),
+            "export VOLTA_HOME=\"$HOME/.volta\""
+        );
+        assert_eq!(
+            Shell::Zsh.prepend_path("$VOLTA_HOME/bin"),
+            "export PATH=\"$VOLTA_HOME/bin:$PATH\""
+        );
+        assert_eq!(
+            Shell::Fish.export_var("VOLTA_HOME", "$HOME/.volta"),
+            "set -gx VOLTA_HOME \"$HOME/.volta\""
+        );
+        assert_eq!(
+            Shell::Fish.prepend_path("$VOLTA_HOME/bin"),
+            "set -gx PATH \"$VOLTA_HOME/bin\" $PATH"
+        );
+        assert_eq!(
+            Shell::PowerShell.prepend_path("/home/user/.volta/bin"),
+            "$env:PATH = \"/home/user/.volta/bin\" + [IO.Path]::PathSeparator + $env:PATH"
+        );
+    }
+
+    #[test]
+    fn test_shells_to_update() {
+        let home = tempfile::tempdir().unwrap();
+        assert!(shells_to_update(home.path(), None).is_empty());
+        assert_eq!(
+            shells_to_update(home.path(), Some(Shell::Zsh)),
+            [Shell::Zsh]
+        );
+
+        let fish_config = home.path().join(".config/fish/config.fish");
+        std::fs::create_dir_all(fish_config.parent().unwrap()).unwrap();
+        write(&fish_config, "").unwrap();
+        assert_eq!(
+            shells_to_update(home.path(), Some(Shell::Bash)),
+            [Shell::Bash, Shell::Fish]
+        );
+    }
+
+    #[test]
+    fn test_update_profile_creates_preferred() {
+        let home = tempfile::tempdir().unwrap();
+        let volta_home = home.path().join(".volta");
+        let bin_dir = volta_home.join("bin");
+
+        let profile = update_profile(Shell::Fish, home.path(), &volta_home, &bin_dir).unwrap();
+
+        assert_eq!(profile, home.path().join(".config/fish/config.fish"));
+        assert_eq!(
+            read_to_string(&profile).unwrap(),
+            format!(
+                "set -gx VOLTA_HOME \"{}\" # Added by Volta\n\
+                 set -gx PATH \"{}\" $PATH # Added by Volta\n",
+                volta_home.display(),
+                bin_dir.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_update_profile_uses_existing() {
+        let home = tempfile::tempdir().unwrap();
+        let volta_home = home.path().join(".volta");
+        let bin_dir = volta_home.join("bin");
+        let bash_profile = home.path().join(".bash_profile");
+        let user_lines = "alias ll='ls -l'\nexport VOLTA_FEATURE_PNPM=1\n";
+        write(&bash_profile, user_lines).unwrap();
+
+        // Updating twice should not duplicate the lines, nor remove any that Volta didn't write
+        update_profile(Shell::Bash, home.path(), &volta_home, &bin_dir).unwrap();
+        let profile = update_profile(Shell::Bash, home.path(), &volta_home, &bin_dir).unwrap();
+
+        // No `.bashrc` exists, so the existing `.bash_profile` is used on every platform
+        assert_eq!(profile, bash_profile);
+        assert_eq!(
+            read_to_string(&profile).unwrap(),
+            format!(
+                "{}export VOLTA_HOME=\"{}\" # Added by Volta\n\
+                 export PATH=\"{}:$PATH\" # Added by Volta\n",
+                user_lines,
+                volta_home.display(),
+                bin_dir.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_update_profile_replaces_unmarked_lines() {
+        let home = tempfile::tempdir().unwrap();
+        let volta_home = home.path().join(".volta");
+        let bin_dir = volta_home.join("bin");
+        let zshenv = home.path().join(".zshenv");
+        write(
+            &zshenv,
+            "export EDITOR=vim\n\
+             export VOLTA_HOME=\"$HOME/.volta\"\n\
+             export PATH=\"$VOLTA_HOME/bin:$PATH\"\n",
+        )
+        .unwrap();
+
+        let profile = update_profile(Shell::Zsh, home.path(), &volta_home, &bin_dir).unwrap();
+
+        assert_eq!(profile, zshenv);
+        assert_eq!(
+            read_to_string(&profile).unwrap(),
+            format!(
+                "export EDITOR=vim\n\
+                 export VOLTA_HOME=\"{}\" # Added by Volta\n\
+                 export PATH=\"{}:$PATH\" # Added by Volta\n",
+                volta_home.display(),
+                bin_dir.display()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_update_profile_fails_on_unwritable_profile() {
+        use std::fs::{set_permissions, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = tempfile::tempdir().unwrap();
+        let volta_home = home.path().join(".volta");
+        let profile = home.path().join(".zshenv");
+        write(&profile, "").unwrap();
+        set_permissions(&profile, Permissions::from_mode(0o444)).unwrap();
+
+        // Permissions aren't enforced for privileged users, so there is nothing to simulate
+        if std::fs::OpenOptions::new()
+            .write(true)
+            .open(&profile)
+            .is_ok()
+        {
+            return;
+        }
+
+        let error = update_profile(
+            Shell::Zsh,
+            home.path(),
+            &volta_home,
+            &volta_home.join("bin"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::WriteShellProfileError { file: profile }
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_update_profile_fails_on_unreadable_profile() {
+        let home = tempfile::tempdir().unwrap();
+        let volta_home = home.path().join(".volta");
+        // A directory in place of the profile can't be read, but mustn't be treated as empty
+        let profile = home.path().join(".config/fish/config.fish");
+        std::fs::create_dir_all(&profile).unwrap();
+
+        let error = update_profile(
+            Shell::Fish,
+            home.path(),
+            &volta_home,
+            &volta_home.join("bin"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::ReadShellProfileError { file: profile }
+        );
+    }
+}
@@ -6,7 +6,7 @@ use std::io;
 use std::path::Path;
 
 use crate::error::{Context, ErrorKind, Fallible, VoltaError};
-use crate::fs::read_dir_eager;
+use crate::fs::{file_name_key, read_dir_eager};
 use crate::layout::volta_home;
 use crate::sync::VoltaLock;
 use log::debug;
@@ -32,24 +32,38 @@ fn get_shim_list_deduped(dir: &Path) -> Fallible<HashSet<String>> {
 
     #[cfg(unix)]
     {
-        let mut shims: HashSet<String> =
-            contents.filter_map(platform::entry_to_shim_name).collect();
-        shims.insert("node".into());
-        shims.insert("npm".into());
-        shims.insert("npx".into());
-        shims.insert("pnpm".into());
-        shims.insert("yarn".into());
-        shims.insert("yarnpkg".into());
-        Ok(shims)
+        let defaults = ["node", "npm", "npx", "pnpm", "yarn", "yarnpkg"].map(String::from);
+        Ok(dedup_shim_names(
+            defaults
+                .into_iter()
+                .chain(contents.filter_map(platform::entry_to_shim_name)),
+        ))
     }
 
     #[cfg(windows)]
     {
         // On Windows, the default shims are installed in Program Files, so we don't need to generate them here
-        Ok(contents.filter_map(platform::entry_to_shim_name).collect())
+        Ok(dedup_shim_names(
+            contents.filter_map(platform::entry_to_shim_name),
+        ))
     }
 }
 
+/// Removes duplicate shim names, keeping the first of any names that refer to the same shim file
+///
+/// On case-insensitive filesystems, `Node` and `node` are the same shim, so regenerating both
+/// would delete the shim created for the other one.
+fn dedup_shim_names<I>(names: I) -> HashSet<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut seen = HashSet::new();
+    names
+        .into_iter()
+        .filter(|name| seen.insert(file_name_key(name).into_owned()))
+        .collect()
+}
+
 #[derive(PartialEq, Eq)]
 pub enum ShimResult {
     Created,
@@ -195,3 +209,32 @@ volta run "$(basename $0)" "$@""#;
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_dedup_shim_names() {
+        let shims = dedup_shim_names(names(&["node", "tsc", "node", "tsc"]));
+        assert_eq!(shims, HashSet::from(["node".into(), "tsc".into()]));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", windows))]
+    fn test_dedup_shim_names_ignores_case() {
+        let shims = dedup_shim_names(names(&["node", "Node", "TSC", "tsc"]));
+        assert_eq!(shims, HashSet::from(["node".into(), "TSC".into()]));
+    }
+
+    #[test]
+    #[cfg(not(any(target_os = "macos", windows)))]
+    fn test_dedup_shim_names_keeps_case() {
+        let shims = dedup_shim_names(names(&["node", "Node"]));
+        assert_eq!(shims, HashSet::from(["node".into(), "Node".into()]));
+    }
+}
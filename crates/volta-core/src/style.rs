@@ -1,16 +1,23 @@
 //! The view layer of Volta, with utilities for styling command-line output.
 use std::borrow::Cow;
+use std::cell::Cell;
+use std::env;
 use std::error::Error;
+use std::io::IsTerminal;
 use std::time::Duration;
 
 use archive::Origin;
 use cfg_if::cfg_if;
 use console::{style, StyledObject};
 use indicatif::{ProgressBar, ProgressStyle};
+use log::info;
 use terminal_size::{terminal_size, Width};
 
 pub const MAX_WIDTH: usize = 100;
 const MAX_PROGRESS_WIDTH: usize = 40;
+const VOLTA_NO_PROGRESS: &str = "VOLTA_NO_PROGRESS";
+/// The number of plain progress lines reported over the course of an operation
+const PLAIN_PROGRESS_STEPS: u64 = 10;
 
 /// Generate the styled prefix for a success message
 pub fn success_prefix() -> StyledObject<&'static str> {
@@ -53,12 +60,136 @@ pub fn text_width() -> Option<usize> {
     terminal_size().map(|(Width(w), _)| (w as usize).min(MAX_WIDTH))
 }
 
+/// Determines whether Volta is writing its output to an interactive terminal
+pub(crate) fn is_terminal() -> bool {
+    std::io::stdout().is_terminal()
+}
+
+/// Determines whether progress should be shown as an animation, rather than plain lines
+///
+/// Animations are only used in an interactive terminal, and can be disabled entirely by
+/// setting `VOLTA_NO_PROGRESS`.
+fn animate_progress() -> bool {
+    env::var_os(VOLTA_NO_PROGRESS).is_none() && is_terminal()
+}
+
+/// Formats a number of bytes in a human-readable form (e.g. `12.3 MB`)
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// A progress indicator for a long-running operation with a known length.
+///
+/// In an interactive terminal this is an animated progress bar. Otherwise, progress is reported
+/// with periodic plain lines, so that piped output (e.g. CI logs) remains readable.
+pub enum Progress {
+    Bar(ProgressBar),
+    Plain(PlainProgress),
+}
+
+impl Progress {
+    /// Advances the progress by the given number of logical steps
+    pub fn inc(&self, delta: u64) {
+        match self {
+            Progress::Bar(bar) => bar.inc(delta),
+            Progress::Plain(plain) => plain.inc(delta),
+        }
+    }
+
+    /// Completes the progress, removing the bar from the terminal if one was shown
+    pub fn finish_and_clear(&self) {
+        match self {
+            Progress::Bar(bar) => bar.finish_and_clear(),
+            Progress::Plain(plain) => plain.finish(),
+        }
+    }
+}
+
+/// Progress that is reported as a plain line each time another step of the total is completed
+pub struct PlainProgress {
+    action: &'static str,
+    details: String,
+    len: u64,
+    position: Cell<u64>,
+    reported: Cell<u64>,
+}
+
+impl PlainProgress {
+    fn new(action: &'static str, details: &str, len: u64) -> Self {
+        PlainProgress {
+            action,
+            details: details.to_string(),
+            len,
+            position: Cell::new(0),
+            reported: Cell::new(0),
+        }
+    }
+
+    fn inc(&self, delta: u64) {
+        let position = self.position.get().saturating_add(delta).min(self.len);
+        self.position.set(position);
+
+        let step = plain_progress_step(position, self.len);
+        if step > self.reported.get() {
+            self.reported.set(step);
+            self.report();
+        }
+    }
+
+    fn finish(&self) {
+        if self.reported.get() < PLAIN_PROGRESS_STEPS {
+            self.position.set(self.len);
+            self.reported.set(PLAIN_PROGRESS_STEPS);
+            self.report();
+        }
+    }
+
+    fn report(&self) {
+        info!(
+            "{} {}: {} of {}",
+            self.action,
+            self.details,
+            format_bytes(self.position.get()),
+            format_bytes(self.len)
+        );
+    }
+}
+
+/// Determines how many whole steps of the total have been completed
+fn plain_progress_step(position: u64, len: u64) -> u64 {
+    if len == 0 {
+        PLAIN_PROGRESS_STEPS
+    } else {
+        position.saturating_mul(PLAIN_PROGRESS_STEPS) / len
+    }
+}
+
 /// Constructs a command-line progress bar based on the specified Origin enum
 /// (e.g., `Origin::Remote`), details string (e.g., `"v1.23.4"`), and logical
 /// length (i.e., the number of logical progress steps in the process being
 /// visualized by the progress bar).
-pub fn progress_bar(origin: Origin, details: &str, len: u64) -> ProgressBar {
+///
+/// When not running in an interactive terminal, or if `VOLTA_NO_PROGRESS` is set, this will
+/// instead report progress with periodic plain lines.
+pub fn progress_bar(origin: Origin, details: &str, len: u64) -> Progress {
     let action = action_str(origin);
+    if !animate_progress() {
+        return Progress::Plain(PlainProgress::new(action, details, len));
+    }
+
     let action_width = action.len() + 2; // plus 2 spaces to look nice
     let msg_width = action_width + 1 + details.len();
 
@@ -88,17 +219,23 @@ pub fn progress_bar(origin: Origin, details: &str, len: u64) -> ProgressBar {
             .progress_chars("=> "),
     );
 
-    progress
+    Progress::Bar(progress)
 }
 
 cfg_if! {
     if #[cfg(windows)] {
         /// Constructs a command-line progress spinner with the specified "message"
         /// string. The spinner is ticked by default every 100ms.
+        ///
+        /// When progress isn't animated, the message is printed once instead.
         pub fn progress_spinner<S>(message: S) -> ProgressBar
         where
             S: Into<Cow<'static, str>>,
         {
+            if !animate_progress() {
+                return plain_spinner(message);
+            }
+
             let spinner = ProgressBar::new_spinner();
             // Windows CMD prompt doesn't support Unicode characters, so use a simplified spinner
             let style = ProgressStyle::default_spinner().tick_chars(r#"-\|/-"#);
@@ -112,10 +249,16 @@ cfg_if! {
     } else {
         /// Constructs a command-line progress spinner with the specified "message"
         /// string. The spinner is ticked by default every 50ms.
+        ///
+        /// When progress isn't animated, the message is printed once instead.
         pub fn progress_spinner<S>(message: S) -> ProgressBar
         where
             S: Into<Cow<'static, str>>,
         {
+            if !animate_progress() {
+                return plain_spinner(message);
+            }
+
             // ⠋ Fetching public registry: https://nodejs.org/dist/index.json
             let spinner = ProgressBar::new_spinner();
 
@@ -127,3 +270,52 @@ cfg_if! {
         }
     }
 }
+
+/// Logs the spinner message as a plain line, returning a hidden spinner in its place
+fn plain_spinner<S>(message: S) -> ProgressBar
+where
+    S: Into<Cow<'static, str>>,
+{
+    info!("{}", message.into());
+    ProgressBar::hidden()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(42 * 1024 * 1024), "42.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[test]
+    fn test_plain_progress_step() {
+        assert_eq!(plain_progress_step(0, 1000), 0);
+        assert_eq!(plain_progress_step(99, 1000), 0);
+        assert_eq!(plain_progress_step(100, 1000), 1);
+        assert_eq!(plain_progress_step(550, 1000), 5);
+        assert_eq!(plain_progress_step(1000, 1000), PLAIN_PROGRESS_STEPS);
+        assert_eq!(plain_progress_step(0, 0), PLAIN_PROGRESS_STEPS);
+    }
+
+    #[test]
+    fn test_plain_progress_reports_each_step_once() {
+        let progress = PlainProgress::new("Fetching", "node@20.5.1", 1000);
+
+        progress.inc(50);
+        assert_eq!(progress.reported.get(), 0);
+
+        progress.inc(60);
+        assert_eq!(progress.reported.get(), 1);
+
+        progress.inc(2000);
+        assert_eq!(progress.position.get(), 1000);
+        assert_eq!(progress.reported.get(), PLAIN_PROGRESS_STEPS);
+    }
+}
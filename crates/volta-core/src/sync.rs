@@ -20,13 +20,25 @@
 //! This allows multiple code paths to request a lock and not worry about
 //! potential deadlocks, while still preventing multiple processes from making
 //! concurrent changes.
+//!
+//! Operations that only read from the directory can request a shared lock
+//! instead. Since the Volta directory may be read-only (e.g. a shared,
+//! prebuilt installation), shared locks are best-effort: If the lock file
+//! can't be opened, we proceed without a lock rather than failing.
+//!
+//! If an exclusive lock is requested while this process holds a shared lock
+//! (e.g. when Node needs to be fetched while running a tool), the lock is
+//! upgraded to an exclusive one, which is then kept until every lock has been
+//! released.
 
 use std::fs::{File, OpenOptions};
+use std::io;
 use std::marker::PhantomData;
 use std::ops::Drop;
+use std::path::Path;
 use std::sync::Mutex;
 
-use crate::error::{Context, ErrorKind, Fallible};
+use crate::error::{Context, ErrorKind, Fallible, VoltaError};
 use crate::layout::volta_home;
 use crate::style::progress_spinner;
 use fs2::FileExt;
@@ -42,12 +54,49 @@ static LOCK_STATE: Lazy<Mutex<Option<LockState>>> = Lazy::new(|| Mutex::new(None
 /// from the overall process lock and are only used to ensure the count
 /// is accurately maintained within a given process.
 struct LockState {
-    file: File,
+    file: Option<File>,
+    mode: LockMode,
     count: usize,
 }
 
+impl LockState {
+    /// Upgrades a shared lock to an exclusive one
+    ///
+    /// Locks belong to the file handle, so the shared lock is released before the exclusive lock
+    /// is acquired through a handle that can write to the directory. This fails if the directory
+    /// is read-only, in which case the shared lock is kept.
+    fn upgrade(&mut self, path: &Path) -> Fallible<()> {
+        debug!("Upgrading lock on Volta directory: {}", path.display());
+        let file = open_lock_file(path, LockMode::Exclusive)?
+            .expect("The lock file is always opened for an exclusive lock");
+
+        if let Some(shared) = self.file.take() {
+            let _ = shared.unlock();
+        }
+        lock_file(&file, LockMode::Exclusive)
+            .map_err(|error| VoltaError::from_source(error, ErrorKind::LockAcquireError))?;
+
+        self.file = Some(file);
+        self.mode = LockMode::Exclusive;
+        Ok(())
+    }
+}
+
 const LOCK_FILE: &str = "volta.lock";
 
+/// The OS error code for a read-only filesystem, which is the same on Linux and macOS
+#[cfg(unix)]
+const EROFS: i32 = 30;
+
+/// The kind of access to the Volta directory that a lock provides
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LockMode {
+    /// Allows other processes to read, but not modify, the directory
+    Shared,
+    /// Prevents other processes from accessing the directory
+    Exclusive,
+}
+
 /// An RAII implementation of a process lock on the Volta directory. A given Volta process can have
 /// multiple active locks, but only one process can have any locks at a time.
 ///
@@ -59,40 +108,57 @@ pub struct VoltaLock {
 }
 
 impl VoltaLock {
+    /// Acquire an exclusive lock, for operations that modify the Volta directory
     pub fn acquire() -> Fallible<Self> {
+        Self::acquire_with(LockMode::Exclusive)
+    }
+
+    /// Acquire a shared lock, for operations that only read from the Volta directory
+    ///
+    /// If the Volta directory is read-only, this proceeds without a lock instead of failing.
+    pub fn acquire_shared() -> Fallible<Self> {
+        Self::acquire_with(LockMode::Shared)
+    }
+
+    fn acquire_with(mode: LockMode) -> Fallible<Self> {
         let mut state = LOCK_STATE
             .lock()
             .with_context(|| ErrorKind::LockAcquireError)?;
 
         // Check if there is an active lock for this process. If so, increment
-        // the count of active locks. If not, create a file lock and initialize
-        // the state with a count of 1
+        // the count of active locks (upgrading it if an exclusive lock is
+        // needed). If not, create a file lock and initialize the state with a
+        // count of 1
         match &mut *state {
             Some(inner) => {
+                if mode == LockMode::Exclusive && inner.mode == LockMode::Shared {
+                    inner.upgrade(&volta_home()?.root().join(LOCK_FILE))?;
+                }
                 inner.count += 1;
             }
             None => {
                 let path = volta_home()?.root().join(LOCK_FILE);
                 debug!("Acquiring lock on Volta directory: {}", path.display());
 
-                let file = OpenOptions::new()
-                    .write(true)
-                    .create(true)
-                    .open(path)
-                    .with_context(|| ErrorKind::LockAcquireError)?;
-                // First we try to lock the file without blocking. If that fails, then we show a spinner
-                // and block until the lock completes.
-                if file.try_lock_exclusive().is_err() {
-                    let spinner = progress_spinner("Waiting for file lock on Volta directory");
-                    // Note: Blocks until the file can be locked
-                    let lock_result = file
-                        .lock_exclusive()
-                        .with_context(|| ErrorKind::LockAcquireError);
-                    spinner.finish_and_clear();
-                    lock_result?;
-                }
+                let file = match open_lock_file(&path, mode)? {
+                    Some(file) => match lock_file(&file, mode) {
+                        Ok(()) => Some(file),
+                        Err(_) if mode == LockMode::Shared => {
+                            debug!("Unable to lock Volta directory, continuing without a lock");
+                            None
+                        }
+                        Err(error) => {
+                            return Err(VoltaError::from_source(error, ErrorKind::LockAcquireError))
+                        }
+                    },
+                    None => None,
+                };
 
-                *state = Some(LockState { file, count: 1 });
+                *state = Some(LockState {
+                    file,
+                    mode,
+                    count: 1,
+                });
             }
         }
 
@@ -112,7 +178,9 @@ impl Drop for VoltaLock {
                 Some(inner) => {
                     if inner.count == 1 {
                         debug!("Unlocking Volta Directory");
-                        let _ = inner.file.unlock();
+                        if let Some(file) = &inner.file {
+                            let _ = file.unlock();
+                        }
                         *state = None;
                     } else {
                         inner.count -= 1;
@@ -125,3 +193,141 @@ impl Drop for VoltaLock {
         }
     }
 }
+
+/// Opens the lock file, returning `None` if a shared lock was requested in a read-only directory
+fn open_lock_file(path: &Path, mode: LockMode) -> Fallible<Option<File>> {
+    match OpenOptions::new().write(true).create(true).open(path) {
+        Ok(file) => Ok(Some(file)),
+        Err(error) if is_read_only(&error) => match mode {
+            LockMode::Exclusive => Err(VoltaError::from_source(
+                error,
+                ErrorKind::ReadOnlyVoltaHome {
+                    home: path.parent().unwrap_or(path).to_owned(),
+                },
+            )),
+            // An existing lock file can still be locked through a read-only handle
+            LockMode::Shared => match File::open(path) {
+                Ok(file) => Ok(Some(file)),
+                Err(_) => {
+                    debug!("Volta directory is read-only, continuing without a lock");
+                    Ok(None)
+                }
+            },
+        },
+        Err(error) => Err(VoltaError::from_source(error, ErrorKind::LockAcquireError)),
+    }
+}
+
+/// Locks the file in the given mode, blocking (and showing a spinner) if another process holds it
+fn lock_file(file: &File, mode: LockMode) -> io::Result<()> {
+    let try_lock = match mode {
+        LockMode::Shared => file.try_lock_shared(),
+        LockMode::Exclusive => file.try_lock_exclusive(),
+    };
+
+    // First we try to lock the file without blocking. If that fails, then we show a spinner
+    // and block until the lock completes.
+    if try_lock.is_err() {
+        let spinner = progress_spinner("Waiting for file lock on Volta directory");
+        // Note: Blocks until the file can be locked
+        let lock_result = match mode {
+            LockMode::Shared => file.lock_shared(),
+            LockMode::Exclusive => file.lock_exclusive(),
+        };
+        spinner.finish_and_clear();
+        lock_result?;
+    }
+
+    Ok(())
+}
+
+/// Determines whether an error opening a file was caused by the directory being read-only
+fn is_read_only(error: &io::Error) -> bool {
+    #[cfg(unix)]
+    if error.raw_os_error() == Some(EROFS) {
+        return true;
+    }
+
+    error.kind() == io::ErrorKind::PermissionDenied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_read_only() {
+        assert!(is_read_only(&io::ErrorKind::PermissionDenied.into()));
+        #[cfg(unix)]
+        assert!(is_read_only(&io::Error::from_raw_os_error(EROFS)));
+        assert!(!is_read_only(&io::ErrorKind::NotFound.into()));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_open_lock_file_read_only() {
+        use std::fs::{set_permissions, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(LOCK_FILE);
+        set_permissions(home.path(), Permissions::from_mode(0o555)).unwrap();
+
+        // Permissions aren't enforced for privileged users, so there is nothing to simulate
+        if File::create(home.path().join("probe")).is_ok() {
+            return;
+        }
+
+        assert!(open_lock_file(&path, LockMode::Shared).unwrap().is_none());
+        assert!(open_lock_file(&path, LockMode::Exclusive).is_err());
+
+        // A shared lock can't be upgraded, since the directory can't be modified anyway
+        let mut state = LockState {
+            file: None,
+            mode: LockMode::Shared,
+            count: 1,
+        };
+        assert!(state.upgrade(&path).is_err());
+        assert_eq!(state.mode, LockMode::Shared);
+
+        set_permissions(home.path(), Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_open_lock_file_writable() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(LOCK_FILE);
+
+        let file = open_lock_file(&path, LockMode::Shared).unwrap().unwrap();
+        lock_file(&file, LockMode::Shared).unwrap();
+        file.unlock().unwrap();
+
+        assert!(open_lock_file(&path, LockMode::Exclusive)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_upgrade_shared_lock() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(LOCK_FILE);
+
+        let file = open_lock_file(&path, LockMode::Shared).unwrap().unwrap();
+        lock_file(&file, LockMode::Shared).unwrap();
+        let mut state = LockState {
+            file: Some(file),
+            mode: LockMode::Shared,
+            count: 1,
+        };
+
+        state.upgrade(&path).unwrap();
+        assert_eq!(state.mode, LockMode::Exclusive);
+
+        // Other handles (as in other processes) can no longer lock the directory
+        let other = File::open(&path).unwrap();
+        assert!(other.try_lock_shared().is_err());
+
+        state.file.unwrap().unlock().unwrap();
+        assert!(other.try_lock_shared().is_ok());
+    }
+}
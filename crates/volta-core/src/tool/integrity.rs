@@ -0,0 +1,239 @@
+//! Provides verification of downloaded tarballs against the checksums published by the npm
+//! registry, following npm's own rules: the sha512 `integrity` is preferred, and the legacy sha1
+//! `shasum` is only used when no `integrity` is available.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use super::download_tool_error;
+use super::registry::{fetch_dist_info, public_registry_index, RawDistInfo};
+use super::Spec;
+use crate::error::{Context, ErrorKind, Fallible};
+use crate::style::progress_spinner;
+use archive::{Archive, ArchiveError, Tarball};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use log::debug;
+use node_semver::Version;
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+
+/// The expected checksum of a package tarball
+#[derive(Debug, PartialEq, Eq)]
+pub enum Integrity {
+    /// A base64-encoded sha512 digest, from a Subresource Integrity string
+    Sha512(String),
+
+    /// A hex-encoded sha1 digest, from the legacy `shasum` field
+    Sha1(String),
+}
+
+impl Integrity {
+    /// Determines the integrity to check from the registry's `dist` info
+    pub fn from_dist(dist: &RawDistInfo) -> Option<Self> {
+        // An SRI string may contain multiple space-separated hashes; we only support sha512
+        let sha512 = dist.integrity.as_deref().and_then(|integrity| {
+            integrity
+                .split_whitespace()
+                .find_map(|hash| hash.strip_prefix("sha512-"))
+        });
+
+        // An empty `shasum` is the same as a missing one
+        let sha1 = dist.shasum.as_deref().filter(|shasum| !shasum.is_empty());
+
+        match (sha512, sha1) {
+            (Some(digest), _) => Some(Integrity::Sha512(digest.to_string())),
+            (None, Some(shasum)) => Some(Integrity::Sha1(shasum.to_ascii_lowercase())),
+            (None, None) => None,
+        }
+    }
+
+    /// Checks whether the data matches this integrity
+    pub fn matches<R: Read>(&self, mut data: R) -> io::Result<bool> {
+        Ok(match self {
+            Integrity::Sha512(expected) => sha512_digest(data)? == *expected,
+            Integrity::Sha1(expected) => {
+                let mut hasher = Sha1::new();
+                io::copy(&mut data, &mut hasher)?;
+                format!("{:x}", hasher.finalize()) == *expected
+            }
+        })
+    }
+}
+
+/// Computes the Subresource Integrity string (`sha512-...`) of some data
+pub fn sha512_integrity<R: Read>(data: R) -> io::Result<String> {
+    sha512_digest(data).map(|digest| format!("sha512-{}", digest))
+}
+
+/// Computes the base64-encoded sha512 digest of some data
+fn sha512_digest<R: Read>(mut data: R) -> io::Result<String> {
+    let mut hasher = Sha512::new();
+    io::copy(&mut data, &mut hasher)?;
+    Ok(STANDARD.encode(hasher.finalize()))
+}
+
+/// Looks up the integrity that the public registry publishes for a specific package version
+///
+/// The registry document fetched while resolving the version is used if there is one. Returns
+/// `None` if the registry doesn't publish a checksum for the version, in which case the download
+/// can't be verified.
+pub fn published_integrity(package: &str, version: &Version) -> Fallible<Option<Integrity>> {
+    let dist = fetch_dist_info(public_registry_index(package), package, version)?;
+    if dist.is_none() {
+        debug!(
+            "Registry document for {} doesn't include {}",
+            package, version
+        );
+    }
+
+    Ok(dist.as_ref().and_then(Integrity::from_dist))
+}
+
+/// Downloads a tool archive in full and verifies it against its published integrity, if any
+///
+/// The integrity is only looked up once the download has succeeded, and nothing is unpacked from
+/// the archive until it has been verified, so the returned archive is loaded from the downloaded
+/// file. The checksum computed while verifying is returned along with the archive.
+pub(crate) fn download_verified<F>(
+    tool: Spec,
+    url: &str,
+    staging: &Path,
+    integrity: F,
+) -> Fallible<(Box<dyn Archive>, String)>
+where
+    F: FnOnce() -> Fallible<Option<Integrity>>,
+{
+    let tool_name = tool.to_string();
+    let spinner = progress_spinner(format!("Fetching {}", tool_name));
+    let archive = archive::download(url, staging, &mut |_| {})
+        .and_then(|_| File::open(staging).map_err(ArchiveError::from))
+        .and_then(Tarball::load);
+    spinner.finish_and_clear();
+    let archive = archive.with_context(download_tool_error(tool, url))?;
+
+    let checksum = verify_download(staging, integrity()?.as_ref(), &tool_name, url)?;
+
+    Ok((archive, checksum))
+}
+
+/// Verifies a downloaded archive against its published integrity, if any
+///
+/// Returns the Subresource Integrity string (`sha512-...`) of the archive, so that it doesn't
+/// have to be read again to record its checksum.
+pub fn verify_download(
+    archive: &Path,
+    integrity: Option<&Integrity>,
+    tool: &str,
+    from_url: &str,
+) -> Fallible<String> {
+    let mismatch = || ErrorKind::IntegrityMismatch {
+        tool: tool.into(),
+        from_url: from_url.into(),
+    };
+    let digest = File::open(archive)
+        .and_then(sha512_digest)
+        .with_context(mismatch)?;
+
+    let verified = match integrity {
+        None => true,
+        Some(Integrity::Sha512(expected)) => digest == *expected,
+        // The legacy sha1 isn't computed along with the sha512, so the archive is read again
+        Some(sha1) => File::open(archive)
+            .and_then(|file| sha1.matches(file))
+            .unwrap_or(false),
+    };
+    if !verified {
+        return Err(mismatch().into());
+    }
+    if integrity.is_some() {
+        debug!("Verified the integrity of {}", from_url);
+    }
+
+    Ok(format!("sha512-{}", digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dist(integrity: Option<&str>, shasum: Option<&str>) -> RawDistInfo {
+        RawDistInfo {
+            integrity: integrity.map(String::from),
+            shasum: shasum.map(String::from),
+            tarball: "https://registry.npmjs.org/pkg/-/pkg-1.0.0.tgz".into(),
+        }
+    }
+
+    #[test]
+    fn test_from_dist_prefers_integrity() {
+        let info = dist(
+            Some("sha1-deadbeef sha512-c29tZSBkYXRh"),
+            Some("0123456789ABCDEF"),
+        );
+        assert_eq!(
+            Integrity::from_dist(&info),
+            Some(Integrity::Sha512("c29tZSBkYXRh".into()))
+        );
+
+        let info = dist(None, Some("0123456789ABCDEF"));
+        assert_eq!(
+            Integrity::from_dist(&info),
+            Some(Integrity::Sha1("0123456789abcdef".into()))
+        );
+
+        assert_eq!(Integrity::from_dist(&dist(None, None)), None);
+        assert_eq!(Integrity::from_dist(&dist(Some(""), Some(""))), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let data = b"hello world";
+
+        // echo -n "hello world" | openssl dgst -sha512 -binary | base64
+        let sha512 = Integrity::Sha512(
+            "MJ7MSJwS1utMxA9QyQLytNDtd+5RGnx6m808qG1M2G+YndNbxf9JlnDaNCVbRbDP2DDoH2Bdz33FVC6TrpzXbw=="
+                .into(),
+        );
+        assert!(sha512.matches(&data[..]).unwrap());
+        assert!(!sha512.matches(&b"goodbye world"[..]).unwrap());
+
+        // echo -n "hello world" | shasum
+        let sha1 = Integrity::Sha1("2aae6c35c94fcfb415dbe95f408b9ce91ee846ed".into());
+        assert!(sha1.matches(&data[..]).unwrap());
+        assert!(!sha1.matches(&b"goodbye world"[..]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_download_rejects_mismatched_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("pkg-1.0.0.tgz");
+        std::fs::write(&archive, b"tampered").unwrap();
+
+        let sha1 = Integrity::Sha1("2aae6c35c94fcfb415dbe95f408b9ce91ee846ed".into());
+        let error =
+            verify_download(&archive, Some(&sha1), "pkg", "https://example.com").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::IntegrityMismatch { .. }));
+
+        std::fs::write(&archive, b"hello world").unwrap();
+        assert!(verify_download(&archive, Some(&sha1), "pkg", "https://example.com").is_ok());
+    }
+
+    #[test]
+    fn test_verify_download_returns_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("pkg-1.0.0.tgz");
+        std::fs::write(&archive, b"hello world").unwrap();
+        let expected = sha512_integrity(&b"hello world"[..]).unwrap();
+
+        let sha512 = Integrity::Sha512(expected.trim_start_matches("sha512-").into());
+        let checksum =
+            verify_download(&archive, Some(&sha512), "pkg", "https://example.com").unwrap();
+        assert_eq!(checksum, expected);
+
+        // Unverified downloads still have their checksum computed
+        let checksum = verify_download(&archive, None, "pkg", "https://example.com").unwrap();
+        assert_eq!(checksum, expected);
+    }
+}
@@ -1,40 +1,151 @@
+use std::collections::BTreeSet;
 use std::env;
 use std::fmt::{self, Display};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::error::{ErrorKind, Fallible};
-use crate::layout::volta_home;
+use crate::error::{ErrorKind, Fallible, VoltaError};
+use crate::fs::{file_name_key, path_starts_with, remove_dir_if_exists, remove_file_if_exists};
+use crate::inventory::{node_versions, npm_versions, pnpm_versions, yarn_versions};
+use crate::layout::{
+    installed_node_images, installed_npm_images, installed_pnpm_images, installed_yarn_images,
+    volta_home,
+};
+use crate::platform::{PlatformNode, PlatformSpec, SYSTEM_NODE};
+use crate::project::{is_frozen, Lockfile, PinnedSpecs};
 use crate::session::Session;
 use crate::style::{note_prefix, success_prefix, tool_version};
 use crate::sync::VoltaLock;
-use crate::version::VersionSpec;
+use crate::version::{VersionSpec, VersionTag};
+use crate::warning::Warning;
 use crate::VOLTA_FEATURE_PNPM;
 use cfg_if::cfg_if;
+use dunce::canonicalize;
 use log::{debug, info};
+use node_semver::{Range, Version};
 
+mod integrity;
 pub mod node;
 pub mod npm;
 pub mod package;
+mod plan;
 pub mod pnpm;
+mod provenance;
 mod registry;
 mod serial;
 pub mod yarn;
 
 pub use node::{
-    load_default_npm_version, Node, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION, NODE_DISTRO_OS,
+    load_default_npm_version, Node, NodeTarget, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION,
+    NODE_DISTRO_OS,
 };
 pub use npm::{BundledNpm, Npm};
 pub use package::{BinConfig, Package, PackageConfig, PackageManifest};
+pub use plan::{PlanAction, PlanChange, PlanSource, ToolPlan};
 pub use pnpm::Pnpm;
+pub use provenance::{read_provenance, Provenance};
 pub use registry::PackageDetails;
 pub use yarn::Yarn;
 
+/// Environment variable that enables fetching the whole project platform after `volta pin`
+pub const VOLTA_PREFETCH_ON_PIN: &str = "VOLTA_PREFETCH_ON_PIN";
+
+/// Environment variable that disables keeping downloaded archives in the inventory cache
+const VOLTA_NO_ARCHIVE_CACHE: &str = "VOLTA_NO_ARCHIVE_CACHE";
+
+/// Whether downloaded archives should be kept in the inventory after they are unpacked
+///
+/// Setting `VOLTA_NO_ARCHIVE_CACHE` discards each archive once its image is installed, which
+/// saves disk space at the cost of downloading the archive again if the image is ever removed.
+fn cache_archives() -> bool {
+    env::var_os(VOLTA_NO_ARCHIVE_CACHE).is_none()
+}
+
+/// Environment variable that bypasses cached registry metadata, so that it is always fetched
+const VOLTA_NO_CACHE: &str = "VOLTA_NO_CACHE";
+
+/// Whether cached registry metadata (the Node index and npm package documents) may be used
+fn use_metadata_cache() -> bool {
+    env::var_os(VOLTA_NO_CACHE).is_none()
+}
+
+/// The multiple of an archive's size needed on disk to both keep the archive and unpack it
+///
+/// Node archives unpack to roughly four times their compressed size.
+const DISK_SPACE_FACTOR: u64 = 5;
+
+/// Checks that there is enough disk space to download and unpack an archive of the given size
+///
+/// If the available space can't be determined, the download goes ahead without a check, rather
+/// than failing on a platform or file system that can't report it.
+fn check_disk_space(compressed_size: u64) -> Fallible<()> {
+    let dir = volta_home()?.tmp_dir();
+    match fs2::available_space(dir) {
+        Ok(available) => ensure_disk_space(compressed_size, available),
+        Err(error) => {
+            debug!(
+                "Could not determine the available disk space in '{}': {}",
+                dir.display(),
+                error
+            );
+            Ok(())
+        }
+    }
+}
+
+fn ensure_disk_space(compressed_size: u64, available: u64) -> Fallible<()> {
+    let needed = compressed_size.saturating_mul(DISK_SPACE_FACTOR);
+    if needed > available {
+        Err(ErrorKind::InsufficientDiskSpace { needed, available }.into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Fetches every tool pinned in the current project into the inventory, without changing the
+/// user's defaults, so that the first run of the project's tools doesn't need to download them
+pub fn prefetch_project_platform(session: &mut Session) -> Fallible<()> {
+    if let Some(platform) = session.project_platform()?.map(PlatformSpec::as_project) {
+        debug!("Prefetching pinned platform: {}", platform);
+        platform.checkout(session)?;
+    }
+
+    Ok(())
+}
+
 fn debug_already_fetched<T: Display>(tool: T) {
     debug!("{} has already been fetched, skipping download", tool);
 }
 
-fn info_installed<T: Display>(tool: T) {
-    info!("{} installed and set {tool} as default", success_prefix());
+fn info_installed<T: Display>(tool: T, fetched: FetchSummary) {
+    match fetched.outcome {
+        FetchOutcome::AlreadyInstalled => {
+            info!("{} installed and set {tool} as default", success_prefix())
+        }
+        _ => info!(
+            "{} installed and set {tool} as default ({fetched})",
+            success_prefix()
+        ),
+    }
+}
+
+/// Reports a failure to save the default platform after `tool` was fetched as the tool not being
+/// activated, so that the user knows the fetch succeeded and the previous default is still in use
+///
+/// Setting a package manager without a default Node is already reported clearly, so that error is
+/// passed through as-is.
+fn not_activated(tool: String) -> impl FnOnce(VoltaError) -> VoltaError {
+    move |error| match error.kind() {
+        ErrorKind::NoDefaultNodeVersion { .. } => error,
+        _ => VoltaError::from_source(error, ErrorKind::ToolNotActivated { tool }),
+    }
+}
+
+fn info_already_default<T: Display>(tool: T) {
+    info!(
+        "{} {tool} is already installed and set as default",
+        success_prefix()
+    );
 }
 
 fn info_fetched<T: Display>(tool: T) {
@@ -45,26 +156,53 @@ fn info_pinned<T: Display>(tool: T) {
     info!("{} pinned {tool} in package.json", success_prefix());
 }
 
-fn info_project_version<P, D>(project_version: P, default_version: D)
-where
-    P: Display,
-    D: Display,
-{
-    info!(
-        r#"{} you are using {project_version} in the current project; to
-         instead use {default_version}, run `volta pin {default_version}`"#,
-        note_prefix()
-    );
+fn warn_project_version<D: Display>(
+    session: &mut Session,
+    project_version: String,
+    default_version: D,
+) {
+    session.warn(Warning::ProjectVersionDiffers {
+        project_version,
+        default_version: default_version.to_string(),
+    });
 }
 
 /// Trait representing all of the actions that can be taken with a tool
 pub trait Tool: Display {
-    /// Fetch a Tool into the local inventory
-    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<()>;
+    /// Fetch a Tool into the local inventory, reporting where it was fetched from
+    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<FetchOutcome>;
     /// Install a tool, making it the default so it is available everywhere on the user's machine
     fn install(self: Box<Self>, session: &mut Session) -> Fallible<()>;
     /// Pin a tool in the local project so that it is usable within the project
     fn pin(self: Box<Self>, session: &mut Session) -> Fallible<()>;
+    /// The kind of tool, for logging and metrics
+    fn kind(&self) -> ToolKindName;
+    /// Describe what the given action will do with this tool, without performing it
+    fn describe_plan(&self, action: PlanAction, _session: &Session) -> Fallible<ToolPlan> {
+        Ok(ToolPlan::new(self, action))
+    }
+}
+
+/// The kinds of tools that Volta manages
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ToolKindName {
+    Node,
+    Npm,
+    Pnpm,
+    Yarn,
+    Package,
+}
+
+impl Display for ToolKindName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ToolKindName::Node => "node",
+            ToolKindName::Npm => "npm",
+            ToolKindName::Pnpm => "pnpm",
+            ToolKindName::Yarn => "yarn",
+            ToolKindName::Package => "package",
+        })
+    }
 }
 
 /// Specification for a tool and its associated version.
@@ -82,20 +220,28 @@ impl Spec {
     /// Resolve a tool spec into a fully realized Tool that can be fetched
     pub fn resolve(self, session: &mut Session) -> Fallible<Box<dyn Tool>> {
         match self {
+            Spec::Node(VersionSpec::Local(archive)) => {
+                Ok(Box::new(Node::from_local_archive(archive)?))
+            }
             Spec::Node(version) => {
+                let version = lock_spec(session, "node", version, |lock| lock.node.as_ref())?;
                 let version = node::resolve(version, session)?;
                 Ok(Box::new(Node::new(version)))
             }
-            Spec::Npm(version) => match npm::resolve(version, session)? {
-                Some(version) => Ok(Box::new(Npm::new(version))),
-                None => Ok(Box::new(BundledNpm)),
-            },
+            Spec::Npm(version) => {
+                let version = lock_spec(session, "npm", version, |lock| lock.npm.as_ref())?;
+                match npm::resolve(version, session)? {
+                    Some(version) => Ok(Box::new(Npm::new(version))),
+                    None => Ok(Box::new(BundledNpm)),
+                }
+            }
             Spec::Pnpm(version) => {
                 // If the pnpm feature flag is set, use the special-cased package manager logic
                 // to handle resolving (and ultimately fetching / installing) pnpm. If not, then
                 // fall back to the global package behavior, which was the case prior to pnpm
                 // support being added
                 if env::var_os(VOLTA_FEATURE_PNPM).is_some() {
+                    let version = lock_spec(session, "pnpm", version, |lock| lock.pnpm.as_ref())?;
                     let version = pnpm::resolve(version, session)?;
                     Ok(Box::new(Pnpm::new(version)))
                 } else {
@@ -104,6 +250,7 @@ impl Spec {
                 }
             }
             Spec::Yarn(version) => {
+                let version = lock_spec(session, "yarn", version, |lock| lock.yarn.as_ref())?;
                 let version = yarn::resolve(version, session)?;
                 Ok(Box::new(Yarn::new(version)))
             }
@@ -115,6 +262,63 @@ impl Spec {
         }
     }
 
+    /// Resolve a tool spec for pinning into the current project
+    ///
+    /// Pinning Node without a version pins the user's default Node, so that the project matches
+    /// the version they are already using. Only if there is no default (or the default is the
+    /// system Node) does it fall back to resolving the latest LTS, as `resolve` does.
+    pub fn resolve_for_pin(self, session: &mut Session) -> Fallible<Box<dyn Tool>> {
+        match self {
+            Spec::Node(VersionSpec::None) => {
+                let default = session
+                    .default_platform()?
+                    .map(|platform| platform.node.clone())
+                    .filter(|node| !node.is_system());
+
+                match default {
+                    Some(version) => {
+                        debug!("Pinning the default Node version ({})", version);
+                        Ok(Box::new(Node::new(version)))
+                    }
+                    None => Spec::Node(VersionSpec::None).resolve(session),
+                }
+            }
+            spec => spec.resolve(session),
+        }
+    }
+
+    /// Fetch a tool for the given target platform, which may not be the current one
+    ///
+    /// Only Node is distributed separately for each platform, so every other tool is fetched as
+    /// usual. Node for another platform is only downloaded into the inventory, since it can't be
+    /// unpacked and used here.
+    pub fn fetch_for_target(
+        self,
+        target: &NodeTarget,
+        session: &mut Session,
+    ) -> Fallible<FetchOutcome> {
+        match self {
+            Spec::Node(version)
+                if !target.is_host() && !matches!(version, VersionSpec::Local(_)) =>
+            {
+                let version = lock_spec(session, "node", version, |lock| lock.node.as_ref())?;
+                let PlatformNode::Version(version) = node::resolve(version, session)? else {
+                    return Err(ErrorKind::Unimplemented {
+                        feature: "Fetching the system Node for another platform".into(),
+                    }
+                    .into());
+                };
+
+                let (archive, outcome) =
+                    node::fetch_for_target(&version, target, session.hooks()?.node())?;
+                debug!("Node for {} is at '{}'", target, archive.display());
+                info_fetched(format!("{} for {}", tool_version("node", &version), target));
+                Ok(outcome)
+            }
+            spec => spec.resolve(session)?.fetch(session),
+        }
+    }
+
     /// Uninstall a tool, removing it from the local inventory
     ///
     /// This is implemented on Spec, instead of Resolved, because there is currently no need to
@@ -147,6 +351,84 @@ impl Spec {
         }
     }
 
+    /// Remove the fetched versions of a tool that match its version, except the one in the user's
+    /// default platform
+    ///
+    /// A range (e.g. `node@<18`) removes every fetched version that satisfies it and an exact
+    /// version removes only that version, while a tool without a version has every fetched
+    /// version removed. Both the unpacked image and the cached archive are removed for each
+    /// version. Returns the versions that were removed, from oldest to newest.
+    pub fn uninstall_versions(self, session: &mut Session) -> Fallible<Vec<Version>> {
+        // Acquire a lock on the Volta directory, if possible, to prevent concurrent changes
+        let _lock = VoltaLock::acquire();
+        let home = volta_home()?;
+        let default = session.default_platform()?;
+
+        let removed = match self {
+            Spec::Node(matching) => {
+                let active = default.and_then(|platform| platform.node.version());
+                let removed = images_to_remove(installed_node_images()?, active, &matching);
+                for (version, image_dir) in &removed {
+                    let version_str = version.to_string();
+                    remove_dir_if_exists(image_dir)?;
+                    remove_file_if_exists(home.node_npm_version_file(&version_str))?;
+                    remove_file_if_exists(
+                        home.node_inventory_dir()
+                            .join(Node::archive_filename(version)),
+                    )?;
+                }
+                removed
+            }
+            Spec::Npm(matching) => {
+                let active = default.and_then(|platform| platform.npm.as_ref());
+                let removed = images_to_remove(installed_npm_images()?, active, &matching);
+                for (version, image_dir) in &removed {
+                    let version_str = version.to_string();
+                    remove_dir_if_exists(image_dir)?;
+                    remove_file_if_exists(
+                        home.npm_inventory_dir()
+                            .join(Npm::archive_filename(&version_str)),
+                    )?;
+                }
+                removed
+            }
+            Spec::Pnpm(matching) => {
+                let active = default.and_then(|platform| platform.pnpm.as_ref());
+                let removed = images_to_remove(installed_pnpm_images()?, active, &matching);
+                for (version, image_dir) in &removed {
+                    let version_str = version.to_string();
+                    remove_dir_if_exists(image_dir)?;
+                    remove_file_if_exists(
+                        home.pnpm_inventory_dir()
+                            .join(Pnpm::archive_filename(&version_str)),
+                    )?;
+                }
+                removed
+            }
+            Spec::Yarn(matching) => {
+                let active = default.and_then(|platform| platform.yarn.as_ref());
+                let removed = images_to_remove(installed_yarn_images()?, active, &matching);
+                for (version, image_dir) in &removed {
+                    let version_str = version.to_string();
+                    remove_dir_if_exists(image_dir)?;
+                    remove_file_if_exists(
+                        home.yarn_inventory_dir()
+                            .join(Yarn::archive_filename(&version_str)),
+                    )?;
+                }
+                removed
+            }
+            Spec::Package(..) => {
+                return Err(ErrorKind::Unimplemented {
+                    feature: "Uninstalling versions of a package".into(),
+                }
+                .into())
+            }
+        };
+
+        Ok(removed.into_iter().map(|(version, _)| version).collect())
+    }
+
     /// The name of the tool, without the version, used for messaging
     pub fn name(&self) -> &str {
         match self {
@@ -172,6 +454,334 @@ impl Display for Spec {
     }
 }
 
+/// Lists the versions of a tool that are available remotely, sorted from newest to oldest
+///
+/// Versions are read from the same index or registry (including mirrors and hooks) used when
+/// resolving a version to install. If `filter` is provided, only versions satisfying it are
+/// returned. `lts_only` restricts the list to LTS releases, which only applies to Node.
+pub fn available_versions(
+    session: &mut Session,
+    tool: &str,
+    filter: Option<Range>,
+    lts_only: bool,
+) -> Fallible<Vec<Version>> {
+    let versions = match tool {
+        "node" => node::available(lts_only, session)?,
+        "npm" => npm::available(session)?,
+        "pnpm" if env::var_os(VOLTA_FEATURE_PNPM).is_some() => pnpm::available(session)?,
+        "yarn" => yarn::available(session)?,
+        package => {
+            let (_, index) =
+                registry::fetch_npm_registry(registry::public_registry_index(package), package)?;
+            index
+                .entries
+                .into_iter()
+                .map(|details| details.version)
+                .collect()
+        }
+    };
+
+    Ok(sort_versions(versions, filter.as_ref()))
+}
+
+/// Selects the fetched images matching a version that can be removed without leaving the default
+/// platform dangling
+fn images_to_remove(
+    installed: Vec<(Version, PathBuf)>,
+    active: Option<&Version>,
+    matching: &VersionSpec,
+) -> Vec<(Version, PathBuf)> {
+    installed
+        .into_iter()
+        .filter(|(version, _)| !active.is_some_and(|active| same_image(version, active)))
+        .filter(|(version, _)| match matching {
+            VersionSpec::Semver(range) => range.satisfies(version),
+            VersionSpec::Exact(exact) => version == exact,
+            VersionSpec::None => true,
+            // Tags and paths can't be matched against the fetched versions
+            _ => false,
+        })
+        .collect()
+}
+
+/// Whether two versions use the same image directory, which on case-insensitive filesystems
+/// includes versions whose pre-release tags only differ in case
+fn same_image(version: &Version, other: &Version) -> bool {
+    file_name_key(&version.to_string()) == file_name_key(&other.to_string())
+}
+
+/// Sorts versions from newest to oldest, removing duplicates and any that don't match the filter
+fn sort_versions(mut versions: Vec<Version>, filter: Option<&Range>) -> Vec<Version> {
+    if let Some(range) = filter {
+        versions.retain(|version| range.satisfies(version));
+    }
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    versions.dedup();
+    versions
+}
+
+/// Resolves the versions pinned in a project to the exact platform that its tools run with
+///
+/// A version recorded in the project lockfile is used if it matches the pin, followed by the
+/// newest fetched version that satisfies a range, so only versions that aren't available
+/// locally are resolved against the index or registry. If the lockfile is frozen, nothing is
+/// resolved remotely: the project must have a lockfile, and only the locked and fetched versions
+/// can be used.
+pub(crate) fn resolve_pinned(
+    pinned: &PinnedSpecs,
+    lockfile: Option<&Lockfile>,
+    lockfile_file: &Path,
+    session: &mut Session,
+) -> Fallible<PlatformSpec> {
+    let frozen = is_frozen();
+    let frozen_error = |reason: String| -> VoltaError {
+        ErrorKind::FrozenLockfileError {
+            file: lockfile_file.to_owned(),
+            reason,
+        }
+        .into()
+    };
+    if frozen && lockfile.is_none() {
+        return Err(frozen_error("The project doesn't have a lockfile".into()));
+    }
+    let allow_remote = |tool: &str, spec: &VersionSpec| -> Fallible<()> {
+        if frozen {
+            Err(frozen_error(format!(
+                "The lockfile doesn't have a version of {} matching '{}'",
+                tool, spec
+            )))
+        } else {
+            Ok(())
+        }
+    };
+    let locked = lockfile.cloned().unwrap_or_default();
+
+    let node = match &pinned.node {
+        Some(VersionSpec::Tag(VersionTag::Custom(tag))) if tag == SYSTEM_NODE => {
+            Some(PlatformNode::System)
+        }
+        Some(spec) => pinned_version(spec, locked.node.as_ref(), node_versions, |spec| {
+            allow_remote("node", &spec)?;
+            node::resolve_version(spec, session).map(Some)
+        })?
+        .map(PlatformNode::Version),
+        None => None,
+    };
+    let npm = match &pinned.npm {
+        Some(spec) => pinned_version(spec, locked.npm.as_ref(), npm_versions, |spec| {
+            allow_remote("npm", &spec)?;
+            npm::resolve(spec, session)
+        })?,
+        None => None,
+    };
+    let pnpm = match &pinned.pnpm {
+        Some(spec) => pinned_version(spec, locked.pnpm.as_ref(), pnpm_versions, |spec| {
+            allow_remote("pnpm", &spec)?;
+            pnpm::resolve(spec, session).map(Some)
+        })?,
+        None => None,
+    };
+    let yarn = match &pinned.yarn {
+        Some(spec) => pinned_version(spec, locked.yarn.as_ref(), yarn_versions, |spec| {
+            allow_remote("yarn", &spec)?;
+            yarn::resolve(spec, session).map(Some)
+        })?,
+        None => None,
+    };
+
+    Ok(PlatformSpec {
+        node: node.ok_or(ErrorKind::NoProjectNodeInManifest)?,
+        npm,
+        pnpm,
+        yarn,
+    })
+}
+
+/// Selects the version for a single pinned tool, only calling `resolve` if there isn't a locked
+/// or fetched version that can be used
+fn pinned_version<R>(
+    spec: &VersionSpec,
+    locked: Option<&Version>,
+    fetched: fn() -> Fallible<BTreeSet<Version>>,
+    resolve: R,
+) -> Fallible<Option<Version>>
+where
+    R: FnOnce(VersionSpec) -> Fallible<Option<Version>>,
+{
+    let local = match spec {
+        VersionSpec::Exact(version) => Some(version.clone()),
+        VersionSpec::Semver(range) => match locked {
+            Some(locked) if range.satisfies(locked) => Some(locked.clone()),
+            _ => fetched()?
+                .into_iter()
+                .rev()
+                .find(|version| range.satisfies(version)),
+        },
+        VersionSpec::Tag(_) => locked.cloned(),
+        _ => None,
+    };
+
+    match local {
+        Some(version) => {
+            debug!("Using {} for pinned version '{}'", version, spec);
+            Ok(Some(version))
+        }
+        None => resolve(spec.clone()),
+    }
+}
+
+/// Replaces a semver range with the version recorded in the project lockfile, if it matches
+///
+/// Exact versions don't need resolving and tags (e.g. `latest`) are always resolved against the
+/// registry, so only ranges are affected. If the lockfile is frozen, however, only the locked
+/// version can be used: a missing version resolves to it, and any other version (or a missing
+/// lockfile or locked version) is an error.
+fn lock_spec<F>(
+    session: &Session,
+    tool: &str,
+    version: VersionSpec,
+    select: F,
+) -> Fallible<VersionSpec>
+where
+    F: FnOnce(&Lockfile) -> Option<&Version>,
+{
+    let Some(project) = session.project()? else {
+        return Ok(version);
+    };
+    let frozen_error = |reason: String| -> Fallible<VersionSpec> {
+        Err(ErrorKind::FrozenLockfileError {
+            file: project.lockfile_file(),
+            reason,
+        }
+        .into())
+    };
+
+    let locked = match (project.lockfile(), &version) {
+        (_, VersionSpec::Local(_) | VersionSpec::Git(_)) => return Ok(version),
+        (Some(lockfile), _) => select(lockfile),
+        (None, _) if is_frozen() => {
+            return frozen_error("The project doesn't have a lockfile".into())
+        }
+        (None, _) => return Ok(version),
+    };
+    let Some(locked) = locked else {
+        return if is_frozen() {
+            frozen_error(format!("The lockfile doesn't have a version of {}", tool))
+        } else {
+            Ok(version)
+        };
+    };
+
+    match &version {
+        VersionSpec::Semver(range) if range.satisfies(locked) => {
+            debug!("Using version {} from the project lockfile", locked);
+            Ok(VersionSpec::Exact(locked.clone()))
+        }
+        VersionSpec::None if is_frozen() => {
+            debug!("Using version {} from the frozen project lockfile", locked);
+            Ok(VersionSpec::Exact(locked.clone()))
+        }
+        VersionSpec::Exact(exact) if exact == locked => Ok(version),
+        _ if is_frozen() => frozen_error(format!(
+            "The lockfile has {}, but {} was requested",
+            tool_version(tool, locked),
+            tool_version(tool, &version)
+        )),
+        _ => Ok(version),
+    }
+}
+
+/// Returns an exact version as-is when it is already in the inventory
+///
+/// Neither hooks nor any index or registry are consulted in that case, so re-installing a version
+/// that was fetched before keeps working offline.
+fn fetched_exact_version<F>(
+    tool: &str,
+    matching: &VersionSpec,
+    available: F,
+) -> Fallible<Option<Version>>
+where
+    F: FnOnce(&Version) -> Fallible<bool>,
+{
+    match matching {
+        VersionSpec::Exact(version) if available(version)? => {
+            debug!(
+                "Found {} in the inventory, skipping resolution",
+                tool_version(tool, version)
+            );
+            Ok(Some(version.clone()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Where the artifacts for a fetched tool came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchOutcome {
+    /// The tool was already unpacked in the inventory, so nothing was fetched
+    AlreadyInstalled,
+    /// The tool was unpacked from an archive in the inventory cache
+    Cache,
+    /// The tool was unpacked from a user-provided local archive
+    LocalArchive,
+    /// The tool was downloaded, as an archive of the given size
+    Network { bytes: u64 },
+}
+
+impl Display for FetchOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FetchOutcome::AlreadyInstalled => "already installed",
+            FetchOutcome::Cache => "from cache",
+            FetchOutcome::LocalArchive => "from local archive",
+            FetchOutcome::Network { .. } => "downloaded",
+        })
+    }
+}
+
+/// Where a tool being installed was fetched from, and how long that took
+struct FetchSummary {
+    outcome: FetchOutcome,
+    elapsed: Duration,
+}
+
+impl FetchSummary {
+    /// Summarizes a fetch that began at `started` and has just finished
+    fn since(outcome: FetchOutcome, started: Instant) -> Self {
+        FetchSummary {
+            outcome,
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+impl Display for FetchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.outcome {
+            FetchOutcome::Network { bytes } => write!(
+                f,
+                "downloaded {} in {:.1}s",
+                byte_size(bytes),
+                self.elapsed.as_secs_f64()
+            ),
+            outcome => outcome.fmt(f),
+        }
+    }
+}
+
+/// Formats a number of bytes for display, e.g. `24.3 MiB`
+fn byte_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = 1024.0 * 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes < MIB {
+        format!("{:.1} KiB", bytes / KIB)
+    } else {
+        format!("{:.1} MiB", bytes / MIB)
+    }
+}
+
 /// Represents the result of checking if a tool is available locally or not
 ///
 /// If a fetch is required, will include an exclusive lock on the Volta directory where possible
@@ -225,6 +835,48 @@ fn registry_fetch_error(
     || ErrorKind::RegistryFetchError { tool, from_url }
 }
 
+/// Splits a mirror setting into its candidate URLs, in order of preference
+///
+/// Multiple mirrors can be listed by separating them with commas, so that a backup mirror can
+/// be used when the primary one is unavailable.
+#[cfg_attr(feature = "mock-network", allow(dead_code))]
+fn mirror_candidates(setting: &str) -> Vec<String> {
+    setting
+        .split(',')
+        .map(|url| url.trim().trim_end_matches('/'))
+        .filter(|url| !url.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Attempts a download from each of the candidate URLs in order, returning the first success
+///
+/// If every candidate fails, the error from the last attempt is returned.
+fn fetch_with_failover<T, F>(urls: &[String], mut fetch: F) -> Fallible<T>
+where
+    F: FnMut(&str) -> Fallible<T>,
+{
+    // A custom resolver may not provide any URLs at all
+    let (last, fallbacks) = urls.split_last().ok_or(ErrorKind::NoDownloadUrls)?;
+
+    for url in fallbacks {
+        match fetch(url) {
+            Ok(value) => {
+                debug!("Downloaded successfully from {}", url);
+                return Ok(value);
+            }
+            Err(error) => debug!(
+                "Download from {} failed, trying the next mirror: {}",
+                url, error
+            ),
+        }
+    }
+
+    let value = fetch(last)?;
+    debug!("Downloaded successfully from {}", last);
+    Ok(value)
+}
+
 cfg_if!(
     if #[cfg(windows)] {
         const PATH_VAR_NAME: &str = "Path";
@@ -251,7 +903,7 @@ pub fn check_shim_reachable(shim_name: &str) {
         return;
     };
 
-    if !resolved.starts_with(&expected_dir) {
+    if !is_within(&resolved, &expected_dir) {
         info!(
             "{} {} is shadowed by another binary of the same name at {}. To ensure your commands work as expected, please move {} to the start of your {}.",
             note_prefix(),
@@ -263,6 +915,23 @@ pub fn check_shim_reachable(shim_name: &str) {
     }
 }
 
+/// Determine whether `path` is inside of `dir`, resolving any symlinks in either path first
+///
+/// The Volta home may be a symlink, in which case `which` can return a canonical path to a shim
+/// while the expected directory is not canonical, or vice-versa. On case-insensitive filesystems,
+/// the paths may also differ in case.
+///
+/// Only the parent of `path` is resolved, since on Unix the shim itself is a symlink to the
+/// `volta-shim` binary, which may be installed elsewhere.
+fn is_within(path: &Path, dir: &Path) -> bool {
+    let canonical = |path: &Path| canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    let path = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => canonical(parent).join(name),
+        _ => path.to_owned(),
+    };
+    path_starts_with(&path, &canonical(dir))
+}
+
 /// Locate the base directory for the relevant shim in the Volta directories.
 ///
 /// On Unix, all of the shims, including the default ones, are installed in `VoltaHome::shim_dir`
@@ -292,3 +961,276 @@ fn find_expected_shim_dir(shim_name: &str) -> Option<PathBuf> {
             .map(|install| install.root().to_owned())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::CASE_INSENSITIVE;
+
+    #[test]
+    fn test_tool_kind() {
+        let version: node_semver::Version = "1.2.3".parse().unwrap();
+        let tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(Node::new(version.clone())),
+            Box::new(Npm::new(version.clone())),
+            Box::new(BundledNpm),
+            Box::new(Pnpm::new(version.clone())),
+            Box::new(Yarn::new(version)),
+        ];
+
+        let kinds: Vec<String> = tools.iter().map(|tool| tool.kind().to_string()).collect();
+        assert_eq!(kinds, ["node", "npm", "npm", "pnpm", "yarn"]);
+    }
+
+    #[test]
+    fn test_sort_versions() {
+        let versions = ["1.22.19", "4.0.2", "3.6.4", "1.22.19", "4.0.0-rc.1"]
+            .iter()
+            .map(|v| v.parse().unwrap())
+            .collect();
+
+        let sorted: Vec<String> = sort_versions(versions, None)
+            .iter()
+            .map(Version::to_string)
+            .collect();
+        assert_eq!(sorted, ["4.0.2", "4.0.0-rc.1", "3.6.4", "1.22.19"]);
+    }
+
+    #[test]
+    fn test_sort_versions_with_filter() {
+        let versions = ["16.20.2", "18.17.1", "18.16.0", "20.5.1"]
+            .iter()
+            .map(|v| v.parse().unwrap())
+            .collect();
+        let filter: Range = "18".parse().unwrap();
+
+        let sorted: Vec<String> = sort_versions(versions, Some(&filter))
+            .iter()
+            .map(Version::to_string)
+            .collect();
+        assert_eq!(sorted, ["18.17.1", "18.16.0"]);
+    }
+
+    #[test]
+    fn test_mirror_candidates() {
+        assert_eq!(
+            mirror_candidates("https://primary.example/, https://backup.example,,"),
+            ["https://primary.example", "https://backup.example"]
+        );
+        assert!(mirror_candidates("").is_empty());
+    }
+
+    #[test]
+    fn test_fetch_with_failover() {
+        let urls = mirror_candidates("https://primary.example,https://backup.example");
+        let mut attempted = Vec::new();
+
+        let archive = fetch_with_failover(&urls, |url| {
+            attempted.push(url.to_string());
+            if url.contains("primary") {
+                Err(ErrorKind::DownloadToolNetworkError {
+                    tool: Spec::Node(VersionSpec::default()),
+                    from_url: url.into(),
+                }
+                .into())
+            } else {
+                Ok(format!("{}/node-v20.5.1.tar.gz", url))
+            }
+        })
+        .unwrap();
+
+        assert_eq!(archive, "https://backup.example/node-v20.5.1.tar.gz");
+        assert_eq!(
+            attempted,
+            ["https://primary.example", "https://backup.example"]
+        );
+    }
+
+    #[test]
+    fn test_ensure_disk_space() {
+        const MIB: u64 = 1024 * 1024;
+        assert!(ensure_disk_space(40 * MIB, 10_000 * MIB).is_ok());
+
+        let error = ensure_disk_space(40 * MIB, 100 * MIB).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::InsufficientDiskSpace {
+                needed: 200 * MIB,
+                available: 100 * MIB,
+            }
+        );
+    }
+
+    #[test]
+    fn test_fetch_summary() {
+        let downloaded = FetchSummary {
+            outcome: FetchOutcome::Network { bytes: 25_480_000 },
+            elapsed: Duration::from_millis(5_100),
+        };
+        assert_eq!(downloaded.to_string(), "downloaded 24.3 MiB in 5.1s");
+
+        let cached = FetchSummary {
+            outcome: FetchOutcome::Cache,
+            elapsed: Duration::from_millis(800),
+        };
+        assert_eq!(cached.to_string(), "from cache");
+
+        assert_eq!(byte_size(512 * 1024), "512.0 KiB");
+    }
+
+    #[test]
+    fn test_fetch_with_failover_all_fail() {
+        let urls = mirror_candidates("https://primary.example,https://backup.example");
+
+        let result: Fallible<()> = fetch_with_failover(&urls, |url| {
+            Err(ErrorKind::DownloadToolNetworkError {
+                tool: Spec::Node(VersionSpec::default()),
+                from_url: url.into(),
+            }
+            .into())
+        });
+
+        match result.unwrap_err().kind() {
+            ErrorKind::DownloadToolNetworkError { from_url, .. } => {
+                assert_eq!(from_url, "https://backup.example")
+            }
+            _ => panic!("expected the error from the last mirror"),
+        }
+    }
+
+    #[test]
+    fn test_fetch_with_failover_no_urls() {
+        let result: Fallible<()> = fetch_with_failover(&[], |_| panic!("nothing to fetch"));
+
+        assert!(matches!(
+            result.unwrap_err().kind(),
+            ErrorKind::NoDownloadUrls
+        ));
+    }
+
+    fn fetched() -> Fallible<BTreeSet<Version>> {
+        Ok(["18.17.0", "20.5.1", "20.11.1", "21.0.0"]
+            .into_iter()
+            .map(|version| version.parse().unwrap())
+            .collect())
+    }
+
+    fn unreachable(spec: VersionSpec) -> Fallible<Option<Version>> {
+        panic!("'{}' shouldn't be resolved remotely", spec)
+    }
+
+    #[test]
+    fn test_pinned_version_prefers_local_versions() {
+        let range: VersionSpec = "20".parse().unwrap();
+        let locked: Version = "20.5.1".parse().unwrap();
+
+        assert_eq!(
+            pinned_version(&range, None, fetched, unreachable).unwrap(),
+            Some("20.11.1".parse().unwrap())
+        );
+        assert_eq!(
+            pinned_version(&range, Some(&locked), fetched, unreachable).unwrap(),
+            Some(locked.clone())
+        );
+        assert_eq!(
+            pinned_version(&"lts".parse().unwrap(), Some(&locked), fetched, unreachable).unwrap(),
+            Some(locked)
+        );
+    }
+
+    #[test]
+    fn test_pinned_version_resolves_missing_versions() {
+        let resolved: Version = "22.1.0".parse().unwrap();
+        let resolve = |_| Ok(Some(resolved.clone()));
+
+        assert_eq!(
+            pinned_version(&"22".parse().unwrap(), None, fetched, resolve).unwrap(),
+            Some(resolved.clone())
+        );
+        assert_eq!(
+            pinned_version(&"lts".parse().unwrap(), None, fetched, resolve).unwrap(),
+            Some(resolved.clone())
+        );
+    }
+
+    #[test]
+    fn test_images_to_remove() {
+        let installed: Vec<(Version, PathBuf)> = ["16.20.2", "18.17.1", "20.5.1"]
+            .iter()
+            .map(|v| (v.parse().unwrap(), PathBuf::from("/images").join(v)))
+            .collect();
+        let active: Version = "18.17.1".parse().unwrap();
+
+        let removed: Vec<(String, PathBuf)> =
+            images_to_remove(installed.clone(), Some(&active), &VersionSpec::None)
+                .into_iter()
+                .map(|(version, dir)| (version.to_string(), dir))
+                .collect();
+        assert_eq!(
+            removed,
+            [
+                ("16.20.2".into(), PathBuf::from("/images/16.20.2")),
+                ("20.5.1".into(), PathBuf::from("/images/20.5.1"))
+            ]
+        );
+
+        assert_eq!(
+            images_to_remove(installed.clone(), None, &VersionSpec::None).len(),
+            3
+        );
+
+        // Only the versions matching a range are removed, and never the active one
+        let below_20 = VersionSpec::Semver("<20".parse().unwrap());
+        let removed: Vec<String> = images_to_remove(installed.clone(), Some(&active), &below_20)
+            .into_iter()
+            .map(|(version, _)| version.to_string())
+            .collect();
+        assert_eq!(removed, ["16.20.2"]);
+
+        let exact = VersionSpec::Exact("20.5.1".parse().unwrap());
+        assert_eq!(images_to_remove(installed, Some(&active), &exact).len(), 1);
+    }
+
+    #[test]
+    fn test_same_image() {
+        let rc: Version = "20.0.0-rc.1".parse().unwrap();
+        let upper_rc: Version = "20.0.0-RC.1".parse().unwrap();
+
+        assert!(same_image(&rc, &rc));
+        assert!(!same_image(&rc, &"20.0.0".parse().unwrap()));
+        // Both versions are unpacked into the same directory if the filesystem ignores case
+        assert_eq!(same_image(&rc, &upper_rc), CASE_INSENSITIVE);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "macos", windows))]
+    fn test_is_within_ignores_case() {
+        assert!(is_within(
+            Path::new("/Users/Me/.volta/bin/node"),
+            Path::new("/users/me/.Volta/bin")
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_is_within_symlinked_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let real_home = root.path().join("real-volta");
+        let shim_dir = real_home.join("bin");
+        std::fs::create_dir_all(&shim_dir).unwrap();
+        // Shims are symlinks to `volta-shim`, which may be installed outside of the Volta home
+        let volta_shim = root.path().join("install").join("volta-shim");
+        std::fs::create_dir_all(volta_shim.parent().unwrap()).unwrap();
+        std::fs::write(&volta_shim, "").unwrap();
+        let shim = shim_dir.join("node");
+        std::os::unix::fs::symlink(&volta_shim, &shim).unwrap();
+
+        let linked_home = root.path().join(".volta");
+        std::os::unix::fs::symlink(&real_home, &linked_home).unwrap();
+
+        // `which` reports the canonical path, while the expected directory goes through the link
+        assert!(is_within(&shim, &linked_home.join("bin")));
+        assert!(is_within(&linked_home.join("bin").join("node"), &shim_dir));
+        assert!(!is_within(&shim, &root.path().join("other")));
+    }
+}
@@ -0,0 +1,159 @@
+//! Provides an integrity check of the Node archives cached in the inventory, to catch archives
+//! that have been corrupted since they were installed.
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use super::super::integrity::sha512_integrity;
+use super::super::provenance::read_provenance;
+use super::Node;
+use crate::error::{Context, ErrorKind, Fallible};
+use crate::fs::remove_file_if_exists;
+use crate::layout::{installed_node_images, volta_home};
+use log::debug;
+use node_semver::Version;
+
+/// The result of checking the cached Node archives
+#[derive(Debug, Default)]
+pub struct CacheReport {
+    /// The versions whose cached archive matches the checksum recorded when it was installed
+    pub verified: Vec<Version>,
+    /// The versions with a cached archive but no recorded checksum to compare it against
+    pub unchecked: Vec<Version>,
+    /// The cached archives that don't match their recorded checksum
+    pub corrupt: Vec<CorruptArchive>,
+}
+
+impl CacheReport {
+    /// Whether none of the cached archives are corrupt
+    pub fn is_healthy(&self) -> bool {
+        self.corrupt.is_empty()
+    }
+}
+
+/// A cached archive whose contents no longer match the checksum recorded when it was installed
+#[derive(Debug, PartialEq, Eq)]
+pub struct CorruptArchive {
+    pub version: Version,
+    /// The path to the cached archive
+    pub file: PathBuf,
+    /// The checksum recorded in the provenance of the Node image
+    pub expected: String,
+    /// The checksum of the archive as it is now
+    pub actual: String,
+    /// Whether the archive was removed, so that it will be downloaded again when it is needed
+    pub removed: bool,
+}
+
+/// Checks the cached archive of each installed Node version against the recorded checksum
+///
+/// The checksum of the archive that each Node image was unpacked from is recorded in its
+/// provenance, so only archives with an installed image can be checked. If `remove_corrupt` is
+/// set, archives that don't match are deleted from the inventory.
+pub fn verify_cache(remove_corrupt: bool) -> Fallible<CacheReport> {
+    let inventory_dir = volta_home()?.node_inventory_dir();
+    let mut report = CacheReport::default();
+
+    for (version, image_dir) in installed_node_images()? {
+        let file = inventory_dir.join(Node::archive_filename(&version));
+        if !file.is_file() {
+            continue;
+        }
+
+        let Some(provenance) = read_provenance(&image_dir)? else {
+            report.unchecked.push(version);
+            continue;
+        };
+
+        let actual = File::open(&file)
+            .and_then(sha512_integrity)
+            .with_context(|| ErrorKind::ReadLocalArchiveError { file: file.clone() })?;
+        if actual == provenance.checksum {
+            report.verified.push(version);
+            continue;
+        }
+
+        debug!(
+            "Cached archive '{}' has checksum {}, but {} was recorded",
+            file.display(),
+            actual,
+            provenance.checksum
+        );
+        if remove_corrupt {
+            remove_file_if_exists(&file)?;
+        }
+        report.corrupt.push(CorruptArchive {
+            version,
+            file,
+            expected: provenance.checksum,
+            actual,
+            removed: remove_corrupt,
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::override_volta_home;
+    use crate::tool::Provenance;
+    use std::fs;
+
+    /// Installs a Node image unpacked from an archive with the given contents, caching the archive
+    fn install(version: &Version, archive: &[u8], with_provenance: bool) -> PathBuf {
+        let home = volta_home().unwrap();
+        let image_dir = home.node_image_dir(&version.to_string());
+        fs::create_dir_all(&image_dir).unwrap();
+        fs::create_dir_all(home.node_inventory_dir()).unwrap();
+
+        let file = home
+            .node_inventory_dir()
+            .join(Node::archive_filename(version));
+        fs::write(&file, archive).unwrap();
+        if with_provenance {
+            Provenance::of_archive(&file, "https://example.com/node.tar.gz")
+                .unwrap()
+                .write(&image_dir)
+                .unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn test_verify_cache() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let intact = Version::from((20, 11, 1));
+        let corrupted = Version::from((18, 17, 0));
+        let unrecorded = Version::from((16, 20, 2));
+
+        install(&intact, b"node 20", true);
+        let corrupted_file = install(&corrupted, b"node 18", true);
+        install(&unrecorded, b"node 16", false);
+        // Corrupt one byte of the cached archive after it was installed
+        fs::write(&corrupted_file, b"node 1\x08").unwrap();
+
+        let report = verify_cache(false).unwrap();
+        assert_eq!(report.verified, [intact.clone()]);
+        assert_eq!(report.unchecked, [unrecorded.clone()]);
+        assert!(!report.is_healthy());
+        assert_eq!(report.corrupt.len(), 1);
+        let corrupt = &report.corrupt[0];
+        assert_eq!(corrupt.version, corrupted);
+        assert_eq!(corrupt.file, corrupted_file);
+        assert_ne!(corrupt.expected, corrupt.actual);
+        assert!(!corrupt.removed);
+        assert!(corrupted_file.exists());
+
+        let report = verify_cache(true).unwrap();
+        assert!(report.corrupt[0].removed);
+        assert!(!corrupted_file.exists());
+
+        // Without the corrupt archive, there is nothing left to report
+        let report = verify_cache(true).unwrap();
+        assert!(report.is_healthy());
+        assert_eq!(report.verified, [intact]);
+    }
+}
@@ -1,18 +1,22 @@
 //! Provides fetcher for Node distributions
 
+use std::error::Error;
 use std::fs::{read_to_string, write, File};
 use std::path::{Path, PathBuf};
 use std::env;
 
-use super::NodeVersion;
-use crate::error::{Context, ErrorKind, Fallible};
-use crate::fs::{create_staging_dir, create_staging_file, rename};
+use super::{mirror_fallback, official_node_server_root, NodeTarget, NodeVersion, VersionResolver};
+use crate::error::{Context, ErrorKind, Fallible, VoltaError};
+use crate::fs::{create_staging_dir, create_staging_dir_in, remove_file_if_exists, rename};
 use crate::hook::ToolHooks;
-use crate::layout::volta_home;
+use crate::layout::{image_root_override, record_image_root, volta_home};
+use crate::profile::{time, Phase};
 use crate::style::{progress_bar, tool_version};
-use crate::tool::{self, download_tool_error, Node};
+use crate::tool::provenance::{record_provenance, Provenance};
+use crate::tool::{self, download_tool_error, FetchOutcome, Node, PlanSource};
 use crate::version::{parse_version, VersionSpec};
-use archive::{self, Archive};
+use archive::{self, Archive, ArchiveError, Tarball, Zip};
+use attohttpc::StatusCode;
 use cfg_if::cfg_if;
 use fs_utils::ensure_containing_dir_exists;
 use log::{debug, info};
@@ -24,23 +28,36 @@ cfg_if! {
         // TODO: We need to reconsider our mocking strategy in light of mockito deprecating the
         // SERVER_URL constant: Since our acceptance tests run the binary in a separate process,
         // we can't use `mockito::server_url()`, which relies on shared memory.
-        fn public_node_server_root() -> String {
-            #[allow(deprecated)]
-            mockito::SERVER_URL.to_string()
+        #[allow(deprecated)]
+        fn public_node_server_roots() -> Vec<String> {
+            vec![mockito::SERVER_URL.to_string()]
         }
     } else {
         // NODE_MIRROR=https://mirrors.aliyun.com/nodejs-release
-        fn public_node_server_root() -> String {
-            match env::var_os("ENV_NODE_MIRROR") {
-                Some(val) => format!("{}", val.to_string_lossy()),
-                None => "https://mirrors.aliyun.com/nodejs-release".to_string()
+        /// Returns the Node servers to download from, in order of preference
+        ///
+        /// `ENV_NODE_MIRROR` may list several comma-separated mirrors, which are tried in turn.
+        fn public_node_server_roots() -> Vec<String> {
+            let mirrors = env::var_os("ENV_NODE_MIRROR")
+                .map(|val| tool::mirror_candidates(&val.to_string_lossy()))
+                .unwrap_or_default();
+
+            if mirrors.is_empty() {
+                vec!["https://mirrors.aliyun.com/nodejs-release".to_string()]
+            } else {
+                mirrors
             }
         }
     }
 }
 
 fn npm_manifest_path(version: &Version) -> PathBuf {
-    let mut manifest = PathBuf::from(Node::archive_basename(version));
+    Path::new(&Node::archive_basename(version)).join(image_npm_manifest_path())
+}
+
+/// The location of the bundled npm's manifest within a Node image
+fn image_npm_manifest_path() -> PathBuf {
+    let mut manifest = PathBuf::new();
 
     #[cfg(unix)]
     manifest.push("lib");
@@ -52,12 +69,15 @@ fn npm_manifest_path(version: &Version) -> PathBuf {
     manifest
 }
 
-pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<NodeVersion> {
+pub fn fetch(
+    version: &Version,
+    resolver: &dyn VersionResolver,
+) -> Fallible<(NodeVersion, FetchOutcome)> {
     let home = volta_home()?;
     let node_dir = home.node_inventory_dir();
     let cache_file = node_dir.join(Node::archive_filename(version));
 
-    let (archive, staging) = match load_cached_distro(&cache_file) {
+    let (archive, partial) = match load_cached_distro(&cache_file) {
         Some(archive) => {
             info!(
                 "Loading {} from cached archive at '{}'",
@@ -67,34 +87,185 @@ pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<Nod
             (archive, None)
         }
         None => {
-            let staging = create_staging_file()?;
-            let remote_url = determine_remote_url(version, hooks)?;
-            let archive = fetch_remote_distro(version, &remote_url, staging.path())?;
-            (archive, Some(staging))
+            // Download into a stable location, so that an interrupted download can be resumed
+            let partial = partial_download_file(version)?;
+            let remote_urls = resolver.distro_urls(version)?;
+            let (archive, url) = tool::fetch_with_failover(&remote_urls, |url| {
+                fetch_remote_distro(version, url, &partial)
+                    .map(|archive| (archive, url.to_string()))
+            })
+            .map_err(|error| with_suggestions(error, version, resolver))?;
+            // Only the response headers have been read so far, so nothing has been written yet
+            tool::check_disk_space(archive.compressed_size())?;
+            (archive, Some((partial, url)))
         }
     };
 
-    let node_version = unpack_archive(archive, version)?;
-
-    if let Some(staging_file) = staging {
-        ensure_containing_dir_exists(&cache_file).with_context(|| {
-            ErrorKind::ContainingDirError {
-                path: cache_file.clone(),
+    let bytes = archive.compressed_size();
+    let node_version = match time(Phase::Unpack, || unpack_archive(archive, version)) {
+        Ok(node_version) => node_version,
+        Err(error) => {
+            // The partial download may be corrupt, so make sure the next attempt starts over
+            if let Some((partial_file, _)) = &partial {
+                let _ = remove_file_if_exists(partial_file);
+                let _ = archive::remove_validator(partial_file);
             }
-        })?;
-        staging_file
-            .persist(cache_file)
-            .with_context(|| ErrorKind::PersistInventoryError {
+            return Err(error);
+        }
+    };
+
+    let outcome = match partial {
+        Some(_) => FetchOutcome::Network { bytes },
+        None => FetchOutcome::Cache,
+    };
+
+    let image_dir = volta_home()?.node_image_dir(&version.to_string());
+    if let Some((partial_file, url)) = partial {
+        record_provenance(Provenance::of_archive(&partial_file, url), &image_dir);
+        // The download is complete, so it won't be resumed
+        let _ = archive::remove_validator(&partial_file);
+
+        if tool::cache_archives() {
+            ensure_containing_dir_exists(&cache_file).with_context(|| {
+                ErrorKind::ContainingDirError {
+                    path: cache_file.clone(),
+                }
+            })?;
+            rename(partial_file, cache_file).with_context(|| ErrorKind::PersistInventoryError {
                 tool: "Node".into(),
             })?;
+        } else {
+            remove_file_if_exists(partial_file)?;
+        }
+    } else {
+        let source = cache_file.display().to_string();
+        record_provenance(Provenance::of_archive(&cache_file, source), &image_dir);
     }
 
-    Ok(node_version)
+    Ok((node_version, outcome))
+}
+
+/// Fetch Node from a local distribution archive, bypassing the registry entirely
+pub fn fetch_local(
+    version: &Version,
+    archive_path: &Path,
+) -> Fallible<(NodeVersion, FetchOutcome)> {
+    info!(
+        "Loading {} from local archive at '{}'",
+        tool_version("node", version),
+        archive_path.display()
+    );
+
+    let read_error = || ErrorKind::ReadLocalArchiveError {
+        file: archive_path.to_owned(),
+    };
+    let file = File::open(archive_path).with_context(read_error)?;
+    let archive = archive::load_native(file).with_context(read_error)?;
+
+    let node_version = time(Phase::Unpack, || unpack_archive(archive, version))?;
+
+    let image_dir = volta_home()?.node_image_dir(&version.to_string());
+    let source = archive_path.display().to_string();
+    record_provenance(Provenance::of_archive(archive_path, source), &image_dir);
+
+    Ok((node_version, FetchOutcome::LocalArchive))
+}
+
+/// Fetch the Node distribution archive for a platform other than the current one
+///
+/// The archive can't be used on this machine, so rather than being unpacked into an image, it is
+/// kept in the inventory under a directory for the target. Since the archive is all that is
+/// fetched, it is kept even if `VOLTA_NO_ARCHIVE_CACHE` is set. Returns the path to the archive.
+pub fn fetch_for_target(
+    version: &Version,
+    target: &NodeTarget,
+    hooks: Option<&ToolHooks<Node>>,
+) -> Fallible<(PathBuf, FetchOutcome)> {
+    let filename = target.archive_filename(version);
+    let cache_file = volta_home()?
+        .node_inventory_dir()
+        .join(target.to_string())
+        .join(&filename);
+
+    if cache_file.is_file() {
+        debug!(
+            "Found {} for {} in the inventory",
+            tool_version("node", version),
+            target
+        );
+        return Ok((cache_file, FetchOutcome::Cache));
+    }
+
+    let partial = volta_home()?
+        .tmp_dir()
+        .join(format!("{}.partial", filename));
+    let remote_urls = determine_remote_urls_for(version, target, &filename, hooks)?;
+    let archive = tool::fetch_with_failover(&remote_urls, |url| {
+        info!(
+            "Downloading {} for {} from {}",
+            tool_version("node", version),
+            target,
+            url
+        );
+        let fetched = if target.extension() == "zip" {
+            Zip::fetch(url, &partial)
+        } else {
+            Tarball::fetch(url, &partial)
+        };
+        fetched.with_context(download_tool_error(
+            tool::Spec::Node(VersionSpec::Exact(version.clone())),
+            url,
+        ))
+    })?;
+    tool::check_disk_space(archive.compressed_size())?;
+
+    // The archive is only written to disk as it is read, so unpack it into a throwaway directory
+    // to complete the download, which also checks that the archive is intact
+    let temp = create_staging_dir()?;
+    let bytes = archive.compressed_size();
+    let progress = progress_bar(
+        archive.origin(),
+        &format!("{} for {}", tool_version("node", version), target),
+        archive.compressed_size(),
+    );
+    let unpacked = archive
+        .unpack(temp.path(), &mut |_, read| {
+            progress.inc(read as u64);
+        })
+        .with_context(|| ErrorKind::UnpackArchiveError {
+            tool: "Node".into(),
+            version: version.to_string(),
+        });
+    progress.finish_and_clear();
+
+    if let Err(error) = unpacked {
+        let _ = remove_file_if_exists(&partial);
+        return Err(error);
+    }
+
+    ensure_containing_dir_exists(&cache_file).with_context(|| ErrorKind::ContainingDirError {
+        path: cache_file.clone(),
+    })?;
+    rename(&partial, &cache_file).with_context(|| ErrorKind::PersistInventoryError {
+        tool: "Node".into(),
+    })?;
+
+    Ok((cache_file, FetchOutcome::Network { bytes }))
+}
+
+/// Determine the file that holds an in-progress download of the given Node version
+fn partial_download_file(version: &Version) -> Fallible<PathBuf> {
+    let filename = format!("{}.partial", Node::archive_filename(version));
+    Ok(volta_home()?.tmp_dir().join(filename))
 }
 
 /// Unpack the node archive into the image directory so that it is ready for use
 fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<NodeVersion> {
-    let temp = create_staging_dir()?;
+    let temp = match image_root_override()? {
+        // Stage alongside the relocated images, so moving the image into place is a simple rename
+        Some(root) => create_staging_dir_in(&root.join("tmp"))?,
+        None => create_staging_dir()?,
+    };
     debug!("Unpacking node into '{}'", temp.path().display());
 
     let progress = progress_bar(
@@ -129,6 +300,8 @@ fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<Node
             dir: dest.clone(),
         }
     })?;
+    // Later commands need to find the image even if they aren't run with `VOLTA_IMAGE_ROOT`
+    record_image_root()?;
 
     progress.finish_and_clear();
 
@@ -142,6 +315,21 @@ fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<Node
     })
 }
 
+/// Determine where the archive for a Node version will come from, without fetching it
+pub(super) fn plan_source(
+    version: &Version,
+    resolver: &dyn VersionResolver,
+) -> Fallible<PlanSource> {
+    let cache_file = volta_home()?
+        .node_inventory_dir()
+        .join(Node::archive_filename(version));
+    if cache_file.is_file() {
+        Ok(PlanSource::Cache(cache_file))
+    } else {
+        resolver.distro_urls(version).map(PlanSource::Download)
+    }
+}
+
 /// Return the archive if it is valid. It may have been corrupted or interrupted in the middle of
 /// downloading.
 // ISSUE(#134) - verify checksum
@@ -154,39 +342,98 @@ fn load_cached_distro(file: &Path) -> Option<Box<dyn Archive>> {
     }
 }
 
-/// Determine the remote URL to download from, using the hooks if available
-fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<String> {
+/// Determine the remote URLs to download from, using the hooks if available
+///
+/// Without a hook, there is one candidate URL for each configured mirror, in order of preference,
+/// followed by the official Node server if `VOLTA_MIRROR_FALLBACK` is set.
+pub(super) fn determine_remote_urls(
+    version: &Version,
+    hooks: Option<&ToolHooks<Node>>,
+) -> Fallible<Vec<String>> {
     let distro_file_name = Node::archive_filename(version);
+    determine_remote_urls_for(version, &NodeTarget::host(), &distro_file_name, hooks)
+}
+
+/// Determine the remote URLs to download the archive for a platform from
+///
+/// The architecture given to a `node.distro` hook is the one in the archive name, so that versions
+/// which fall back on the x64 binaries are downloaded from the x64 URL.
+fn determine_remote_urls_for(
+    version: &Version,
+    target: &NodeTarget,
+    distro_file_name: &str,
+    hooks: Option<&ToolHooks<Node>>,
+) -> Fallible<Vec<String>> {
     match hooks {
         Some(&ToolHooks {
             distro: Some(ref hook),
             ..
         }) => {
             debug!("Using node.distro hook to determine download URL");
-            hook.resolve(version, &distro_file_name)
+            hook.resolve_for_target(
+                version,
+                distro_file_name,
+                target.os(),
+                target.archive_arch(version),
+            )
+            .map(|url| vec![url])
         }
-        _ => Ok(format!(
-            "{}/v{}/{}",
-            public_node_server_root(),
-            version,
-            distro_file_name
-        )),
+        _ => Ok(public_distro_urls(version, distro_file_name)),
     }
 }
 
-/// Fetch the distro archive from the internet
+/// The candidate URLs for an archive on the public servers, in order of preference
+fn public_distro_urls(version: &Version, distro_file_name: &str) -> Vec<String> {
+    let mut roots = public_node_server_roots();
+    let official = official_node_server_root();
+    if mirror_fallback() && !roots.contains(&official) {
+        roots.push(official);
+    }
+
+    roots
+        .into_iter()
+        .map(|root| format!("{}/v{}/{}", root, version, distro_file_name))
+        .collect()
+}
+
+/// Fetch the distro archive from the internet, resuming any partial download at `partial_path`
 fn fetch_remote_distro(
     version: &Version,
     url: &str,
-    staging_path: &Path,
+    partial_path: &Path,
 ) -> Fallible<Box<dyn Archive>> {
     info!("Downloading {} from {}", tool_version("node", version), url);
-    archive::fetch_native(url, staging_path).with_context(download_tool_error(
+    archive::fetch_native(url, partial_path).with_context(download_tool_error(
         tool::Spec::Node(VersionSpec::Exact(version.clone())),
         url,
     ))
 }
 
+/// Replaces a 404 for the distro archive, which means that the version doesn't exist, with an
+/// error suggesting the closest versions that do
+fn with_suggestions(
+    error: VoltaError,
+    version: &Version,
+    resolver: &dyn VersionResolver,
+) -> VoltaError {
+    let not_found = error
+        .source()
+        .and_then(|source| source.downcast_ref::<ArchiveError>())
+        .is_some_and(|source| {
+            matches!(source, ArchiveError::HttpError(status) if *status == StatusCode::NOT_FOUND)
+        });
+    if !not_found {
+        return error;
+    }
+
+    let kind = ErrorKind::NodeVersionNotFound {
+        matching: version.to_string(),
+        suggestions: resolver.suggestions(version),
+        index_url: None,
+    };
+    VoltaError::from_source(error, kind)
+}
+
 /// The portion of npm's `package.json` file that we care about
 #[derive(Deserialize)]
 struct Manifest {
@@ -204,21 +451,135 @@ impl Manifest {
 }
 
 /// Load the local npm version file to determine the default npm version for a given version of Node
+///
+/// The file is kept with the Node archives, so it is missing if the inventory was relocated (e.g.
+/// with `VOLTA_INVENTORY`) after the image was unpacked. In that case, the version is read from
+/// the npm in the image and recorded again.
 pub fn load_default_npm_version(node: &Version) -> Fallible<Version> {
-    let npm_version_file_path = volta_home()?.node_npm_version_file(&node.to_string());
-    let npm_version =
-        read_to_string(&npm_version_file_path).with_context(|| ErrorKind::ReadDefaultNpmError {
-            file: npm_version_file_path,
-        })?;
-    parse_version(npm_version)
+    let home = volta_home()?;
+    let npm_version_file_path = home.node_npm_version_file(&node.to_string());
+    let error = match read_to_string(&npm_version_file_path) {
+        Ok(npm_version) => return parse_version(npm_version),
+        Err(error) => error,
+    };
+
+    let image_manifest = home
+        .node_image_dir(&node.to_string())
+        .join(image_npm_manifest_path());
+    if !image_manifest.is_file() {
+        return Err(VoltaError::from_source(
+            error,
+            ErrorKind::ReadDefaultNpmError {
+                file: npm_version_file_path,
+            },
+        ));
+    }
+
+    let npm = Manifest::version(&image_manifest)?;
+    if let Err(error) = save_default_npm_version(node, &npm) {
+        debug!(
+            "Could not record the npm version for Node {}: {}",
+            node, error
+        );
+    }
+    Ok(npm)
 }
 
 /// Save the default npm version to the filesystem for a given version of Node
 fn save_default_npm_version(node: &Version, npm: &Version) -> Fallible<()> {
     let npm_version_file_path = volta_home()?.node_npm_version_file(&node.to_string());
-    write(&npm_version_file_path, npm.to_string().as_bytes()).with_context(|| {
-        ErrorKind::WriteDefaultNpmError {
+    ensure_containing_dir_exists(&npm_version_file_path)
+        .and_then(|()| write(&npm_version_file_path, npm.to_string().as_bytes()))
+        .with_context(|| ErrorKind::WriteDefaultNpmError {
             file: npm_version_file_path,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::override_volta_home;
+    use crate::session::Session;
+    use std::fs;
+
+    #[test]
+    fn test_distro_hook_uses_fallback_arch() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let hooks_file = volta_home().unwrap().default_hooks_file().to_owned();
+        fs::write(
+            hooks_file,
+            r#"{ "node": { "distro": { "template": "https://builds.test/{{arch}}/{{filename}}" } } }"#,
+        )
+        .unwrap();
+        let session = Session::init_unchecked();
+        let hooks = session.hooks().unwrap().node();
+
+        let cases = [
+            (
+                "darwin-arm64",
+                "15.14.0",
+                "x64/node-v15.14.0-darwin-x64.tar.gz",
+            ),
+            (
+                "darwin-arm64",
+                "16.0.0",
+                "arm64/node-v16.0.0-darwin-arm64.tar.gz",
+            ),
+            ("win-arm64", "19.9.0", "x64/node-v19.9.0-win-x64.zip"),
+            ("win-arm64", "20.0.0", "arm64/node-v20.0.0-win-arm64.zip"),
+        ];
+
+        for (target, version, path) in cases {
+            let target: NodeTarget = target.parse().unwrap();
+            let version = Version::parse(version).unwrap();
+            let filename = target.archive_filename(&version);
+
+            assert_eq!(
+                determine_remote_urls_for(&version, &target, &filename, hooks).unwrap(),
+                vec![format!("https://builds.test/{}", path)]
+            );
         }
-    })
+    }
+
+    #[test]
+    fn test_default_npm_version_from_image() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let node = Version::parse("20.5.1").unwrap();
+        let volta_home = volta_home().unwrap();
+
+        assert!(load_default_npm_version(&node).is_err());
+
+        // The image is unpacked, but the npm version file isn't in the inventory
+        let manifest = volta_home
+            .node_image_dir("20.5.1")
+            .join(image_npm_manifest_path());
+        fs::create_dir_all(manifest.parent().unwrap()).unwrap();
+        fs::write(&manifest, r#"{ "name": "npm", "version": "9.8.0" }"#).unwrap();
+
+        assert_eq!(
+            load_default_npm_version(&node).unwrap(),
+            Version::parse("9.8.0").unwrap()
+        );
+        assert_eq!(
+            fs::read_to_string(volta_home.node_npm_version_file("20.5.1")).unwrap(),
+            "9.8.0"
+        );
+    }
+
+    #[test]
+    fn test_public_url_uses_fallback_arch() {
+        let target: NodeTarget = "darwin-arm64".parse().unwrap();
+        let version = Version::parse("14.21.3").unwrap();
+        let filename = target.archive_filename(&version);
+
+        for url in determine_remote_urls_for(&version, &target, &filename, None).unwrap() {
+            assert!(
+                url.ends_with("/v14.21.3/node-v14.21.3-darwin-x64.tar.gz"),
+                "unexpected URL {}",
+                url
+            );
+        }
+    }
 }
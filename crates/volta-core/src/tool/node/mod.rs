@@ -1,24 +1,37 @@
+use std::env;
 use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use super::{
-    check_fetched, check_shim_reachable, debug_already_fetched, info_fetched, info_installed,
-    info_pinned, info_project_version, FetchStatus, Tool,
+    check_fetched, check_shim_reachable, debug_already_fetched, info_already_default, info_fetched,
+    info_installed, info_pinned, not_activated, package::packages_for_other_node_major,
+    plan::planned_changes, warn_project_version, FetchOutcome, FetchStatus, FetchSummary,
+    PlanAction, PlanSource, Tool, ToolKindName, ToolPlan,
 };
 use crate::error::{ErrorKind, Fallible};
 use crate::inventory::node_available;
+use crate::layout::volta_home;
+use crate::platform::{PlatformNode, System};
+use crate::profile::{time, Phase};
 use crate::session::Session;
-use crate::style::{note_prefix, tool_version};
+use crate::style::tool_version;
 use crate::sync::VoltaLock;
+use crate::warning::Warning;
 use cfg_if::cfg_if;
-use log::info;
+use log::debug;
 use node_semver::Version;
 
+mod cache;
 mod fetch;
 mod metadata;
 mod resolve;
+mod target;
 
-pub use fetch::load_default_npm_version;
-pub use resolve::resolve;
+pub use cache::{verify_cache, CacheReport, CorruptArchive};
+pub use fetch::{fetch_for_target, load_default_npm_version};
+pub use resolve::{available, resolve, resolve_version, IndexResolver, VersionResolver};
+pub use target::NodeTarget;
 
 cfg_if! {
     if #[cfg(all(target_os = "windows", target_arch = "x86"))] {
@@ -114,6 +127,32 @@ cfg_if! {
     }
 }
 
+/// Environment variable that enables falling back to the official Node server when the
+/// configured mirror doesn't have a version yet
+const VOLTA_MIRROR_FALLBACK: &str = "VOLTA_MIRROR_FALLBACK";
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        #[allow(deprecated)]
+        fn official_node_server_root() -> String {
+            format!("{}/node-official", mockito::SERVER_URL)
+        }
+    } else {
+        /// Returns the root of the official Node server, used when falling back from a mirror
+        fn official_node_server_root() -> String {
+            "https://nodejs.org/dist".to_string()
+        }
+    }
+}
+
+/// Whether to fall back to the official Node server when a version is missing from the mirror
+///
+/// Mirrors can lag behind nodejs.org for brand-new releases. With `VOLTA_MIRROR_FALLBACK` set,
+/// both resolving against the index and downloading the archive fall back to the official server.
+fn mirror_fallback() -> bool {
+    env::var_os(VOLTA_MIRROR_FALLBACK).is_some()
+}
+
 /// A full Node version including not just the version of Node itself
 /// but also the specific version of npm installed globally with that
 /// Node installation.
@@ -138,52 +177,37 @@ impl Display for NodeVersion {
 
 /// The Tool implementation for fetching and installing Node
 pub struct Node {
-    pub(super) version: Version,
+    pub(super) version: PlatformNode,
+    local_archive: Option<PathBuf>,
 }
 
 impl Node {
-    pub fn new(version: Version) -> Self {
-        Node { version }
-    }
-
-    #[cfg(not(any(
-        all(target_os = "macos", target_arch = "aarch64"),
-        all(target_os = "windows", target_arch = "aarch64")
-    )))]
-    pub fn archive_basename(version: &Version) -> String {
-        format!("node-v{}-{}-{}", version, NODE_DISTRO_OS, NODE_DISTRO_ARCH)
+    pub fn new(version: impl Into<PlatformNode>) -> Self {
+        Node {
+            version: version.into(),
+            local_archive: None,
+        }
     }
 
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-    pub fn archive_basename(version: &Version) -> String {
-        // Note: Node began shipping pre-built binaries for Apple Silicon with Major version 16
-        // Prior to that, we need to fall back on the x64 binaries
-        format!(
-            "node-v{}-{}-{}",
-            version,
-            NODE_DISTRO_OS,
-            if version.major >= 16 {
-                NODE_DISTRO_ARCH
-            } else {
-                NODE_DISTRO_ARCH_FALLBACK
-            }
-        )
+    /// Create a Node tool that is fetched from a local distribution archive
+    ///
+    /// The version is determined from the archive's file name, which must match the official
+    /// distribution name for the current platform (e.g. `node-v20.5.1-linux-x64.tar.gz`).
+    pub fn from_local_archive(archive: PathBuf) -> Fallible<Self> {
+        let version = version_from_archive_name(&archive)?;
+
+        Ok(Node {
+            version: PlatformNode::Version(version),
+            local_archive: Some(archive),
+        })
     }
 
-    #[cfg(all(target_os = "windows", target_arch = "aarch64"))]
+    /// The name of the distribution archive for this platform, without the extension
+    ///
+    /// On platforms where Node began publishing ARM64 binaries later (macOS with major version
+    /// 16 and Windows with major version 20), older versions fall back on the x64 binaries.
     pub fn archive_basename(version: &Version) -> String {
-        // Note: Node began shipping pre-built binaries for Windows ARM with Major version 20
-        // Prior to that, we need to fall back on the x64 binaries
-        format!(
-            "node-v{}-{}-{}",
-            version,
-            NODE_DISTRO_OS,
-            if version.major >= 20 {
-                NODE_DISTRO_ARCH
-            } else {
-                NODE_DISTRO_ARCH_FALLBACK
-            }
-        )
+        NodeTarget::host().archive_basename(version)
     }
 
     pub fn archive_filename(version: &Version) -> String {
@@ -194,66 +218,176 @@ impl Node {
         )
     }
 
-    pub(crate) fn ensure_fetched(&self, session: &mut Session) -> Fallible<NodeVersion> {
-        match check_fetched(|| node_available(&self.version))? {
+    fn ensure_fetched(
+        &self,
+        version: &Version,
+        session: &mut Session,
+    ) -> Fallible<(NodeVersion, FetchOutcome)> {
+        match check_fetched(|| node_available(version))? {
             FetchStatus::AlreadyFetched => {
                 debug_already_fetched(self);
-                let npm = fetch::load_default_npm_version(&self.version)?;
+                let npm = fetch::load_default_npm_version(version)?;
+
+                Ok((
+                    NodeVersion {
+                        runtime: version.clone(),
+                        npm,
+                    },
+                    FetchOutcome::AlreadyInstalled,
+                ))
+            }
+            FetchStatus::FetchNeeded(_lock) => time(Phase::Fetch, || match &self.local_archive {
+                Some(archive) => fetch::fetch_local(version, archive),
+                None => resolve::with_resolver(session, |resolver| fetch::fetch(version, resolver)),
+            }),
+        }
+    }
 
-                Ok(NodeVersion {
-                    runtime: self.version.clone(),
-                    npm,
-                })
+    /// Ensures that this version of Node can be used, fetching it if necessary
+    ///
+    /// Returns `None` for the system Node, which is only checked for on the PATH, since the
+    /// version of npm bundled with it isn't known.
+    pub(crate) fn ensure_available(
+        &self,
+        session: &mut Session,
+    ) -> Fallible<(Option<NodeVersion>, FetchOutcome)> {
+        match &self.version {
+            PlatformNode::Version(version) => self
+                .ensure_fetched(version, session)
+                .map(|(node_version, outcome)| (Some(node_version), outcome)),
+            PlatformNode::System => {
+                System::node_path()?;
+                Ok((None, FetchOutcome::AlreadyInstalled))
             }
-            FetchStatus::FetchNeeded(_lock) => fetch::fetch(&self.version, session.hooks()?.node()),
         }
     }
 }
 
+/// Determine the Node version from the file name of a distribution archive
+fn version_from_archive_name(archive: &Path) -> Fallible<Version> {
+    let file_name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    let version = file_name
+        .strip_prefix("node-v")
+        .and_then(|rest| rest.split('-').next())
+        .and_then(|version| Version::parse(version).ok());
+
+    match version {
+        Some(version) if file_name == Node::archive_filename(&version) => Ok(version),
+        Some(version) => Err(ErrorKind::LocalArchiveMismatch {
+            file: archive.to_owned(),
+            expected: Node::archive_filename(&version),
+        }
+        .into()),
+        None => Err(ErrorKind::LocalArchiveMismatch {
+            file: archive.to_owned(),
+            expected: format!(
+                "node-v<version>-{}-{}.{}",
+                NODE_DISTRO_OS, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION
+            ),
+        }
+        .into()),
+    }
+}
+
+/// Warns about the global packages that may need to be reinstalled after the default Node changed
+/// to a new major version
+///
+/// This is only advice, so a failure to read the installed packages is ignored.
+fn warn_packages_for_other_major(node: &Version, session: &mut Session) {
+    match packages_for_other_node_major(node) {
+        Ok(packages) if !packages.is_empty() => {
+            session.warn(Warning::PackagesMayNeedReinstall {
+                node: node.clone(),
+                packages,
+            });
+        }
+        Ok(_) => {}
+        Err(error) => debug!("Could not check the installed packages: {}", error),
+    }
+}
+
 impl Tool for Node {
-    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<()> {
-        let node_version = self.ensure_fetched(session)?;
+    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<FetchOutcome> {
+        // There is nothing to fetch for the system Node, only check that it exists
+        let (node_version, outcome) = self.ensure_available(session)?;
 
-        info_fetched(node_version);
-        Ok(())
+        match node_version {
+            Some(node_version) => info_fetched(node_version),
+            None => info_fetched(self),
+        }
+        Ok(outcome)
     }
     fn install(self: Box<Self>, session: &mut Session) -> Fallible<()> {
         // Acquire a lock on the Volta directory, if possible, to prevent concurrent changes
         let _lock = VoltaLock::acquire();
-        let node_version = self.ensure_fetched(session)?;
+        let started = Instant::now();
+        let (node_version, outcome) = self.ensure_available(session)?;
+        let fetched = FetchSummary::since(outcome, started);
 
         let default_toolchain = session.toolchain_mut()?;
-        default_toolchain.set_active_node(&self.version)?;
-
-        // If the user has a default version of `npm`, we shouldn't show the "(with npm@X.Y.ZZZ)" text in the success message
-        // Instead we should check if the bundled version is higher than the default and inform the user
-        // Note: The previous line ensures that there will be a default platform
-        if let Some(default_npm) = &default_toolchain.platform().unwrap().npm {
-            info_installed(&self); // includes node version
-
-            if node_version.npm > *default_npm {
-                info!("{} this version of Node includes {}, which is higher than your default version ({}).
-      To use the version included with Node, run `volta install npm@bundled`",
-                    note_prefix(),
-                    tool_version("npm", node_version.npm),
-                    default_npm.to_string()
-                );
-            }
+        let already_default = default_toolchain
+            .platform()
+            .is_some_and(|platform| platform.node == self.version);
+
+        if already_default {
+            // Nothing changes, so there's no need to rewrite the default platform
+            info_already_default(&self);
         } else {
-            info_installed(node_version); // includes node and npm version
+            let previous_major = default_toolchain
+                .platform()
+                .and_then(|platform| platform.node.version())
+                .map(|version| version.major);
+            default_toolchain
+                .set_active_node(&self.version)
+                .map_err(not_activated(tool_version("node", &self.version)))?;
+
+            // If the user has a default version of `npm`, we shouldn't show the "(with npm@X.Y.ZZZ)" text in the success message
+            // Instead we should check if the bundled version is higher than the default and inform the user
+            // Note: The previous line ensures that there will be a default platform
+            // The npm bundled with the system Node isn't known, so it is never shown or compared
+            let default_npm = default_toolchain.platform().unwrap().npm.clone();
+            match (default_npm, node_version) {
+                (Some(default_npm), Some(node_version)) => {
+                    info_installed(&self, fetched); // includes node version
+
+                    if node_version.npm > default_npm {
+                        session.warn(Warning::BundledNpmNewerThanDefault {
+                            bundled: node_version.npm,
+                            default: default_npm,
+                        });
+                    }
+                }
+                (None, Some(node_version)) => info_installed(node_version, fetched), // includes node and npm version
+                (_, None) => info_installed(&self, fetched),
+            }
+
+            if let PlatformNode::Version(version) = &self.version {
+                if previous_major.is_some_and(|major| major != version.major) {
+                    warn_packages_for_other_major(version, session);
+                }
+            }
         }
 
         check_shim_reachable("node");
 
-        if let Ok(Some(project)) = session.project_platform() {
-            info_project_version(tool_version("node", &project.node), &self);
+        let project_version = session
+            .project_platform()
+            .ok()
+            .flatten()
+            .map(|project| tool_version("node", &project.node));
+        if let Some(project_version) = project_version {
+            warn_project_version(session, project_version, &self);
         }
 
         Ok(())
     }
     fn pin(self: Box<Self>, session: &mut Session) -> Fallible<()> {
         if session.project()?.is_some() {
-            let node_version = self.ensure_fetched(session)?;
+            let (node_version, _) = self.ensure_available(session)?;
 
             // Note: We know this will succeed, since we checked above
             let project = session.project_mut()?.unwrap();
@@ -262,19 +396,19 @@ impl Tool for Node {
             // If the user has a pinned version of `npm`, we shouldn't show the "(with npm@X.Y.ZZZ)" text in the success message
             // Instead we should check if the bundled version is higher than the pinned and inform the user
             // Note: The pin operation guarantees there will be a platform
-            if let Some(pinned_npm) = &project.platform().unwrap().npm {
-                info_pinned(self); // includes node version
-
-                if node_version.npm > *pinned_npm {
-                    info!("{} this version of Node includes {}, which is higher than your pinned version ({}).
-      To use the version included with Node, run `volta pin npm@bundled`",
-                        note_prefix(),
-                        tool_version("npm", node_version.npm),
-                        pinned_npm.to_string()
-                    );
+            match (project.platform().unwrap().npm.clone(), node_version) {
+                (Some(pinned_npm), Some(node_version)) => {
+                    info_pinned(self); // includes node version
+
+                    if node_version.npm > pinned_npm {
+                        session.warn(Warning::BundledNpmNewerThanPinned {
+                            bundled: node_version.npm,
+                            pinned: pinned_npm,
+                        });
+                    }
                 }
-            } else {
-                info_pinned(node_version); // includes node and npm version
+                (None, Some(node_version)) => info_pinned(node_version), // includes node and npm version
+                (_, None) => info_pinned(self),
             }
 
             Ok(())
@@ -282,6 +416,26 @@ impl Tool for Node {
             Err(ErrorKind::NotInPackage.into())
         }
     }
+
+    fn kind(&self) -> ToolKindName {
+        ToolKindName::Node
+    }
+    fn describe_plan(&self, action: PlanAction, session: &Session) -> Fallible<ToolPlan> {
+        let mut plan = ToolPlan::new(self, action);
+        // The system Node is never fetched or unpacked, so only the state changes apply to it
+        if let PlatformNode::Version(version) = &self.version {
+            plan.source = Some(if node_available(version)? {
+                PlanSource::AlreadyFetched
+            } else {
+                resolve::with_resolver(session, |resolver| fetch::plan_source(version, resolver))?
+            });
+            plan.image_dir = Some(volta_home()?.node_image_dir(&version.to_string()));
+        }
+        plan.changes = planned_changes("node", &self.version, action, session, |platform| {
+            Some(&platform.node)
+        })?;
+        Ok(plan)
+    }
 }
 
 impl Display for Node {
@@ -293,6 +447,160 @@ impl Display for Node {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layout::override_volta_home;
+    use crate::tool::PlanChange;
+    use std::fs;
+
+    fn write_default_node(version: &str) {
+        let path = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!(
+                r#"{{ "node": {{ "runtime": "{}", "npm": null }} }}"#,
+                version
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_install_plan_downloads_and_replaces_default() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        write_default_node("18.17.0");
+
+        let version = Version::parse("20.5.1").unwrap();
+        let session = Session::init_unchecked();
+        let plan = Node::new(version.clone())
+            .describe_plan(PlanAction::Install, &session)
+            .unwrap();
+
+        assert_eq!(plan.tool, "node@20.5.1");
+        assert_eq!(plan.action, PlanAction::Install);
+        match &plan.source {
+            Some(PlanSource::Download(urls)) => {
+                let suffix = format!("/v20.5.1/{}", Node::archive_filename(&version));
+                assert!(urls[0].ends_with(&suffix), "unexpected URL {}", urls[0]);
+            }
+            other => panic!("expected a download, got {:?}", other),
+        }
+        assert_eq!(
+            plan.image_dir,
+            Some(volta_home().unwrap().node_image_dir("20.5.1"))
+        );
+        assert_eq!(
+            plan.changes,
+            vec![PlanChange::Default {
+                previous: Some("node@18.17.0".into())
+            }]
+        );
+        assert!(plan
+            .to_string()
+            .contains("set as default, replacing node@18.17.0"));
+    }
+
+    #[test]
+    fn test_install_plan_uses_cached_archive() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        write_default_node("20.5.1");
+
+        let version = Version::parse("20.5.1").unwrap();
+        let archive = volta_home()
+            .unwrap()
+            .node_inventory_dir()
+            .join(Node::archive_filename(&version));
+        fs::create_dir_all(archive.parent().unwrap()).unwrap();
+        fs::write(&archive, b"").unwrap();
+
+        let session = Session::init_unchecked();
+        let plan = Node::new(version)
+            .describe_plan(PlanAction::Install, &session)
+            .unwrap();
+
+        assert_eq!(plan.source, Some(PlanSource::Cache(archive)));
+        // Node 20.5.1 is already the default, so installing it changes nothing
+        assert!(plan.changes.is_empty());
+    }
+
+    #[test]
+    fn test_install_keeps_default_when_save_fails() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        write_default_node("18.17.0");
+
+        // Node 20.5.1 is already fetched, but without a tmp directory the default platform
+        // can't be saved
+        let volta_home = volta_home().unwrap();
+        fs::create_dir_all(volta_home.node_image_dir("20.5.1")).unwrap();
+        let npm_version_file = volta_home.node_npm_version_file("20.5.1");
+        fs::create_dir_all(npm_version_file.parent().unwrap()).unwrap();
+        fs::write(npm_version_file, "9.8.0").unwrap();
+
+        let mut session = Session::init_unchecked();
+        let error = Box::new(Node::new(Version::parse("20.5.1").unwrap()))
+            .install(&mut session)
+            .unwrap_err();
+
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::ToolNotActivated {
+                tool: "node@20.5.1".into()
+            }
+        );
+        assert_eq!(
+            session
+                .default_platform()
+                .unwrap()
+                .map(|platform| platform.node.to_string()),
+            Some("18.17.0".into())
+        );
+    }
+
+    #[test]
+    fn test_install_new_major_lists_packages() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        write_default_node("18.17.0");
+
+        let volta_home = volta_home().unwrap();
+        fs::create_dir_all(volta_home.tmp_dir()).unwrap();
+        fs::create_dir_all(volta_home.node_image_dir("20.5.1")).unwrap();
+        let npm_version_file = volta_home.node_npm_version_file("20.5.1");
+        fs::create_dir_all(npm_version_file.parent().unwrap()).unwrap();
+        fs::write(npm_version_file, "9.8.0").unwrap();
+
+        fs::create_dir_all(volta_home.default_package_dir()).unwrap();
+        for (name, node) in [("cowsay", "18.17.0"), ("typescript", "20.1.0")] {
+            fs::write(
+                volta_home.default_package_config_file(name),
+                format!(
+                    r#"{{
+  "name": "{}",
+  "version": "1.0.0",
+  "platform": {{ "node": "{}", "npm": null, "yarn": null }},
+  "bins": [],
+  "manager": "Npm"
+}}"#,
+                    name, node
+                ),
+            )
+            .unwrap();
+        }
+
+        let mut session = Session::init_unchecked();
+        Box::new(Node::new(Version::parse("20.5.1").unwrap()))
+            .install(&mut session)
+            .unwrap();
+
+        assert!(session
+            .warnings()
+            .contains(&Warning::PackagesMayNeedReinstall {
+                node: Version::parse("20.5.1").unwrap(),
+                packages: vec!["cowsay".into()],
+            }));
+    }
 
     #[test]
     fn test_node_archive_basename() {
@@ -313,6 +621,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_version_from_archive_name() {
+        let version = Version::parse("20.5.1").unwrap();
+        let archive = PathBuf::from("/tmp").join(Node::archive_filename(&version));
+        assert_eq!(version_from_archive_name(&archive).unwrap(), version);
+
+        // Archives for a different platform or with an unrecognizable name are rejected
+        let other_platform = PathBuf::from("/tmp/node-v20.5.1-sunos-x64.tar.xz");
+        assert!(version_from_archive_name(&other_platform).is_err());
+
+        let unversioned = PathBuf::from("/tmp/node.tar.gz");
+        assert!(version_from_archive_name(&unversioned).is_err());
+    }
+
     #[test]
     #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
     fn test_fallback_node_archive_basename() {
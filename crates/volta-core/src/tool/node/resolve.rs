@@ -1,20 +1,27 @@
 //! Provides resolution of Node requirements into specific versions, using the NodeJS index
 
+use std::fmt;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
 use std::time::{Duration, SystemTime};
 use std::env;
 
 use super::super::registry_fetch_error;
+use super::fetch::determine_remote_urls;
 use super::metadata::{NodeEntry, NodeIndex, RawNodeIndex};
+use super::{mirror_fallback, official_node_server_root, version_from_archive_name};
 use crate::error::{Context, ErrorKind, Fallible};
 use crate::fs::{create_staging_file, read_file};
+use crate::hook::tool::MetadataHook;
 use crate::hook::ToolHooks;
+use crate::inventory::node_available;
 use crate::layout::volta_home;
+use crate::platform::{PlatformNode, SYSTEM_NODE};
 use crate::session::Session;
 use crate::style::progress_spinner;
-use crate::tool::Node;
+use crate::tool::{self, fetched_exact_version, Node, ToolKindName};
 use crate::version::{VersionSpec, VersionTag};
+use archive::request::{self, RequestKind};
 use attohttpc::header::HeaderMap;
 use attohttpc::Response;
 use cfg_if::cfg_if;
@@ -22,6 +29,7 @@ use fs_utils::ensure_containing_dir_exists;
 use headers::{CacheControl, Expires, HeaderMapExt};
 use log::debug;
 use node_semver::{Range, Version};
+use serde::{Deserialize, Serialize};
 
 // ISSUE (#86): Move public repository URLs to config file
 cfg_if! {
@@ -31,128 +39,434 @@ cfg_if! {
         // we can't use `mockito::server_url()`, which relies on shared memory.
         #[allow(deprecated)]
         const SERVER_URL: &str = mockito::SERVER_URL;
-        fn public_node_version_index() -> String {
-            format!("{}/node-dist/index.json", SERVER_URL)
+        // Mirrors are still honored, so that failing over between them can be tested
+        fn public_node_version_index() -> (Vec<String>, IndexSource) {
+            match mirror_indexes() {
+                Some(urls) => (urls, IndexSource::Mirror),
+                None => (vec![format!("{}/node-dist/index.json", SERVER_URL)], IndexSource::Default),
+            }
         }
     } else {
         // NODE_MIRROR=https://mirrors.aliyun.com/nodejs-release
-        /// Returns the URL of the index of available Node versions on the public Node server.
-        fn public_node_version_index() -> String {
+        /// Returns the URLs of the index of available Node versions on the public Node servers,
+        /// in order of preference, along with whether they were overridden by a mirror.
+        fn public_node_version_index() -> (Vec<String>, IndexSource) {
             // "https://mirrors.aliyun.com/nodejs-release/index.json".to_string()
-            match env::var_os("ENV_NODE_MIRROR") {
-                Some(val) =>  format!("{}/index.json", val.to_string_lossy()),
-                None => "https://mirrors.aliyun.com/nodejs-release/index.json".to_string()
+            match mirror_indexes() {
+                Some(urls) => (urls, IndexSource::Mirror),
+                None => (vec!["https://mirrors.aliyun.com/nodejs-release/index.json".to_string()], IndexSource::Default)
             }
         }
     }
 }
 
-pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
-    let hooks = session.hooks()?.node();
+/// Returns the index URLs of the mirrors listed in `ENV_NODE_MIRROR`, if any are set
+///
+/// As with downloads, several comma-separated mirrors can be listed, which are tried in turn.
+fn mirror_indexes() -> Option<Vec<String>> {
+    let setting = env::var_os("ENV_NODE_MIRROR")?;
+    let urls: Vec<String> = tool::mirror_candidates(&setting.to_string_lossy())
+        .into_iter()
+        .map(|mirror| format!("{}/index.json", mirror))
+        .collect();
+
+    (!urls.is_empty()).then_some(urls)
+}
+
+/// Environment variable that allows pre-release Node versions to satisfy a version range
+const VOLTA_INCLUDE_PRERELEASE: &str = "VOLTA_INCLUDE_PRERELEASE";
+
+/// A source of Node versions and distributions
+///
+/// The default, [`IndexResolver`], uses the Node index and the public Node servers (or the
+/// `node` hooks, when they are configured). Another implementation can be set on the session,
+/// which is then used both to resolve versions and to determine where to download them from.
+pub trait VersionResolver {
+    /// Resolves a version requirement into a specific version of Node
+    ///
+    /// Local archives, git sources, and the system Node are handled by [`resolve`] before a
+    /// resolver is consulted, so they are never passed here.
+    fn resolve(&self, spec: VersionSpec) -> Fallible<Version>;
+
+    /// Determines the URL to download the Node distribution archive for a version from
+    fn distro_url(&self, version: &Version) -> Fallible<String>;
+
+    /// Determines the URLs to download the distribution archive from, in order of preference
+    ///
+    /// Each URL is tried in turn until a download succeeds. By default, this is only the URL
+    /// from `distro_url`.
+    fn distro_urls(&self, version: &Version) -> Fallible<Vec<String>> {
+        self.distro_url(version).map(|url| vec![url])
+    }
+
+    /// Suggests the available versions closest to one that couldn't be downloaded
+    ///
+    /// By default, there are no suggestions.
+    fn suggestions(&self, _version: &Version) -> Vec<Version> {
+        Vec::new()
+    }
+}
+
+/// The default resolver, using the Node index and public servers, or the `node` hooks
+pub struct IndexResolver<'a> {
+    hooks: Option<&'a ToolHooks<Node>>,
+}
+
+impl<'a> IndexResolver<'a> {
+    pub fn new(hooks: Option<&'a ToolHooks<Node>>) -> Self {
+        IndexResolver { hooks }
+    }
+}
+
+impl VersionResolver for IndexResolver<'_> {
+    fn resolve(&self, spec: VersionSpec) -> Fallible<Version> {
+        let hooks = self.hooks;
+        match spec {
+            VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks),
+            VersionSpec::Exact(version) => Ok(version),
+            VersionSpec::None | VersionSpec::Tag(VersionTag::Lts) => resolve_lts(hooks),
+            VersionSpec::Tag(VersionTag::Latest) => resolve_latest(hooks),
+            // Node doesn't have "tagged" versions (apart from 'latest', 'lts', and 'system'), so custom tags will always be an error
+            spec @ (VersionSpec::Tag(VersionTag::Custom(_))
+            | VersionSpec::Local(_)
+            | VersionSpec::Git(_)) => Err(ErrorKind::NodeVersionNotFound {
+                matching: spec.to_string(),
+                suggestions: Vec::new(),
+                index_url: None,
+            }
+            .into()),
+        }
+    }
+
+    fn distro_url(&self, version: &Version) -> Fallible<String> {
+        let mut urls = self.distro_urls(version)?;
+        Ok(urls.remove(0))
+    }
+
+    fn distro_urls(&self, version: &Version) -> Fallible<Vec<String>> {
+        determine_remote_urls(version, self.hooks)
+    }
+
+    fn suggestions(&self, version: &Version) -> Vec<Version> {
+        nearest_available(version, self.hooks).unwrap_or_else(|error| {
+            debug!(
+                "Could not look up the closest available versions: {}",
+                error
+            );
+            Vec::new()
+        })
+    }
+}
+
+/// Calls `f` with the resolver for the session: the custom resolver if one has been set, or else
+/// an `IndexResolver` using the configured hooks
+pub(super) fn with_resolver<T, F>(session: &Session, f: F) -> Fallible<T>
+where
+    F: FnOnce(&dyn VersionResolver) -> Fallible<T>,
+{
+    match session.node_resolver() {
+        Some(resolver) => f(resolver),
+        None => f(&IndexResolver::new(session.hooks()?.node())),
+    }
+}
+
+pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<PlatformNode> {
     match matching {
-        VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks),
-        VersionSpec::Exact(version) => Ok(version),
-        VersionSpec::None | VersionSpec::Tag(VersionTag::Lts) => resolve_lts(hooks),
-        VersionSpec::Tag(VersionTag::Latest) => resolve_latest(hooks),
-        // Node doesn't have "tagged" versions (apart from 'latest' and 'lts'), so custom tags will always be an error
-        VersionSpec::Tag(VersionTag::Custom(tag)) => {
-            Err(ErrorKind::NodeVersionNotFound { matching: tag }.into())
+        VersionSpec::Tag(VersionTag::Custom(tag)) if tag == SYSTEM_NODE => {
+            debug!("Using the system Node");
+            Ok(PlatformNode::System)
+        }
+        matching => resolve_version(matching, session).map(PlatformNode::Version),
+    }
+}
+
+/// Resolves a spec to a Node version that Volta manages, without special-casing the system Node
+pub fn resolve_version(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    session.resolve_cached(ToolKindName::Node, matching, resolve_uncached)
+}
+
+fn resolve_uncached(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    // These don't come from a version source, so they don't depend on the resolver
+    let matching = match matching {
+        VersionSpec::Local(archive) => {
+            return version_from_archive_name(&archive);
+        }
+        VersionSpec::Git(_) => {
+            return Err(ErrorKind::GitSourceNotSupported {
+                tool: "node".into(),
+            }
+            .into());
+        }
+        matching => matching,
+    };
+
+    if let Some(version) = fetched_exact_version("node", &matching, node_available)? {
+        return Ok(version);
+    }
+
+    with_resolver(session, |resolver| resolver.resolve(matching))
+}
+
+/// Where the index used to resolve a Node version came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IndexSource {
+    /// A URL provided by the named hook
+    Hook(&'static str),
+    /// The mirrors set with `ENV_NODE_MIRROR`
+    Mirror,
+    /// The default public index
+    Default,
+    /// The official index, used when the mirror doesn't have a matching version
+    Fallback,
+}
+
+impl fmt::Display for IndexSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexSource::Hook(name) => write!(f, "{} hook", name),
+            IndexSource::Mirror => f.write_str("ENV_NODE_MIRROR"),
+            IndexSource::Default => f.write_str("default index"),
+            IndexSource::Fallback => f.write_str("VOLTA_MIRROR_FALLBACK"),
+        }
+    }
+}
+
+/// Determines the URLs of the index to resolve against, using the hook if one is configured
+fn index_url(
+    hook: Option<&MetadataHook>,
+    hook_name: &'static str,
+) -> Fallible<(Vec<String>, IndexSource)> {
+    match hook {
+        Some(hook) => {
+            debug!("Using {} hook to determine node index URL", hook_name);
+            Ok((
+                vec![hook.resolve("index.json")?],
+                IndexSource::Hook(hook_name),
+            ))
         }
+        None => Ok(public_node_version_index()),
     }
 }
 
+/// Lists the Node versions available from the index, newest first
+///
+/// If `lts_only` is set, only LTS releases are included.
+pub fn available(lts_only: bool, session: &mut Session) -> Fallible<Vec<Version>> {
+    let hooks = session.hooks()?.node();
+    let (urls, source) = index_url(hooks.and_then(|hooks| hooks.index.as_ref()), "node.index")?;
+
+    let (_, index) = fetch_node_index(&urls, source)?;
+    let index: NodeIndex = index.into();
+    Ok(index
+        .entries
+        .into_iter()
+        .filter(|entry| !lts_only || entry.lts)
+        .map(|NodeEntry { version, .. }| version)
+        .collect())
+}
+
 fn resolve_latest(hooks: Option<&ToolHooks<Node>>) -> Fallible<Version> {
     // NOTE: This assumes the registry always produces a list in sorted order
     //       from newest to oldest. This should be specified as a requirement
     //       when we document the plugin API.
-    let url = match hooks {
-        Some(&ToolHooks {
-            latest: Some(ref hook),
-            ..
-        }) => {
-            debug!("Using node.latest hook to determine node index URL");
-            hook.resolve("index.json")?
-        }
-        _ => public_node_version_index(),
-    };
-    let version_opt = match_node_version(&url, |_| true)?;
+    let index = index_url(hooks.and_then(|hooks| hooks.latest.as_ref()), "node.latest")?;
+    let (version_opt, _, searched) = match_node_version("latest", &index, "none", |_| true)?;
 
-    match version_opt {
-        Some(version) => {
-            debug!("Found latest node version ({}) from {}", version, url);
-            Ok(version)
-        }
-        None => Err(ErrorKind::NodeVersionNotFound {
+    version_opt.ok_or_else(|| {
+        ErrorKind::NodeVersionNotFound {
             matching: "latest".into(),
+            suggestions: Vec::new(),
+            index_url: Some(searched),
         }
-        .into()),
-    }
+        .into()
+    })
 }
 
 fn resolve_lts(hooks: Option<&ToolHooks<Node>>) -> Fallible<Version> {
-    let url = match hooks {
-        Some(&ToolHooks {
-            index: Some(ref hook),
-            ..
-        }) => {
-            debug!("Using node.index hook to determine node index URL");
-            hook.resolve("index.json")?
-        }
-        _ => public_node_version_index(),
-    };
-    let version_opt = match_node_version(&url, |&NodeEntry { lts, .. }| lts)?;
+    let index = index_url(hooks.and_then(|hooks| hooks.index.as_ref()), "node.index")?;
+    let (version_opt, _, searched) = match_node_version(
+        "lts",
+        &index,
+        "LTS releases only",
+        |&NodeEntry { lts, .. }| lts,
+    )?;
 
-    match version_opt {
-        Some(version) => {
-            debug!("Found newest LTS node version ({}) from {}", version, url);
-            Ok(version)
-        }
-        None => Err(ErrorKind::NodeVersionNotFound {
+    version_opt.ok_or_else(|| {
+        ErrorKind::NodeVersionNotFound {
             matching: "lts".into(),
+            suggestions: Vec::new(),
+            index_url: Some(searched),
         }
-        .into()),
-    }
+        .into()
+    })
 }
 
 fn resolve_semver(matching: Range, hooks: Option<&ToolHooks<Node>>) -> Fallible<Version> {
-    let url = match hooks {
-        Some(&ToolHooks {
-            index: Some(ref hook),
-            ..
-        }) => {
-            debug!("Using node.index hook to determine node index URL");
-            hook.resolve("index.json")?
-        }
-        _ => public_node_version_index(),
+    let index = index_url(hooks.and_then(|hooks| hooks.index.as_ref()), "node.index")?;
+    let include_prerelease = env::var_os(VOLTA_INCLUDE_PRERELEASE).is_some();
+    let filter = if include_prerelease {
+        format!("satisfies '{}', including pre-releases", matching)
+    } else {
+        format!("satisfies '{}'", matching)
     };
-    let version_opt = match_node_version(&url, |NodeEntry { version, .. }| {
-        matching.satisfies(version)
-    })?;
+    let (version_opt, candidates, searched) = match_node_version(
+        &matching.to_string(),
+        &index,
+        &filter,
+        |NodeEntry { version, .. }| satisfies(&matching, version, include_prerelease),
+    )?;
 
-    match version_opt {
-        Some(version) => {
-            debug!(
-                "Found node@{} matching requirement '{}' from {}",
-                version, matching, url
-            );
-            Ok(version)
-        }
-        None => Err(ErrorKind::NodeVersionNotFound {
+    version_opt.ok_or_else(|| {
+        ErrorKind::NodeVersionNotFound {
+            suggestions: nearest_versions(&matching, &candidates),
             matching: matching.to_string(),
+            index_url: Some(searched),
         }
-        .into()),
-    }
+        .into()
+    })
 }
 
+/// Finds the first version in the index that matches the predicate
+///
+/// Also returns every version in the index, so that callers can suggest alternatives when no
+/// version matches, along with the URLs of the indexes that were searched. The decision is logged
+/// as a single trace at the debug level.
+///
+/// If `VOLTA_MIRROR_FALLBACK` is set and no version in a public index matches, the official
+/// index is searched as well. Indexes provided by hooks are authoritative and never fall back.
 fn match_node_version(
-    url: &str,
+    matching: &str,
+    index: &(Vec<String>, IndexSource),
+    filter: &str,
     predicate: impl Fn(&NodeEntry) -> bool,
-) -> Fallible<Option<Version>> {
-    let index: NodeIndex = resolve_node_versions(url)?.into();
-    let mut entries = index.entries.into_iter();
-    Ok(entries
-        .find(predicate)
-        .map(|NodeEntry { version, .. }| version))
+) -> Fallible<(Option<Version>, Vec<Version>, String)> {
+    let (found, candidates, url) = match_in_index(matching, index, filter, &predicate)?;
+
+    match index {
+        (_, IndexSource::Mirror | IndexSource::Default) if found.is_none() && mirror_fallback() => {
+            let fallback = (
+                vec![format!("{}/index.json", official_node_server_root())],
+                IndexSource::Fallback,
+            );
+            let (found, candidates, fallback_url) =
+                match_in_index(matching, &fallback, filter, &predicate)?;
+            Ok((found, candidates, format!("{}, {}", url, fallback_url)))
+        }
+        _ => Ok((found, candidates, url)),
+    }
+}
+
+/// Finds the first version matching the predicate in the index from the first of the candidate
+/// URLs that responds, also returning that URL
+fn match_in_index(
+    matching: &str,
+    (urls, source): &(Vec<String>, IndexSource),
+    filter: &str,
+    predicate: &dyn Fn(&NodeEntry) -> bool,
+) -> Fallible<(Option<Version>, Vec<Version>, String)> {
+    let (url, index) = fetch_node_index(urls, *source)?;
+    let index: NodeIndex = index.into();
+    let found = index
+        .entries
+        .iter()
+        .find(|entry| predicate(entry))
+        .map(|entry| entry.version.clone());
+    let candidates: Vec<Version> = index
+        .entries
+        .into_iter()
+        .map(|NodeEntry { version, .. }| version)
+        .collect();
+
+    debug!(
+        "{}",
+        resolution_trace(
+            matching,
+            &url,
+            *source,
+            candidates.len(),
+            filter,
+            found.as_ref()
+        )
+    );
+
+    Ok((found, candidates, url))
+}
+
+/// Describes how a Node version was chosen from the index
+fn resolution_trace(
+    matching: &str,
+    url: &str,
+    source: IndexSource,
+    considered: usize,
+    filter: &str,
+    chosen: Option<&Version>,
+) -> String {
+    let chosen = chosen.map_or_else(|| "none".to_string(), Version::to_string);
+
+    format!(
+        "Resolving node '{}'
+    index: {} (from {})
+    entries considered: {}
+    filter: {}
+    chosen: {}",
+        matching, url, source, considered, filter, chosen
+    )
+}
+
+/// Finds the available versions closest to a range that none of them satisfy
+///
+/// Returns the highest release below the range and the lowest release above it, where present.
+fn nearest_versions(range: &Range, candidates: &[Version]) -> Vec<Version> {
+    let mut below: Option<&Version> = None;
+    let mut above: Option<&Version> = None;
+
+    for version in candidates.iter().filter(|version| !version.is_prerelease()) {
+        let is_below =
+            Range::parse(format!("<={}", version)).map_or(false, |lower| !range.allows_any(&lower));
+        let is_above =
+            Range::parse(format!(">={}", version)).map_or(false, |upper| !range.allows_any(&upper));
+
+        if is_below && below.map_or(true, |best| version > best) {
+            below = Some(version);
+        }
+        if is_above && above.map_or(true, |best| version < best) {
+            above = Some(version);
+        }
+    }
+
+    below.into_iter().chain(above).cloned().collect()
+}
+
+/// Finds the versions in the index closest to a version that isn't available
+fn nearest_available(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<Vec<Version>> {
+    let (urls, source) = index_url(hooks.and_then(|hooks| hooks.index.as_ref()), "node.index")?;
+    let (_, index) = fetch_node_index(&urls, source)?;
+    let index: NodeIndex = index.into();
+    let candidates: Vec<Version> = index
+        .entries
+        .into_iter()
+        .map(|NodeEntry { version, .. }| version)
+        .collect();
+
+    Ok(Range::parse(version.to_string()).map_or_else(
+        |_| Vec::new(),
+        |range| nearest_versions(&range, &candidates),
+    ))
+}
+
+/// Determines whether a version satisfies a range, following npm's pre-release conventions
+///
+/// By default, a pre-release version only matches if the range explicitly names a pre-release
+/// of the same `major.minor.patch`. With `include_prerelease`, pre-releases are treated like any
+/// other version and match whenever they fall within the bounds of the range.
+fn satisfies(range: &Range, version: &Version, include_prerelease: bool) -> bool {
+    if range.satisfies(version) {
+        return true;
+    }
+
+    include_prerelease
+        && version.is_prerelease()
+        && Range::parse(format!("={}", version)).map_or(false, |exact| range.allows_any(&exact))
 }
 
 /// Reads a public index from the Node cache, if it exists and hasn't expired.
@@ -176,16 +490,100 @@ fn read_cached_opt(url: &str) -> Fallible<Option<RawNodeIndex>> {
         file: index_file.to_owned(),
     })?;
 
-    let Some(json) = cached
-        .as_ref()
-        .and_then(|content| content.strip_prefix(url))
+    let Some((header, json)) = cached
+        .as_deref()
+        .and_then(|content| content.split_once('\n'))
     else {
         return Ok(None);
     };
 
+    // Caches written before the header was added start with the bare URL instead
+    let Ok(header) = serde_json::de::from_str::<IndexCacheHeader>(header) else {
+        debug!("Node index cache is in an older format");
+        return Ok(None);
+    };
+
+    if header.url != url {
+        debug!(
+            "Node index cache is for '{}' rather than '{}'",
+            header.url, url
+        );
+        return Ok(None);
+    }
+
+    debug!(
+        "Node index cache was fetched from '{}' at {}",
+        header.url, header.fetched
+    );
     serde_json::de::from_str(json).with_context(|| ErrorKind::ParseNodeIndexCacheError)
 }
 
+/// The first line of the Node index cache, identifying where the cached index came from
+///
+/// The index is only used for the exact URL it was fetched from, so switching mirrors (or
+/// hooks) always fetches the index again rather than using one from a different server.
+#[derive(Serialize, Deserialize)]
+struct IndexCacheHeader {
+    /// The URL the index was fetched from
+    url: String,
+    /// When the index was fetched, as an HTTP date, for diagnostics
+    fetched: String,
+}
+
+/// Writes a fetched index to the Node cache, along with the time at which it expires
+fn write_cached(url: &str, index: &str, expires: SystemTime) -> Fallible<()> {
+    let header = IndexCacheHeader {
+        url: url.to_string(),
+        fetched: httpdate::fmt_http_date(SystemTime::now()),
+    };
+
+    let cached = create_staging_file()?;
+
+    let mut cached_file: &File = cached.as_file();
+    serde_json::ser::to_writer(cached_file, &header)
+        .map_err(io::Error::from)
+        .and_then(|_| writeln!(cached_file))
+        .and_then(|_| cached_file.write_all(index.as_bytes()))
+        .with_context(|| ErrorKind::WriteNodeIndexCacheError {
+            file: cached.path().to_path_buf(),
+        })?;
+
+    let index_cache_file = volta_home()?.node_index_file();
+    ensure_containing_dir_exists(&index_cache_file).with_context(|| {
+        ErrorKind::ContainingDirError {
+            path: index_cache_file.to_owned(),
+        }
+    })?;
+    cached
+        .persist(index_cache_file)
+        .with_context(|| ErrorKind::WriteNodeIndexCacheError {
+            file: index_cache_file.to_owned(),
+        })?;
+
+    let expiry = create_staging_file()?;
+    let mut expiry_file: &File = expiry.as_file();
+
+    write!(expiry_file, "{}", httpdate::fmt_http_date(expires)).with_context(|| {
+        ErrorKind::WriteNodeIndexExpiryError {
+            file: expiry.path().to_path_buf(),
+        }
+    })?;
+
+    let index_expiry_file = volta_home()?.node_index_expiry_file();
+    ensure_containing_dir_exists(&index_expiry_file).with_context(|| {
+        ErrorKind::ContainingDirError {
+            path: index_expiry_file.to_owned(),
+        }
+    })?;
+    expiry
+        .persist(index_expiry_file)
+        .with_context(|| ErrorKind::WriteNodeIndexExpiryError {
+            file: index_expiry_file.to_owned(),
+        })?;
+
+    Ok(())
+}
+
 /// Get the cache max-age of an HTTP response.
 fn max_age(headers: &HeaderMap) -> Duration {
     const FOUR_HOURS: Duration = Duration::from_secs(4 * 60 * 60);
@@ -195,8 +593,29 @@ fn max_age(headers: &HeaderMap) -> Duration {
         .unwrap_or(FOUR_HOURS)
 }
 
-fn resolve_node_versions(url: &str) -> Fallible<RawNodeIndex> {
-    match read_cached_opt(url)? {
+/// Fetches the Node index from each of the candidate URLs in turn, returning the first index that
+/// is fetched successfully along with the URL it came from
+fn fetch_node_index(urls: &[String], source: IndexSource) -> Fallible<(String, RawNodeIndex)> {
+    tool::fetch_with_failover(urls, |url| {
+        resolve_node_versions(url, source).map(|index| (url.to_string(), index))
+    })
+}
+
+fn resolve_node_versions(url: &str, source: IndexSource) -> Fallible<RawNodeIndex> {
+    // The cache only holds a single index, and the official index is only consulted when the
+    // mirror doesn't have a version, so caching it would evict the mirror's index
+    let cacheable = source != IndexSource::Fallback;
+
+    let cached = if !cacheable {
+        None
+    } else if tool::use_metadata_cache() {
+        read_cached_opt(url)?
+    } else {
+        debug!("Skipping the Node index cache, since VOLTA_NO_CACHE is set");
+        None
+    };
+
+    match cached {
         Some(serial) => {
             debug!("Found valid cache of Node version index");
             Ok(serial)
@@ -205,7 +624,7 @@ fn resolve_node_versions(url: &str) -> Fallible<RawNodeIndex> {
             debug!("Node index cache was not found or was invalid");
             let spinner = progress_spinner(format!("Fetching public registry: {}", url));
 
-            let (_, headers, response) = attohttpc::get(url)
+            let (_, headers, response) = request::get(url, RequestKind::Metadata)
                 .send()
                 .and_then(Response::error_for_status)
                 .with_context(registry_fetch_error("Node", url))?
@@ -227,50 +646,210 @@ fn resolve_node_versions(url: &str) -> Fallible<RawNodeIndex> {
                     }
                 })?;
 
-            let cached = create_staging_file()?;
+            if cacheable {
+                write_cached(url, &response_text, expires)?;
+            }
 
-            let mut cached_file: &File = cached.as_file();
-            writeln!(cached_file, "{}", url)
-                .and_then(|_| cached_file.write(response_text.as_bytes()))
-                .with_context(|| ErrorKind::WriteNodeIndexCacheError {
-                    file: cached.path().to_path_buf(),
-                })?;
+            spinner.finish_and_clear();
+            Ok(index)
+        }
+    }
+}
 
-            let index_cache_file = volta_home()?.node_index_file();
-            ensure_containing_dir_exists(&index_cache_file).with_context(|| {
-                ErrorKind::ContainingDirError {
-                    path: index_cache_file.to_owned(),
-                }
-            })?;
-            cached.persist(index_cache_file).with_context(|| {
-                ErrorKind::WriteNodeIndexCacheError {
-                    file: index_cache_file.to_owned(),
-                }
-            })?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::override_volta_home;
+    use crate::tool::{PlanAction, PlanSource, Tool};
 
-            let expiry = create_staging_file()?;
-            let mut expiry_file: &File = expiry.as_file();
+    #[test]
+    fn test_cache_is_keyed_by_mirror() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        std::fs::create_dir_all(volta_home().unwrap().tmp_dir()).unwrap();
 
-            write!(expiry_file, "{}", httpdate::fmt_http_date(expires)).with_context(|| {
-                ErrorKind::WriteNodeIndexExpiryError {
-                    file: expiry.path().to_path_buf(),
-                }
-            })?;
+        let aliyun = "https://mirrors.aliyun.com/nodejs-release/index.json";
+        let npmmirror = "https://npmmirror.com/mirrors/node/index.json";
+        let expires = SystemTime::now() + Duration::from_secs(60 * 60);
+        write_cached(aliyun, "[]", expires).unwrap();
+
+        assert!(read_cached_opt(aliyun).unwrap().is_some());
+        // Switching mirrors ignores the cached index, so it is fetched again
+        assert!(read_cached_opt(npmmirror).unwrap().is_none());
+        // A URL that merely starts with the cached one is a different index
+        assert!(read_cached_opt(&format!("{}?v2", aliyun))
+            .unwrap()
+            .is_none());
 
-            let index_expiry_file = volta_home()?.node_index_expiry_file();
-            ensure_containing_dir_exists(&index_expiry_file).with_context(|| {
-                ErrorKind::ContainingDirError {
-                    path: index_expiry_file.to_owned(),
+        let cached = std::fs::read_to_string(volta_home().unwrap().node_index_file()).unwrap();
+        let (header, _) = cached.split_once('\n').unwrap();
+        let header: IndexCacheHeader = serde_json::from_str(header).unwrap();
+        assert_eq!(header.url, aliyun);
+        assert!(httpdate::parse_http_date(&header.fetched).is_ok());
+    }
+
+    #[test]
+    fn test_cache_in_older_format_is_ignored() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        std::fs::create_dir_all(volta_home().unwrap().tmp_dir()).unwrap();
+
+        let url = "https://mirrors.aliyun.com/nodejs-release/index.json";
+        write_cached(url, "[]", SystemTime::now() + Duration::from_secs(60 * 60)).unwrap();
+        std::fs::write(
+            volta_home().unwrap().node_index_file(),
+            format!("{}\n[]", url),
+        )
+        .unwrap();
+
+        assert!(read_cached_opt(url).unwrap().is_none());
+    }
+
+    /// A resolver for an internal build server, which only knows about the `acme` tag
+    struct StubResolver;
+
+    impl VersionResolver for StubResolver {
+        fn resolve(&self, spec: VersionSpec) -> Fallible<Version> {
+            match spec {
+                VersionSpec::Tag(VersionTag::Custom(tag)) if tag == "acme" => {
+                    Ok(Version::parse("20.5.1-acme.3").unwrap())
                 }
-            })?;
-            expiry.persist(index_expiry_file).with_context(|| {
-                ErrorKind::WriteNodeIndexExpiryError {
-                    file: index_expiry_file.to_owned(),
+                spec => Err(ErrorKind::NodeVersionNotFound {
+                    matching: spec.to_string(),
+                    suggestions: Vec::new(),
+                    index_url: None,
                 }
-            })?;
+                .into()),
+            }
+        }
 
-            spinner.finish_and_clear();
-            Ok(index)
+        fn distro_url(&self, version: &Version) -> Fallible<String> {
+            Ok(format!("https://builds.acme.test/node/{}.tar.gz", version))
         }
     }
+
+    #[test]
+    fn test_custom_resolver() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let mut session = Session::init_unchecked();
+        session.set_node_resolver(Box::new(StubResolver));
+
+        let version = resolve_version("acme".parse().unwrap(), &mut session).unwrap();
+        assert_eq!(version, Version::parse("20.5.1-acme.3").unwrap());
+        assert!(resolve("^20".parse().unwrap(), &mut session).is_err());
+        // The system Node never reaches the resolver
+        assert_eq!(
+            resolve("system".parse().unwrap(), &mut session).unwrap(),
+            PlatformNode::System
+        );
+
+        let plan = Node::new(version)
+            .describe_plan(PlanAction::Fetch, &session)
+            .unwrap();
+        assert_eq!(
+            plan.source,
+            Some(PlanSource::Download(vec![
+                "https://builds.acme.test/node/20.5.1-acme.3.tar.gz".into()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_exact_version_in_inventory() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let cached = Version::parse("18.17.0").unwrap();
+        std::fs::create_dir_all(
+            volta_home()
+                .unwrap()
+                .node_image_root_dir()
+                .join(cached.to_string()),
+        )
+        .unwrap();
+        let mut session = Session::init_unchecked();
+        // The stub resolver fails for exact versions, as an unreachable index would
+        session.set_node_resolver(Box::new(StubResolver));
+
+        assert_eq!(
+            resolve_version(VersionSpec::Exact(cached.clone()), &mut session).unwrap(),
+            cached
+        );
+        let missing = Version::parse("18.17.1").unwrap();
+        assert!(resolve(VersionSpec::Exact(missing), &mut session).is_err());
+    }
+
+    #[test]
+    fn test_resolution_trace() {
+        let chosen = Version::parse("20.11.1").unwrap();
+        let trace = resolution_trace(
+            "^20",
+            "https://example.com/index.json",
+            IndexSource::Hook("node.index"),
+            42,
+            "satisfies '^20'",
+            Some(&chosen),
+        );
+
+        assert_eq!(
+            trace,
+            "Resolving node '^20'
+    index: https://example.com/index.json (from node.index hook)
+    entries considered: 42
+    filter: satisfies '^20'
+    chosen: 20.11.1"
+        );
+
+        let trace = resolution_trace("lts", "/index.json", IndexSource::Default, 0, "LTS", None);
+        assert!(trace.contains("(from default index)"));
+        assert!(trace.ends_with("chosen: none"));
+    }
+
+    #[test]
+    fn test_satisfies_excludes_prerelease_by_default() {
+        let range = Range::parse(">=20").unwrap();
+        let release = Version::parse("21.0.0").unwrap();
+        let prerelease = Version::parse("21.0.0-rc.1").unwrap();
+
+        assert!(satisfies(&range, &release, false));
+        assert!(!satisfies(&range, &prerelease, false));
+
+        // A range that names a pre-release explicitly still admits matching pre-releases
+        let explicit = Range::parse(">=21.0.0-rc.0").unwrap();
+        assert!(satisfies(&explicit, &prerelease, false));
+    }
+
+    #[test]
+    fn test_nearest_versions() {
+        let candidates: Vec<Version> = ["20.0.0", "19.0.0", "19.0.0-rc.1", "18.20.4", "18.20.3"]
+            .iter()
+            .map(|version| Version::parse(version).unwrap())
+            .collect();
+
+        let impossible = Range::parse("=18.99.0").unwrap();
+        assert_eq!(
+            nearest_versions(&impossible, &candidates),
+            vec![
+                Version::parse("18.20.4").unwrap(),
+                Version::parse("19.0.0").unwrap()
+            ]
+        );
+
+        let too_new = Range::parse(">=21").unwrap();
+        assert_eq!(
+            nearest_versions(&too_new, &candidates),
+            vec![Version::parse("20.0.0").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_satisfies_includes_prerelease_when_toggled() {
+        let range = Range::parse(">=20").unwrap();
+        let prerelease = Version::parse("21.0.0-rc.1").unwrap();
+        assert!(satisfies(&range, &prerelease, true));
+
+        // Pre-releases outside of the bounds of the range are still excluded
+        let caret = Range::parse("^20").unwrap();
+        assert!(!satisfies(&caret, &prerelease, true));
+    }
 }
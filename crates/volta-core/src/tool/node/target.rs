@@ -0,0 +1,201 @@
+//! Provides the target platforms that Node distributions are published for
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use super::{NODE_DISTRO_ARCH, NODE_DISTRO_OS};
+use crate::error::{ErrorKind, VoltaError};
+use node_semver::Version;
+
+/// The operating systems that Node distributions are published for, as named in the archives
+const KNOWN_OS: [&str; 3] = ["darwin", "linux", "win"];
+
+/// The architectures that Node distributions are published for, as named in the archives
+const KNOWN_ARCH: [&str; 6] = ["arm64", "armv7l", "ppc64le", "s390x", "x64", "x86"];
+
+/// The platforms where Node only began publishing ARM64 binaries with a given major version
+///
+/// Older versions fall back on the x64 binaries, which run under Rosetta 2 on macOS and under
+/// emulation on Windows.
+const NATIVE_ARM64_SINCE: [(&str, u64); 2] = [("darwin", 16), ("win", 20)];
+
+/// A platform to fetch Node for, written as `<os>-<arch>` (e.g. `linux-arm64` or `win-x64`)
+///
+/// This allows pre-fetching Node for a platform other than the one Volta is running on, such as
+/// when building artifacts for several platforms in CI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeTarget {
+    os: String,
+    arch: String,
+}
+
+impl NodeTarget {
+    /// The target for the platform Volta is running on
+    pub fn host() -> Self {
+        NodeTarget {
+            os: NODE_DISTRO_OS.into(),
+            arch: NODE_DISTRO_ARCH.into(),
+        }
+    }
+
+    /// Whether this is the platform Volta is running on
+    pub fn is_host(&self) -> bool {
+        *self == NodeTarget::host()
+    }
+
+    /// The extension of the distribution archives for this target
+    pub fn extension(&self) -> &'static str {
+        if self.os == "win" {
+            "zip"
+        } else {
+            "tar.gz"
+        }
+    }
+
+    /// The name of the distribution archive for this target, without the extension
+    pub fn archive_basename(&self, version: &Version) -> String {
+        format!(
+            "node-v{}-{}-{}",
+            version,
+            self.os,
+            self.archive_arch(version)
+        )
+    }
+
+    /// The file name of the distribution archive for this target
+    pub fn archive_filename(&self, version: &Version) -> String {
+        format!("{}.{}", self.archive_basename(version), self.extension())
+    }
+
+    pub(super) fn os(&self) -> &str {
+        &self.os
+    }
+
+    /// The architecture of the distribution archive for a version of Node on this target
+    ///
+    /// This is the target's architecture, unless the version predates native ARM64 binaries for
+    /// the target's OS, in which case it is `x64`.
+    pub(super) fn archive_arch(&self, version: &Version) -> &str {
+        let predates_arm64 = NATIVE_ARM64_SINCE
+            .iter()
+            .any(|&(os, major)| os == self.os && version.major < major);
+
+        if self.arch == "arm64" && predates_arm64 {
+            "x64"
+        } else {
+            &self.arch
+        }
+    }
+}
+
+impl FromStr for NodeTarget {
+    type Err = VoltaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('-') {
+            Some((os, arch)) if KNOWN_OS.contains(&os) && KNOWN_ARCH.contains(&arch) => {
+                Ok(NodeTarget {
+                    os: os.into(),
+                    arch: arch.into(),
+                })
+            }
+            _ => Err(ErrorKind::InvalidNodeTarget { target: s.into() }.into()),
+        }
+    }
+}
+
+impl Display for NodeTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.os, self.arch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version() -> Version {
+        Version::parse("20.5.1").unwrap()
+    }
+
+    #[test]
+    fn test_archive_filename_for_targets() {
+        let cases = [
+            ("linux-x64", "node-v20.5.1-linux-x64.tar.gz"),
+            ("linux-arm64", "node-v20.5.1-linux-arm64.tar.gz"),
+            ("linux-armv7l", "node-v20.5.1-linux-armv7l.tar.gz"),
+            ("darwin-arm64", "node-v20.5.1-darwin-arm64.tar.gz"),
+            ("darwin-x64", "node-v20.5.1-darwin-x64.tar.gz"),
+            ("win-x64", "node-v20.5.1-win-x64.zip"),
+            ("win-x86", "node-v20.5.1-win-x86.zip"),
+        ];
+
+        for (target, filename) in cases {
+            let target: NodeTarget = target.parse().unwrap();
+            assert_eq!(target.archive_filename(&version()), filename);
+        }
+    }
+
+    #[test]
+    fn test_archive_basename_for_target() {
+        let target: NodeTarget = "win-arm64".parse().unwrap();
+        assert_eq!(
+            target.archive_basename(&version()),
+            "node-v20.5.1-win-arm64"
+        );
+        assert_eq!(target.to_string(), "win-arm64");
+    }
+
+    #[test]
+    fn test_archive_basename_falls_back_to_x64() {
+        let cases = [
+            ("darwin-arm64", "15.14.0", "node-v15.14.0-darwin-x64"),
+            ("darwin-arm64", "16.0.0", "node-v16.0.0-darwin-arm64"),
+            ("darwin-arm64", "14.21.3", "node-v14.21.3-darwin-x64"),
+            ("win-arm64", "19.9.0", "node-v19.9.0-win-x64"),
+            ("win-arm64", "20.0.0", "node-v20.0.0-win-arm64"),
+            ("win-arm64", "18.17.0", "node-v18.17.0-win-x64"),
+            // Only ARM64 on macOS and Windows falls back, other targets are used as-is
+            ("linux-arm64", "14.21.3", "node-v14.21.3-linux-arm64"),
+            ("darwin-x64", "15.14.0", "node-v15.14.0-darwin-x64"),
+            ("win-x86", "19.9.0", "node-v19.9.0-win-x86"),
+        ];
+
+        for (target, version, basename) in cases {
+            let target: NodeTarget = target.parse().unwrap();
+            let version = Version::parse(version).unwrap();
+            assert_eq!(target.archive_basename(&version), basename);
+        }
+    }
+
+    #[test]
+    fn test_host_target() {
+        let host = NodeTarget::host();
+        assert!(host.is_host());
+        assert_eq!(
+            host.to_string(),
+            format!("{}-{}", NODE_DISTRO_OS, NODE_DISTRO_ARCH)
+        );
+        assert_eq!(host.to_string().parse::<NodeTarget>().unwrap(), host);
+    }
+
+    #[test]
+    fn test_invalid_targets() {
+        for target in [
+            "linux",
+            "linux-",
+            "-x64",
+            "macos-arm64",
+            "linux-amd64",
+            "x64-linux",
+        ] {
+            let error = target.parse::<NodeTarget>().unwrap_err();
+            assert_eq!(
+                error.kind(),
+                &ErrorKind::InvalidNodeTarget {
+                    target: target.into()
+                }
+            );
+        }
+    }
+}
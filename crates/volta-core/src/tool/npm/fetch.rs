@@ -3,21 +3,23 @@
 use std::fs::{write, File};
 use std::path::Path;
 
-use super::super::download_tool_error;
+use super::super::integrity::{self, Integrity};
+use super::super::provenance::{record_provenance, Provenance};
 use super::super::registry::public_registry_package;
 use crate::error::{Context, ErrorKind, Fallible};
 use crate::fs::{create_staging_dir, create_staging_file, rename, set_executable};
 use crate::hook::ToolHooks;
 use crate::layout::volta_home;
+use crate::profile::{time, Phase};
 use crate::style::{progress_bar, tool_version};
-use crate::tool::{self, Npm};
+use crate::tool::{self, FetchOutcome, Npm, PlanSource};
 use crate::version::VersionSpec;
 use archive::{Archive, Tarball};
 use fs_utils::ensure_containing_dir_exists;
 use log::{debug, info};
 use node_semver::Version;
 
-pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fallible<()> {
+pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fallible<FetchOutcome> {
     let npm_dir = volta_home()?.npm_inventory_dir();
     let cache_file = npm_dir.join(Npm::archive_filename(&version.to_string()));
 
@@ -33,25 +35,41 @@ pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fallible<()>
         None => {
             let staging = create_staging_file()?;
             let remote_url = determine_remote_url(version, hooks)?;
-            let archive = fetch_remote_distro(version, &remote_url, staging.path())?;
-            (archive, Some(staging))
+            let (archive, checksum) =
+                fetch_remote_distro(version, hooks, &remote_url, staging.path())?;
+            let provenance = Provenance::new(remote_url, checksum);
+            (archive, Some((staging, provenance)))
         }
     };
 
-    unpack_archive(archive, version)?;
+    let bytes = archive.compressed_size();
+    time(Phase::Unpack, || unpack_archive(archive, version))?;
 
-    if let Some(staging_file) = staging {
-        ensure_containing_dir_exists(&cache_file).with_context(|| {
-            ErrorKind::ContainingDirError {
-                path: cache_file.clone(),
-            }
-        })?;
-        staging_file
-            .persist(cache_file)
-            .with_context(|| ErrorKind::PersistInventoryError { tool: "npm".into() })?;
+    let outcome = match staging {
+        Some(_) => FetchOutcome::Network { bytes },
+        None => FetchOutcome::Cache,
+    };
+
+    let image_dir = volta_home()?.npm_image_dir(&version.to_string());
+    if let Some((staging_file, provenance)) = staging {
+        record_provenance(Ok(provenance), &image_dir);
+
+        if tool::cache_archives() {
+            ensure_containing_dir_exists(&cache_file).with_context(|| {
+                ErrorKind::ContainingDirError {
+                    path: cache_file.clone(),
+                }
+            })?;
+            staging_file
+                .persist(cache_file)
+                .with_context(|| ErrorKind::PersistInventoryError { tool: "npm".into() })?;
+        }
+    } else {
+        let source = cache_file.display().to_string();
+        record_provenance(Provenance::of_archive(&cache_file, source), &image_dir);
     }
 
-    Ok(())
+    Ok(outcome)
 }
 
 /// Unpack the npm archive into the image directory so that it is ready for use
@@ -115,6 +133,21 @@ fn load_cached_distro(file: &Path) -> Option<Box<dyn Archive>> {
     }
 }
 
+/// Determine where the archive for an npm version will come from, without fetching it
+pub(super) fn plan_source(
+    version: &Version,
+    hooks: Option<&ToolHooks<Npm>>,
+) -> Fallible<PlanSource> {
+    let cache_file = volta_home()?
+        .npm_inventory_dir()
+        .join(Npm::archive_filename(&version.to_string()));
+    if cache_file.is_file() {
+        Ok(PlanSource::Cache(cache_file))
+    } else {
+        determine_remote_url(version, hooks).map(|url| PlanSource::Download(vec![url]))
+    }
+}
+
 /// Determine the remote URL to download from, using the hooks if avaialble
 fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fallible<String> {
     let version_str = version.to_string();
@@ -131,17 +164,33 @@ fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fa
     }
 }
 
-/// Fetch the distro archive from the internet
+/// Look up the integrity of the archive, if it is downloaded from the public registry
+fn published_integrity(
+    version: &Version,
+    hooks: Option<&ToolHooks<Npm>>,
+) -> Fallible<Option<Integrity>> {
+    match hooks {
+        Some(&ToolHooks {
+            distro: Some(_), ..
+        }) => Ok(None),
+        _ => integrity::published_integrity("npm", version),
+    }
+}
+
+/// Fetch the distro archive from the internet, verifying it before anything is unpacked
 fn fetch_remote_distro(
     version: &Version,
+    hooks: Option<&ToolHooks<Npm>>,
     url: &str,
     staging_path: &Path,
-) -> Fallible<Box<dyn Archive>> {
+) -> Fallible<(Box<dyn Archive>, String)> {
     info!("Downloading {} from {}", tool_version("npm", version), url);
-    Tarball::fetch(url, staging_path).with_context(download_tool_error(
+    integrity::download_verified(
         tool::Spec::Npm(VersionSpec::Exact(version.clone())),
         url,
-    ))
+        staging_path,
+        || published_integrity(version, hooks),
+    )
 }
 
 /// Overwrite the launcher script
@@ -1,12 +1,16 @@
 use std::fmt::{self, Display};
+use std::time::Instant;
 
 use super::node::load_default_npm_version;
 use super::{
     check_fetched, check_shim_reachable, debug_already_fetched, info_fetched, info_installed,
-    info_pinned, info_project_version, FetchStatus, Tool,
+    info_pinned, not_activated, plan::planned_changes, warn_project_version, FetchOutcome,
+    FetchStatus, FetchSummary, PlanAction, PlanSource, Tool, ToolKindName, ToolPlan,
 };
 use crate::error::{Context, ErrorKind, Fallible};
 use crate::inventory::npm_available;
+use crate::layout::volta_home;
+use crate::profile::{time, Phase};
 use crate::session::Session;
 use crate::style::{success_prefix, tool_version};
 use crate::sync::VoltaLock;
@@ -16,7 +20,7 @@ use node_semver::Version;
 mod fetch;
 mod resolve;
 
-pub use resolve::resolve;
+pub use resolve::{available, resolve, BUNDLED_TAG};
 
 /// The Tool implementation for fetching and installing npm
 pub struct Npm {
@@ -36,40 +40,50 @@ impl Npm {
         format!("{}.tgz", Npm::archive_basename(version))
     }
 
-    pub(crate) fn ensure_fetched(&self, session: &mut Session) -> Fallible<()> {
+    pub(crate) fn ensure_fetched(&self, session: &mut Session) -> Fallible<FetchOutcome> {
         match check_fetched(|| npm_available(&self.version))? {
             FetchStatus::AlreadyFetched => {
                 debug_already_fetched(self);
-                Ok(())
+                Ok(FetchOutcome::AlreadyInstalled)
+            }
+            FetchStatus::FetchNeeded(_lock) => {
+                let hooks = session.hooks()?.npm();
+                time(Phase::Fetch, || fetch::fetch(&self.version, hooks))
             }
-            FetchStatus::FetchNeeded(_lock) => fetch::fetch(&self.version, session.hooks()?.npm()),
         }
     }
 }
 
 impl Tool for Npm {
-    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<()> {
-        self.ensure_fetched(session)?;
+    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<FetchOutcome> {
+        let outcome = self.ensure_fetched(session)?;
 
         info_fetched(self);
-        Ok(())
+        Ok(outcome)
     }
     fn install(self: Box<Self>, session: &mut Session) -> Fallible<()> {
         // Acquire a lock on the Volta directory, if possible, to prevent concurrent changes
         let _lock = VoltaLock::acquire();
-        self.ensure_fetched(session)?;
+        let started = Instant::now();
+        let outcome = self.ensure_fetched(session)?;
+        let fetched = FetchSummary::since(outcome, started);
 
         session
             .toolchain_mut()?
-            .set_active_npm(Some(self.version.clone()))?;
+            .set_active_npm(Some(self.version.clone()))
+            .map_err(not_activated(tool_version("npm", &self.version)))?;
 
-        info_installed(&self);
+        info_installed(&self, fetched);
         check_shim_reachable("npm");
 
-        if let Ok(Some(project)) = session.project_platform() {
-            if let Some(npm) = &project.npm {
-                info_project_version(tool_version("npm", npm), &self);
-            }
+        let project_version = session
+            .project_platform()
+            .ok()
+            .flatten()
+            .and_then(|project| project.npm.as_ref())
+            .map(|npm| tool_version("npm", npm));
+        if let Some(project_version) = project_version {
+            warn_project_version(session, project_version, &self);
         }
         Ok(())
     }
@@ -87,6 +101,23 @@ impl Tool for Npm {
             Err(ErrorKind::NotInPackage.into())
         }
     }
+
+    fn kind(&self) -> ToolKindName {
+        ToolKindName::Npm
+    }
+    fn describe_plan(&self, action: PlanAction, session: &Session) -> Fallible<ToolPlan> {
+        let mut plan = ToolPlan::new(self, action);
+        plan.source = Some(if npm_available(&self.version)? {
+            PlanSource::AlreadyFetched
+        } else {
+            fetch::plan_source(&self.version, session.hooks()?.npm())?
+        });
+        plan.image_dir = Some(volta_home()?.npm_image_dir(&self.version.to_string()));
+        plan.changes = planned_changes("npm", &self.version, action, session, |platform| {
+            platform.npm.as_ref()
+        })?;
+        Ok(plan)
+    }
 }
 
 impl Display for Npm {
@@ -99,9 +130,9 @@ impl Display for Npm {
 pub struct BundledNpm;
 
 impl Tool for BundledNpm {
-    fn fetch(self: Box<Self>, _session: &mut Session) -> Fallible<()> {
+    fn fetch(self: Box<Self>, _session: &mut Session) -> Fallible<FetchOutcome> {
         info!("Bundled npm is included with Node, use `volta fetch node` to fetch Node");
-        Ok(())
+        Ok(FetchOutcome::AlreadyInstalled)
     }
 
     fn install(self: Box<Self>, session: &mut Session) -> Fallible<()> {
@@ -109,22 +140,10 @@ impl Tool for BundledNpm {
 
         toolchain.set_active_npm(None)?;
 
-        let bundled_version = match toolchain.platform() {
-            Some(platform) => {
-                let version = load_default_npm_version(&platform.node).with_context(|| {
-                    ErrorKind::NoBundledNpm {
-                        command: "install".into(),
-                    }
-                })?;
-                version.to_string()
-            }
-            None => {
-                return Err(ErrorKind::NoBundledNpm {
-                    command: "install".into(),
-                }
-                .into());
-            }
-        };
+        let bundled_version = bundled_version(
+            toolchain.platform().and_then(|p| p.node.version()),
+            "install",
+        )?;
 
         info!(
             "{} set bundled npm (currently {}) as default",
@@ -140,23 +159,8 @@ impl Tool for BundledNpm {
             Some(project) => {
                 project.pin_npm(None)?;
 
-                let bundled_version = match project.platform() {
-                    Some(platform) => {
-                        let version =
-                            load_default_npm_version(&platform.node).with_context(|| {
-                                ErrorKind::NoBundledNpm {
-                                    command: "pin".into(),
-                                }
-                            })?;
-                        version.to_string()
-                    }
-                    None => {
-                        return Err(ErrorKind::NoBundledNpm {
-                            command: "pin".into(),
-                        }
-                        .into());
-                    }
-                };
+                let bundled_version =
+                    bundled_version(project.platform().and_then(|p| p.node.version()), "pin")?;
 
                 info!(
                     "{} set package.json to use bundled npm (currently {})",
@@ -169,11 +173,27 @@ impl Tool for BundledNpm {
             None => Err(ErrorKind::NotInPackage.into()),
         }
     }
+
+    fn kind(&self) -> ToolKindName {
+        ToolKindName::Npm
+    }
+}
+
+/// Determine the version of npm bundled with the given Node version, for use in messages
+fn bundled_version(node: Option<&Version>, command: &str) -> Fallible<Version> {
+    let no_bundled_npm = || ErrorKind::NoBundledNpm {
+        command: command.into(),
+    };
+
+    match node {
+        Some(node) => load_default_npm_version(node).with_context(no_bundled_npm),
+        None => Err(no_bundled_npm().into()),
+    }
 }
 
 impl Display for BundledNpm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(&tool_version("npm", "bundled"))
+        f.write_str(&tool_version("npm", BUNDLED_TAG))
     }
 }
 
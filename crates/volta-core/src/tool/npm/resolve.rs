@@ -5,25 +5,59 @@ use super::super::registry::{
 };
 use crate::error::{ErrorKind, Fallible};
 use crate::hook::ToolHooks;
+use crate::inventory::npm_available;
 use crate::session::Session;
-use crate::tool::Npm;
+use crate::tool::{fetched_exact_version, Npm, ToolKindName};
 use crate::version::{VersionSpec, VersionTag};
 use log::debug;
 use node_semver::{Range, Version};
 
+/// The tag that selects the version of npm bundled with Node, rather than a separate npm
+pub const BUNDLED_TAG: &str = "bundled";
+
+/// Resolve an npm version spec, returning `None` if the bundled version of npm is requested
 pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<Option<Version>> {
+    match matching {
+        VersionSpec::Tag(VersionTag::Custom(tag)) if tag == BUNDLED_TAG => {
+            debug!("Using the version of npm bundled with Node");
+            Ok(None)
+        }
+        matching => session
+            .resolve_cached(ToolKindName::Npm, matching, resolve_release)
+            .map(Some),
+    }
+}
+
+/// Resolve an npm version spec to a specific release from the registry
+fn resolve_release(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    if let Some(version) = fetched_exact_version("npm", &matching, npm_available)? {
+        return Ok(version);
+    }
+
     let hooks = session.hooks()?.npm();
     match matching {
-        VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks).map(Some),
-        VersionSpec::Exact(version) => Ok(Some(version)),
-        VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => {
-            resolve_tag("latest", hooks).map(Some)
+        VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks),
+        VersionSpec::Exact(version) => Ok(version),
+        VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => resolve_tag("latest", hooks),
+        VersionSpec::Tag(tag) => resolve_tag(&tag.to_string(), hooks),
+        VersionSpec::Local(_) => Err(ErrorKind::Unimplemented {
+            feature: "Installing npm from a local archive".into(),
         }
-        VersionSpec::Tag(VersionTag::Custom(tag)) if tag == "bundled" => Ok(None),
-        VersionSpec::Tag(tag) => resolve_tag(&tag.to_string(), hooks).map(Some),
+        .into()),
+        VersionSpec::Git(_) => Err(ErrorKind::GitSourceNotSupported { tool: "npm".into() }.into()),
     }
 }
 
+/// Lists the npm versions available from the registry
+pub fn available(session: &mut Session) -> Fallible<Vec<Version>> {
+    let (_, index) = fetch_npm_index(session.hooks()?.npm())?;
+    Ok(index
+        .entries
+        .into_iter()
+        .map(|details| details.version)
+        .collect())
+}
+
 fn fetch_npm_index(hooks: Option<&ToolHooks<Npm>>) -> Fallible<(String, PackageIndex)> {
     let url = match hooks {
         Some(&ToolHooks {
@@ -49,6 +83,7 @@ fn resolve_tag(tag: &str, hooks: Option<&ToolHooks<Npm>>) -> Fallible<Version> {
         }
         None => Err(ErrorKind::NpmVersionNotFound {
             matching: tag.into(),
+            registry_url: Some(url),
         }
         .into()),
     }
@@ -72,7 +107,53 @@ fn resolve_semver(matching: Range, hooks: Option<&ToolHooks<Npm>>) -> Fallible<V
         }
         None => Err(ErrorKind::NpmVersionNotFound {
             matching: matching.to_string(),
+            registry_url: Some(url),
         }
         .into()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{override_volta_home, volta_home};
+
+    #[test]
+    fn test_resolve_bundled() {
+        let mut session = Session::init_unchecked();
+        let bundled = VersionSpec::Tag(VersionTag::Custom(BUNDLED_TAG.into()));
+
+        // Resolving the bundled tag doesn't need the registry at all
+        assert_eq!(resolve(bundled, &mut session).unwrap(), None);
+    }
+
+    #[test]
+    fn test_resolve_exact() {
+        let mut session = Session::init_unchecked();
+        let version = Version::from((9, 8, 1));
+
+        assert_eq!(
+            resolve(VersionSpec::Exact(version.clone()), &mut session).unwrap(),
+            Some(version)
+        );
+    }
+
+    #[test]
+    fn test_exact_version_in_inventory() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let home = volta_home().unwrap();
+        let cached = Version::from((9, 8, 1));
+        std::fs::create_dir_all(home.npm_image_dir(&cached.to_string())).unwrap();
+        // Unreadable hooks make any lookup in the registry fail
+        std::fs::write(home.default_hooks_file(), "not hooks").unwrap();
+        let mut session = Session::init_unchecked();
+
+        assert_eq!(
+            resolve(VersionSpec::Exact(cached.clone()), &mut session).unwrap(),
+            Some(cached)
+        );
+        let missing = Version::from((9, 8, 2));
+        assert!(resolve(VersionSpec::Exact(missing), &mut session).is_err());
+    }
+}
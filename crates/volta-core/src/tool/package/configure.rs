@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
 use super::manager::PackageManager;
-use super::metadata::{BinConfig, PackageConfig, PackageManifest};
+use super::metadata::{BinConfig, PackageConfig, PackageManifest, CONFIG_SCHEMA_VERSION};
+use super::uninstall::remove_config_and_shim;
 use crate::error::{ErrorKind, Fallible};
 use crate::layout::volta_home;
 use crate::platform::{Image, PlatformSpec};
@@ -21,13 +22,20 @@ pub(super) fn parse_manifest(
 }
 
 /// Generate configuration files and shims for the package and each of its bins
+///
+/// If `selected` is set, only those bins are configured, and any bins configured by a previous
+/// install that are no longer selected are removed. Returns the bins that were configured.
 pub(super) fn write_config_and_shims(
     name: &str,
     manifest: &PackageManifest,
     image: &Image,
     manager: PackageManager,
-) -> Fallible<()> {
-    validate_bins(name, manifest)?;
+    requested: Option<String>,
+    selected: Option<Vec<String>>,
+) -> Fallible<Vec<String>> {
+    let bins = select_bins(name, &manifest.bin, selected.as_deref())?;
+    validate_bins(name, &bins)?;
+    remove_unselected_bins(name, &bins)?;
 
     let platform = PlatformSpec {
         node: image.node.value.clone(),
@@ -37,7 +45,7 @@ pub(super) fn write_config_and_shims(
     };
 
     // Generate the shims and bin configs for each bin provided by the package
-    for bin_name in &manifest.bin {
+    for bin_name in &bins {
         shim::create(bin_name)?;
         check_shim_reachable(bin_name);
 
@@ -47,6 +55,7 @@ pub(super) fn write_config_and_shims(
             version: manifest.version.clone(),
             platform: platform.clone(),
             manager,
+            schema_version: CONFIG_SCHEMA_VERSION,
         }
         .write()?;
     }
@@ -56,19 +65,49 @@ pub(super) fn write_config_and_shims(
         name: name.into(),
         version: manifest.version.clone(),
         platform,
-        bins: manifest.bin.clone(),
+        bins: bins.clone(),
         manager,
+        requested,
+        selected_bins: selected,
+        schema_version: CONFIG_SCHEMA_VERSION,
     }
     .write()?;
 
-    Ok(())
+    Ok(bins)
+}
+
+/// Determine which of the package's bins to configure, given the bins selected by the user
+fn select_bins(
+    package_name: &str,
+    bins: &[String],
+    selected: Option<&[String]>,
+) -> Fallible<Vec<String>> {
+    let selected = match selected {
+        Some(selected) => selected,
+        None => return Ok(bins.to_vec()),
+    };
+
+    if let Some(missing) = selected.iter().find(|bin| !bins.contains(bin)) {
+        return Err(ErrorKind::PackageBinaryNotFound {
+            package: package_name.into(),
+            bin: missing.clone(),
+        }
+        .into());
+    }
+
+    // Keep the package's own ordering, so the config doesn't depend on the order of `--bin`
+    Ok(bins
+        .iter()
+        .filter(|bin| selected.contains(bin))
+        .cloned()
+        .collect())
 }
 
 /// Validate that we aren't attempting to install a bin that is already installed by
 /// another package.
-fn validate_bins(package_name: &str, manifest: &PackageManifest) -> Fallible<()> {
+fn validate_bins(package_name: &str, bins: &[String]) -> Fallible<()> {
     let home = volta_home()?;
-    for bin_name in &manifest.bin {
+    for bin_name in bins {
         // Check for name conflicts with already-installed bins
         // Some packages may install bins with the same name
         if let Ok(config) = BinConfig::from_file(home.default_tool_bin_config(bin_name)) {
@@ -87,3 +126,109 @@ fn validate_bins(package_name: &str, manifest: &PackageManifest) -> Fallible<()>
 
     Ok(())
 }
+
+/// Remove the shims and bin configs from a previous install of the package for any bins that
+/// aren't being configured now, e.g. when reinstalling with `--bin`
+fn remove_unselected_bins(package_name: &str, bins: &[String]) -> Fallible<()> {
+    let home = volta_home()?;
+    let previous =
+        match PackageConfig::from_file_if_exists(home.default_package_config_file(package_name))? {
+            Some(config) => config.bins,
+            None => return Ok(()),
+        };
+
+    for bin_name in previous.iter().filter(|bin| !bins.contains(bin)) {
+        // Leave the bin alone if another package has installed one with the same name since
+        let owned = BinConfig::from_file(home.default_tool_bin_config(bin_name))
+            .map_or(false, |config| config.package == package_name);
+        if owned {
+            remove_config_and_shim(bin_name, package_name)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::override_volta_home;
+    use crate::platform::Sourced;
+    use node_semver::Version;
+
+    fn bins() -> Vec<String> {
+        vec!["tsc".into(), "tsserver".into()]
+    }
+
+    #[test]
+    fn selects_all_bins_by_default() {
+        assert_eq!(select_bins("typescript", &bins(), None).unwrap(), bins());
+    }
+
+    #[test]
+    fn selects_only_requested_bins() {
+        let selected = ["tsserver".to_owned()];
+
+        assert_eq!(
+            select_bins("typescript", &bins(), Some(&selected)).unwrap(),
+            ["tsserver"]
+        );
+    }
+
+    #[test]
+    fn rejects_bins_not_in_package() {
+        let selected = ["tsc".to_owned(), "tslint".to_owned()];
+        let error = select_bins("typescript", &bins(), Some(&selected)).unwrap_err();
+
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::PackageBinaryNotFound {
+                package: "typescript".into(),
+                bin: "tslint".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn reinstall_with_selected_bins_removes_other_shims() {
+        let dir = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(dir.path().to_owned());
+        let home = volta_home().unwrap();
+        std::fs::create_dir_all(home.shim_dir()).unwrap();
+
+        let manifest = PackageManifest {
+            name: "typescript".into(),
+            version: Version::parse("5.0.0").unwrap(),
+            bin: bins(),
+        };
+        let image = Image {
+            node: Sourced::with_default(Version::parse("20.11.1").unwrap().into()),
+            npm: None,
+            pnpm: None,
+            yarn: None,
+        };
+        let install = |selected| {
+            write_config_and_shims(
+                "typescript",
+                &manifest,
+                &image,
+                PackageManager::Npm,
+                None,
+                selected,
+            )
+        };
+
+        install(None).unwrap();
+        assert!(home.default_tool_bin_config("tsserver").exists());
+
+        install(Some(vec!["tsc".into()])).unwrap();
+        assert!(home.default_tool_bin_config("tsc").exists());
+        assert!(home.shim_file("tsc").symlink_metadata().is_ok());
+        assert!(!home.default_tool_bin_config("tsserver").exists());
+        assert!(home.shim_file("tsserver").symlink_metadata().is_err());
+
+        let config =
+            PackageConfig::from_file(home.default_package_config_file("typescript")).unwrap();
+        assert_eq!(config.bins, ["tsc"]);
+    }
+}
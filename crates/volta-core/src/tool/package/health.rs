@@ -0,0 +1,136 @@
+//! Provides consistency checks for installed global packages, to catch packages whose
+//! binaries can no longer be run (e.g. after the Node version they were installed with has been
+//! removed).
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::PackageConfig;
+use crate::error::Fallible;
+use crate::inventory::{node_available, package_configs};
+use crate::layout::volta_home;
+use crate::platform::PlatformNode;
+use node_semver::Version;
+
+/// The result of verifying a single installed package
+pub struct PackageHealth {
+    /// The package name
+    pub name: String,
+    /// The installed package version
+    pub version: Version,
+    /// The problems that were found with the package, if any
+    pub issues: Vec<PackageIssue>,
+}
+
+impl PackageHealth {
+    /// Whether all of the package's binaries are expected to run
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A problem that prevents a package binary from running
+#[derive(Debug, PartialEq, Eq)]
+pub enum PackageIssue {
+    /// The executable for a binary is missing from the package image
+    MissingBinary(String),
+    /// The Node version that the package was installed with is no longer fetched
+    MissingNodeImage(Version),
+    /// The shim for a binary is missing, so it isn't available on the PATH
+    MissingShim(String),
+}
+
+impl fmt::Display for PackageIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageIssue::MissingBinary(bin) => write!(f, "executable '{}' is missing", bin),
+            PackageIssue::MissingNodeImage(version) => {
+                write!(f, "Node {} is not installed", version)
+            }
+            PackageIssue::MissingShim(bin) => write!(f, "shim for '{}' is missing", bin),
+        }
+    }
+}
+
+/// Verifies that the binaries of every installed package are reachable
+///
+/// For each package, this checks that the Node version it was installed with is still available,
+/// and that each of its binaries has both an executable in the package image and a shim.
+pub fn verify_packages() -> Fallible<Vec<PackageHealth>> {
+    package_configs()?.into_iter().map(verify_package).collect()
+}
+
+fn verify_package(config: PackageConfig) -> Fallible<PackageHealth> {
+    let home = volta_home()?;
+    let mut issues = Vec::new();
+
+    // The system Node isn't an image, so there is nothing to check for it
+    if let PlatformNode::Version(node) = &config.platform.node {
+        if !node_available(node)? {
+            issues.push(PackageIssue::MissingNodeImage(node.clone()));
+        }
+    }
+
+    let binary_dir = config
+        .manager
+        .binary_dir(home.package_image_dir(&config.name));
+    issues.extend(check_bins(&config.bins, &binary_dir, |bin| {
+        home.shim_file(bin)
+    }));
+
+    Ok(PackageHealth {
+        name: config.name,
+        version: config.version,
+        issues,
+    })
+}
+
+/// Checks that each binary has an executable in `binary_dir` and a shim at `shim_file(bin)`
+fn check_bins<F>(bins: &[String], binary_dir: &Path, shim_file: F) -> Vec<PackageIssue>
+where
+    F: Fn(&str) -> PathBuf,
+{
+    let mut issues = Vec::new();
+
+    for bin in bins {
+        if !binary_dir.join(bin).exists() {
+            issues.push(PackageIssue::MissingBinary(bin.clone()));
+        }
+
+        if !shim_file(bin).exists() {
+            issues.push(PackageIssue::MissingShim(bin.clone()));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_check_bins() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary_dir = dir.path().join("bin");
+        let shim_dir = dir.path().join("shims");
+        fs::create_dir_all(&binary_dir).unwrap();
+        fs::create_dir_all(&shim_dir).unwrap();
+
+        fs::write(binary_dir.join("healthy"), "").unwrap();
+        fs::write(shim_dir.join("healthy"), "").unwrap();
+        fs::write(shim_dir.join("unlinked"), "").unwrap();
+        fs::write(binary_dir.join("unshimmed"), "").unwrap();
+
+        let bins = ["healthy", "unlinked", "unshimmed"].map(String::from);
+
+        assert_eq!(
+            check_bins(&bins, &binary_dir, |bin| shim_dir.join(bin)),
+            vec![
+                PackageIssue::MissingBinary("unlinked".into()),
+                PackageIssue::MissingShim("unshimmed".into()),
+            ]
+        );
+    }
+}
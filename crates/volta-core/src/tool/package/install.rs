@@ -7,27 +7,22 @@ use crate::platform::Image;
 use crate::style::progress_spinner;
 use log::debug;
 
-/// Use `npm install --global` to install the package
+/// Use the package manager's global install (e.g. `npm install --global`) to install the package
 ///
-/// Sets the environment variable `npm_config_prefix` to redirect the install to the Volta
+/// Sets up the environment (e.g. `npm_config_prefix` for npm) to redirect the install to the Volta
 /// data directory, taking advantage of the standard global install behavior with a custom
 /// location
 pub(super) fn run_global_install(
     package: String,
+    manager: PackageManager,
     staging_dir: PathBuf,
     platform_image: &Image,
 ) -> Fallible<()> {
-    let mut command = create_command("npm");
-    command.args([
-        "install",
-        "--global",
-        "--loglevel=warn",
-        "--no-update-notifier",
-        "--no-audit",
-    ]);
+    let mut command = create_command(manager_executable(manager));
+    command.args(install_args(manager));
     command.arg(&package);
     command.env("PATH", platform_image.path()?);
-    PackageManager::Npm.setup_global_command(&mut command, staging_dir);
+    manager.setup_global_command(&mut command, staging_dir);
 
     debug!("Installing {} with command: {:?}", package, command);
     let spinner = progress_spinner(format!("Installing {}", package));
@@ -56,3 +51,41 @@ pub(super) fn run_global_install(
         Err(ErrorKind::PackageInstallFailed { package }.into())
     }
 }
+
+pub(super) fn manager_executable(manager: PackageManager) -> &'static str {
+    match manager {
+        PackageManager::Npm => "npm",
+        PackageManager::Pnpm => "pnpm",
+        PackageManager::Yarn => "yarn",
+    }
+}
+
+/// The arguments for a global install with the given package manager, before the package name
+pub(super) fn install_args(manager: PackageManager) -> &'static [&'static str] {
+    match manager {
+        PackageManager::Npm => &[
+            "install",
+            "--global",
+            "--loglevel=warn",
+            "--no-update-notifier",
+            "--no-audit",
+        ],
+        PackageManager::Pnpm => &["add", "--global"],
+        PackageManager::Yarn => &["global", "add"],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_args() {
+        assert_eq!(
+            install_args(PackageManager::Npm)[..2],
+            ["install", "--global"]
+        );
+        assert_eq!(install_args(PackageManager::Pnpm), ["add", "--global"]);
+        assert_eq!(install_args(PackageManager::Yarn), ["global", "add"]);
+    }
+}
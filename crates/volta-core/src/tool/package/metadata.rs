@@ -6,11 +6,19 @@ use std::path::Path;
 use super::manager::PackageManager;
 use crate::error::{Context, ErrorKind, Fallible, VoltaError};
 use crate::layout::volta_home;
-use crate::platform::PlatformSpec;
-use crate::version::{option_version_serde, version_serde};
+use crate::platform::{PlatformNode, PlatformSpec};
+use crate::version::{option_version_serde, version_serde, VersionSpec};
 use fs_utils::ensure_containing_dir_exists;
+use log::warn;
 use node_semver::Version;
 
+/// The version of the package and bin config format written by this version of Volta
+///
+/// Configs written before the format was versioned have no `schema_version` and are read as
+/// version 0. A config with a higher version than this was written by a newer Volta, so it is
+/// still read, ignoring any fields that aren't known here, but with a warning.
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
 /// Configuration information about an installed package
 ///
 /// Will be stored in `<VOLTA_HOME>/tools/user/packages/<package>.json`
@@ -28,6 +36,20 @@ pub struct PackageConfig {
     pub bins: Vec<String>,
     /// The package manager that was used to install this package
     pub manager: PackageManager,
+    /// The version originally requested by the user (e.g. `latest` or `^3`), if any
+    ///
+    /// Recorded so that a reinstall can re-resolve against the user's original intent, rather
+    /// than pinning the package to the version that happened to be resolved at install time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested: Option<String>,
+    /// The binaries the user selected with `--bin`, if only some of them were installed
+    ///
+    /// Recorded so that reinstalls and upgrades create shims for the same binaries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_bins: Option<Vec<String>>,
+    /// The version of the config format, see `CONFIG_SCHEMA_VERSION`
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
 impl PackageConfig {
@@ -39,7 +61,10 @@ impl PackageConfig {
         let config = File::open(&file).with_context(|| ErrorKind::ReadPackageConfigError {
             file: file.as_ref().to_owned(),
         })?;
-        serde_json::from_reader(config).with_context(|| ErrorKind::ParsePackageConfigError)
+        let config: Self =
+            serde_json::from_reader(config).with_context(|| ErrorKind::ParsePackageConfigError)?;
+        check_schema_version(config.schema_version, file.as_ref());
+        Ok(config)
     }
 
     pub fn from_file_if_exists<P>(file: P) -> Fallible<Option<Self>>
@@ -59,12 +84,26 @@ impl PackageConfig {
                     ))
                 }
             }
-            Ok(config) => serde_json::from_reader(config)
-                .with_context(|| ErrorKind::ParsePackageConfigError)
-                .map(Some),
+            Ok(config) => {
+                let config: Self = serde_json::from_reader(config)
+                    .with_context(|| ErrorKind::ParsePackageConfigError)?;
+                check_schema_version(config.schema_version, file.as_ref());
+                Ok(Some(config))
+            }
         }
     }
 
+    /// Whether this config was written by a newer version of Volta, which may have recorded
+    /// settings that would be lost if it were written again
+    pub fn is_from_newer_volta(&self) -> bool {
+        is_newer_schema(self.schema_version)
+    }
+
+    /// The version spec originally requested by the user, if one was recorded
+    pub fn requested_spec(&self) -> Option<VersionSpec> {
+        self.requested.as_deref().and_then(|spec| spec.parse().ok())
+    }
+
     /// Write this `PackageConfig` into the appropriate config file
     pub fn write(self) -> Fallible<()> {
         let config_file_path = volta_home()?.default_package_config_file(&self.name);
@@ -102,6 +141,9 @@ pub struct BinConfig {
     pub platform: PlatformSpec,
     /// The package manager used to install this binary
     pub manager: PackageManager,
+    /// The version of the config format, see `CONFIG_SCHEMA_VERSION`
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
 impl BinConfig {
@@ -113,7 +155,10 @@ impl BinConfig {
         let config = File::open(&file).with_context(|| ErrorKind::ReadBinConfigError {
             file: file.as_ref().to_owned(),
         })?;
-        serde_json::from_reader(config).with_context(|| ErrorKind::ParseBinConfigError)
+        let config: Self =
+            serde_json::from_reader(config).with_context(|| ErrorKind::ParseBinConfigError)?;
+        check_schema_version(config.schema_version, file.as_ref());
+        Ok(config)
     }
 
     pub fn from_file_if_exists<P>(file: P) -> Fallible<Option<Self>>
@@ -133,9 +178,12 @@ impl BinConfig {
                     ))
                 }
             }
-            Ok(config) => serde_json::from_reader(config)
-                .with_context(|| ErrorKind::ParseBinConfigError)
-                .map(Some),
+            Ok(config) => {
+                let config: Self = serde_json::from_reader(config)
+                    .with_context(|| ErrorKind::ParseBinConfigError)?;
+                check_schema_version(config.schema_version, file.as_ref());
+                Ok(Some(config))
+            }
         }
     }
 
@@ -158,11 +206,25 @@ impl BinConfig {
     }
 }
 
+fn is_newer_schema(schema_version: u32) -> bool {
+    schema_version > CONFIG_SCHEMA_VERSION
+}
+
+/// Warns if a config was written by a newer version of Volta, as some of its fields may be ignored
+fn check_schema_version(schema_version: u32, file: &Path) {
+    if is_newer_schema(schema_version) {
+        warn!(
+            "{} was written by a newer version of Volta (config version {}), some of its settings may be ignored",
+            file.display(),
+            schema_version
+        );
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(remote = "PlatformSpec")]
 struct RawPlatformSpec {
-    #[serde(with = "version_serde")]
-    node: Version,
+    node: PlatformNode,
     #[serde(with = "option_version_serde")]
     npm: Option<Version>,
     // The magic:
@@ -207,19 +269,26 @@ impl PackageManifest {
             }
         })?;
 
-        // If the bin list contains only an empty string, that means `bin` was a string value,
-        // rather than a map. In that case, to match `npm`s behavior, we use the name of the package
-        // as the bin name.
-        // Note: For a scoped package, we should remove the scope and only use the package name
-        if manifest.bin == [""] {
-            manifest.bin.pop();
-            manifest.bin.push(default_binary_name(&manifest.name));
-        }
+        manifest.bin = bin_names(&manifest.name, manifest.bin);
 
         Ok(manifest)
     }
 }
 
+/// Determine the binary names from a `bin` section read with `serde_bins`
+pub(crate) fn bin_names(package_name: &str, mut bins: Vec<String>) -> Vec<String> {
+    // If the bin list contains only an empty string, that means `bin` was a string value,
+    // rather than a map. In that case, to match `npm`s behavior, we use the name of the package
+    // as the bin name.
+    // Note: For a scoped package, we should remove the scope and only use the package name
+    if bins == [""] {
+        bins.pop();
+        bins.push(default_binary_name(package_name));
+    }
+
+    bins
+}
+
 #[derive(serde::Deserialize)]
 /// Struct to read the `dependencies` out of Yarn's global manifest.
 ///
@@ -230,10 +299,12 @@ pub(super) struct GlobalYarnManifest {
     pub dependencies: HashMap<String, String>,
 }
 
-mod serde_bins {
+pub(crate) mod serde_bins {
     use std::fmt;
 
+    use log::debug;
     use serde::de::{Deserializer, Error, MapAccess, Visitor};
+    use serde_json::Value;
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
     where
@@ -267,7 +338,14 @@ mod serde_bins {
             M: MapAccess<'de>,
         {
             let mut bins = Vec::new();
-            while let Some((name, _)) = access.next_entry::<String, String>()? {
+            while let Some((name, path)) = access.next_entry::<String, Value>()? {
+                // A malformed entry only affects that binary, so it is skipped rather than
+                // rejecting the whole document (which may describe many other versions)
+                if !path.is_string() {
+                    debug!("Skipping binary '{}' with an invalid path: {}", name, path);
+                    continue;
+                }
+
                 // Bin names that include path separators are invalid, as they would then point to
                 // other locations on the filesystem. To match the behavior of npm & Yarn, we
                 // filter those values out of the list of bins.
@@ -309,7 +387,8 @@ fn default_binary_name(package_name: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::default_binary_name;
+    use super::{default_binary_name, BinConfig, PackageConfig, CONFIG_SCHEMA_VERSION};
+    use crate::version::{VersionSpec, VersionTag};
 
     #[test]
     fn default_binary_uses_full_name_if_unscoped() {
@@ -320,4 +399,181 @@ mod tests {
     fn default_binary_removes_scope() {
         assert_eq!(default_binary_name("@scope/my-package"), "my-package");
     }
+
+    #[test]
+    fn malformed_bin_entry_is_skipped() {
+        #[derive(serde::Deserialize)]
+        struct Manifest {
+            #[serde(deserialize_with = "super::serde_bins::deserialize")]
+            bin: Vec<String>,
+        }
+
+        let manifest: Manifest = serde_json::from_str(
+            r#"{ "bin": { "cli": "bin/cli.js", "broken": null, "other": 1 } }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.bin, ["cli"]);
+    }
+
+    #[test]
+    fn package_config_without_requested_spec() {
+        let config: PackageConfig = serde_json::from_str(
+            r#"{
+                "name": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "bins": ["cowsay"],
+                "manager": "Npm"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.requested, None);
+        assert_eq!(config.requested_spec(), None);
+    }
+
+    #[test]
+    fn package_config_with_requested_spec() {
+        let config: PackageConfig = serde_json::from_str(
+            r#"{
+                "name": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "bins": ["cowsay"],
+                "manager": "Npm",
+                "requested": "latest"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.requested_spec(),
+            Some(VersionSpec::Tag(VersionTag::Latest))
+        );
+    }
+
+    #[test]
+    fn config_without_schema_version() {
+        let package: PackageConfig = serde_json::from_str(
+            r#"{
+                "name": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "bins": ["cowsay"],
+                "manager": "Npm"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(package.schema_version, 0);
+
+        let bin: BinConfig = serde_json::from_str(
+            r#"{
+                "name": "cowsay",
+                "package": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "manager": "Npm"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(bin.schema_version, 0);
+    }
+
+    #[test]
+    fn config_with_newer_schema_version() {
+        // Fields added by a newer version of Volta are ignored
+        let package: PackageConfig = serde_json::from_str(
+            r#"{
+                "name": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "bins": ["cowsay"],
+                "manager": "Npm",
+                "integrity": "sha512-abc",
+                "schema_version": 7
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(package.name, "cowsay");
+        assert_eq!(package.schema_version, 7);
+
+        let bin: BinConfig = serde_json::from_str(
+            r#"{
+                "name": "cowsay",
+                "package": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "manager": "Npm",
+                "schema_version": 7
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(bin.package, "cowsay");
+        assert_eq!(bin.schema_version, 7);
+    }
+
+    #[test]
+    fn newer_config_files_are_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_file = dir.path().join("cowsay.json");
+        std::fs::write(
+            &package_file,
+            r#"{
+                "name": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "bins": ["cowsay"],
+                "manager": "Npm",
+                "schema_version": 7
+            }"#,
+        )
+        .unwrap();
+        let bin_file = dir.path().join("cowsay-bin.json");
+        std::fs::write(
+            &bin_file,
+            r#"{
+                "name": "cowsay",
+                "package": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "manager": "Npm",
+                "schema_version": 7
+            }"#,
+        )
+        .unwrap();
+
+        // A newer config is only warned about, so it can still be read through either method
+        let package = PackageConfig::from_file(&package_file).unwrap();
+        assert!(package.is_from_newer_volta());
+        let package = PackageConfig::from_file_if_exists(&package_file)
+            .unwrap()
+            .unwrap();
+        assert!(package.is_from_newer_volta());
+
+        assert_eq!(BinConfig::from_file(&bin_file).unwrap().schema_version, 7);
+        assert_eq!(
+            BinConfig::from_file_if_exists(&bin_file)
+                .unwrap()
+                .unwrap()
+                .schema_version,
+            7
+        );
+    }
+
+    #[test]
+    fn current_config_is_not_from_newer_volta() {
+        let config: PackageConfig = serde_json::from_str(&format!(
+            r#"{{
+                "name": "cowsay",
+                "version": "1.5.0",
+                "platform": {{ "node": "18.17.1", "npm": null, "yarn": null }},
+                "bins": ["cowsay"],
+                "manager": "Npm",
+                "schema_version": {}
+            }}"#,
+            CONFIG_SCHEMA_VERSION
+        ))
+        .unwrap();
+        assert!(!config.is_from_newer_volta());
+    }
 }
@@ -3,7 +3,7 @@ use std::fs::create_dir_all;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use super::Tool;
+use super::{FetchOutcome, Tool, ToolKindName};
 use crate::error::{Context, ErrorKind, Fallible};
 use crate::fs::{remove_dir_if_exists, rename, symlink_dir};
 use crate::layout::volta_home;
@@ -14,16 +14,24 @@ use crate::sync::VoltaLock;
 use crate::version::VersionSpec;
 use fs_utils::ensure_containing_dir_exists;
 use log::info;
+use node_semver::Version;
 use tempfile::{tempdir_in, TempDir};
 
 mod configure;
+mod health;
 mod install;
 mod manager;
 mod metadata;
+mod reinstall;
+mod resolve;
 mod uninstall;
 
+pub use health::{verify_packages, PackageHealth, PackageIssue};
 pub use manager::PackageManager;
+pub(crate) use metadata::{bin_names, serde_bins};
 pub use metadata::{BinConfig, PackageConfig, PackageManifest};
+pub use reinstall::{packages_for_other_node_major, reinstall_packages_for_node, ReinstallReport};
+pub use resolve::{preview_package, resolve, PackagePreview, ResolvedPackage};
 pub use uninstall::uninstall;
 
 /// The Tool implementation for installing 3rd-party global packages
@@ -31,6 +39,7 @@ pub struct Package {
     name: String,
     version: VersionSpec,
     staging: TempDir,
+    bins: Option<Vec<String>>,
 }
 
 impl Package {
@@ -41,12 +50,20 @@ impl Package {
             name,
             version,
             staging,
+            bins: None,
         })
     }
 
+    /// Only create shims for the given bins, rather than every bin provided by the package
+    pub fn with_bins(mut self, bins: Vec<String>) -> Self {
+        self.bins = Some(bins);
+        self
+    }
+
     pub fn run_install(&self, platform_image: &Image) -> Fallible<()> {
         install::run_global_install(
             self.to_string(),
+            PackageManager::Npm,
             self.staging.path().to_owned(),
             platform_image,
         )
@@ -54,19 +71,25 @@ impl Package {
 
     pub fn complete_install(self, image: &Image) -> Fallible<PackageManifest> {
         let manager = PackageManager::Npm;
-        let manifest =
+        let mut manifest =
             configure::parse_manifest(&self.name, self.staging.path().to_owned(), manager)?;
 
         persist_install(&self.name, &self.version, self.staging.path())?;
         link_package_to_shared_dir(&self.name, manager)?;
-        configure::write_config_and_shims(&self.name, &manifest, image, manager)?;
+        let requested = match self.version {
+            VersionSpec::None => None,
+            ref version => Some(version.to_string()),
+        };
+        manifest.bin = configure::write_config_and_shims(
+            &self.name, &manifest, image, manager, requested, self.bins,
+        )?;
 
         Ok(manifest)
     }
 }
 
 impl Tool for Package {
-    fn fetch(self: Box<Self>, _session: &mut Session) -> Fallible<()> {
+    fn fetch(self: Box<Self>, _session: &mut Session) -> Fallible<FetchOutcome> {
         Err(ErrorKind::CannotFetchPackage {
             package: self.to_string(),
         }
@@ -108,6 +131,10 @@ impl Tool for Package {
     fn pin(self: Box<Self>, _session: &mut Session) -> Fallible<()> {
         Err(ErrorKind::CannotPinPackage { package: self.name }.into())
     }
+
+    fn kind(&self) -> ToolKindName {
+        ToolKindName::Package
+    }
 }
 
 impl Display for Package {
@@ -159,6 +186,17 @@ impl DirectInstall {
     }
 
     pub fn complete_install(self, image: &Image) -> Fallible<()> {
+        self.complete_install_with_requested(image, None, None)
+    }
+
+    /// Completes the install, recording `requested` as the version spec the user asked for and
+    /// only configuring the `selected` bins, if set
+    fn complete_install_with_requested(
+        self,
+        image: &Image,
+        requested: Option<String>,
+        selected: Option<Vec<String>>,
+    ) -> Fallible<()> {
         let DirectInstall {
             staging,
             name,
@@ -172,10 +210,45 @@ impl DirectInstall {
 
         persist_install(&name, &manifest.version, staging.path())?;
         link_package_to_shared_dir(&name, manager)?;
-        configure::write_config_and_shims(&name, &manifest, image, manager)
+        configure::write_config_and_shims(&name, &manifest, image, manager, requested, selected)?;
+        Ok(())
     }
 }
 
+/// Installs an exact version of a package with a specific package manager, e.g. the one that
+/// originally installed it on another machine
+pub fn install_with_manager(
+    name: &str,
+    version: &Version,
+    manager: PackageManager,
+    session: &mut Session,
+) -> Fallible<()> {
+    let _lock = VoltaLock::acquire();
+
+    let image = session
+        .default_platform()?
+        .map(PlatformSpec::as_default)
+        .ok_or(ErrorKind::NoPlatform)?
+        .checkout(session)?;
+
+    let installer = DirectInstall::with_name(manager, name.into())?;
+    install::run_global_install(
+        tool_version(name, version),
+        manager,
+        installer.staging.path().to_owned(),
+        &image,
+    )?;
+    installer.complete_install_with_requested(&image, Some(version.to_string()), None)?;
+
+    info!(
+        "{} installed {} with {}",
+        success_prefix(),
+        tool_version(name, version),
+        install::manager_executable(manager)
+    );
+    Ok(())
+}
+
 /// Helper struct for direct in-place upgrades using `npm update -g` or `yarn global upgrade`
 ///
 /// Upgrades the requested package directly in the image directory
@@ -225,9 +298,23 @@ impl InPlaceUpgrade {
 
     pub fn complete_upgrade(self, image: &Image) -> Fallible<()> {
         let manifest = configure::parse_manifest(&self.package, self.directory, self.manager)?;
+        // Keep the originally requested spec and selected bins, since an upgrade doesn't change
+        // the user's intent
+        let (requested, selected) =
+            PackageConfig::from_file(volta_home()?.default_package_config_file(&self.package))
+                .map(|config| (config.requested, config.selected_bins))
+                .unwrap_or_default();
 
         link_package_to_shared_dir(&self.package, self.manager)?;
-        configure::write_config_and_shims(&self.package, &manifest, image, self.manager)
+        configure::write_config_and_shims(
+            &self.package,
+            &manifest,
+            image,
+            self.manager,
+            requested,
+            selected,
+        )?;
+        Ok(())
     }
 }
 
@@ -0,0 +1,170 @@
+//! Provides reinstalling of global packages against a new version of Node, so that packages which
+//! were built for the previous version (e.g. those with native addons) keep working after the
+//! default Node is upgraded.
+
+use super::install;
+use super::{DirectInstall, PackageConfig};
+use crate::error::{ErrorKind, Fallible, VoltaError};
+use crate::inventory::package_configs;
+use crate::platform::{Image, PlatformSpec};
+use crate::session::Session;
+use crate::style::{success_prefix, tool_version};
+use crate::sync::VoltaLock;
+use log::{debug, info, warn};
+use node_semver::Version;
+
+/// The result of reinstalling the global packages
+#[derive(Default)]
+pub struct ReinstallReport {
+    /// The packages that were reinstalled
+    pub reinstalled: Vec<String>,
+    /// The packages that could not be reinstalled, along with the reason
+    pub failed: Vec<(String, VoltaError)>,
+}
+
+impl ReinstallReport {
+    /// Whether every package was reinstalled successfully
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Lists the global packages that were installed with a different major version of Node than
+/// `node`, which may need to be reinstalled when it becomes the default
+///
+/// Packages with native addons are built for a specific major version of Node, so they usually
+/// keep working across minor and patch upgrades, but not across major ones.
+pub fn packages_for_other_node_major(node: &Version) -> Fallible<Vec<String>> {
+    Ok(package_configs()?
+        .into_iter()
+        // Packages installed with the system Node don't use the default Node at all
+        .filter(|config| {
+            config
+                .platform
+                .node
+                .version()
+                .is_some_and(|installed| installed.major != node.major)
+        })
+        .map(|config| config.name)
+        .collect())
+}
+
+/// Reinstalls every global package that was installed with a Node version other than `new_node`
+///
+/// Each package is reinstalled at its currently installed version, with the package manager that
+/// originally installed it, against the default platform using `new_node`. A failure to reinstall
+/// one package is recorded in the report and doesn't stop the others from being reinstalled.
+pub fn reinstall_packages_for_node(
+    new_node: &Version,
+    session: &mut Session,
+) -> Fallible<ReinstallReport> {
+    let _lock = VoltaLock::acquire();
+
+    let platform = match session.default_platform()? {
+        Some(default) => PlatformSpec {
+            node: new_node.clone().into(),
+            ..default.clone()
+        },
+        None => PlatformSpec {
+            node: new_node.clone().into(),
+            npm: None,
+            pnpm: None,
+            yarn: None,
+        },
+    };
+    let image = platform.as_default().checkout(session)?;
+
+    let mut report = ReinstallReport::default();
+    for config in package_configs()? {
+        if config.platform.node.version() == Some(new_node) {
+            debug!(
+                "Skipping {}, which is already installed with {}",
+                config.name,
+                tool_version("node", new_node)
+            );
+            continue;
+        }
+
+        let name = config.name.clone();
+        let version = config.version.clone();
+        match reinstall_package(config, &image) {
+            Ok(()) => {
+                info!(
+                    "{} reinstalled {} with {}",
+                    success_prefix(),
+                    tool_version(&name, version),
+                    tool_version("node", new_node)
+                );
+                report.reinstalled.push(name);
+            }
+            Err(error) => {
+                warn!("Could not reinstall {}: {}", name, error);
+                report.failed.push((name, error));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Reinstalls a single package at its current version into the given platform image
+fn reinstall_package(config: PackageConfig, image: &Image) -> Fallible<()> {
+    // Writing the config again would drop any settings that only a newer Volta knows about
+    if config.is_from_newer_volta() {
+        return Err(ErrorKind::NewerPackageConfig {
+            package: config.name,
+            schema_version: config.schema_version,
+        }
+        .into());
+    }
+
+    let package = format!("{}@{}", config.name, config.version);
+    let installer = DirectInstall::with_name(config.manager, config.name)?;
+
+    install::run_global_install(
+        package,
+        config.manager,
+        installer.staging.path().to_owned(),
+        image,
+    )?;
+
+    // Keep the originally requested spec and selected bins, since a reinstall doesn't change the
+    // user's intent
+    installer.complete_install_with_requested(image, config.requested, config.selected_bins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::Sourced;
+
+    #[test]
+    fn reinstall_refuses_newer_config() {
+        let config: PackageConfig = serde_json::from_str(
+            r#"{
+                "name": "cowsay",
+                "version": "1.5.0",
+                "platform": { "node": "18.17.1", "npm": null, "yarn": null },
+                "bins": ["cowsay"],
+                "manager": "Npm",
+                "schema_version": 7
+            }"#,
+        )
+        .unwrap();
+        let image = Image {
+            node: Sourced::with_default(Version::parse("20.11.1").unwrap().into()),
+            npm: None,
+            pnpm: None,
+            yarn: None,
+        };
+
+        let error = reinstall_package(config, &image).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::NewerPackageConfig {
+                package: "cowsay".into(),
+                schema_version: 7,
+            }
+        );
+    }
+}
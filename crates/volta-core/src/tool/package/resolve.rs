@@ -0,0 +1,242 @@
+//! Provides resolution of global package version specs against the npm registry
+//!
+//! Installing a package leaves version resolution to the package manager, but resolving directly
+//! allows us to report what an install will fetch before running it.
+
+use super::super::registry::{
+    fetch_npm_registry, public_registry_index, PackageDetails, PackageIndex,
+};
+use crate::error::{ErrorKind, Fallible};
+use crate::platform::PlatformNode;
+use crate::session::Session;
+use crate::version::{VersionSpec, VersionTag};
+use log::debug;
+use node_semver::Version;
+
+/// A package version resolved from the registry
+#[derive(Debug)]
+pub struct ResolvedPackage {
+    /// The package name, including any scope
+    pub name: String,
+    /// The resolved version
+    pub version: Version,
+    /// The URL of the tarball for the resolved version
+    pub tarball_url: String,
+    /// The dist-tags that point at the resolved version, e.g. `latest` or `next`
+    pub tags: Vec<String>,
+}
+
+/// A preview of what installing a package will fetch, resolved without downloading anything
+#[derive(Debug)]
+pub struct PackagePreview {
+    /// The package name, including any scope
+    pub name: String,
+    /// The resolved version
+    pub version: Version,
+    /// The URL of the tarball for the resolved version
+    pub tarball_url: String,
+    /// The Subresource Integrity string published for the tarball, if any
+    pub integrity: Option<String>,
+    /// The legacy sha1 checksum published for the tarball, if any
+    pub shasum: Option<String>,
+    /// The binaries declared by the package
+    pub bins: Vec<String>,
+    /// The dist-tags that point at the resolved version
+    pub tags: Vec<String>,
+    /// The default Node version the package would be installed with, if one is set
+    pub node: Option<PlatformNode>,
+}
+
+/// Resolve a package version spec using the package's registry metadata
+pub fn resolve(name: &str, matching: &VersionSpec) -> Fallible<ResolvedPackage> {
+    let details = fetch_details(name, matching)?;
+
+    Ok(ResolvedPackage {
+        tarball_url: details.dist.tarball,
+        name: name.into(),
+        version: details.version,
+        tags: details.tags,
+    })
+}
+
+/// Preview the result of installing a package, resolving it against the registry without
+/// downloading the tarball
+pub fn preview_package(
+    name: &str,
+    matching: &VersionSpec,
+    session: &mut Session,
+) -> Fallible<PackagePreview> {
+    let node = session
+        .default_platform()?
+        .map(|platform| platform.node.clone());
+    let PackageDetails {
+        version,
+        dist,
+        bins,
+        tags,
+    } = fetch_details(name, matching)?;
+
+    Ok(PackagePreview {
+        name: name.into(),
+        tarball_url: dist.tarball,
+        version,
+        integrity: dist.integrity,
+        shasum: dist.shasum,
+        bins,
+        tags,
+        node,
+    })
+}
+
+fn fetch_details(name: &str, matching: &VersionSpec) -> Fallible<PackageDetails> {
+    let (url, index) = fetch_npm_registry(public_registry_index(name), name)?;
+    let details = resolve_in_index(name, index, matching)?;
+    let tags = if details.tags.is_empty() {
+        String::new()
+    } else {
+        format!(" (tag: {})", details.tags.join(", "))
+    };
+    debug!(
+        "Found {}@{} matching '{}' from {}{}",
+        name, details.version, matching, url, tags
+    );
+
+    Ok(details)
+}
+
+fn resolve_in_index(
+    name: &str,
+    mut index: PackageIndex,
+    matching: &VersionSpec,
+) -> Fallible<PackageDetails> {
+    let tagged = match matching {
+        VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => index.tags.remove("latest"),
+        VersionSpec::Tag(tag) => index.tags.remove(&tag.to_string()),
+        VersionSpec::Exact(version) => Some(version.clone()),
+        VersionSpec::Semver(_) => None,
+        VersionSpec::Local(_) | VersionSpec::Git(_) => {
+            return Err(ErrorKind::Unimplemented {
+                feature: "Resolving a package from outside of the registry".into(),
+            }
+            .into())
+        }
+    };
+
+    let found = match (matching, tagged) {
+        (VersionSpec::Semver(range), _) => index
+            .entries
+            .into_iter()
+            .find(|details| range.satisfies(&details.version)),
+        (_, Some(version)) => index
+            .entries
+            .into_iter()
+            .find(|details| details.version == version),
+        (_, None) => None,
+    };
+
+    found.ok_or_else(|| {
+        ErrorKind::PackageVersionNotFound {
+            name: name.into(),
+            matching: matching.to_string(),
+        }
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::registry::RawPackageMetadata;
+
+    /// The registry document for a scoped package with a `canary` dist-tag
+    const SCOPED_METADATA: &str = r#"{
+        "name": "@myorg/cli",
+        "dist-tags": { "latest": "1.2.0", "canary": "1.3.0-canary.4" },
+        "versions": {
+            "1.2.0": {
+                "version": "1.2.0",
+                "bin": "bin/cli.js",
+                "dist": {
+                    "integrity": "sha512-c29tZSBkYXRh",
+                    "shasum": "0123456789abcdef",
+                    "tarball": "https://registry.example.com/@myorg/cli/-/cli-1.2.0.tgz"
+                }
+            },
+            "1.3.0-canary.4": {
+                "version": "1.3.0-canary.4",
+                "dist": {
+                    "tarball": "https://registry.example.com/@myorg/cli/-/cli-1.3.0-canary.4.tgz"
+                }
+            }
+        }
+    }"#;
+
+    fn scoped_index() -> PackageIndex {
+        serde_json::from_str::<RawPackageMetadata>(SCOPED_METADATA)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn resolves_scoped_package_tag() {
+        let matching: VersionSpec = "canary".parse().unwrap();
+        let details = resolve_in_index("@myorg/cli", scoped_index(), &matching).unwrap();
+        assert_eq!(details.version, Version::parse("1.3.0-canary.4").unwrap());
+        assert_eq!(details.tags, ["canary"]);
+        assert_eq!(
+            details.dist.tarball,
+            "https://registry.example.com/@myorg/cli/-/cli-1.3.0-canary.4.tgz"
+        );
+    }
+
+    #[test]
+    fn preview_includes_published_metadata() {
+        let details = resolve_in_index("@myorg/cli", scoped_index(), &VersionSpec::None).unwrap();
+
+        assert_eq!(details.version, Version::parse("1.2.0").unwrap());
+        assert_eq!(
+            details.dist.integrity.as_deref(),
+            Some("sha512-c29tZSBkYXRh")
+        );
+        assert_eq!(details.dist.shasum.as_deref(), Some("0123456789abcdef"));
+        assert_eq!(details.bins, ["cli"]);
+        assert_eq!(details.tags, ["latest"]);
+    }
+
+    #[test]
+    fn reports_missing_scoped_package_tag() {
+        let matching: VersionSpec = "nightly".parse().unwrap();
+        let error = resolve_in_index("@myorg/cli", scoped_index(), &matching).unwrap_err();
+
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::PackageVersionNotFound {
+                name: "@myorg/cli".into(),
+                matching: "nightly".into(),
+            }
+        );
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[test]
+    fn resolves_scoped_package_tag_from_registry() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = crate::layout::override_volta_home(home.path().to_owned());
+        let _mock = mockito::mock("GET", "/@myorg/cli")
+            .with_status(200)
+            .with_header("Content-Type", "application/json")
+            .with_body(SCOPED_METADATA)
+            .create();
+
+        let matching: VersionSpec = "canary".parse().unwrap();
+        let resolved = resolve("@myorg/cli", &matching).unwrap();
+
+        assert_eq!(resolved.version, Version::parse("1.3.0-canary.4").unwrap());
+        assert_eq!(resolved.tags, ["canary"]);
+        // The tarball comes from the registry document, which may point at another host
+        assert_eq!(
+            resolved.tarball_url,
+            "https://registry.example.com/@myorg/cli/-/cli-1.3.0-canary.4.tgz"
+        );
+    }
+}
@@ -63,7 +63,7 @@ pub fn uninstall(name: &str) -> Fallible<()> {
 }
 
 /// Remove a shim and its associated configuration file
-fn remove_config_and_shim(bin_name: &str, pkg_name: &str) -> Fallible<()> {
+pub(super) fn remove_config_and_shim(bin_name: &str, pkg_name: &str) -> Fallible<()> {
     shim::delete(bin_name)?;
     let config_file = volta_home()?.default_tool_bin_config(bin_name);
     remove_file_if_exists(config_file)?;
@@ -0,0 +1,168 @@
+//! Provides a description of what fetching, installing, or pinning a tool will do, without doing it
+
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+use crate::error::{ErrorKind, Fallible};
+use crate::platform::PlatformSpec;
+use crate::session::Session;
+use crate::style::tool_version;
+
+/// The action that a plan describes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanAction {
+    Fetch,
+    Install,
+    Pin,
+}
+
+impl Display for PlanAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PlanAction::Fetch => "fetch",
+            PlanAction::Install => "install",
+            PlanAction::Pin => "pin",
+        })
+    }
+}
+
+/// Where the files for a tool will come from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlanSource {
+    /// The tool is already unpacked in the inventory, so nothing will be fetched
+    AlreadyFetched,
+    /// The archive is in the inventory cache and will be unpacked from there
+    Cache(PathBuf),
+    /// The archive will be downloaded from the first of these URLs that succeeds
+    Download(Vec<String>),
+}
+
+/// A change to the user's default toolchain or to the project manifest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlanChange {
+    /// The tool will become the default, replacing the previous default version (if any)
+    Default { previous: Option<String> },
+    /// The tool will be pinned in the manifest, replacing the previously pinned version (if any)
+    Pin {
+        manifest: PathBuf,
+        previous: Option<String>,
+    },
+}
+
+/// A preview of what performing an action with a tool will do
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ToolPlan {
+    /// The tool the plan is for, e.g. `node@20.5.1`
+    pub tool: String,
+    pub action: PlanAction,
+    /// Where the tool will be fetched from, if it is known
+    pub source: Option<PlanSource>,
+    /// The directory the tool will be unpacked into, if it is known
+    pub image_dir: Option<PathBuf>,
+    pub changes: Vec<PlanChange>,
+}
+
+impl ToolPlan {
+    /// A plan that only records the tool and action, for tools that can't describe their steps
+    pub fn new<T: Display>(tool: T, action: PlanAction) -> Self {
+        ToolPlan {
+            tool: tool.to_string(),
+            action,
+            source: None,
+            image_dir: None,
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl Display for ToolPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.action, self.tool)?;
+
+        match &self.source {
+            Some(PlanSource::AlreadyFetched) => write!(f, "\n    already fetched")?,
+            Some(PlanSource::Cache(archive)) => {
+                write!(f, "\n    use cached archive '{}'", archive.display())?
+            }
+            Some(PlanSource::Download(urls)) => {
+                let mut urls = urls.iter();
+                if let Some(url) = urls.next() {
+                    write!(f, "\n    download from {}", url)?;
+                }
+                for url in urls {
+                    write!(f, "\n    falling back to {}", url)?;
+                }
+            }
+            None => {}
+        }
+
+        if let Some(image_dir) = &self.image_dir {
+            match self.source {
+                Some(PlanSource::AlreadyFetched) => {
+                    write!(f, "\n    use image in '{}'", image_dir.display())?
+                }
+                _ => write!(f, "\n    unpack into '{}'", image_dir.display())?,
+            }
+        }
+
+        for change in &self.changes {
+            match change {
+                PlanChange::Default {
+                    previous: Some(previous),
+                } => write!(f, "\n    set as default, replacing {}", previous)?,
+                PlanChange::Default { previous: None } => write!(f, "\n    set as default")?,
+                PlanChange::Pin {
+                    manifest,
+                    previous: Some(previous),
+                } => write!(
+                    f,
+                    "\n    pin in '{}', replacing {}",
+                    manifest.display(),
+                    previous
+                )?,
+                PlanChange::Pin {
+                    manifest,
+                    previous: None,
+                } => write!(f, "\n    pin in '{}'", manifest.display())?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Describe the changes that installing or pinning a tool will make
+///
+/// `current` selects the tool's version from a platform, so that the default or pinned version
+/// that would be replaced can be reported. No change is listed when that version already matches.
+pub(super) fn planned_changes<T, F>(
+    name: &str,
+    version: &T,
+    action: PlanAction,
+    session: &Session,
+    current: F,
+) -> Fallible<Vec<PlanChange>>
+where
+    T: PartialEq + fmt::Display,
+    F: Fn(&PlatformSpec) -> Option<&T>,
+{
+    let change = match action {
+        PlanAction::Fetch => None,
+        PlanAction::Install => {
+            let previous = session.default_platform()?.and_then(&current);
+            (previous != Some(version)).then(|| PlanChange::Default {
+                previous: previous.map(|previous| tool_version(name, previous)),
+            })
+        }
+        PlanAction::Pin => {
+            let project = session.project()?.ok_or(ErrorKind::NotInPackage)?;
+            let previous = project.platform().and_then(&current);
+            (previous != Some(version)).then(|| PlanChange::Pin {
+                manifest: project.manifest_file().to_owned(),
+                previous: previous.map(|previous| tool_version(name, previous)),
+            })
+        }
+    };
+
+    Ok(change.into_iter().collect())
+}
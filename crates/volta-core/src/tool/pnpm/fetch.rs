@@ -12,12 +12,15 @@ use crate::error::{Context, ErrorKind, Fallible};
 use crate::fs::{create_staging_dir, create_staging_file, rename, set_executable};
 use crate::hook::ToolHooks;
 use crate::layout::volta_home;
+use crate::profile::{time, Phase};
 use crate::style::{progress_bar, tool_version};
+use crate::tool::integrity::{self, Integrity};
+use crate::tool::provenance::{record_provenance, Provenance};
 use crate::tool::registry::public_registry_package;
-use crate::tool::{self, download_tool_error, Pnpm};
+use crate::tool::{self, FetchOutcome, PlanSource, Pnpm};
 use crate::version::VersionSpec;
 
-pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<()> {
+pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<FetchOutcome> {
     let pnpm_dir = volta_home()?.pnpm_inventory_dir();
     let cache_file = pnpm_dir.join(Pnpm::archive_filename(&version.to_string()));
 
@@ -33,27 +36,43 @@ pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<()>
         None => {
             let staging = create_staging_file()?;
             let remote_url = determine_remote_url(version, hooks)?;
-            let archive = fetch_remote_distro(version, &remote_url, staging.path())?;
-            (archive, Some(staging))
+            let (archive, checksum) =
+                fetch_remote_distro(version, hooks, &remote_url, staging.path())?;
+            let provenance = Provenance::new(remote_url, checksum);
+            (archive, Some((staging, provenance)))
         }
     };
 
-    unpack_archive(archive, version)?;
+    let bytes = archive.compressed_size();
+    time(Phase::Unpack, || unpack_archive(archive, version))?;
 
-    if let Some(staging_file) = staging {
-        ensure_containing_dir_exists(&cache_file).with_context(|| {
-            ErrorKind::ContainingDirError {
-                path: cache_file.clone(),
-            }
-        })?;
-        staging_file
-            .persist(cache_file)
-            .with_context(|| ErrorKind::PersistInventoryError {
-                tool: "pnpm".into(),
+    let outcome = match staging {
+        Some(_) => FetchOutcome::Network { bytes },
+        None => FetchOutcome::Cache,
+    };
+
+    let image_dir = volta_home()?.pnpm_image_dir(&version.to_string());
+    if let Some((staging_file, provenance)) = staging {
+        record_provenance(Ok(provenance), &image_dir);
+
+        if tool::cache_archives() {
+            ensure_containing_dir_exists(&cache_file).with_context(|| {
+                ErrorKind::ContainingDirError {
+                    path: cache_file.clone(),
+                }
             })?;
+            staging_file
+                .persist(cache_file)
+                .with_context(|| ErrorKind::PersistInventoryError {
+                    tool: "pnpm".into(),
+                })?;
+        }
+    } else {
+        let source = cache_file.display().to_string();
+        record_provenance(Provenance::of_archive(&cache_file, source), &image_dir);
     }
 
-    Ok(())
+    Ok(outcome)
 }
 
 /// Unpack the pnpm archive into the image directory so that it is ready for use
@@ -117,6 +136,21 @@ fn load_cached_distro(file: &Path) -> Option<Box<dyn Archive>> {
     }
 }
 
+/// Determine where the archive for a pnpm version will come from, without fetching it
+pub(super) fn plan_source(
+    version: &Version,
+    hooks: Option<&ToolHooks<Pnpm>>,
+) -> Fallible<PlanSource> {
+    let cache_file = volta_home()?
+        .pnpm_inventory_dir()
+        .join(Pnpm::archive_filename(&version.to_string()));
+    if cache_file.is_file() {
+        Ok(PlanSource::Cache(cache_file))
+    } else {
+        determine_remote_url(version, hooks).map(|url| PlanSource::Download(vec![url]))
+    }
+}
+
 /// Determine the remote URL to download from, using the hooks if avaialble
 fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<String> {
     let version_str = version.to_string();
@@ -133,17 +167,33 @@ fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Pnpm>>) -> F
     }
 }
 
-/// Fetch the distro archive from the internet
+/// Look up the integrity of the archive, if it is downloaded from the public registry
+fn published_integrity(
+    version: &Version,
+    hooks: Option<&ToolHooks<Pnpm>>,
+) -> Fallible<Option<Integrity>> {
+    match hooks {
+        Some(&ToolHooks {
+            distro: Some(_), ..
+        }) => Ok(None),
+        _ => integrity::published_integrity("pnpm", version),
+    }
+}
+
+/// Fetch the distro archive from the internet, verifying it before anything is unpacked
 fn fetch_remote_distro(
     version: &Version,
+    hooks: Option<&ToolHooks<Pnpm>>,
     url: &str,
     staging_path: &Path,
-) -> Fallible<Box<dyn Archive>> {
+) -> Fallible<(Box<dyn Archive>, String)> {
     info!("Downloading {} from {}", tool_version("pnpm", version), url);
-    Tarball::fetch(url, staging_path).with_context(download_tool_error(
+    integrity::download_verified(
         tool::Spec::Pnpm(VersionSpec::Exact(version.clone())),
         url,
-    ))
+        staging_path,
+        || published_integrity(version, hooks),
+    )
 }
 
 /// Create executable launchers for the pnpm and pnpx binaries
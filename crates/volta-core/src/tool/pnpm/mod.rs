@@ -1,21 +1,25 @@
 use node_semver::Version;
 use std::fmt::{self, Display};
+use std::time::Instant;
 
 use crate::error::{ErrorKind, Fallible};
 use crate::inventory::pnpm_available;
+use crate::layout::volta_home;
+use crate::profile::{time, Phase};
 use crate::session::Session;
 use crate::style::tool_version;
 use crate::sync::VoltaLock;
 
 use super::{
     check_fetched, check_shim_reachable, debug_already_fetched, info_fetched, info_installed,
-    info_pinned, info_project_version, FetchStatus, Tool,
+    info_pinned, not_activated, plan::planned_changes, warn_project_version, FetchOutcome,
+    FetchStatus, FetchSummary, PlanAction, PlanSource, Tool, ToolKindName, ToolPlan,
 };
 
 mod fetch;
 mod resolve;
 
-pub use resolve::resolve;
+pub use resolve::{available, resolve};
 
 /// The Tool implementation for fetching and installing pnpm
 pub struct Pnpm {
@@ -35,41 +39,51 @@ impl Pnpm {
         format!("{}.tgz", Pnpm::archive_basename(version))
     }
 
-    pub(crate) fn ensure_fetched(&self, session: &mut Session) -> Fallible<()> {
+    pub(crate) fn ensure_fetched(&self, session: &mut Session) -> Fallible<FetchOutcome> {
         match check_fetched(|| pnpm_available(&self.version))? {
             FetchStatus::AlreadyFetched => {
                 debug_already_fetched(self);
-                Ok(())
+                Ok(FetchOutcome::AlreadyInstalled)
+            }
+            FetchStatus::FetchNeeded(_lock) => {
+                let hooks = session.hooks()?.pnpm();
+                time(Phase::Fetch, || fetch::fetch(&self.version, hooks))
             }
-            FetchStatus::FetchNeeded(_lock) => fetch::fetch(&self.version, session.hooks()?.pnpm()),
         }
     }
 }
 
 impl Tool for Pnpm {
-    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<()> {
-        self.ensure_fetched(session)?;
+    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<FetchOutcome> {
+        let outcome = self.ensure_fetched(session)?;
 
         info_fetched(self);
-        Ok(())
+        Ok(outcome)
     }
 
     fn install(self: Box<Self>, session: &mut Session) -> Fallible<()> {
         // Acquire a lock on the Volta directory, if possible, to prevent concurrent changes
         let _lock = VoltaLock::acquire();
-        self.ensure_fetched(session)?;
+        let started = Instant::now();
+        let outcome = self.ensure_fetched(session)?;
+        let fetched = FetchSummary::since(outcome, started);
 
         session
             .toolchain_mut()?
-            .set_active_pnpm(Some(self.version.clone()))?;
+            .set_active_pnpm(Some(self.version.clone()))
+            .map_err(not_activated(tool_version("pnpm", &self.version)))?;
 
-        info_installed(&self);
+        info_installed(&self, fetched);
         check_shim_reachable("pnpm");
 
-        if let Ok(Some(project)) = session.project_platform() {
-            if let Some(pnpm) = &project.pnpm {
-                info_project_version(tool_version("pnpm", pnpm), &self);
-            }
+        let project_version = session
+            .project_platform()
+            .ok()
+            .flatten()
+            .and_then(|project| project.pnpm.as_ref())
+            .map(|pnpm| tool_version("pnpm", pnpm));
+        if let Some(project_version) = project_version {
+            warn_project_version(session, project_version, &self);
         }
         Ok(())
     }
@@ -88,6 +102,23 @@ impl Tool for Pnpm {
             Err(ErrorKind::NotInPackage.into())
         }
     }
+
+    fn kind(&self) -> ToolKindName {
+        ToolKindName::Pnpm
+    }
+    fn describe_plan(&self, action: PlanAction, session: &Session) -> Fallible<ToolPlan> {
+        let mut plan = ToolPlan::new(self, action);
+        plan.source = Some(if pnpm_available(&self.version)? {
+            PlanSource::AlreadyFetched
+        } else {
+            fetch::plan_source(&self.version, session.hooks()?.pnpm())?
+        });
+        plan.image_dir = Some(volta_home()?.pnpm_image_dir(&self.version.to_string()));
+        plan.changes = planned_changes("pnpm", &self.version, action, session, |platform| {
+            platform.pnpm.as_ref()
+        })?;
+        Ok(plan)
+    }
 }
 
 impl Display for Pnpm {
@@ -3,21 +3,48 @@ use node_semver::{Range, Version};
 
 use crate::error::{ErrorKind, Fallible};
 use crate::hook::ToolHooks;
+use crate::inventory::pnpm_available;
 use crate::session::Session;
 use crate::tool::registry::{fetch_npm_registry, public_registry_index, PackageIndex};
-use crate::tool::{PackageDetails, Pnpm};
+use crate::tool::{fetched_exact_version, PackageDetails, Pnpm, ToolKindName};
 use crate::version::{VersionSpec, VersionTag};
 
 pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    session.resolve_cached(ToolKindName::Pnpm, matching, resolve_uncached)
+}
+
+fn resolve_uncached(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    if let Some(version) = fetched_exact_version("pnpm", &matching, pnpm_available)? {
+        return Ok(version);
+    }
+
     let hooks = session.hooks()?.pnpm();
     match matching {
         VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks),
         VersionSpec::Exact(version) => Ok(version),
         VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => resolve_tag("latest", hooks),
         VersionSpec::Tag(tag) => resolve_tag(&tag.to_string(), hooks),
+        VersionSpec::Local(_) => Err(ErrorKind::Unimplemented {
+            feature: "Installing pnpm from a local archive".into(),
+        }
+        .into()),
+        VersionSpec::Git(_) => Err(ErrorKind::GitSourceNotSupported {
+            tool: "pnpm".into(),
+        }
+        .into()),
     }
 }
 
+/// Lists the pnpm versions available from the registry
+pub fn available(session: &mut Session) -> Fallible<Vec<Version>> {
+    let (_, index) = fetch_pnpm_index(session.hooks()?.pnpm())?;
+    Ok(index
+        .entries
+        .into_iter()
+        .map(|details| details.version)
+        .collect())
+}
+
 fn resolve_tag(tag: &str, hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<Version> {
     let (url, mut index) = fetch_pnpm_index(hooks)?;
 
@@ -28,6 +55,7 @@ fn resolve_tag(tag: &str, hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<Version>
         }
         None => Err(ErrorKind::PnpmVersionNotFound {
             matching: tag.into(),
+            registry_url: Some(url),
         }
         .into()),
     }
@@ -51,6 +79,7 @@ fn resolve_semver(matching: Range, hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<
         }
         None => Err(ErrorKind::PnpmVersionNotFound {
             matching: matching.to_string(),
+            registry_url: Some(url),
         }
         .into()),
     }
@@ -71,3 +100,28 @@ fn fetch_pnpm_index(hooks: Option<&ToolHooks<Pnpm>>) -> Fallible<(String, Packag
 
     fetch_npm_registry(url, "pnpm")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{override_volta_home, volta_home};
+
+    #[test]
+    fn test_exact_version_in_inventory() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let home = volta_home().unwrap();
+        let cached = Version::from((8, 6, 0));
+        std::fs::create_dir_all(home.pnpm_image_dir(&cached.to_string())).unwrap();
+        // Unreadable hooks make any lookup in the registry fail
+        std::fs::write(home.default_hooks_file(), "not hooks").unwrap();
+        let mut session = Session::init_unchecked();
+
+        assert_eq!(
+            resolve(VersionSpec::Exact(cached.clone()), &mut session).unwrap(),
+            cached
+        );
+        let missing = Version::from((8, 6, 1));
+        assert!(resolve(VersionSpec::Exact(missing), &mut session).is_err());
+    }
+}
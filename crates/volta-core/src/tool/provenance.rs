@@ -0,0 +1,143 @@
+//! Provides a record of where each tool image was installed from, along with the checksum of the
+//! archive it was unpacked from, so that an install can be audited after the fact without
+//! downloading it again.
+
+use std::fs::{read_to_string, write, File};
+use std::path::{Path, PathBuf};
+
+use super::integrity::sha512_integrity;
+use crate::error::{Context, ErrorKind, Fallible};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+/// The name of the provenance file, which is stored at the root of each tool image
+const PROVENANCE_FILE: &str = "provenance.json";
+
+/// Where a tool image came from
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    /// The URL or file path that the archive was loaded from
+    pub source: String,
+    /// The Subresource Integrity string (`sha512-...`) of the archive that was installed
+    pub checksum: String,
+}
+
+impl Provenance {
+    /// Creates the provenance of an archive whose checksum is already known (e.g. because it was
+    /// computed while verifying the download)
+    pub(crate) fn new(source: impl Into<String>, checksum: String) -> Self {
+        Provenance {
+            source: source.into(),
+            checksum,
+        }
+    }
+
+    /// Determines the provenance of an archive file, computing its checksum
+    ///
+    /// Downloads are written to disk as they are unpacked, so this must only be called once the
+    /// archive has been unpacked in full.
+    pub(crate) fn of_archive(archive: &Path, source: impl Into<String>) -> Fallible<Self> {
+        let checksum = File::open(archive)
+            .and_then(sha512_integrity)
+            .with_context(|| ErrorKind::WriteProvenanceError {
+                file: archive.to_owned(),
+            })?;
+
+        Ok(Provenance::new(source, checksum))
+    }
+
+    /// Records the provenance in the given image directory
+    pub(crate) fn write(&self, image_dir: &Path) -> Fallible<()> {
+        let file = provenance_file(image_dir);
+        let contents = serde_json::to_string_pretty(self)
+            .with_context(|| ErrorKind::WriteProvenanceError { file: file.clone() })?;
+
+        write(&file, contents).with_context(|| ErrorKind::WriteProvenanceError { file })
+    }
+}
+
+/// Records the provenance of the archive that a tool image was unpacked from
+///
+/// Provenance is only kept for auditing, so the image is still usable without it and a failure to
+/// determine or record it is reported as a warning rather than failing the install.
+pub(crate) fn record_provenance(provenance: Fallible<Provenance>, image_dir: &Path) {
+    let recorded = provenance.and_then(|provenance| {
+        debug!(
+            "Recording provenance of '{}' ({})",
+            image_dir.display(),
+            provenance.checksum
+        );
+        provenance.write(image_dir)
+    });
+
+    if let Err(error) = recorded {
+        warn!("{}", error);
+    }
+}
+
+/// Reads the provenance recorded for a tool image, if there is one
+///
+/// Images installed before provenance was recorded won't have one.
+pub fn read_provenance(image_dir: &Path) -> Fallible<Option<Provenance>> {
+    let file = provenance_file(image_dir);
+    if !file.is_file() {
+        return Ok(None);
+    }
+
+    let contents = read_to_string(&file)
+        .with_context(|| ErrorKind::ReadProvenanceError { file: file.clone() })?;
+
+    serde_json::from_str(&contents)
+        .map(Some)
+        .with_context(|| ErrorKind::ReadProvenanceError { file })
+}
+
+fn provenance_file(image_dir: &Path) -> PathBuf {
+    image_dir.join(PROVENANCE_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance_records_archive_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("tool-1.0.0.tgz");
+        std::fs::write(&archive, b"hello world").unwrap();
+        let image = dir.path().join("image");
+        std::fs::create_dir(&image).unwrap();
+
+        assert_eq!(read_provenance(&image).unwrap(), None);
+
+        let provenance = Provenance::of_archive(&archive, "https://example.com/tool.tgz").unwrap();
+        provenance.write(&image).unwrap();
+
+        // echo -n "hello world" | openssl dgst -sha512 -binary | base64
+        assert_eq!(
+            read_provenance(&image).unwrap(),
+            Some(Provenance {
+                source: "https://example.com/tool.tgz".into(),
+                checksum: "sha512-MJ7MSJwS1utMxA9QyQLytNDtd+5RGnx6m808qG1M2G+YndNbxf9JlnDaNCVbRbDP2DDoH2Bdz33FVC6TrpzXbw=="
+                    .into(),
+            })
+        );
+    }
+    #[test]
+    fn test_record_provenance_failure_is_not_fatal() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing_image = dir.path().join("missing");
+
+        // The image directory doesn't exist, so the provenance can't be written
+        let provenance = Provenance::new("https://example.com/tool.tgz", "sha512-abc".into());
+        record_provenance(Ok(provenance), &missing_image);
+        assert_eq!(read_provenance(&missing_image).unwrap(), None);
+
+        let missing_archive = dir.path().join("missing.tgz");
+        record_provenance(
+            Provenance::of_archive(&missing_archive, "missing.tgz"),
+            dir.path(),
+        );
+        assert_eq!(read_provenance(dir.path()).unwrap(), None);
+    }
+}
@@ -1,17 +1,27 @@
 use std::collections::HashMap;
+use std::fs::write;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
 use std::env;
 
 use super::registry_fetch_error;
 use crate::error::{Context, ErrorKind, Fallible};
-use crate::fs::read_dir_eager;
+use crate::fs::{read_dir_eager, read_file};
+use crate::layout::volta_home;
 use crate::style::progress_spinner;
+use crate::tool::package::{bin_names, serde_bins};
 use crate::version::{hashmap_version_serde, version_serde};
-use attohttpc::header::ACCEPT;
+use archive::request::{self, RequestKind};
+use attohttpc::header::{HeaderMap, ACCEPT};
 use attohttpc::Response;
 use cfg_if::cfg_if;
+use fs_utils::ensure_containing_dir_exists;
+use headers::{CacheControl, Expires, HeaderMapExt};
+use log::debug;
 use node_semver::Version;
-use serde::Deserialize;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 
 // Accept header needed to request the abbreviated metadata from the npm registry
 // See https://github.com/npm/registry/blob/master/docs/responses/package-metadata.md
@@ -33,25 +43,176 @@ cfg_if! {
             // http://npmmirror.com
             match env::var_os("ENV_NPM_MIRROR") {
                 Some(val) =>  format!("{}/{}", val.to_string_lossy(), package),
-                None => format!("https://registry.npmmirror.com/{}", package)
-                // None => format!("https://registry.npmjs.org/{}", package)
+                None => match &crate::npmrc::npmrc().registry {
+                    // Use the registry that npm is configured with, if any
+                    Some(registry) => format!("{}/{}", registry, package),
+                    None => format!("https://registry.npmmirror.com/{}", package)
+                    // None => format!("https://registry.npmjs.org/{}", package)
+                }
             }
         }
     }
 }
 
+/// How long package metadata is cached for when the registry doesn't say
+///
+/// Packages are published far more often than Node, so this is much shorter than for the index.
+const DEFAULT_METADATA_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
 // fetch a registry that returns info in Npm format
 pub fn fetch_npm_registry(url: String, name: &str) -> Fallible<(String, PackageIndex)> {
+    if super::use_metadata_cache() {
+        if let Some(metadata) = read_cached_metadata(name, &url) {
+            debug!("Found valid cache of registry metadata for {}", name);
+            let index = metadata.into();
+            remember_dists(&url, &index);
+            return Ok((url, index));
+        }
+    }
+
     let spinner = progress_spinner(format!("Fetching npm registry: {}", url));
-    let metadata: RawPackageMetadata = attohttpc::get(&url)
+    let (_, headers, response) = request::get(&url, RequestKind::Metadata)
         .header(ACCEPT, NPM_ABBREVIATED_ACCEPT_HEADER)
         .send()
         .and_then(Response::error_for_status)
-        .and_then(Response::json)
+        .with_context(registry_fetch_error(name, &url))?
+        .split();
+    let text = response
+        .text()
         .with_context(registry_fetch_error(name, &url))?;
+    let metadata: RawPackageMetadata =
+        serde_json::from_str(&text).with_context(registry_fetch_error(name, &url))?;
+
+    if let Some(expires) = metadata_expiry(&headers) {
+        write_cached_metadata(name, &url, &text, expires);
+    }
 
     spinner.finish_and_clear();
-    Ok((url, metadata.into()))
+    let index = metadata.into();
+    remember_dists(&url, &index);
+    Ok((url, index))
+}
+
+/// The `dist` info of each package version in the registry documents fetched so far, keyed by the
+/// document URL and version
+static FETCHED_DISTS: Lazy<Mutex<HashMap<(String, Version), RawDistInfo>>> =
+    Lazy::new(Default::default);
+
+/// Remembers the `dist` info in a fetched registry document, so that the checksum of a version
+/// resolved from it can be looked up without fetching the document again
+fn remember_dists(url: &str, index: &PackageIndex) {
+    let mut dists = FETCHED_DISTS.lock().unwrap_or_else(PoisonError::into_inner);
+    for entry in &index.entries {
+        dists.insert((url.to_string(), entry.version.clone()), entry.dist.clone());
+    }
+}
+
+/// Looks up the `dist` info for a specific version in the registry document at `url`
+///
+/// The document fetched while resolving the version is used if there is one, so that this usually
+/// doesn't need another request. Returns `None` if the document doesn't include the version.
+pub fn fetch_dist_info(
+    url: String,
+    name: &str,
+    version: &Version,
+) -> Fallible<Option<RawDistInfo>> {
+    let key = (url, version.clone());
+    let remembered = FETCHED_DISTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+        .cloned();
+    if remembered.is_some() {
+        return Ok(remembered);
+    }
+
+    let (_, index) = fetch_npm_registry(key.0, name)?;
+    Ok(index
+        .entries
+        .into_iter()
+        .find(|entry| entry.version == *version)
+        .map(|entry| entry.dist))
+}
+
+/// The first line of a cached package document, identifying where it came from and when it
+/// expires
+#[derive(Serialize, Deserialize)]
+struct MetadataCacheHeader {
+    url: String,
+    expires: String,
+}
+
+/// Determine when a fetched package document expires, based on the response headers
+///
+/// Returns `None` if the registry asked for the response not to be cached.
+fn metadata_expiry(headers: &HeaderMap) -> Option<SystemTime> {
+    let cache_control = headers.typed_get::<CacheControl>();
+    if cache_control
+        .as_ref()
+        .is_some_and(|control| control.no_store() || control.no_cache())
+    {
+        return None;
+    }
+
+    let expiry = headers
+        .typed_get::<Expires>()
+        .map(SystemTime::from)
+        .unwrap_or_else(|| {
+            let max_age = cache_control
+                .and_then(|control| control.max_age())
+                .unwrap_or(DEFAULT_METADATA_MAX_AGE);
+            SystemTime::now() + max_age
+        });
+
+    Some(expiry)
+}
+
+/// Reads a package document from the cache, if it was fetched from `url` and hasn't expired
+///
+/// The cache is only an optimization, so any problem reading it is treated as a cache miss.
+fn read_cached_metadata(name: &str, url: &str) -> Option<RawPackageMetadata> {
+    let file = volta_home().ok()?.package_metadata_cache_file(name);
+    let cached = read_file(file).ok()??;
+    let (header, json) = cached.split_once('\n')?;
+    let header: MetadataCacheHeader = serde_json::from_str(header).ok()?;
+
+    if header.url != url {
+        debug!(
+            "Registry metadata cache for {} is for '{}' rather than '{}'",
+            name, header.url, url
+        );
+        return None;
+    }
+
+    let expires = httpdate::parse_http_date(&header.expires).ok()?;
+    if SystemTime::now() >= expires {
+        debug!("Registry metadata cache for {} has expired", name);
+        return None;
+    }
+
+    serde_json::from_str(json).ok()
+}
+
+/// Writes a fetched package document to the cache, along with the time at which it expires
+///
+/// Failing to write the cache doesn't affect the result of the fetch, so errors are only logged.
+fn write_cached_metadata(name: &str, url: &str, json: &str, expires: SystemTime) {
+    let header = MetadataCacheHeader {
+        url: url.into(),
+        expires: httpdate::fmt_http_date(expires),
+    };
+
+    let header = serde_json::to_string(&header).expect("Cache header can always be serialized");
+    let file = match volta_home() {
+        Ok(home) => home.package_metadata_cache_file(name),
+        Err(_) => return,
+    };
+
+    let result = ensure_containing_dir_exists(&file)
+        .and_then(|_| write(&file, format!("{}\n{}", header, json)));
+    if let Err(error) = result {
+        debug!("Could not cache registry metadata for {}: {}", name, error);
+    }
 }
 
 pub fn public_registry_package(package: &str, version: &str) -> String {
@@ -97,6 +258,10 @@ pub fn find_unpack_dir(in_dir: &Path) -> Fallible<PathBuf> {
 #[derive(Debug)]
 pub struct PackageDetails {
     pub(crate) version: Version,
+    pub(crate) dist: RawDistInfo,
+    pub(crate) bins: Vec<String>,
+    /// The dist-tags that point at this version, sorted by name
+    pub(crate) tags: Vec<String>,
 }
 
 /// Index of versions of a specific package from the npm Registry
@@ -126,21 +291,42 @@ pub struct RawPackageVersionInfo {
     #[serde(with = "version_serde")]
     pub version: Version,
     pub dist: RawDistInfo,
+    #[serde(default, deserialize_with = "serde_bins::deserialize")]
+    pub bin: Vec<String>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct RawDistInfo {
-    pub shasum: String,
+    /// Subresource Integrity string (e.g. `sha512-...`), published by modern registries
+    #[serde(default)]
+    pub integrity: Option<String>,
+    /// Legacy hex-encoded sha1 checksum
+    #[serde(default)]
+    pub shasum: Option<String>,
     pub tarball: String,
 }
 
 impl From<RawPackageMetadata> for PackageIndex {
     fn from(serial: RawPackageMetadata) -> PackageIndex {
+        let name = serial.name;
+        let dist_tags = &serial.dist_tags;
         let mut entries: Vec<PackageDetails> = serial
             .versions
             .into_values()
-            .map(|version_info| PackageDetails {
-                version: version_info.version,
+            .map(|version_info| {
+                let mut tags: Vec<String> = dist_tags
+                    .iter()
+                    .filter(|(_, version)| **version == version_info.version)
+                    .map(|(tag, _)| tag.clone())
+                    .collect();
+                tags.sort();
+
+                PackageDetails {
+                    version: version_info.version,
+                    dist: version_info.dist,
+                    bins: bin_names(&name, version_info.bin),
+                    tags,
+                }
             })
             .collect();
 
@@ -152,3 +338,144 @@ impl From<RawPackageMetadata> for PackageIndex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::override_volta_home;
+    use attohttpc::header::{HeaderValue, CACHE_CONTROL};
+
+    const METADATA: &str = r#"{
+        "name": "cowsay",
+        "dist-tags": { "latest": "1.5.0" },
+        "versions": {
+            "1.5.0": {
+                "version": "1.5.0",
+                "dist": { "tarball": "https://registry.example.com/cowsay/-/cowsay-1.5.0.tgz" }
+            }
+        }
+    }"#;
+
+    fn in_an_hour() -> SystemTime {
+        SystemTime::now() + Duration::from_secs(60 * 60)
+    }
+
+    #[test]
+    fn test_metadata_cache_hit_and_miss() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let url = "https://registry.npmmirror.com/cowsay";
+
+        assert!(read_cached_metadata("cowsay", url).is_none());
+
+        write_cached_metadata("cowsay", url, METADATA, in_an_hour());
+        let cached = read_cached_metadata("cowsay", url).unwrap();
+        assert_eq!(cached.name, "cowsay");
+
+        // The cache is keyed by package, and ignored if the registry has changed
+        assert!(read_cached_metadata("cowthink", url).is_none());
+        assert!(read_cached_metadata("cowsay", "https://registry.npmjs.org/cowsay").is_none());
+    }
+
+    #[test]
+    fn test_metadata_cache_for_scoped_package() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let url = "https://registry.npmmirror.com/@myorg/cli";
+
+        write_cached_metadata("@myorg/cli", url, METADATA, in_an_hour());
+        assert!(read_cached_metadata("@myorg/cli", url).is_some());
+    }
+
+    #[test]
+    fn test_metadata_cache_expiry() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let url = "https://registry.npmmirror.com/cowsay";
+
+        let expired = SystemTime::now() - Duration::from_secs(1);
+        write_cached_metadata("cowsay", url, METADATA, expired);
+        assert!(read_cached_metadata("cowsay", url).is_none());
+    }
+
+    #[test]
+    fn test_metadata_expiry_honors_cache_control() {
+        let mut headers = HeaderMap::new();
+        let default = metadata_expiry(&headers).unwrap();
+        assert!(default <= SystemTime::now() + DEFAULT_METADATA_MAX_AGE);
+        assert!(default > SystemTime::now() + Duration::from_secs(4 * 60));
+
+        headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=3600"),
+        );
+        let expiry = metadata_expiry(&headers).unwrap();
+        assert!(expiry > SystemTime::now() + Duration::from_secs(59 * 60));
+
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        assert!(metadata_expiry(&headers).is_none());
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[test]
+    fn test_fetch_npm_registry_uses_cache() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let mock = mockito::mock("GET", "/cowsay")
+            .with_status(200)
+            .with_header("Content-Type", "application/json")
+            .with_header("Cache-Control", "max-age=300")
+            .with_body(METADATA)
+            .expect(1)
+            .create();
+
+        let url = format!("{}/cowsay", mockito::server_url());
+        for _ in 0..2 {
+            let (_, index) = fetch_npm_registry(url.clone(), "cowsay").unwrap();
+            assert_eq!(index.entries[0].version, Version::parse("1.5.0").unwrap());
+        }
+
+        mock.assert();
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[test]
+    fn test_fetch_dist_info_reuses_resolved_document() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let mock = mockito::mock("GET", "/cowsay-dist")
+            .with_status(200)
+            .with_header("Content-Type", "application/json")
+            .with_header("Cache-Control", "no-store")
+            .with_body(METADATA)
+            .expect(1)
+            .create();
+
+        let url = format!("{}/cowsay-dist", mockito::server_url());
+        let version = Version::parse("1.5.0").unwrap();
+        fetch_npm_registry(url.clone(), "cowsay").unwrap();
+        let dist = fetch_dist_info(url, "cowsay", &version).unwrap().unwrap();
+
+        assert_eq!(
+            dist.tarball,
+            "https://registry.example.com/cowsay/-/cowsay-1.5.0.tgz"
+        );
+        mock.assert();
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[test]
+    fn test_fetch_dist_info_reports_registry_errors() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let _mock = mockito::mock("GET", "/cowsay-unavailable")
+            .with_status(500)
+            .create();
+
+        let url = format!("{}/cowsay-unavailable", mockito::server_url());
+        let version = Version::parse("1.5.0").unwrap();
+        let error = fetch_dist_info(url, "cowsay", &version).unwrap_err();
+
+        assert!(matches!(error.kind(), ErrorKind::RegistryFetchError { .. }));
+    }
+}
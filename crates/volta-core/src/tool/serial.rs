@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
 
 use super::Spec;
 use crate::error::{ErrorKind, Fallible};
@@ -33,8 +34,14 @@ impl Spec {
                     tool_spec: tool_spec.into(),
                 })?;
 
-        // Validate that the captured name is a valid NPM package name.
+        // Reject characters that would otherwise be reported confusingly (or not at all) by the
+        // package name validation, such as a tab pasted into the middle of a name
         let name = &captures["name"];
+        if name.chars().any(char::is_control) || name.trim() != name {
+            return Err(ErrorKind::InvalidToolNameCharacters { name: name.into() }.into());
+        }
+
+        // Validate that the captured name is a valid NPM package name.
         if let Validity::Invalid { errors, .. } = validate(name) {
             return Err(ErrorKind::InvalidToolName {
                 name: name.into(),
@@ -49,6 +56,13 @@ impl Spec {
             .transpose()?
             .unwrap_or_default();
 
+        // Only packages can be installed from a git repository, the other tools need a release
+        if let VersionSpec::Git(_) = version {
+            if matches!(name, "node" | "npm" | "pnpm" | "yarn") {
+                return Err(ErrorKind::GitSourceNotSupported { tool: name.into() }.into());
+            }
+        }
+
         Ok(match name {
             "node" => Spec::Node(version),
             "npm" => Spec::Npm(version),
@@ -65,6 +79,7 @@ impl Spec {
     /// - `volta install node 12`, where the user intended to install `node@12`
     ///   but used syntax like in nodenv or nvm
     /// - invalid version specs
+    /// - more than one version of the same tool, unless fetching
     ///
     /// Returns a listed sorted so that if `node` is included in the list, it is
     /// always first.
@@ -79,6 +94,8 @@ impl Spec {
             .map(|arg| Self::try_from_str(arg.as_ref()))
             .collect::<Fallible<Vec<Spec>>>()?;
 
+        Self::check_duplicates(&tools, action)?;
+
         tools.sort_by(Self::sort_comparator);
         Ok(tools)
     }
@@ -129,6 +146,27 @@ impl Spec {
         }
     }
 
+    /// Check for more than one version of the same tool
+    ///
+    /// `volta fetch node@18 node@20` downloads both versions without changing anything else, so
+    /// it is allowed. Installing or pinning both would only leave the last one set, so any other
+    /// action is an error.
+    fn check_duplicates(tools: &[Spec], action: &str) -> Fallible<()> {
+        if action == "fetch" {
+            return Ok(());
+        }
+
+        let mut seen = HashSet::new();
+        match tools.iter().find(|tool| !seen.insert(tool.name())) {
+            Some(duplicate) => Err(ErrorKind::DuplicateToolSpec {
+                action: action.to_string(),
+                tool: duplicate.name().to_string(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+
     /// Compare `Spec`s for sorting when converting from strings
     ///
     /// We want to preserve the original order as much as possible, so we treat tools in
@@ -172,6 +210,8 @@ mod tests {
         use std::str::FromStr as _;
 
         use super::super::super::Spec;
+        use crate::error::ErrorKind;
+        use crate::style::tool_version;
         use crate::version::{VersionSpec, VersionTag};
 
         const LTS: &str = "lts";
@@ -226,6 +266,25 @@ mod tests {
             );
         }
 
+        #[test]
+        fn parses_node_with_local_archive() {
+            let expected = Spec::Node(VersionSpec::Local(
+                "/tmp/node-v20.5.1-linux-x64.tar.gz".into(),
+            ));
+
+            assert_eq!(
+                Spec::try_from_str("node@file:/tmp/node-v20.5.1-linux-x64.tar.gz")
+                    .expect("succeeds"),
+                expected
+            );
+
+            assert_eq!(
+                Spec::try_from_str("node@file:///tmp/node-v20.5.1-linux-x64.tar.gz")
+                    .expect("succeeds"),
+                expected
+            );
+        }
+
         #[test]
         fn parses_bare_yarn() {
             assert_eq!(
@@ -370,6 +429,67 @@ mod tests {
                 )
             );
         }
+
+        #[test]
+        fn parses_packages_with_github_source() {
+            let source = "github:user/repo#v1.2.3";
+
+            assert_eq!(
+                Spec::try_from_str(&versioned_tool!("tool", source)).expect("succeeds"),
+                Spec::Package("tool".into(), VersionSpec::Git(source.into()))
+            );
+
+            assert_eq!(
+                Spec::try_from_str(&versioned_tool!("@something/awesome", source))
+                    .expect("succeeds"),
+                Spec::Package("@something/awesome".into(), VersionSpec::Git(source.into()))
+            );
+        }
+
+        #[test]
+        fn parses_packages_with_git_ssh_source() {
+            let source = "git+ssh://git@github.com:user/repo.git#main";
+
+            let spec = Spec::try_from_str(&versioned_tool!("tool", source)).expect("succeeds");
+            assert_eq!(
+                spec,
+                Spec::Package("tool".into(), VersionSpec::Git(source.into()))
+            );
+
+            // The source is passed through to the package manager unchanged
+            if let Spec::Package(name, version) = spec {
+                assert_eq!(
+                    tool_version(name, version),
+                    "tool@git+ssh://git@github.com:user/repo.git#main"
+                );
+            }
+        }
+
+        #[test]
+        fn rejects_names_with_control_characters() {
+            for name in ["type\tscript", "ember\u{1}cli", " typescript"] {
+                let err = Spec::try_from_str(&versioned_tool!(name, MAJOR))
+                    .expect_err("control characters and surrounding whitespace are invalid");
+
+                assert_eq!(
+                    err.kind(),
+                    &ErrorKind::InvalidToolNameCharacters { name: name.into() }
+                );
+            }
+        }
+
+        #[test]
+        fn rejects_git_sources_for_tools() {
+            for tool in ["node", "npm", "pnpm", "yarn"] {
+                let err = Spec::try_from_str(&versioned_tool!(tool, "github:user/repo"))
+                    .expect_err("git sources are only supported for packages");
+
+                assert_eq!(
+                    err.kind(),
+                    &ErrorKind::GitSourceNotSupported { tool: tool.into() }
+                );
+            }
+        }
     }
 
     mod from_strings {
@@ -377,6 +497,8 @@ mod tests {
         use std::str::FromStr;
 
         static PIN: &str = "pin";
+        static INSTALL: &str = "install";
+        static FETCH: &str = "fetch";
 
         #[test]
         fn special_cases_just_number() {
@@ -508,5 +630,40 @@ mod tests {
                 expected
             );
         }
+
+        #[test]
+        fn rejects_duplicate_tools_when_installing() {
+            let args = ["node@18", "yarn@1", "node@20"];
+            let err = Spec::from_strings(&args, INSTALL).unwrap_err();
+            assert_eq!(
+                err.kind(),
+                &ErrorKind::DuplicateToolSpec {
+                    action: "install".into(),
+                    tool: "Node".into(),
+                }
+            );
+
+            let packages = ["typescript@4", "typescript@5"];
+            let err = Spec::from_strings(&packages, PIN).unwrap_err();
+            assert_eq!(
+                err.kind(),
+                &ErrorKind::DuplicateToolSpec {
+                    action: "pin".into(),
+                    tool: "typescript".into(),
+                }
+            );
+        }
+
+        #[test]
+        fn allows_duplicate_tools_when_fetching() {
+            let args = ["node@18", "yarn@1", "node@20"];
+            let expected = [
+                Spec::Node(VersionSpec::from_str("18").expect("requirement is valid")),
+                Spec::Node(VersionSpec::from_str("20").expect("requirement is valid")),
+                Spec::Yarn(VersionSpec::from_str("1").expect("requirement is valid")),
+            ];
+
+            assert_eq!(Spec::from_strings(&args, FETCH).expect("is ok"), expected);
+        }
     }
 }
@@ -3,7 +3,8 @@
 use std::fs::File;
 use std::path::Path;
 
-use super::super::download_tool_error;
+use super::super::integrity::{self, Integrity};
+use super::super::provenance::{record_provenance, Provenance};
 use super::super::registry::{
     find_unpack_dir, public_registry_package, scoped_public_registry_package,
 };
@@ -11,15 +12,16 @@ use crate::error::{Context, ErrorKind, Fallible};
 use crate::fs::{create_staging_dir, create_staging_file, rename, set_executable};
 use crate::hook::YarnHooks;
 use crate::layout::volta_home;
+use crate::profile::{time, Phase};
 use crate::style::{progress_bar, tool_version};
-use crate::tool::{self, Yarn};
+use crate::tool::{self, FetchOutcome, PlanSource, Yarn};
 use crate::version::VersionSpec;
 use archive::{Archive, Tarball};
 use fs_utils::ensure_containing_dir_exists;
 use log::{debug, info};
 use node_semver::Version;
 
-pub fn fetch(version: &Version, hooks: Option<&YarnHooks>) -> Fallible<()> {
+pub fn fetch(version: &Version, hooks: Option<&YarnHooks>) -> Fallible<FetchOutcome> {
     let yarn_dir = volta_home()?.yarn_inventory_dir();
     let cache_file = yarn_dir.join(Yarn::archive_filename(&version.to_string()));
 
@@ -35,27 +37,43 @@ pub fn fetch(version: &Version, hooks: Option<&YarnHooks>) -> Fallible<()> {
         None => {
             let staging = create_staging_file()?;
             let remote_url = determine_remote_url(version, hooks)?;
-            let archive = fetch_remote_distro(version, &remote_url, staging.path())?;
-            (archive, Some(staging))
+            let (archive, checksum) =
+                fetch_remote_distro(version, hooks, &remote_url, staging.path())?;
+            let provenance = Provenance::new(remote_url, checksum);
+            (archive, Some((staging, provenance)))
         }
     };
 
-    unpack_archive(archive, version)?;
+    let bytes = archive.compressed_size();
+    time(Phase::Unpack, || unpack_archive(archive, version))?;
 
-    if let Some(staging_file) = staging {
-        ensure_containing_dir_exists(&cache_file).with_context(|| {
-            ErrorKind::ContainingDirError {
-                path: cache_file.clone(),
-            }
-        })?;
-        staging_file
-            .persist(cache_file)
-            .with_context(|| ErrorKind::PersistInventoryError {
-                tool: "Yarn".into(),
+    let outcome = match staging {
+        Some(_) => FetchOutcome::Network { bytes },
+        None => FetchOutcome::Cache,
+    };
+
+    let image_dir = volta_home()?.yarn_image_dir(&version.to_string());
+    if let Some((staging_file, provenance)) = staging {
+        record_provenance(Ok(provenance), &image_dir);
+
+        if tool::cache_archives() {
+            ensure_containing_dir_exists(&cache_file).with_context(|| {
+                ErrorKind::ContainingDirError {
+                    path: cache_file.clone(),
+                }
             })?;
+            staging_file
+                .persist(cache_file)
+                .with_context(|| ErrorKind::PersistInventoryError {
+                    tool: "Yarn".into(),
+                })?;
+        }
+    } else {
+        let source = cache_file.display().to_string();
+        record_provenance(Provenance::of_archive(&cache_file, source), &image_dir);
     }
 
-    Ok(())
+    Ok(outcome)
 }
 
 /// Unpack the yarn archive into the image directory so that it is ready for use
@@ -113,6 +131,18 @@ fn load_cached_distro(file: &Path) -> Option<Box<dyn Archive>> {
     }
 }
 
+/// Determine where the archive for a Yarn version will come from, without fetching it
+pub(super) fn plan_source(version: &Version, hooks: Option<&YarnHooks>) -> Fallible<PlanSource> {
+    let cache_file = volta_home()?
+        .yarn_inventory_dir()
+        .join(Yarn::archive_filename(&version.to_string()));
+    if cache_file.is_file() {
+        Ok(PlanSource::Cache(cache_file))
+    } else {
+        determine_remote_url(version, hooks).map(|url| PlanSource::Download(vec![url]))
+    }
+}
+
 /// Determine the remote URL to download from, using the hooks if available
 fn determine_remote_url(version: &Version, hooks: Option<&YarnHooks>) -> Fallible<String> {
     let version_str = version.to_string();
@@ -139,20 +169,64 @@ fn determine_remote_url(version: &Version, hooks: Option<&YarnHooks>) -> Fallibl
     }
 }
 
-/// Fetch the distro archive from the internet
+/// Look up the integrity of the archive, if it is downloaded from the public registry
+fn published_integrity(
+    version: &Version,
+    hooks: Option<&YarnHooks>,
+) -> Fallible<Option<Integrity>> {
+    match hooks {
+        Some(&YarnHooks {
+            distro: Some(_), ..
+        }) => Ok(None),
+        _ => {
+            let package = if version.major >= 2 {
+                "@yarnpkg/cli-dist"
+            } else {
+                "yarn"
+            };
+            integrity::published_integrity(package, version)
+        }
+    }
+}
+
+/// Fetch the distro archive from the internet, verifying it before anything is unpacked
 fn fetch_remote_distro(
     version: &Version,
+    hooks: Option<&YarnHooks>,
     url: &str,
     staging_path: &Path,
-) -> Fallible<Box<dyn Archive>> {
+) -> Fallible<(Box<dyn Archive>, String)> {
     info!("Downloading {} from {}", tool_version("yarn", version), url);
-    Tarball::fetch(url, staging_path).with_context(download_tool_error(
+    integrity::download_verified(
         tool::Spec::Yarn(VersionSpec::Exact(version.clone())),
         url,
-    ))
+        staging_path,
+        || published_integrity(version, hooks),
+    )
 }
 
 fn ensure_bin_is_executable(unpack_dir: &Path, tool: &str) -> Fallible<()> {
     let exec_path = unpack_dir.join("bin").join(tool);
     set_executable(&exec_path).with_context(|| ErrorKind::SetToolExecutable { tool: tool.into() })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::registry::public_registry_index;
+
+    #[test]
+    fn test_determine_remote_url() {
+        assert_eq!(
+            determine_remote_url(&Version::from((4, 1, 0)), None).unwrap(),
+            format!(
+                "{}/-/cli-dist-4.1.0.tgz",
+                public_registry_index("@yarnpkg/cli-dist")
+            )
+        );
+        assert_eq!(
+            determine_remote_url(&Version::from((1, 22, 19)), None).unwrap(),
+            format!("{}/-/yarn-1.22.19.tgz", public_registry_index("yarn"))
+        );
+    }
+}
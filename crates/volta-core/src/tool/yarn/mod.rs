@@ -1,11 +1,15 @@
 use std::fmt::{self, Display};
+use std::time::Instant;
 
 use super::{
     check_fetched, check_shim_reachable, debug_already_fetched, info_fetched, info_installed,
-    info_pinned, info_project_version, FetchStatus, Tool,
+    info_pinned, not_activated, plan::planned_changes, warn_project_version, FetchOutcome,
+    FetchStatus, FetchSummary, PlanAction, PlanSource, Tool, ToolKindName, ToolPlan,
 };
 use crate::error::{ErrorKind, Fallible};
 use crate::inventory::yarn_available;
+use crate::layout::volta_home;
+use crate::profile::{time, Phase};
 use crate::session::Session;
 use crate::style::tool_version;
 use crate::sync::VoltaLock;
@@ -15,7 +19,7 @@ mod fetch;
 mod metadata;
 mod resolve;
 
-pub use resolve::resolve;
+pub use resolve::{available, resolve};
 
 /// The Tool implementation for fetching and installing Yarn
 pub struct Yarn {
@@ -35,40 +39,50 @@ impl Yarn {
         format!("{}.tar.gz", Yarn::archive_basename(version))
     }
 
-    pub(crate) fn ensure_fetched(&self, session: &mut Session) -> Fallible<()> {
+    pub(crate) fn ensure_fetched(&self, session: &mut Session) -> Fallible<FetchOutcome> {
         match check_fetched(|| yarn_available(&self.version))? {
             FetchStatus::AlreadyFetched => {
                 debug_already_fetched(self);
-                Ok(())
+                Ok(FetchOutcome::AlreadyInstalled)
+            }
+            FetchStatus::FetchNeeded(_lock) => {
+                let hooks = session.hooks()?.yarn();
+                time(Phase::Fetch, || fetch::fetch(&self.version, hooks))
             }
-            FetchStatus::FetchNeeded(_lock) => fetch::fetch(&self.version, session.hooks()?.yarn()),
         }
     }
 }
 
 impl Tool for Yarn {
-    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<()> {
-        self.ensure_fetched(session)?;
+    fn fetch(self: Box<Self>, session: &mut Session) -> Fallible<FetchOutcome> {
+        let outcome = self.ensure_fetched(session)?;
 
         info_fetched(self);
-        Ok(())
+        Ok(outcome)
     }
     fn install(self: Box<Self>, session: &mut Session) -> Fallible<()> {
         // Acquire a lock on the Volta directory, if possible, to prevent concurrent changes
         let _lock = VoltaLock::acquire();
-        self.ensure_fetched(session)?;
+        let started = Instant::now();
+        let outcome = self.ensure_fetched(session)?;
+        let fetched = FetchSummary::since(outcome, started);
 
         session
             .toolchain_mut()?
-            .set_active_yarn(Some(self.version.clone()))?;
+            .set_active_yarn(Some(self.version.clone()))
+            .map_err(not_activated(tool_version("yarn", &self.version)))?;
 
-        info_installed(&self);
+        info_installed(&self, fetched);
         check_shim_reachable("yarn");
 
-        if let Ok(Some(project)) = session.project_platform() {
-            if let Some(yarn) = &project.yarn {
-                info_project_version(tool_version("yarn", yarn), &self);
-            }
+        let project_version = session
+            .project_platform()
+            .ok()
+            .flatten()
+            .and_then(|project| project.yarn.as_ref())
+            .map(|yarn| tool_version("yarn", yarn));
+        if let Some(project_version) = project_version {
+            warn_project_version(session, project_version, &self);
         }
         Ok(())
     }
@@ -86,6 +100,23 @@ impl Tool for Yarn {
             Err(ErrorKind::NotInPackage.into())
         }
     }
+
+    fn kind(&self) -> ToolKindName {
+        ToolKindName::Yarn
+    }
+    fn describe_plan(&self, action: PlanAction, session: &Session) -> Fallible<ToolPlan> {
+        let mut plan = ToolPlan::new(self, action);
+        plan.source = Some(if yarn_available(&self.version)? {
+            PlanSource::AlreadyFetched
+        } else {
+            fetch::plan_source(&self.version, session.hooks()?.yarn())?
+        });
+        plan.image_dir = Some(volta_home()?.yarn_image_dir(&self.version.to_string()));
+        plan.changes = planned_changes("yarn", &self.version, action, session, |platform| {
+            platform.yarn.as_ref()
+        })?;
+        Ok(plan)
+    }
 }
 
 impl Display for Yarn {
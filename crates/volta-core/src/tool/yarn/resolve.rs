@@ -7,21 +7,81 @@ use super::super::registry_fetch_error;
 use super::metadata::{RawYarnIndex, YarnIndex};
 use crate::error::{Context, ErrorKind, Fallible};
 use crate::hook::{RegistryFormat, YarnHooks};
+use crate::inventory::yarn_available;
 use crate::session::Session;
 use crate::style::progress_spinner;
+use crate::tool::{fetched_exact_version, ToolKindName};
 use crate::version::{parse_version, VersionSpec, VersionTag};
+use archive::request::{self, RequestKind};
 use attohttpc::Response;
 use log::debug;
 use node_semver::{Range, Version};
 
+/// The npm package that distributes Yarn 2+ ("berry")
+const BERRY_PACKAGE: &str = "@yarnpkg/cli-dist";
+
 pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    session.resolve_cached(ToolKindName::Yarn, matching, resolve_uncached)
+}
+
+fn resolve_uncached(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    if let Some(version) = fetched_exact_version("yarn", &matching, yarn_available)? {
+        return Ok(version);
+    }
+
     let hooks = session.hooks()?.yarn();
     match matching {
         VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks),
-        VersionSpec::Exact(version) => Ok(version),
+        VersionSpec::Exact(version) => resolve_exact(version, hooks),
         VersionSpec::None => resolve_tag(VersionTag::Latest, hooks),
         VersionSpec::Tag(tag) => resolve_tag(tag, hooks),
+        VersionSpec::Local(_) => Err(ErrorKind::Unimplemented {
+            feature: "Installing Yarn from a local archive".into(),
+        }
+        .into()),
+        VersionSpec::Git(_) => Err(ErrorKind::GitSourceNotSupported {
+            tool: "yarn".into(),
+        }
+        .into()),
+    }
+}
+
+/// Lists the Yarn versions available from the registry (or the index hook, if configured)
+pub fn available(session: &mut Session) -> Fallible<Vec<Version>> {
+    if let Some(&YarnHooks {
+        index: Some(ref hook),
+        ..
+    }) = session.hooks()?.yarn()
+    {
+        debug!("Using yarn.index hook to determine yarn index URL");
+        let versions = match hook.format {
+            RegistryFormat::Github => fetch_legacy_index(hook.resolve("releases")?)?
+                .entries
+                .into_iter()
+                .collect(),
+            RegistryFormat::Npm => fetch_npm_registry(hook.resolve("")?, "Yarn")?
+                .1
+                .entries
+                .into_iter()
+                .map(|details| details.version)
+                .collect(),
+        };
+        return Ok(versions);
     }
+
+    // Yarn 2 is not supported, so only Yarn 3+ versions from @yarnpkg/cli-dist are included
+    let mut versions: Vec<Version> = fetch_berry_index()
+        .map(|(_, index)| index.entries)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|details| details.version)
+        .filter(|version| version.major >= 3)
+        .collect();
+
+    let (_, index) = fetch_yarn_index("yarn")?;
+    versions.extend(index.entries.into_iter().map(|details| details.version));
+
+    Ok(versions)
 }
 
 fn resolve_tag(tag: VersionTag, hooks: Option<&YarnHooks>) -> Fallible<Version> {
@@ -46,12 +106,48 @@ fn resolve_tag(tag: VersionTag, hooks: Option<&YarnHooks>) -> Fallible<Version>
         (VersionTag::Latest, _) => resolve_custom_tag(VersionTag::Latest.to_string()),
         (tag, Some(&YarnHooks { index: Some(_), .. })) => Err(ErrorKind::YarnVersionNotFound {
             matching: tag.to_string(),
+            registry_url: None,
         }
         .into()),
         (tag, _) => resolve_custom_tag(tag.to_string()),
     }
 }
 
+/// Checks that an exact version of Yarn 2+ is published as `@yarnpkg/cli-dist`
+///
+/// Classic versions, and any versions from a custom index or distro hook, are used as-is, since
+/// they are checked when they are downloaded. Checking first means that a version that was never
+/// published is reported as not found, rather than as a failed download.
+fn resolve_exact(version: Version, hooks: Option<&YarnHooks>) -> Fallible<Version> {
+    let custom = hooks.is_some_and(|hooks| hooks.index.is_some() || hooks.distro.is_some());
+    if version.major < 2 || custom {
+        return Ok(version);
+    }
+    if version.major == 2 {
+        return Err(ErrorKind::Yarn2NotSupported.into());
+    }
+
+    // If the registry can't be reached, the download will report the problem instead
+    let Ok((url, index)) = fetch_berry_index() else {
+        debug!("Could not check that yarn@{} is published", version);
+        return Ok(version);
+    };
+    let published = index
+        .entries
+        .iter()
+        .any(|details| details.version == version);
+    if published {
+        debug!("Found yarn@{} from {}", version, url);
+        Ok(version)
+    } else {
+        Err(ErrorKind::YarnVersionNotFound {
+            matching: version.to_string(),
+            registry_url: Some(url),
+        }
+        .into())
+    }
+}
+
 fn resolve_semver(matching: Range, hooks: Option<&YarnHooks>) -> Fallible<Version> {
     // For semver, the triage is less complicated: The previous behavior _always_ used
     // the 'index' hook, so we can check for that to decide which behavior to use.
@@ -78,20 +174,58 @@ fn fetch_yarn_index(package: &str) -> Fallible<(String, PackageIndex)> {
     fetch_npm_registry(url, "Yarn")
 }
 
+/// Fetches the registry metadata for `@yarnpkg/cli-dist`, which distributes Yarn 2+ ("berry")
+fn fetch_berry_index() -> Fallible<(String, PackageIndex)> {
+    fetch_yarn_index(BERRY_PACKAGE)
+}
+
+/// Whether a requirement can only be satisfied by Yarn 2+, so there's no need to consult the
+/// classic `yarn` package
+fn requires_berry(matching: &Range) -> bool {
+    let classic: Range = "<2".parse().expect("Static range is valid");
+    !matching.allows_any(&classic)
+}
+
+/// Finds the version of `@yarnpkg/cli-dist` published under a dist-tag
+fn find_berry_tag(mut index: PackageIndex, tag: &str) -> Fallible<Option<Version>> {
+    match index.tags.remove(tag) {
+        Some(version) if version.major == 2 => Err(ErrorKind::Yarn2NotSupported.into()),
+        found => Ok(found),
+    }
+}
+
+/// Finds the latest version of `@yarnpkg/cli-dist` that satisfies a requirement
+///
+/// Yarn 2 is not supported, so if only Yarn 2 versions satisfy the requirement, this is an error.
+fn find_berry_semver(index: PackageIndex, matching: &Range) -> Fallible<Option<Version>> {
+    let mut matching_versions = index
+        .entries
+        .into_iter()
+        .map(|PackageDetails { version, .. }| version)
+        .filter(|version| matching.satisfies(version))
+        .peekable();
+
+    if matching_versions.peek().is_none() {
+        return Ok(None);
+    }
+
+    matching_versions
+        .find(|version| version.major >= 3)
+        .map(Some)
+        .ok_or_else(|| ErrorKind::Yarn2NotSupported.into())
+}
+
 fn resolve_custom_tag(tag: String) -> Fallible<Version> {
     // first try yarn2+, which uses "@yarnpkg/cli-dist" instead of "yarn"
-    if let Ok((url, mut index)) = fetch_yarn_index("@yarnpkg/cli-dist") {
-        if let Some(version) = index.tags.remove(&tag) {
+    if let Ok((url, index)) = fetch_berry_index() {
+        if let Some(version) = find_berry_tag(index, &tag)? {
             debug!("Found yarn@{} matching tag '{}' from {}", version, tag, url);
-            if version.major == 2 {
-                return Err(ErrorKind::Yarn2NotSupported.into());
-            }
             return Ok(version);
         }
     }
     debug!(
-        "Did not find yarn matching tag '{}' from @yarnpkg/cli-dist",
-        tag
+        "Did not find yarn matching tag '{}' from {}",
+        tag, BERRY_PACKAGE
     );
 
     let (url, mut index) = fetch_yarn_index("yarn")?;
@@ -100,12 +234,16 @@ fn resolve_custom_tag(tag: String) -> Fallible<Version> {
             debug!("Found yarn@{} matching tag '{}' from {}", version, tag, url);
             Ok(version)
         }
-        None => Err(ErrorKind::YarnVersionNotFound { matching: tag }.into()),
+        None => Err(ErrorKind::YarnVersionNotFound {
+            matching: tag,
+            registry_url: Some(url),
+        }
+        .into()),
     }
 }
 
 fn resolve_latest_legacy(url: String) -> Fallible<Version> {
-    let response_text = attohttpc::get(&url)
+    let response_text = request::get(&url, RequestKind::Metadata)
         .send()
         .and_then(Response::error_for_status)
         .and_then(Response::text)
@@ -118,36 +256,39 @@ fn resolve_latest_legacy(url: String) -> Fallible<Version> {
 }
 
 fn resolve_semver_from_registry(matching: Range) -> Fallible<Version> {
-    // first try yarn2+, which uses "@yarnpkg/cli-dist" instead of "yarn"
-    if let Ok((url, index)) = fetch_yarn_index("@yarnpkg/cli-dist") {
-        let matching_entries: Vec<PackageDetails> = index
-            .entries
-            .into_iter()
-            .filter(|PackageDetails { version, .. }| matching.satisfies(version))
-            .collect();
-
-        if !matching_entries.is_empty() {
-            let details_opt = matching_entries
-                .iter()
-                .find(|PackageDetails { version, .. }| version.major >= 3);
-
-            match details_opt {
-                Some(details) => {
-                    debug!(
-                        "Found yarn@{} matching requirement '{}' from {}",
-                        details.version, matching, url
-                    );
-                    return Ok(details.version.clone());
-                }
-                None => {
-                    return Err(ErrorKind::Yarn2NotSupported.into());
-                }
+    // Requirements that only Yarn 2+ can satisfy are resolved solely from "@yarnpkg/cli-dist",
+    // so that failing to reach it isn't hidden behind a lookup in the classic index
+    if requires_berry(&matching) {
+        let (url, index) = fetch_berry_index()?;
+        return match find_berry_semver(index, &matching)? {
+            Some(version) => {
+                debug!(
+                    "Found yarn@{} matching requirement '{}' from {}",
+                    version, matching, url
+                );
+                Ok(version)
             }
+            None => Err(ErrorKind::YarnVersionNotFound {
+                matching: matching.to_string(),
+                registry_url: Some(url),
+            }
+            .into()),
+        };
+    }
+
+    // first try yarn2+, which uses "@yarnpkg/cli-dist" instead of "yarn"
+    if let Ok((url, index)) = fetch_berry_index() {
+        if let Some(version) = find_berry_semver(index, &matching)? {
+            debug!(
+                "Found yarn@{} matching requirement '{}' from {}",
+                version, matching, url
+            );
+            return Ok(version);
         }
     }
     debug!(
-        "Did not find yarn matching requirement '{}' for @yarnpkg/cli-dist",
-        matching
+        "Did not find yarn matching requirement '{}' for {}",
+        matching, BERRY_PACKAGE
     );
 
     let (url, index) = fetch_yarn_index("yarn")?;
@@ -168,21 +309,25 @@ fn resolve_semver_from_registry(matching: Range) -> Fallible<Version> {
         // at this point Yarn is not found in either registry
         None => Err(ErrorKind::YarnVersionNotFound {
             matching: matching.to_string(),
+            registry_url: Some(url),
         }
         .into()),
     }
 }
 
-fn resolve_semver_legacy(matching: Range, url: String) -> Fallible<Version> {
+fn fetch_legacy_index(url: String) -> Fallible<YarnIndex> {
     let spinner = progress_spinner(format!("Fetching registry: {}", url));
-    let releases: RawYarnIndex = attohttpc::get(&url)
+    let releases: RawYarnIndex = request::get(&url, RequestKind::Metadata)
         .send()
         .and_then(Response::error_for_status)
         .and_then(Response::json)
         .with_context(registry_fetch_error("Yarn", &url))?;
-    let index = YarnIndex::from(releases);
-    let releases = index.entries;
     spinner.finish_and_clear();
+    Ok(YarnIndex::from(releases))
+}
+
+fn resolve_semver_legacy(matching: Range, url: String) -> Fallible<Version> {
+    let releases = fetch_legacy_index(url.clone())?.entries;
     let version_opt = releases.into_iter().rev().find(|v| matching.satisfies(v));
 
     match version_opt {
@@ -195,6 +340,7 @@ fn resolve_semver_legacy(matching: Range, url: String) -> Fallible<Version> {
         }
         None => Err(ErrorKind::YarnVersionNotFound {
             matching: matching.to_string(),
+            registry_url: Some(url),
         }
         .into()),
     }
@@ -218,7 +364,164 @@ fn resolve_semver_npm(matching: Range, url: String) -> Fallible<Version> {
         }
         None => Err(ErrorKind::YarnVersionNotFound {
             matching: matching.to_string(),
+            registry_url: Some(url),
         }
         .into()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{override_volta_home, volta_home};
+    use crate::tool::registry::RawPackageMetadata;
+
+    /// A trimmed registry document for `@yarnpkg/cli-dist`
+    const BERRY_METADATA: &str = r#"{
+        "name": "@yarnpkg/cli-dist",
+        "dist-tags": { "latest": "4.1.0", "canary": "4.2.0-rc.1", "berry": "2.4.3" },
+        "versions": {
+            "2.4.3": { "version": "2.4.3", "dist": { "tarball": "" } },
+            "3.6.4": { "version": "3.6.4", "dist": { "tarball": "" } },
+            "4.0.2": { "version": "4.0.2", "dist": { "tarball": "" } },
+            "4.1.0": { "version": "4.1.0", "dist": { "tarball": "" } },
+            "4.2.0-rc.1": { "version": "4.2.0-rc.1", "dist": { "tarball": "" } }
+        }
+    }"#;
+
+    fn berry_index() -> PackageIndex {
+        serde_json::from_str::<RawPackageMetadata>(BERRY_METADATA)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_requires_berry() {
+        assert!(requires_berry(&"^4".parse().unwrap()));
+        assert!(requires_berry(&">=3".parse().unwrap()));
+        assert!(!requires_berry(&"^1.22".parse().unwrap()));
+        assert!(!requires_berry(&"*".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_find_berry_tag() {
+        assert_eq!(
+            find_berry_tag(berry_index(), "latest").unwrap(),
+            Some(Version::parse("4.1.0").unwrap())
+        );
+        assert_eq!(find_berry_tag(berry_index(), "nightly").unwrap(), None);
+        assert_eq!(
+            find_berry_tag(berry_index(), "berry").unwrap_err().kind(),
+            &ErrorKind::Yarn2NotSupported
+        );
+    }
+
+    #[test]
+    fn test_find_berry_semver() {
+        assert_eq!(
+            find_berry_semver(berry_index(), &"^4".parse().unwrap()).unwrap(),
+            Some(Version::parse("4.1.0").unwrap())
+        );
+        assert_eq!(
+            find_berry_semver(berry_index(), &"^3".parse().unwrap()).unwrap(),
+            Some(Version::parse("3.6.4").unwrap())
+        );
+        assert_eq!(
+            find_berry_semver(berry_index(), &"^1".parse().unwrap()).unwrap(),
+            None
+        );
+        assert_eq!(
+            find_berry_semver(berry_index(), &"^2".parse().unwrap())
+                .unwrap_err()
+                .kind(),
+            &ErrorKind::Yarn2NotSupported
+        );
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[test]
+    fn test_resolve_berry_from_registry() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = crate::layout::override_volta_home(home.path().to_owned());
+        let _mock = mockito::mock("GET", "/@yarnpkg/cli-dist")
+            .with_status(200)
+            .with_header("Content-Type", "application/json")
+            .with_body(BERRY_METADATA)
+            .create();
+
+        assert_eq!(
+            resolve_semver_from_registry("^4".parse().unwrap()).unwrap(),
+            Version::parse("4.1.0").unwrap()
+        );
+        assert_eq!(
+            resolve_custom_tag("latest".into()).unwrap(),
+            Version::parse("4.1.0").unwrap()
+        );
+        assert_eq!(
+            resolve_semver_from_registry("^5".parse().unwrap())
+                .unwrap_err()
+                .kind(),
+            &ErrorKind::YarnVersionNotFound {
+                matching: "^5".parse::<Range>().unwrap().to_string(),
+                registry_url: Some(public_registry_index(BERRY_PACKAGE)),
+            }
+        );
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[test]
+    fn test_resolve_berry_tag_and_exact() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let _mock = mockito::mock("GET", "/@yarnpkg/cli-dist")
+            .with_status(200)
+            .with_header("Content-Type", "application/json")
+            .with_body(BERRY_METADATA)
+            .create();
+        let mut session = Session::init_unchecked();
+
+        let canary: VersionSpec = "canary".parse().unwrap();
+        assert_eq!(
+            resolve(canary, &mut session).unwrap(),
+            Version::parse("4.2.0-rc.1").unwrap()
+        );
+        assert_eq!(
+            resolve(VersionSpec::Exact(Version::from((4, 0, 2))), &mut session).unwrap(),
+            Version::from((4, 0, 2))
+        );
+        assert_eq!(
+            resolve(VersionSpec::Exact(Version::from((4, 0, 3))), &mut session)
+                .unwrap_err()
+                .kind(),
+            &ErrorKind::YarnVersionNotFound {
+                matching: "4.0.3".into(),
+                registry_url: Some(public_registry_index(BERRY_PACKAGE)),
+            }
+        );
+        assert_eq!(
+            resolve(VersionSpec::Exact(Version::from((2, 4, 3))), &mut session)
+                .unwrap_err()
+                .kind(),
+            &ErrorKind::Yarn2NotSupported
+        );
+    }
+
+    #[test]
+    fn test_exact_version_in_inventory() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let home = volta_home().unwrap();
+        let cached = Version::from((1, 22, 19));
+        std::fs::create_dir_all(home.yarn_image_dir(&cached.to_string())).unwrap();
+        // Unreadable hooks make any lookup in the registry fail
+        std::fs::write(home.default_hooks_file(), "not hooks").unwrap();
+        let mut session = Session::init_unchecked();
+
+        assert_eq!(
+            resolve(VersionSpec::Exact(cached.clone()), &mut session).unwrap(),
+            cached
+        );
+        let missing = Version::from((1, 22, 20));
+        assert!(resolve(VersionSpec::Exact(missing), &mut session).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Provides a portable description of the default toolchain, so that it can be reproduced on
+//! another machine
+
+use super::serial::Platform;
+use crate::error::{Context, ErrorKind, Fallible};
+use crate::inventory::package_configs;
+use crate::session::Session;
+use crate::tool::package::PackageManager;
+use crate::version::version_serde;
+use node_semver::Version;
+use serde::{Deserialize, Serialize};
+
+/// The default platform and global packages of a toolchain
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ToolchainExport {
+    /// The default platform, in the same format as the default platform file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platform>,
+    /// The globally installed packages
+    #[serde(default)]
+    pub packages: Vec<ExportedPackage>,
+}
+
+/// A globally installed package, as recorded in an export
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ExportedPackage {
+    pub name: String,
+    #[serde(with = "version_serde")]
+    pub version: Version,
+    /// The package manager that was used to install the package
+    pub manager: PackageManager,
+}
+
+impl ToolchainExport {
+    /// Parse an export from a JSON document
+    pub fn from_json(src: &str) -> Fallible<Self> {
+        serde_json::from_str(src).with_context(|| ErrorKind::ParseToolchainExportError)
+    }
+
+    /// Serialize the export to a JSON document
+    pub fn to_json(&self) -> Fallible<String> {
+        serde_json::to_string_pretty(self).with_context(|| ErrorKind::StringifyToolchainExportError)
+    }
+}
+
+/// Describes the default platform and the globally installed packages
+pub fn export_toolchain(session: &Session) -> Fallible<ToolchainExport> {
+    let platform = session.default_platform()?.map(Platform::of);
+    let packages = package_configs()?
+        .into_iter()
+        .map(|config| ExportedPackage {
+            name: config.name,
+            version: config.version,
+            manager: config.manager,
+        })
+        .collect();
+
+    Ok(ToolchainExport { platform, packages })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{override_volta_home, volta_home};
+    use crate::toolchain::serial::NodeVersion;
+    use std::fs;
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    fn example() -> ToolchainExport {
+        ToolchainExport {
+            platform: Some(Platform {
+                node: Some(NodeVersion {
+                    runtime: version("20.11.1").into(),
+                    npm: Some(version("10.4.0")),
+                }),
+                pnpm: None,
+                yarn: Some(version("1.22.19")),
+            }),
+            packages: vec![
+                ExportedPackage {
+                    name: "@vue/cli".into(),
+                    version: version("5.0.8"),
+                    manager: PackageManager::Yarn,
+                },
+                ExportedPackage {
+                    name: "typescript".into(),
+                    version: version("5.3.3"),
+                    manager: PackageManager::Npm,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let export = example();
+        let json = export.to_json().unwrap();
+
+        assert_eq!(ToolchainExport::from_json(&json).unwrap(), export);
+    }
+
+    #[test]
+    fn test_format() {
+        let json: serde_json::Value = serde_json::from_str(&example().to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "platform": {
+                    "node": { "runtime": "20.11.1", "npm": "10.4.0" },
+                    "pnpm": null,
+                    "yarn": "1.22.19"
+                },
+                "packages": [
+                    { "name": "@vue/cli", "version": "5.0.8", "manager": "Yarn" },
+                    { "name": "typescript", "version": "5.3.3", "manager": "Npm" }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty_document() {
+        let export = ToolchainExport::from_json("{}").unwrap();
+        assert_eq!(export.platform, None);
+        assert!(export.packages.is_empty());
+
+        assert_eq!(export.to_json().unwrap(), "{\n  \"packages\": []\n}");
+    }
+
+    #[test]
+    fn test_invalid_document() {
+        let error = ToolchainExport::from_json(r#"{ "packages": [{ "name": "typescript" }] }"#)
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::ParseToolchainExportError);
+    }
+
+    #[test]
+    fn test_export_toolchain() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let home = volta_home().unwrap();
+        fs::create_dir_all(home.default_package_dir()).unwrap();
+        fs::write(
+            home.default_platform_file(),
+            r#"{"node":{"runtime":"20.11.1","npm":null},"yarn":"1.22.19"}"#,
+        )
+        .unwrap();
+        fs::write(
+            home.default_package_dir().join("typescript.json"),
+            r#"{
+                "name": "typescript",
+                "version": "5.3.3",
+                "platform": { "node": "20.11.1", "npm": null, "yarn": null },
+                "bins": ["tsc", "tsserver"],
+                "manager": "Npm"
+            }"#,
+        )
+        .unwrap();
+
+        let export = export_toolchain(&Session::init_unchecked()).unwrap();
+
+        assert_eq!(
+            export.platform,
+            Some(Platform {
+                node: Some(NodeVersion {
+                    runtime: version("20.11.1").into(),
+                    npm: None,
+                }),
+                pnpm: None,
+                yarn: Some(version("1.22.19")),
+            })
+        );
+        assert_eq!(
+            export.packages,
+            vec![ExportedPackage {
+                name: "typescript".into(),
+                version: version("5.3.3"),
+                manager: PackageManager::Npm,
+            }]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Provides installation of an exported toolchain, to reproduce it on this machine
+
+use super::serial::Platform;
+use super::{ExportedPackage, ToolchainExport};
+use crate::error::{Fallible, VoltaError};
+use crate::platform::{FieldChange, PlatformNode, SYSTEM_NODE};
+use crate::session::Session;
+use crate::style::tool_version;
+use crate::tool::package::install_with_manager;
+use crate::tool::Spec;
+use crate::version::{VersionSpec, VersionTag};
+use log::debug;
+use node_semver::Version;
+
+/// The outcome of importing a toolchain
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// The tools and packages that were installed, e.g. `node@20.11.1`
+    pub installed: Vec<String>,
+    /// The packages that could not be installed, along with the reason
+    pub failed: Vec<(String, VoltaError)>,
+    /// How the default platform changed, if there was a default platform before the import
+    pub changes: Vec<FieldChange>,
+}
+
+impl ImportReport {
+    /// Whether everything in the export was installed
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Installs the default platform and global packages described by an export
+///
+/// The platform is installed first and its tools become the defaults, since the packages are
+/// installed with them. A tool that can't be installed stops the import, but a package that
+/// can't be installed is recorded in the report and the remaining packages are still installed.
+///
+/// Each package is installed with the package manager that installed it originally.
+pub fn import_toolchain(export: &ToolchainExport, session: &mut Session) -> Fallible<ImportReport> {
+    import_with(
+        export,
+        session,
+        |spec, session| spec.resolve(session)?.install(session),
+        |package, session| {
+            install_with_manager(&package.name, &package.version, package.manager, session)
+        },
+    )
+}
+
+fn import_with<F, P>(
+    export: &ToolchainExport,
+    session: &mut Session,
+    mut install: F,
+    mut install_package: P,
+) -> Fallible<ImportReport>
+where
+    F: FnMut(Spec, &mut Session) -> Fallible<()>,
+    P: FnMut(&ExportedPackage, &mut Session) -> Fallible<()>,
+{
+    let mut report = ImportReport::default();
+
+    if let Some(platform) = &export.platform {
+        import_platform(platform, session, &mut install, &mut report)?;
+    }
+
+    for package in &export.packages {
+        let name = tool_version(&package.name, &package.version);
+
+        match install_package(package, session) {
+            Ok(()) => report.installed.push(name),
+            Err(error) => {
+                debug!("Could not install {} from the export: {}", name, error);
+                report.failed.push((name, error));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Installs the tools in the exported platform, making the default platform match it
+fn import_platform<F>(
+    platform: &Platform,
+    session: &mut Session,
+    install: &mut F,
+    report: &mut ImportReport,
+) -> Fallible<()>
+where
+    F: FnMut(Spec, &mut Session) -> Fallible<()>,
+{
+    let Some(node) = &platform.node else {
+        return Ok(());
+    };
+    let exact = |version: &Option<Version>| version.clone().map(VersionSpec::Exact);
+    let previous = session.default_platform()?.cloned();
+
+    let tools = [
+        Some(Spec::Node(match &node.runtime {
+            PlatformNode::Version(version) => VersionSpec::Exact(version.clone()),
+            PlatformNode::System => VersionSpec::Tag(VersionTag::Custom(SYSTEM_NODE.into())),
+        })),
+        exact(&node.npm).map(Spec::Npm),
+        exact(&platform.pnpm).map(Spec::Pnpm),
+        exact(&platform.yarn).map(Spec::Yarn),
+    ];
+    for spec in tools.into_iter().flatten() {
+        let name = spec.to_string();
+        install(spec, session)?;
+        report.installed.push(name);
+    }
+
+    // Tools that aren't part of the exported platform shouldn't remain as defaults either
+    let toolchain = session.toolchain_mut()?;
+    if node.npm.is_none() {
+        toolchain.set_active_npm(None)?;
+    }
+    if platform.pnpm.is_none() {
+        toolchain.set_active_pnpm(None)?;
+    }
+    if platform.yarn.is_none() {
+        toolchain.set_active_yarn(None)?;
+    }
+
+    if let (Some(previous), Some(current)) = (previous, session.default_platform()?) {
+        report.changes = previous.diff(current);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::layout::{override_volta_home, volta_home};
+    use crate::tool::package::PackageManager;
+    use std::fs;
+
+    const EXPORT: &str = r#"{
+        "platform": {
+            "node": { "runtime": "20.11.1", "npm": null },
+            "yarn": "1.22.19"
+        },
+        "packages": [
+            { "name": "cowsay", "version": "1.5.0", "manager": "Npm" },
+            { "name": "missing-package", "version": "1.0.0", "manager": "Npm" },
+            { "name": "typescript", "version": "5.3.3", "manager": "Yarn" }
+        ]
+    }"#;
+
+    /// Stands in for the registries when installing tools
+    fn stub_install_tool(
+        installed: &mut Vec<String>,
+    ) -> impl FnMut(Spec, &mut Session) -> Fallible<()> + '_ {
+        move |spec, session| {
+            if let Spec::Node(VersionSpec::Exact(version)) = &spec {
+                session
+                    .toolchain_mut()?
+                    .set_active_node(&version.clone().into())?;
+            }
+            installed.push(spec.to_string());
+            Ok(())
+        }
+    }
+
+    /// Stands in for the registries when installing packages, failing to install `missing-package`
+    fn stub_install_package(
+        installed: &mut Vec<(String, PackageManager)>,
+    ) -> impl FnMut(&ExportedPackage, &mut Session) -> Fallible<()> + '_ {
+        move |package, _| {
+            if package.name == "missing-package" {
+                return Err(ErrorKind::PackageNotFound {
+                    package: package.name.clone(),
+                }
+                .into());
+            }
+            installed.push((
+                tool_version(&package.name, &package.version),
+                package.manager,
+            ));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_import_continues_after_package_failure() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+        let platform_file = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(platform_file.parent().unwrap()).unwrap();
+        fs::create_dir_all(volta_home().unwrap().tmp_dir()).unwrap();
+        // A pnpm default that isn't part of the export is removed
+        fs::write(
+            platform_file,
+            r#"{"node":{"runtime":"18.17.0","npm":"9.8.1"},"pnpm":"8.6.0"}"#,
+        )
+        .unwrap();
+
+        let export = ToolchainExport::from_json(EXPORT).unwrap();
+        let mut session = Session::init_unchecked();
+        let mut tools = Vec::new();
+        let mut packages = Vec::new();
+        let report = import_with(
+            &export,
+            &mut session,
+            stub_install_tool(&mut tools),
+            stub_install_package(&mut packages),
+        )
+        .unwrap();
+
+        assert_eq!(tools, ["node@20.11.1", "yarn@1.22.19"]);
+        // Each package is installed with the package manager recorded in the export
+        assert_eq!(
+            packages,
+            [
+                ("cowsay@1.5.0".to_string(), PackageManager::Npm),
+                ("typescript@5.3.3".to_string(), PackageManager::Yarn)
+            ]
+        );
+        assert_eq!(
+            report.installed,
+            [
+                "node@20.11.1",
+                "yarn@1.22.19",
+                "cowsay@1.5.0",
+                "typescript@5.3.3"
+            ]
+        );
+        assert!(!report.is_complete());
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "missing-package@1.0.0");
+
+        let platform = session.default_platform().unwrap().unwrap();
+        assert_eq!(platform.node, "20.11.1".parse().unwrap());
+        assert_eq!(platform.npm, None);
+        assert_eq!(platform.pnpm, None);
+
+        let changes: Vec<_> = report.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "changed node from 18.17.0 to 20.11.1",
+                "removed npm@9.8.1",
+                "removed pnpm@8.6.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_stops_when_platform_fails() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+
+        let export = ToolchainExport::from_json(EXPORT).unwrap();
+        let mut session = Session::init_unchecked();
+        let mut attempted = Vec::new();
+        let mut packages = Vec::new();
+        let result = import_with(
+            &export,
+            &mut session,
+            |spec, _| {
+                attempted.push(spec.to_string());
+                Err(ErrorKind::NodeVersionNotFound {
+                    matching: "20.11.1".into(),
+                    suggestions: Vec::new(),
+                    index_url: None,
+                }
+                .into())
+            },
+            stub_install_package(&mut packages),
+        );
+
+        assert!(result.is_err());
+        // Without the platform, none of the packages could be installed
+        assert_eq!(attempted, ["node@20.11.1"]);
+        assert!(packages.is_empty());
+    }
+}
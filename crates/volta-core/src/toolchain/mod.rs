@@ -1,16 +1,23 @@
 use std::fs::write;
+use std::path::Path;
 
 use crate::error::{Context, ErrorKind, Fallible};
-use crate::fs::touch;
+use crate::fs::{create_staging_file, rename, touch};
 use crate::layout::volta_home;
-use crate::platform::PlatformSpec;
-use log::debug;
+use crate::platform::{PlatformNode, PlatformSpec};
+use crate::profile::{time, Phase};
+use log::{debug, warn};
 use node_semver::Version;
 use once_cell::unsync::OnceCell;
 use readext::ReadExt;
 
+mod export;
+mod import;
 pub mod serial;
 
+pub use export::{export_toolchain, ExportedPackage, ToolchainExport};
+pub use import::{import_toolchain, ImportReport};
+
 /// Lazily loaded toolchain
 pub struct LazyToolchain {
     toolchain: OnceCell<Toolchain>,
@@ -26,14 +33,41 @@ impl LazyToolchain {
 
     /// Forces loading of the toolchain and returns an immutable reference to it
     pub fn get(&self) -> Fallible<&Toolchain> {
-        self.toolchain.get_or_try_init(Toolchain::current)
+        self.toolchain
+            .get_or_try_init(|| time(Phase::LoadToolchain, Toolchain::current))
     }
 
     /// Forces loading of the toolchain and returns a mutable reference to it
     pub fn get_mut(&mut self) -> Fallible<&mut Toolchain> {
-        let _ = self.toolchain.get_or_try_init(Toolchain::current)?;
+        let _ = self
+            .toolchain
+            .get_or_try_init(|| time(Phase::LoadToolchain, Toolchain::current))?;
         Ok(self.toolchain.get_mut().unwrap())
     }
+
+    /// Returns whether the toolchain has already been loaded
+    pub fn is_loaded(&self) -> bool {
+        self.toolchain.get().is_some()
+    }
+
+    /// Discards the loaded toolchain, so that it is read again the next time it is needed
+    pub fn reset(&mut self) {
+        self.toolchain.take();
+    }
+}
+
+/// Moves a corrupt platform file aside, so that it can be recovered and a new default set
+fn backup_corrupt_file(path: &Path) {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".corrupt");
+
+    match rename(path, &backup) {
+        Ok(()) => warn!(
+            "The corrupt file was moved to '{}'.",
+            Path::new(&backup).display()
+        ),
+        Err(error) => debug!("Could not back up corrupt platform file: {}", error),
+    }
 }
 
 pub struct Toolchain {
@@ -49,7 +83,20 @@ impl Toolchain {
                 file: path.to_owned(),
             })?;
 
-        let platform: Option<PlatformSpec> = serial::Platform::try_from(src)?.into();
+        // A corrupt default platform shouldn't prevent every command from running, since command
+        // line overrides (e.g. `volta run --node`) don't depend on it
+        let platform: Option<PlatformSpec> = match serial::Platform::try_from(src) {
+            Ok(platform) => platform.into(),
+            Err(error) => {
+                warn!(
+                    "{}\n\nIgnoring the default platform in '{}'.",
+                    error,
+                    path.display()
+                );
+                backup_corrupt_file(path);
+                None
+            }
+        };
         if platform.is_some() {
             debug!("Found default configuration at '{}'", path.display());
         }
@@ -61,93 +108,182 @@ impl Toolchain {
     }
 
     /// Set the active Node version in the default platform file.
-    pub fn set_active_node(&mut self, node_version: &Version) -> Fallible<()> {
-        let mut dirty = false;
-
-        match self.platform.as_mut() {
-            Some(platform) => {
-                if platform.node != *node_version {
-                    platform.node = node_version.clone();
-                    dirty = true;
-                }
-            }
+    pub fn set_active_node(&mut self, node_version: &PlatformNode) -> Fallible<()> {
+        self.update(|platform| match platform {
+            Some(platform) => platform.node = node_version.clone(),
             None => {
-                self.platform = Some(PlatformSpec {
+                *platform = Some(PlatformSpec {
                     node: node_version.clone(),
                     npm: None,
                     pnpm: None,
                     yarn: None,
-                });
-                dirty = true;
+                })
             }
-        }
-
-        if dirty {
-            self.save()?;
-        }
-
-        Ok(())
+        })
     }
 
     /// Set the active Yarn version in the default platform file.
     pub fn set_active_yarn(&mut self, yarn: Option<Version>) -> Fallible<()> {
-        if let Some(platform) = self.platform.as_mut() {
-            if platform.yarn != yarn {
-                platform.yarn = yarn;
-                self.save()?;
-            }
-        } else if yarn.is_some() {
+        if self.platform.is_none() && yarn.is_some() {
             return Err(ErrorKind::NoDefaultNodeVersion {
                 tool: "Yarn".into(),
             }
             .into());
         }
 
-        Ok(())
+        self.update(|platform| {
+            if let Some(platform) = platform {
+                platform.yarn = yarn;
+            }
+        })
     }
 
     /// Set the active pnpm version in the default platform file.
     pub fn set_active_pnpm(&mut self, pnpm: Option<Version>) -> Fallible<()> {
-        if let Some(platform) = self.platform.as_mut() {
-            if platform.pnpm != pnpm {
-                platform.pnpm = pnpm;
-                self.save()?;
-            }
-        } else if pnpm.is_some() {
+        if self.platform.is_none() && pnpm.is_some() {
             return Err(ErrorKind::NoDefaultNodeVersion {
                 tool: "pnpm".into(),
             }
             .into());
         }
 
-        Ok(())
+        self.update(|platform| {
+            if let Some(platform) = platform {
+                platform.pnpm = pnpm;
+            }
+        })
     }
 
     /// Set the active Npm version in the default platform file.
     pub fn set_active_npm(&mut self, npm: Option<Version>) -> Fallible<()> {
-        if let Some(platform) = self.platform.as_mut() {
-            if platform.npm != npm {
+        if self.platform.is_none() && npm.is_some() {
+            return Err(ErrorKind::NoDefaultNodeVersion { tool: "npm".into() }.into());
+        }
+
+        self.update(|platform| {
+            if let Some(platform) = platform {
                 platform.npm = npm;
-                self.save()?;
             }
-        } else if npm.is_some() {
-            return Err(ErrorKind::NoDefaultNodeVersion { tool: "npm".into() }.into());
+        })
+    }
+
+    /// Applies a change to the default platform and saves it, if anything changed
+    ///
+    /// Saving is the commit point of the change: if the platform can't be saved, the previous
+    /// platform is restored, so the toolchain always matches the platform file.
+    fn update<F>(&mut self, change: F) -> Fallible<()>
+    where
+        F: FnOnce(&mut Option<PlatformSpec>),
+    {
+        let previous = self.platform.clone();
+        change(&mut self.platform);
+        if self.platform == previous {
+            return Ok(());
         }
 
-        Ok(())
+        self.save().map_err(|error| {
+            self.platform = previous;
+            error
+        })
     }
 
+    /// Writes the default platform file
+    ///
+    /// The platform is written to a staging file that is then renamed into place, so that an
+    /// interrupted write can never replace a good platform file with a partial one.
     pub fn save(&self) -> Fallible<()> {
         let path = volta_home()?.default_platform_file();
-        let result = match &self.platform {
-            Some(platform) => {
-                let src = serial::Platform::of(platform).into_json()?;
-                write(path, src)
-            }
-            None => write(path, "{}"),
+        let src = match &self.platform {
+            Some(platform) => serial::Platform::of(platform).into_json()?,
+            None => "{}".to_string(),
         };
-        result.with_context(|| ErrorKind::WritePlatformError {
-            file: path.to_owned(),
-        })
+
+        let staging = create_staging_file()?;
+        write(staging.path(), src)
+            .and_then(|_| rename(staging.path(), path))
+            .with_context(|| ErrorKind::WritePlatformError {
+                file: path.to_owned(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::override_volta_home;
+    use std::fs;
+
+    #[test]
+    fn test_corrupt_platform_is_ignored() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+
+        let path = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, r#"{ "node": { "runtime": "#).unwrap();
+
+        let toolchain = Toolchain::current().expect("corrupt platform is not an error");
+        assert!(toolchain.platform().is_none());
+
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".corrupt");
+        assert_eq!(
+            fs::read_to_string(backup).unwrap(),
+            r#"{ "node": { "runtime": "#
+        );
+    }
+    #[test]
+    fn test_save_replaces_platform() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+
+        let path = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::create_dir_all(volta_home().unwrap().tmp_dir()).unwrap();
+
+        let mut toolchain = Toolchain::current().unwrap();
+        toolchain
+            .set_active_node(&"20.11.1".parse().unwrap())
+            .unwrap();
+
+        let saved = Toolchain::current().unwrap();
+        assert_eq!(
+            saved.platform().map(|platform| platform.node.to_string()),
+            Some("20.11.1".into())
+        );
+        // The staging file was moved into place, rather than left behind
+        assert_eq!(
+            fs::read_dir(volta_home().unwrap().tmp_dir())
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_failed_save_preserves_platform() {
+        let home = tempfile::tempdir().unwrap();
+        let _home = override_volta_home(home.path().to_owned());
+
+        let path = volta_home().unwrap().default_platform_file();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let original = r#"{"node":{"runtime":"18.19.0","npm":null},"pnpm":null,"yarn":null}"#;
+        fs::write(path, original).unwrap();
+
+        // Without a tmp directory the staging file can't be created, so the save fails before
+        // the platform file is touched
+        let mut toolchain = Toolchain::current().unwrap();
+        assert!(toolchain
+            .set_active_node(&"20.11.1".parse().unwrap())
+            .is_err());
+
+        assert_eq!(fs::read_to_string(path).unwrap(), original);
+        // The change is also undone in memory, so it doesn't leak into later saves
+        assert_eq!(
+            toolchain
+                .platform()
+                .map(|platform| platform.node.to_string()),
+            Some("18.19.0".into())
+        );
     }
 }
@@ -1,13 +1,12 @@
 use crate::error::{Context, ErrorKind, Fallible, VoltaError};
-use crate::platform::PlatformSpec;
-use crate::version::{option_version_serde, version_serde};
+use crate::platform::{PlatformNode, PlatformSpec};
+use crate::version::option_version_serde;
 use node_semver::Version;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct NodeVersion {
-    #[serde(with = "version_serde")]
-    pub runtime: Version,
+    pub runtime: PlatformNode,
     #[serde(with = "option_version_serde")]
     pub npm: Option<Version>,
 }
@@ -95,7 +94,9 @@ pub mod tests {
             pnpm: Some(Version::parse("3.2.1").expect("could not parse version")),
             yarn: Some(Version::parse("1.2.3").expect("could not parse version")),
             node: Some(NodeVersion {
-                runtime: Version::parse("4.5.6").expect("could not parse version"),
+                runtime: Version::parse("4.5.6")
+                    .expect("could not parse version")
+                    .into(),
                 npm: Some(Version::parse("7.8.9").expect("could not parse version")),
             }),
         };
@@ -119,7 +120,9 @@ pub mod tests {
         let platform_spec = platform::PlatformSpec {
             pnpm: Some(Version::parse("3.2.1").expect("could not parse version")),
             yarn: Some(Version::parse("1.2.3").expect("could not parse version")),
-            node: Version::parse("4.5.6").expect("could not parse version"),
+            node: Version::parse("4.5.6")
+                .expect("could not parse version")
+                .into(),
             npm: Some(Version::parse("7.8.9").expect("could not parse version")),
         };
         let json_str = Platform::of(&platform_spec)
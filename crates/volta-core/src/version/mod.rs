@@ -1,12 +1,14 @@
 use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use crate::error::{Context, ErrorKind, Fallible, VoltaError};
 use node_semver::{Range, Version};
+use url::Url;
 
 mod serial;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum VersionSpec {
     /// No version specified (default)
@@ -21,9 +23,15 @@ pub enum VersionSpec {
 
     /// Arbitrary Version Tag
     Tag(VersionTag),
+
+    /// Path to a local archive, specified with a `file:` prefix
+    Local(PathBuf),
+
+    /// A git repository, like `github:user/repo#ref` or `git+https://host/repo.git`
+    Git(String),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum VersionTag {
     /// The 'latest' tag, a special case that exists for all packages
@@ -43,6 +51,8 @@ impl fmt::Display for VersionSpec {
             VersionSpec::Semver(req) => req.fmt(f),
             VersionSpec::Exact(version) => version.fmt(f),
             VersionSpec::Tag(tag) => tag.fmt(f),
+            VersionSpec::Local(path) => write!(f, "file:{}", path.display()),
+            VersionSpec::Git(source) => source.fmt(f),
         }
     }
 }
@@ -57,11 +67,22 @@ impl fmt::Display for VersionTag {
     }
 }
 
+/// Parses a version specifier
+///
+/// A full version like `18.0.0` is an exact version, while a partial version like `18` or `18.0`
+/// is a range (`18.x` or `18.0.x`), which resolves to the highest matching version. Surrounding
+/// whitespace and a leading `v` are ignored, so `v18.0.0` is the same exact version as `18.0.0`.
 impl FromStr for VersionSpec {
     type Err = VoltaError;
 
     fn from_str(s: &str) -> Fallible<Self> {
-        if let Ok(version) = parse_version(s) {
+        if s.starts_with("file:") {
+            parse_local_path(s)
+                .map(VersionSpec::Local)
+                .ok_or_else(|| ErrorKind::VersionParseError { version: s.into() }.into())
+        } else if is_git_source(s) {
+            Ok(VersionSpec::Git(s.into()))
+        } else if let Ok(version) = parse_version(s) {
             Ok(VersionSpec::Exact(version))
         } else if let Ok(req) = parse_requirements(s) {
             Ok(VersionSpec::Semver(req))
@@ -93,10 +114,45 @@ pub fn parse_requirements(s: impl AsRef<str>) -> Fallible<Range> {
 
 pub fn parse_version(s: impl AsRef<str>) -> Fallible<Version> {
     let s = s.as_ref();
-    s.parse()
+    trim_version(s)
+        .parse()
         .with_context(|| ErrorKind::VersionParseError { version: s.into() })
 }
 
+/// Parse the path out of a `file:` specifier, accepting both `file:/path` and `file:///path`
+///
+/// `file://` specifiers are parsed as URLs, so that percent-escapes are decoded and drive letters
+/// are handled on Windows. URLs with a (non-local) host don't refer to a local path.
+fn parse_local_path(s: &str) -> Option<PathBuf> {
+    if s.starts_with("file://") {
+        return Url::parse(s).ok()?.to_file_path().ok();
+    }
+
+    let path = s.strip_prefix("file:")?;
+    if path.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(path))
+    }
+}
+
+/// Determine whether a specifier refers to a git repository, using the prefixes supported by npm
+fn is_git_source(s: &str) -> bool {
+    const GIT_PREFIXES: &[&str] = &[
+        "github:",
+        "gitlab:",
+        "bitbucket:",
+        "gist:",
+        "git:",
+        "git+https:",
+        "git+http:",
+        "git+ssh:",
+        "git+file:",
+    ];
+
+    GIT_PREFIXES.iter().any(|prefix| s.starts_with(prefix))
+}
+
 // remove the leading 'v' from the version string, if present
 fn trim_version(s: &str) -> &str {
     let s = s.trim();
@@ -197,3 +253,76 @@ pub mod hashmap_version_serde {
         Ok(m.into_iter().map(|(k, Wrapper(v))| (k, v)).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(spec: &str) -> Range {
+        match spec.parse().unwrap() {
+            VersionSpec::Semver(range) => range,
+            other => panic!("{} should be a range, not {:?}", spec, other),
+        }
+    }
+
+    #[test]
+    fn partial_versions_are_ranges() {
+        let major = range("18");
+        assert!(major.satisfies(&Version::from((18, 0, 0))));
+        assert!(major.satisfies(&Version::from((18, 19, 1))));
+        assert!(!major.satisfies(&Version::from((19, 0, 0))));
+
+        let minor = range("18.0");
+        assert!(minor.satisfies(&Version::from((18, 0, 0))));
+        assert!(minor.satisfies(&Version::from((18, 0, 6))));
+        assert!(!minor.satisfies(&Version::from((18, 1, 0))));
+
+        assert_eq!(range("v18.0"), minor);
+    }
+
+    #[test]
+    fn full_versions_are_exact() {
+        let exact = VersionSpec::Exact(Version::from((18, 0, 0)));
+
+        assert_eq!("18.0.0".parse::<VersionSpec>().unwrap(), exact);
+        assert_eq!("v18.0.0".parse::<VersionSpec>().unwrap(), exact);
+        assert_eq!(" 18.0.0\n".parse::<VersionSpec>().unwrap(), exact);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn local_paths() {
+        let local = |spec: &str| match spec.parse().unwrap() {
+            VersionSpec::Local(path) => path,
+            other => panic!("{} should be a local path, not {:?}", spec, other),
+        };
+
+        assert_eq!(
+            local("file:/tmp/node.tar.gz"),
+            PathBuf::from("/tmp/node.tar.gz")
+        );
+        assert_eq!(
+            local("file:///tmp/node.tar.gz"),
+            PathBuf::from("/tmp/node.tar.gz")
+        );
+        assert_eq!(
+            local("file:///tmp/my%20downloads/node.tar.gz"),
+            PathBuf::from("/tmp/my downloads/node.tar.gz")
+        );
+
+        // Neither of these is a local path
+        assert!("file://host/tmp/node.tar.gz"
+            .parse::<VersionSpec>()
+            .is_err());
+        assert!("file:".parse::<VersionSpec>().is_err());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn local_paths_with_drive_letters() {
+        match "file:///C:/Downloads/node.zip".parse().unwrap() {
+            VersionSpec::Local(path) => assert_eq!(path, PathBuf::from(r"C:\Downloads\node.zip")),
+            other => panic!("expected a local path, not {:?}", other),
+        }
+    }
+}
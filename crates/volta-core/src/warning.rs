@@ -0,0 +1,101 @@
+//! Provides the `Warning` type, which represents advice produced by an operation
+//!
+//! Warnings are logged as they occur and also collected on the `Session`, so that programmatic
+//! callers (e.g. editor integrations) can surface them in their own UI.
+
+use std::fmt;
+
+use crate::platform::PlatformNode;
+use crate::style::tool_version;
+use node_semver::Version;
+
+/// An advisory message produced while running an operation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// The npm bundled with an installed Node is newer than the user's default npm
+    BundledNpmNewerThanDefault { bundled: Version, default: Version },
+
+    /// The npm bundled with a pinned Node is newer than the project's pinned npm
+    BundledNpmNewerThanPinned { bundled: Version, pinned: Version },
+
+    /// The default Node changed to a new major version, so global packages installed with another
+    /// major version may need to be reinstalled (e.g. to rebuild native addons)
+    PackagesMayNeedReinstall {
+        node: Version,
+        packages: Vec<String>,
+    },
+
+    /// The project's pinned Node couldn't be fetched, so the default platform is used instead
+    PinnedNodeUnavailable {
+        pinned: Version,
+        default: PlatformNode,
+    },
+
+    /// The current project uses a different version of a tool than the one set as the default
+    ProjectVersionDiffers {
+        project_version: String,
+        default_version: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::BundledNpmNewerThanDefault { bundled, default } => write!(
+                f,
+                "this version of Node includes {}, which is higher than your default version ({}).
+      To use the version included with Node, run `volta install npm@bundled`",
+                tool_version("npm", bundled),
+                default
+            ),
+            Warning::BundledNpmNewerThanPinned { bundled, pinned } => write!(
+                f,
+                "this version of Node includes {}, which is higher than your pinned version ({}).
+      To use the version included with Node, run `volta pin npm@bundled`",
+                tool_version("npm", bundled),
+                pinned
+            ),
+            Warning::PackagesMayNeedReinstall { node, packages } => write!(
+                f,
+                "these global packages were installed with another major version of Node and may need to be reinstalled for {}: {}.
+      To rebuild any native addons, reinstall them with `volta install <package>`",
+                tool_version("node", node),
+                packages.join(", ")
+            ),
+            Warning::PinnedNodeUnavailable { pinned, default } => write!(
+                f,
+                "could not fetch {}, which is pinned by the project, so the default {} is used.
+      To fail instead, unset `VOLTA_STRICT_PIN`",
+                tool_version("node", pinned),
+                tool_version("node", default)
+            ),
+            Warning::ProjectVersionDiffers {
+                project_version,
+                default_version,
+            } => write!(
+                f,
+                r#"you are using {project_version} in the current project; to
+         instead use {default_version}, run `volta pin {default_version}`"#
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let warning = Warning::BundledNpmNewerThanPinned {
+            bundled: Version::from((9, 8, 1)),
+            pinned: Version::from((8, 19, 4)),
+        };
+
+        assert_eq!(
+            warning.to_string(),
+            "this version of Node includes npm@9.8.1, which is higher than your pinned version (8.19.4).
+      To use the version included with Node, run `volta pin npm@bundled`"
+        );
+    }
+}
@@ -11,6 +11,7 @@ layout! {
                 "index.json": node_index_file;
                 "index.json.expires": node_index_expiry_file;
             }
+            "packages": package_cache_dir {}
         }
         "bin": shim_dir {}
         "log": log_dir {}
@@ -37,15 +38,43 @@ layout! {
         }
         "tmp": tmp_dir {}
         "hooks.json": default_hooks_file;
+        "image-root": image_root_file;
         "layout.v4": layout_file;
     }
 }
 
 impl VoltaHome {
+    /// Relocates the Node images to the given directory, leaving the rest of the layout in place
+    pub fn with_node_image_root(mut self, node_image_root_dir: PathBuf) -> Self {
+        self.node_image_root_dir = node_image_root_dir;
+        self
+    }
+
+    /// Relocates the staging directory, leaving the rest of the layout in place
+    pub fn with_tmp_dir(mut self, tmp_dir: PathBuf) -> Self {
+        self.tmp_dir = tmp_dir;
+        self
+    }
+
+    /// Relocates the inventory of downloaded archives, leaving the rest of the layout in place
+    pub fn with_inventory_dir(mut self, inventory_dir: PathBuf) -> Self {
+        self.node_inventory_dir = path_buf!(inventory_dir.clone(), "node");
+        self.npm_inventory_dir = path_buf!(inventory_dir.clone(), "npm");
+        self.pnpm_inventory_dir = path_buf!(inventory_dir.clone(), "pnpm");
+        self.yarn_inventory_dir = path_buf!(inventory_dir.clone(), "yarn");
+        self.inventory_dir = inventory_dir;
+        self
+    }
+
     pub fn node_image_dir(&self, node: &str) -> PathBuf {
         path_buf!(self.node_image_root_dir.clone(), node)
     }
 
+    /// The cached registry metadata for a package, which may be scoped (e.g. `@scope/package`)
+    pub fn package_metadata_cache_file(&self, package: &str) -> PathBuf {
+        path_buf!(self.package_cache_dir.clone(), format!("{}.json", package))
+    }
+
     pub fn npm_image_dir(&self, npm: &str) -> PathBuf {
         path_buf!(self.npm_image_root_dir.clone(), npm)
     }
@@ -85,6 +114,8 @@ impl VoltaHome {
         path_buf!(self.default_bin_dir.clone(), format!("{}.json", bin_name))
     }
 
+    /// The file recording the version of npm bundled with a Node image, which is kept alongside
+    /// the Node archives
     pub fn node_npm_version_file(&self, version: &str) -> PathBuf {
         path_buf!(
             self.node_inventory_dir.clone(),
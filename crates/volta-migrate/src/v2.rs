@@ -107,8 +107,8 @@ fn clear_default_npm(platform_file: &Path) -> Fallible<()> {
     let mut existing_platform = Platform::try_from(platform_json)?;
 
     if let Some(ref mut node_version) = &mut existing_platform.node {
-        if let Some(npm) = &node_version.npm {
-            if let Ok(default_npm) = load_default_npm_version(&node_version.runtime) {
+        if let (Some(npm), Some(node)) = (&node_version.npm, node_version.runtime.version()) {
+            if let Ok(default_npm) = load_default_npm_version(node) {
                 if *npm == default_npm {
                     node_version.npm = None;
                     write(platform_file, existing_platform.into_json()?).with_context(|| {
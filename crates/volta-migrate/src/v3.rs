@@ -91,7 +91,7 @@ impl TryFrom<V2> for V3 {
 
 fn migrate_packages(old_home: &v2::VoltaHome) -> Fallible<()> {
     let packages = get_installed_packages(old_home);
-    let mut session = Session::init();
+    let mut session = Session::init_unchecked();
 
     for package in packages {
         migrate_single_package(package, &mut session)?;
@@ -40,7 +40,7 @@ impl LegacyPackageConfig {
 impl From<LegacyPlatform> for PlatformSpec {
     fn from(config_platform: LegacyPlatform) -> Self {
         PlatformSpec {
-            node: config_platform.node.runtime,
+            node: config_platform.node.runtime.into(),
             npm: config_platform.node.npm,
             // LegacyPlatform (layout.v2) doesn't have a pnpm field
             pnpm: None,
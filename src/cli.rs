@@ -1,7 +1,10 @@
+use std::env;
+
 use clap::{builder::styling, ColorChoice, Parser};
 
 use crate::command::{self, Command};
 use volta_core::error::{ExitCode, Fallible};
+use volta_core::project::VOLTA_FROZEN;
 use volta_core::session::Session;
 use volta_core::style::{text_width, MAX_WIDTH};
 
@@ -38,6 +41,10 @@ pub(crate) struct Volta {
     )]
     pub(crate) quiet: bool,
 
+    /// Fails instead of changing `volta.lock.json`, so that the locked versions are used as-is
+    #[arg(long, global = true)]
+    pub(crate) frozen: bool,
+
     /// Prints the current version of Volta
     #[arg(short, long)]
     pub(crate) version: bool,
@@ -45,6 +52,12 @@ pub(crate) struct Volta {
 
 impl Volta {
     pub(crate) fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        // Set through the environment so that tools launched by the command (via the shims) also
+        // use the frozen lockfile
+        if self.frozen {
+            env::set_var(VOLTA_FROZEN, "1");
+        }
+
         if self.version {
             // suffix indicator for dev build
             if cfg!(debug_assertions) {
@@ -1,11 +1,20 @@
+use log::{debug, info};
 use volta_core::error::{ExitCode, Fallible};
 use volta_core::session::{ActivityKind, Session};
-use volta_core::tool;
+use volta_core::tool::{self, NodeTarget, PlanAction};
 
 use crate::command::Command;
 
 #[derive(clap::Args)]
 pub(crate) struct Fetch {
+    /// Fetch Node for another platform, like `linux-arm64` or `win-x64`
+    #[arg(long, value_name = "os-arch")]
+    target: Option<String>,
+
+    /// Show where the tools would be fetched from, without fetching anything
+    #[arg(long, conflicts_with = "target")]
+    dry_run: bool,
+
     /// Tools to fetch, like `node`, `yarn@latest` or `your-package@^14.4.3`.
     #[arg(value_name = "tool[@version]", required = true)]
     tools: Vec<String>,
@@ -15,8 +24,30 @@ impl Command for Fetch {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Fetch);
 
+        let target = self
+            .target
+            .as_deref()
+            .map(str::parse::<NodeTarget>)
+            .transpose()?;
+
         for tool in tool::Spec::from_strings(&self.tools, "fetch")? {
-            tool.resolve(session)?.fetch(session)?;
+            if let Some(target) = &target {
+                let name = tool.to_string();
+                debug!("Fetching {} for {}", name, target);
+                let outcome = tool.fetch_for_target(target, session)?;
+                debug!("Fetched {} for {} ({})", name, target, outcome);
+                continue;
+            }
+
+            let tool = tool.resolve(session)?;
+            if self.dry_run {
+                info!("{}", tool.describe_plan(PlanAction::Fetch, session)?);
+                continue;
+            }
+            let name = tool.to_string();
+            debug!("Fetching {} ({})", name, tool.kind());
+            let outcome = tool.fetch(session)?;
+            debug!("Fetched {} ({})", name, outcome);
         }
 
         session.add_event_end(ActivityKind::Fetch, ExitCode::Success);
@@ -1,11 +1,20 @@
-use volta_core::error::{ExitCode, Fallible};
+use log::{debug, info};
+use volta_core::error::{ErrorKind, ExitCode, Fallible};
 use volta_core::session::{ActivityKind, Session};
-use volta_core::tool::Spec;
+use volta_core::tool::{Package, PlanAction, Spec, Tool};
 
 use crate::command::Command;
 
 #[derive(clap::Args)]
 pub(crate) struct Install {
+    /// Only create shims for the named executables of a package (can be used multiple times)
+    #[arg(long = "bin", value_name = "name", num_args = 1)]
+    bins: Vec<String>,
+
+    /// Show what would be fetched and changed, without installing anything
+    #[arg(long)]
+    dry_run: bool,
+
     /// Tools to install, like `node`, `yarn@latest` or `your-package@^14.4.3`.
     #[arg(value_name = "tool[@version]", required = true)]
     tools: Vec<String>,
@@ -15,8 +24,31 @@ impl Command for Install {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Install);
 
-        for tool in Spec::from_strings(&self.tools, "install")? {
-            tool.resolve(session)?.install(session)?;
+        let tools = Spec::from_strings(&self.tools, "install")?;
+
+        if self.bins.is_empty() {
+            for tool in tools {
+                let tool = tool.resolve(session)?;
+                if self.dry_run {
+                    info!("{}", tool.describe_plan(PlanAction::Install, session)?);
+                    continue;
+                }
+                debug!("Installing {} ({})", tool, tool.kind());
+                tool.install(session)?;
+            }
+        } else {
+            let package = match <[Spec; 1]>::try_from(tools) {
+                Ok([Spec::Package(name, version)]) => {
+                    Package::new(name, version)?.with_bins(self.bins)
+                }
+                _ => return Err(ErrorKind::BinarySelectionRequiresPackage.into()),
+            };
+            if self.dry_run {
+                info!("{}", package.describe_plan(PlanAction::Install, session)?);
+            } else {
+                debug!("Installing {} with selected executables", package);
+                Box::new(package).install(session)?;
+            }
         }
 
         session.add_event_end(ActivityKind::Install, ExitCode::Success);
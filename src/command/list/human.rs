@@ -773,7 +773,7 @@ See options for more detailed reports by running `volta list --help`.";
                         name: "create-react-app".to_string(),
                         version: Version::from((3, 0, 1)),
                     },
-                    node: NODE_12.clone(),
+                    node: NODE_12.clone().into(),
                     tools: vec!["create-react-app".to_string()],
                 },
                 Package::Default {
@@ -781,7 +781,7 @@ See options for more detailed reports by running `volta list --help`.";
                         name: "typescript".to_string(),
                         version: Version::from((3, 4, 3)),
                     },
-                    node: NODE_12.clone(),
+                    node: NODE_12.clone().into(),
                     tools: vec!["tsc".to_string(), "tsserver".to_string()],
                 },
             ];
@@ -832,7 +832,7 @@ See options for more detailed reports by running `volta list --help`.";
                         name: "typescript".to_string(),
                         version: Version::from((3, 4, 3)),
                     },
-                    node: NODE_12.clone(),
+                    node: NODE_12.clone().into(),
                     tools: vec!["tsc".to_string(), "tsserver".to_string()],
                 },
             ];
@@ -1159,7 +1159,7 @@ See `volta help install` for details and more options.";
                     name: "ember-cli".to_string(),
                     version: Version::from((3, 10, 1)),
                 },
-                node: NODE_12.clone(),
+                node: NODE_12.clone().into(),
                 tools: vec!["ember".to_string()],
             }];
 
@@ -1241,7 +1241,7 @@ See `volta help install` for details and more options.";
                         name: "ember-cli".to_string(),
                         version: Version::from((3, 10, 1)),
                     },
-                    node: NODE_12.clone(),
+                    node: NODE_12.clone().into(),
                     tools: vec!["ember".to_string()],
                 },
                 Package::Project {
@@ -1285,7 +1285,7 @@ See `volta help install` for details and more options.";
                     name: "ember-cli".to_string(),
                     version: Version::from((3, 10, 1)),
                 },
-                node: NODE_12.clone(),
+                node: NODE_12.clone().into(),
                 tools: vec!["ember".to_string()],
             }];
 
@@ -1367,7 +1367,7 @@ See `volta help install` for details and more options.";
                         name: "ember-cli".to_string(),
                         version: Version::from((3, 10, 1)),
                     },
-                    node: NODE_12.clone(),
+                    node: NODE_12.clone().into(),
                     tools: vec!["ember".to_string()],
                 },
                 Package::Project {
@@ -1602,7 +1602,7 @@ See `volta help install` for details and more options.";
                         name: "typescript".to_string(),
                         version: Version::from((3, 4, 3)),
                     },
-                    node: NODE_12.clone(),
+                    node: NODE_12.clone().into(),
                     tools: vec!["tsc".to_string(), "tsserver".to_string()],
                 },
                 Package::Project {
@@ -1620,7 +1620,7 @@ See `volta help install` for details and more options.";
                         name: "ember-cli".to_string(),
                         version: Version::from((3, 8, 2)),
                     },
-                    node: NODE_12.clone(),
+                    node: NODE_12.clone().into(),
                     tools: vec!["ember".to_string()],
                 },
             ];
@@ -11,8 +11,10 @@ use crate::command::Command;
 use toolchain::Toolchain;
 use volta_core::error::{ExitCode, Fallible};
 use volta_core::inventory::package_configs;
+use volta_core::platform::PlatformNode;
 use volta_core::project::Project;
 use volta_core::session::{ActivityKind, Session};
+use volta_core::sync::VoltaLock;
 use volta_core::tool::PackageConfig;
 
 #[derive(clap::ValueEnum, Copy, Clone)]
@@ -35,6 +37,10 @@ enum Source {
     /// The item is the user's default.
     Default,
 
+    /// The item isn't active, but satisfies the range pinned by a project. The
+    /// `PathBuf` is the path to the project's `package.json`.
+    Satisfies { range: String, path: PathBuf },
+
     /// The item is one that has been *fetched* but is not *installed* anywhere.
     None,
 }
@@ -57,6 +63,8 @@ impl fmt::Display for Source {
             match self {
                 Source::Project(path) => format!(" (current @ {})", path.display()),
                 Source::Default => String::from(" (default)"),
+                Source::Satisfies { range, path } =>
+                    format!(" (satisfies {} @ {})", range, path.display()),
                 Source::None => String::from(""),
             }
         )
@@ -76,7 +84,7 @@ enum Package {
     Default {
         details: PackageDetails,
         /// The version of Node the package is installed against.
-        node: Version,
+        node: PlatformNode,
         /// The names of the tools associated with the package.
         tools: Vec<String>,
     },
@@ -107,7 +115,7 @@ impl Package {
                 tools: config.bins.clone(),
                 path: path.clone(),
             },
-            Source::None => Package::Fetched(details),
+            Source::Satisfies { .. } | Source::None => Package::Fetched(details),
         }
     }
 
@@ -263,7 +271,11 @@ impl List {
 impl Command for List {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::List);
+        // Avoid reading the inventory while another process is modifying it
+        let _lock = VoltaLock::acquire_shared();
 
+        // Resolve any ranges the project pins, so that the versions they select are shown as active
+        session.project_platform()?;
         let project = session.project()?;
         let default_platform = session.default_platform()?;
         let format = match self.output_format() {
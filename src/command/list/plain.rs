@@ -244,6 +244,18 @@ mod tests {
                 "runtime node@12.4.0"
             );
         }
+
+        #[test]
+        fn satisfies_project_range() {
+            let source = Source::Satisfies {
+                range: "^12".into(),
+                path: PROJECT_PATH.clone(),
+            };
+            assert_eq!(
+                display_node(&source, &NODE_VERSION).as_str(),
+                "runtime node@12.4.0 (satisfies ^12 @ /a/b/c)"
+            );
+        }
     }
 
     mod npm {
@@ -348,7 +360,7 @@ mod tests {
                         name: "typescript".into(),
                         version: TYPESCRIPT_VERSION.clone(),
                     },
-                    node: NODE_VERSION.clone(),
+                    node: NODE_VERSION.clone().into(),
                     tools: vec!["tsc".into(), "tsserver".into()]
                 }])
                 .expect("Should always return a `String` if given a non-empty set")
@@ -385,7 +397,7 @@ mod tests {
                             name: "ember-cli".into(),
                             version: Version::from((3, 10, 0)),
                         },
-                        node: NODE_VERSION.clone(),
+                        node: NODE_VERSION.clone().into(),
                         tools: vec!["ember".into()],
                     },
                     Package::Fetched(PackageDetails {
@@ -429,7 +441,7 @@ mod tests {
                             name: "typescript".into(),
                             version: TYPESCRIPT_VERSION.clone(),
                         },
-                        node: NODE_VERSION.clone(),
+                        node: NODE_VERSION.clone().into(),
                         tools: vec!["tsc".into(), "tsserver".into()],
                     }
                 )
@@ -518,7 +530,7 @@ mod tests {
                                 name: "ember-cli".into(),
                                 version: Version::from((3, 10, 2)),
                             },
-                            node: NODE_VERSION.clone(),
+                            node: NODE_VERSION.clone().into(),
                             tools: vec!["ember".into()]
                         },
                         Package::Project {
@@ -531,7 +543,7 @@ mod tests {
                                 name: "typescript".into(),
                                 version: TYPESCRIPT_VERSION.clone(),
                             },
-                            node: NODE_VERSION.clone(),
+                            node: NODE_VERSION.clone().into(),
                             tools: vec!["tsc".into(), "tsserver".into()]
                         }
                     ]
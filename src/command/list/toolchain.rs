@@ -1,6 +1,7 @@
 use super::{Filter, Node, Package, PackageManager, Source};
 use crate::command::list::PackageManagerKind;
 use node_semver::Version;
+use std::path::Path;
 use volta_core::error::Fallible;
 use volta_core::inventory::{
     node_versions, npm_versions, package_configs, pnpm_versions, yarn_versions,
@@ -8,6 +9,7 @@ use volta_core::inventory::{
 use volta_core::platform::PlatformSpec;
 use volta_core::project::Project;
 use volta_core::tool::PackageConfig;
+use volta_core::version::VersionSpec;
 
 pub(super) enum Toolchain {
     Node(Vec<Node>),
@@ -47,7 +49,7 @@ enum Lookup {
 impl Lookup {
     fn version_from_spec(&self) -> impl Fn(&PlatformSpec) -> Option<Version> + '_ {
         move |spec| match self {
-            Lookup::Runtime => Some(spec.node.clone()),
+            Lookup::Runtime => spec.node.version().cloned(),
             Lookup::Npm => spec.npm.clone(),
             Lookup::Pnpm => spec.pnpm.clone(),
             Lookup::Yarn => spec.yarn.clone(),
@@ -106,6 +108,43 @@ impl Lookup {
     }
 }
 
+/// Determine the `Source` for an installed Node version, marking versions that satisfy the range
+/// pinned by the project (if any) when they aren't otherwise in use.
+fn runtime_source(
+    project: Option<&Project>,
+    default_platform: Option<&PlatformSpec>,
+    version: &Version,
+) -> Source {
+    let source = Lookup::Runtime.version_source(project, default_platform, version);
+    let pinned = project.and_then(|proj| {
+        proj.pinned_specs()
+            .node
+            .as_ref()
+            .map(|spec| (spec, proj.manifest_file()))
+    });
+
+    mark_satisfying(source, version, pinned)
+}
+
+/// Mark a version that isn't otherwise in use as satisfying the pinned range, if it does.
+///
+/// Only ranges are considered: an exact pin is either the active version or not relevant.
+fn mark_satisfying(
+    source: Source,
+    version: &Version,
+    pinned: Option<(&VersionSpec, &Path)>,
+) -> Source {
+    match (source, pinned) {
+        (Source::None, Some((VersionSpec::Semver(range), path))) if range.satisfies(version) => {
+            Source::Satisfies {
+                range: range.to_string(),
+                path: path.to_owned(),
+            }
+        }
+        (source, _) => source,
+    }
+}
+
 /// Look up the `Source` for a tool with a given name.
 fn tool_source(name: &str, project: Option<&Project>) -> Fallible<Source> {
     match project {
@@ -170,7 +209,7 @@ impl Toolchain {
         let runtimes = node_versions()?
             .iter()
             .map(|version| Node {
-                source: Lookup::Runtime.version_source(project, default_platform, version),
+                source: runtime_source(project, default_platform, version),
                 version: version.clone(),
             })
             .collect();
@@ -211,7 +250,7 @@ impl Toolchain {
         let runtimes = node_versions()?
             .iter()
             .filter_map(|version| {
-                let source = Lookup::Runtime.version_source(project, default_platform, version);
+                let source = runtime_source(project, default_platform, version);
                 if source.allowed_with(filter) {
                     let version = version.clone();
                     Some(Node { source, version })
@@ -407,3 +446,66 @@ impl Toolchain {
         Ok(toolchain)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn marks_versions_satisfying_pinned_range() {
+        let manifest = PathBuf::from("/a/b/c/package.json");
+        let pinned: VersionSpec = "^20".parse().unwrap();
+        let range = match &pinned {
+            VersionSpec::Semver(range) => range.to_string(),
+            _ => unreachable!("'^20' is a range"),
+        };
+
+        let installed = [
+            ("18.19.0", Source::None),
+            ("20.5.1", Source::Default),
+            ("20.11.1", Source::None),
+            ("21.6.2", Source::None),
+        ];
+
+        let marked: Vec<Source> = installed
+            .iter()
+            .map(|(version, source)| {
+                mark_satisfying(
+                    source.clone(),
+                    &version.parse().unwrap(),
+                    Some((&pinned, &manifest)),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            marked,
+            [
+                Source::None,
+                // The default keeps its own marker, even though it satisfies the range
+                Source::Default,
+                Source::Satisfies {
+                    range,
+                    path: manifest.clone(),
+                },
+                Source::None,
+            ]
+        );
+    }
+
+    #[test]
+    fn exact_pin_marks_nothing() {
+        let manifest = PathBuf::from("/a/b/c/package.json");
+        let pinned: VersionSpec = "20.11.1".parse().unwrap();
+
+        assert_eq!(
+            mark_satisfying(
+                Source::None,
+                &"20.11.1".parse().unwrap(),
+                Some((&pinned, &manifest))
+            ),
+            Source::None
+        );
+    }
+}
@@ -1,6 +1,9 @@
+use std::env;
+
+use log::info;
 use volta_core::error::{ExitCode, Fallible};
 use volta_core::session::{ActivityKind, Session};
-use volta_core::tool::Spec;
+use volta_core::tool::{self, PlanAction, Spec, VOLTA_PREFETCH_ON_PIN};
 
 use crate::command::Command;
 
@@ -9,14 +12,45 @@ pub(crate) struct Pin {
     /// Tools to pin, like `node@lts` or `yarn@^1.14`.
     #[arg(value_name = "tool[@version]", required = true)]
     tools: Vec<String>,
+
+    /// Re-resolve the tools and refresh the versions recorded in `volta.lock.json`
+    #[arg(long)]
+    update_lock: bool,
+
+    /// Show what would be pinned, without changing the project
+    #[arg(long, conflicts_with = "update_lock")]
+    dry_run: bool,
 }
 
 impl Command for Pin {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Pin);
 
+        if self.dry_run {
+            for tool in Spec::from_strings(&self.tools, "pin")? {
+                let tool = tool.resolve_for_pin(session)?;
+                info!("{}", tool.describe_plan(PlanAction::Pin, session)?);
+            }
+
+            session.add_event_end(ActivityKind::Pin, ExitCode::Success);
+            return Ok(ExitCode::Success);
+        }
+
+        if self.update_lock {
+            if let Some(project) = session.project_mut()? {
+                project.reset_lockfile()?;
+            }
+        }
+
+        // Pinning rewrites the project's platform, so any ranges or tags it pins are resolved first
+        session.project_platform()?;
+
         for tool in Spec::from_strings(&self.tools, "pin")? {
-            tool.resolve(session)?.pin(session)?;
+            tool.resolve_for_pin(session)?.pin(session)?;
+        }
+
+        if env::var_os(VOLTA_PREFETCH_ON_PIN).is_some() {
+            tool::prefetch_project_platform(session)?;
         }
 
         session.add_event_end(ActivityKind::Pin, ExitCode::Success);
@@ -1,13 +1,15 @@
 use std::collections::HashMap;
+use std::env;
 use std::ffi::OsString;
 
 use crate::command::Command;
 use crate::common::{Error, IntoResult};
 use log::warn;
 use volta_core::error::{report_error, ExitCode, Fallible};
-use volta_core::platform::{CliPlatform, InheritOption};
-use volta_core::run::execute_tool;
+use volta_core::platform::{CliPlatform, InheritOption, VOLTA_CLEAN_ENV};
+use volta_core::run::{execute_tool, Capture};
 use volta_core::session::{ActivityKind, Session};
+use volta_core::style::note_prefix;
 use volta_core::tool::{node, npm, pnpm, yarn};
 
 #[derive(Debug, clap::Args)]
@@ -40,6 +42,14 @@ pub(crate) struct Run {
     #[arg(long, conflicts_with = "yarn")]
     no_yarn: bool,
 
+    /// Run with a clean PATH, containing only the Volta-managed tools and system essentials
+    #[arg(long)]
+    clean_env: bool,
+
+    /// Copy the command's output into files, while still showing it in the terminal
+    #[arg(long)]
+    capture: bool,
+
     /// Set an environment variable (can be used multiple times)
     #[arg(long = "env", value_name = "NAME=value", num_args = 1)]
     envs: Vec<String>,
@@ -61,13 +71,28 @@ impl Command for Run {
         let envs = self.parse_envs();
         let platform = self.parse_platform(session)?;
 
+        // Set through the environment so that tools launched from within the command (via the
+        // shims) also run in a clean environment
+        if self.clean_env {
+            env::set_var(VOLTA_CLEAN_ENV, "1");
+        }
+
         // Safety: At least one value is required for `command_and_args`, so there must be at
         // least one value in the list. If no value is provided, Clap will show a "required
         // argument missing" message and this function won't be called.
         let command = &self.command_and_args[0];
         let args = &self.command_and_args[1..];
 
-        match execute_tool(command, args, &envs, platform, session).into_result() {
+        let capture = self.capture.then(Capture::create).transpose()?;
+        let capture_dir = capture.as_ref().map(|capture| capture.dir().to_owned());
+
+        let result = execute_tool(command, args, &envs, platform, capture, session).into_result();
+        if let Some(dir) = capture_dir {
+            // The note goes to stderr, so that it doesn't mix with the tool's output on stdout
+            eprintln!("{} Captured output in {}", note_prefix(), dir.display());
+        }
+
+        match result {
             Ok(()) => {
                 session.add_event_end(ActivityKind::Run, ExitCode::Success);
                 Ok(ExitCode::Success)
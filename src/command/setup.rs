@@ -1,21 +1,46 @@
-use log::info;
+use std::env;
+use std::fmt;
+use std::path::{Component, Path};
+
+use log::{debug, info};
 use volta_core::error::{ExitCode, Fallible};
 use volta_core::layout::volta_home;
 use volta_core::session::{ActivityKind, Session};
 use volta_core::shim::regenerate_shims_for_dir;
 use volta_core::style::success_prefix;
+use volta_core::tool::Spec;
 
 use crate::command::Command;
 
+/// Environment variable listing the tools to install by default when `--install` isn't used
+const VOLTA_SETUP_TOOLS: &str = "VOLTA_SETUP_TOOLS";
+
 #[derive(clap::Args)]
-pub(crate) struct Setup {}
+pub(crate) struct Setup {
+    /// Install a default tool as part of setup, like `node@lts` (can be used multiple times)
+    ///
+    /// Nothing is installed if a default Node version is already set. Without this option, the
+    /// tools listed in the `VOLTA_SETUP_TOOLS` environment variable are installed, if any.
+    #[arg(long = "install", value_name = "tool[@version]", num_args = 1)]
+    tools: Vec<String>,
+}
 
 impl Command for Setup {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Setup);
 
+        // Check the tools before changing anything, so a typo doesn't leave setup half-done
+        let tools = default_tools(self.tools);
+        let tools = if tools.is_empty() {
+            Vec::new()
+        } else {
+            Spec::from_strings(&tools, "install")?
+        };
+
         os::setup_environment()?;
         regenerate_shims_for_dir(volta_home()?.shim_dir())?;
+        install_default_tools(tools, session)?;
+        verify_setup()?;
 
         info!(
             "{} Setup complete. Open a new terminal to start using Volta!",
@@ -27,20 +52,155 @@ impl Command for Setup {
     }
 }
 
+/// The tools to install by default, from the command line or else from `VOLTA_SETUP_TOOLS`, in
+/// which they may be separated by commas or whitespace
+fn default_tools(from_args: Vec<String>) -> Vec<String> {
+    if !from_args.is_empty() {
+        return from_args;
+    }
+
+    env::var(VOLTA_SETUP_TOOLS)
+        .map(|tools| split_tool_list(&tools))
+        .unwrap_or_default()
+}
+
+fn split_tool_list(tools: &str) -> Vec<String> {
+    tools
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tool| !tool.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Installs the default tools, unless a default Node version has already been set
+fn install_default_tools(tools: Vec<Spec>, session: &mut Session) -> Fallible<()> {
+    if tools.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(platform) = session.default_platform()? {
+        info!(
+            "Default Node {} is already set, skipping the default tools.",
+            platform.node
+        );
+        return Ok(());
+    }
+
+    for tool in tools {
+        let tool = tool.resolve(session)?;
+        debug!("Installing default {} ({})", tool, tool.kind());
+        tool.install(session)?;
+    }
+
+    Ok(())
+}
+
+/// The version manager that a `node` executable appears to belong to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeManager {
+    Asdf,
+    Fnm,
+    Homebrew,
+    N,
+    Nodenv,
+    Nvm,
+    Volta,
+    /// No known version manager, e.g. an install from the system package manager
+    Unknown,
+}
+
+impl NodeManager {
+    /// Classifies a `node` executable based on the directories in its path
+    fn from_path(path: &Path, volta_root: &Path) -> Self {
+        if path.starts_with(volta_root) {
+            return NodeManager::Volta;
+        }
+
+        path.components()
+            .find_map(|component| match component {
+                Component::Normal(dir) => match dir.to_str()? {
+                    ".asdf" => Some(NodeManager::Asdf),
+                    ".fnm" | "fnm" | "fnm_multishells" => Some(NodeManager::Fnm),
+                    "Cellar" | "homebrew" | "linuxbrew" => Some(NodeManager::Homebrew),
+                    "n" => Some(NodeManager::N),
+                    ".nodenv" => Some(NodeManager::Nodenv),
+                    ".nvm" | "nvm" => Some(NodeManager::Nvm),
+                    ".volta" | "Volta" => Some(NodeManager::Volta),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .unwrap_or(NodeManager::Unknown)
+    }
+}
+
+impl fmt::Display for NodeManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            NodeManager::Asdf => "asdf",
+            NodeManager::Fnm => "fnm",
+            NodeManager::Homebrew => "Homebrew",
+            NodeManager::N => "n",
+            NodeManager::Nodenv => "nodenv",
+            NodeManager::Nvm => "nvm",
+            NodeManager::Volta => "Volta",
+            NodeManager::Unknown => "unknown manager",
+        })
+    }
+}
+
+/// Reports every `node` executable on the PATH that isn't managed by Volta
+///
+/// Executables earlier in the PATH shadow later ones, so listing all of them (rather than only
+/// the first) shows which installs may be used instead of Volta. This is only informational; the
+/// PATH isn't modified.
+fn verify_setup() -> Fallible<()> {
+    let home = volta_home()?;
+    let installs: Vec<_> = match which::which_all("node") {
+        Ok(paths) => paths
+            .map(|path| {
+                let manager = NodeManager::from_path(&path, home.root());
+                (path, manager)
+            })
+            .collect(),
+        Err(error) => {
+            debug!("Could not search the PATH for Node: {}", error);
+            return Ok(());
+        }
+    };
+
+    if installs
+        .iter()
+        .all(|(_, manager)| *manager == NodeManager::Volta)
+    {
+        return Ok(());
+    }
+
+    let listing: Vec<String> = installs
+        .iter()
+        .map(|(path, manager)| format!("    {} ({})", path.display(), manager))
+        .collect();
+
+    info!(
+        "Found Node installs on your PATH that aren't managed by Volta. In order of precedence:\n{}\n\n\
+        Installs earlier in the PATH will be used instead of the ones that follow them.",
+        listing.join("\n")
+    );
+
+    Ok(())
+}
+
 #[cfg(unix)]
 mod os {
     use std::env;
-    use std::fs::File;
-    use std::io::{self, BufRead, BufReader, Write};
-    use std::path::{Path, PathBuf};
 
     use log::{debug, warn};
     use volta_core::error::{ErrorKind, Fallible};
     use volta_core::layout::volta_home;
+    use volta_core::shell::{add_to_profile, shells_to_update, Shell};
 
     pub fn setup_environment() -> Fallible<()> {
         let home = volta_home()?;
-        let formatted_home = format_home(home.root());
 
         // Don't update the user's shell config files if VOLTA_HOME and PATH already contain what we need.
         let home_in_path = match env::var_os("PATH") {
@@ -56,24 +216,17 @@ mod os {
         }
 
         debug!("Searching for profiles to update");
-        let profiles = determine_profiles()?;
-
-        let found_profile = profiles.into_iter().fold(false, |prev, profile| {
-            let contents = read_profile_without_volta(&profile).unwrap_or_default();
-
-            let write_profile = match profile.extension() {
-                Some(ext) if ext == "fish" => write_profile_fish,
-                _ => write_profile_sh,
-            };
+        let home_dir = dirs::home_dir().ok_or(ErrorKind::NoHomeEnvironmentVar)?;
+        let shells = shells_to_update(&home_dir, Shell::from_env());
 
-            match write_profile(&profile, contents, &formatted_home) {
-                Ok(()) => true,
+        let found_profile = shells.into_iter().fold(false, |prev, shell| {
+            match add_to_profile(shell, home.shim_dir()) {
+                Ok(profile) => {
+                    debug!("Updated profile script: {}", profile.display());
+                    true
+                }
                 Err(err) => {
-                    warn!(
-                        "Found profile script, but could not modify it: {}",
-                        profile.display()
-                    );
-                    debug!("Profile modification error: {}", err);
+                    warn!("{}", err);
                     prev
                 }
             }
@@ -83,144 +236,15 @@ mod os {
             Ok(())
         } else {
             Err(ErrorKind::NoShellProfile {
-                env_profile: String::new(),
+                profiles: Shell::ALL
+                    .iter()
+                    .flat_map(|shell| shell.profile_candidates(&home_dir))
+                    .collect(),
                 bin_dir: home.shim_dir().to_owned(),
             }
             .into())
         }
     }
-
-    /// Returns a list of profile files to modify / create.
-    ///
-    /// Any file in the list should be created if it doesn't already exist
-    fn determine_profiles() -> Fallible<Vec<PathBuf>> {
-        let home_dir = dirs::home_dir().ok_or(ErrorKind::NoHomeEnvironmentVar)?;
-        let shell = env::var("SHELL").unwrap_or_else(|_| String::new());
-        // Always include `~/.profile`
-        let mut profiles = vec![home_dir.join(".profile")];
-
-        // PROFILE environment variable, if set
-        if let Ok(profile_env) = env::var("PROFILE") {
-            if !profile_env.is_empty() {
-                profiles.push(profile_env.into());
-            }
-        }
-
-        add_zsh_profile(&home_dir, &shell, &mut profiles);
-        add_bash_profiles(&home_dir, &shell, &mut profiles);
-        add_fish_profile(&home_dir, &shell, &mut profiles);
-
-        Ok(profiles)
-    }
-
-    /// Add zsh profile script, if necessary
-    fn add_zsh_profile(home_dir: &Path, shell: &str, profiles: &mut Vec<PathBuf>) {
-        let zdotdir_env = env::var("ZDOTDIR").unwrap_or_else(|_| String::new());
-        let zdotdir = if zdotdir_env.is_empty() {
-            home_dir
-        } else {
-            Path::new(&zdotdir_env)
-        };
-
-        let zshenv = zdotdir.join(".zshenv");
-
-        let zshrc = zdotdir.join(".zshrc");
-
-        if shell.contains("zsh") || zshenv.exists() {
-            profiles.push(zshenv);
-        } else if zshrc.exists() {
-            profiles.push(zshrc);
-        }
-    }
-
-    /// Add bash profile scripts, if necessary
-    ///
-    /// Note: We only add the bash scripts if they already exist, as creating new files can impact
-    /// the processing of existing files in bash (e.g. preventing ~/.profile from being loaded)
-    fn add_bash_profiles(home_dir: &Path, shell: &str, profiles: &mut Vec<PathBuf>) {
-        let mut bash_added = false;
-
-        let bashrc = home_dir.join(".bashrc");
-        if bashrc.exists() {
-            bash_added = true;
-            profiles.push(bashrc);
-        }
-
-        let bash_profile = home_dir.join(".bash_profile");
-        if bash_profile.exists() {
-            bash_added = true;
-            profiles.push(bash_profile);
-        }
-
-        if shell.contains("bash") && !bash_added {
-            let suggested_bash_profile = if cfg!(target_os = "macos") {
-                "~/.bash_profile"
-            } else {
-                "~/.bashrc"
-            };
-
-            warn!(
-                "We detected that you are using bash, however we couldn't find any bash profile scripts.
-If you run into problems running Volta, create {} and run `volta setup` again.",
-                suggested_bash_profile
-            );
-        }
-    }
-
-    /// Add fish profile scripts, if necessary
-    fn add_fish_profile(home_dir: &Path, shell: &str, profiles: &mut Vec<PathBuf>) {
-        let fish_config = home_dir.join(".config/fish/config.fish");
-
-        if shell.contains("fish") || fish_config.exists() {
-            profiles.push(fish_config);
-        }
-    }
-
-    fn read_profile_without_volta(path: &Path) -> Option<String> {
-        let file = File::open(path).ok()?;
-        let reader = BufReader::new(file);
-
-        reader
-            .lines()
-            .filter(|line_result| match line_result {
-                Ok(line) if !line.contains("VOLTA") => true,
-                Ok(_) => false,
-                Err(_) => true,
-            })
-            .collect::<io::Result<Vec<String>>>()
-            .map(|lines| lines.join("\n"))
-            .ok()
-    }
-
-    fn format_home(volta_home: &Path) -> String {
-        if let Some(home_dir) = env::var_os("HOME") {
-            if let Ok(suffix) = volta_home.strip_prefix(home_dir) {
-                // If the HOME environment variable is set _and_ the proposed VOLTA_HOME starts
-                // with that value, use $HOME when writing the profile scripts
-                return format!("$HOME/{}", suffix.display());
-            }
-        }
-
-        volta_home.display().to_string()
-    }
-
-    fn write_profile_sh(path: &Path, contents: String, volta_home: &str) -> io::Result<()> {
-        let mut file = File::create(path)?;
-        write!(
-            file,
-            "{}\nexport VOLTA_HOME=\"{}\"\nexport PATH=\"$VOLTA_HOME/bin:$PATH\"\n",
-            contents, volta_home,
-        )
-    }
-
-    fn write_profile_fish(path: &Path, contents: String, volta_home: &str) -> io::Result<()> {
-        let mut file = File::create(path)?;
-        write!(
-            file,
-            "{}\nset -gx VOLTA_HOME \"{}\"\nset -gx PATH \"$VOLTA_HOME/bin\" $PATH\n",
-            contents, volta_home,
-        )
-    }
 }
 
 #[cfg(windows)]
@@ -264,3 +288,49 @@ mod os {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_node_manager_from_path() {
+        let volta_root = PathBuf::from("/home/user/custom-volta");
+        let classify = |path: &str| NodeManager::from_path(Path::new(path), &volta_root);
+
+        assert_eq!(
+            classify("/home/user/custom-volta/bin/node"),
+            NodeManager::Volta
+        );
+        assert_eq!(classify("/home/user/.volta/bin/node"), NodeManager::Volta);
+        assert_eq!(
+            classify("/home/user/.nvm/versions/node/v20.5.1/bin/node"),
+            NodeManager::Nvm
+        );
+        assert_eq!(
+            classify("/run/user/1000/fnm_multishells/1234_5678/bin/node"),
+            NodeManager::Fnm
+        );
+        assert_eq!(classify("/home/user/.asdf/shims/node"), NodeManager::Asdf);
+        assert_eq!(
+            classify("/home/user/.nodenv/shims/node"),
+            NodeManager::Nodenv
+        );
+        assert_eq!(classify("/opt/homebrew/bin/node"), NodeManager::Homebrew);
+        assert_eq!(
+            classify("/usr/local/n/versions/node/20.5.1/bin/node"),
+            NodeManager::N
+        );
+        assert_eq!(classify("/usr/bin/node"), NodeManager::Unknown);
+    }
+
+    #[test]
+    fn test_split_tool_list() {
+        assert_eq!(
+            split_tool_list("node@lts, yarn@1\ttypescript"),
+            ["node@lts", "yarn@1", "typescript"]
+        );
+        assert!(split_tool_list(" , ").is_empty());
+    }
+}
@@ -1,5 +1,7 @@
+use log::info;
 use volta_core::error::{ErrorKind, ExitCode, Fallible};
 use volta_core::session::{ActivityKind, Session};
+use volta_core::style::success_prefix;
 use volta_core::tool;
 use volta_core::version::VersionSpec;
 
@@ -8,14 +10,56 @@ use crate::command::Command;
 #[derive(clap::Args)]
 pub(crate) struct Uninstall {
     /// The tool to uninstall, like `ember-cli-update`, `typescript`, or <package>
+    ///
+    /// For node, npm, pnpm, or Yarn, a version or range (e.g. `node@<18`) removes every fetched
+    /// version that matches it, keeping only your default
     tool: String,
+
+    /// The version or range of the tool to remove, as an alternative to `<tool>@<version>`
+    version: Option<String>,
+
+    /// Remove every fetched version of node, npm, pnpm, or Yarn, keeping only your default
+    #[arg(long)]
+    all_versions: bool,
 }
 
 impl Command for Uninstall {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Uninstall);
 
-        let tool = tool::Spec::try_from_str(&self.tool)?;
+        let tool = match &self.version {
+            Some(version) => tool::Spec::try_from_str(&format!("{}@{}", self.tool, version))?,
+            None => tool::Spec::try_from_str(&self.tool)?,
+        };
+
+        let matching = selects_versions(&tool);
+        if self.all_versions || matching {
+            let name = tool.name().to_owned();
+            let removed = tool.uninstall_versions(session)?;
+
+            if removed.is_empty() && matching {
+                info!("No matching versions of {} to remove", name);
+            } else if removed.is_empty() {
+                info!("No other versions of {} to remove", name);
+            } else {
+                let noun = if removed.len() == 1 {
+                    "version"
+                } else {
+                    "versions"
+                };
+                let removed: Vec<_> = removed.iter().map(ToString::to_string).collect();
+                info!(
+                    "{} removed {} {}: {}",
+                    success_prefix(),
+                    name,
+                    noun,
+                    removed.join(", ")
+                );
+            }
+
+            session.add_event_end(ActivityKind::Uninstall, ExitCode::Success);
+            return Ok(ExitCode::Success);
+        }
 
         // For packages, specifically report that we do not support uninstalling
         // specific versions. For runtimes and package managers, we currently
@@ -36,3 +80,17 @@ impl Command for Uninstall {
         Ok(ExitCode::Success)
     }
 }
+
+/// Whether a tool is a runtime or package manager with a version or range, so that the matching
+/// fetched versions are removed
+fn selects_versions(tool: &tool::Spec) -> bool {
+    match tool {
+        tool::Spec::Node(version)
+        | tool::Spec::Npm(version)
+        | tool::Spec::Pnpm(version)
+        | tool::Spec::Yarn(version) => {
+            matches!(version, VersionSpec::Semver(_) | VersionSpec::Exact(_))
+        }
+        tool::Spec::Package(..) => false,
+    }
+}
@@ -1,6 +1,8 @@
 use std::env;
 use std::ffi::OsString;
+use std::path::PathBuf;
 
+use serde::Serialize;
 use which::which_in;
 
 use volta_core::error::{Context, ErrorKind, ExitCode, Fallible};
@@ -14,62 +16,101 @@ use crate::command::Command;
 pub(crate) struct Which {
     /// The binary to find, e.g. `node` or `npm`
     binary: OsString,
+
+    /// Print the result as JSON, including where the binary was found
+    #[arg(long)]
+    json: bool,
+}
+
+/// Where `volta which` found a binary
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum Source {
+    /// A binary from the current project's dependencies
+    ProjectLocal,
+    /// A binary from a package installed as a user default
+    DefaultBinary,
+    /// A binary on the lookup path of the current platform image or the system
+    Path,
+    /// The binary couldn't be found
+    NotFound,
+}
+
+/// The structured output of `volta which --json`
+#[derive(Serialize)]
+struct WhichOutput {
+    tool: String,
+    path: Option<PathBuf>,
+    source: Source,
 }
 
 impl Command for Which {
-    // 1. Start by checking if the user has a tool installed in the project or
-    //    as a user default. If so, we're done.
-    // 2. Otherwise, use the platform image and/or the system environment to
-    //    determine a lookup path to run `which` in.
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Which);
 
-        let default_tool = DefaultBinary::from_name(&self.binary, session)?;
-        let project_bin_path = session
-            .project()?
-            .and_then(|project| project.find_bin(&self.binary));
+        let (source, path) = resolve_which(&self.binary, session)?;
 
-        let tool_path = match (default_tool, project_bin_path) {
-            (Some(_), Some(bin_path)) => Some(bin_path),
-            (Some(tool), _) => Some(tool.bin_path),
-            _ => None,
-        };
-
-        if let Some(path) = tool_path {
+        if self.json {
+            let output = WhichOutput {
+                tool: self.binary.to_string_lossy().into_owned(),
+                path: path.clone(),
+                source,
+            };
+            println!(
+                "{}",
+                serde_json::to_string(&output).expect("Which output is serializable")
+            );
+        } else if let Some(path) = &path {
             println!("{}", path.to_string_lossy());
-
-            let exit_code = ExitCode::Success;
-            session.add_event_end(ActivityKind::Which, exit_code);
-            return Ok(exit_code);
         }
 
-        // Treat any error with obtaining the current platform image as if the image doesn't exist
-        // However, errors in obtaining the current working directory or the System path should
-        // still be treated as errors.
-        let path = match Platform::current(session)
-            .unwrap_or(None)
-            .and_then(|platform| platform.checkout(session).ok())
-            .and_then(|image| image.path().ok())
-        {
-            Some(path) => path,
-            None => System::path()?,
-        };
-
-        let cwd = env::current_dir().with_context(|| ErrorKind::CurrentDirError)?;
-        let exit_code = match which_in(&self.binary, Some(path), cwd) {
-            Ok(result) => {
-                println!("{}", result.to_string_lossy());
-                ExitCode::Success
-            }
-            Err(_) => {
-                // `which_in` Will return an Err if it can't find the binary in the path
-                // In that case, we don't want to print anything out, but we want to return
-                // Exit Code 1 (ExitCode::UnknownError)
-                ExitCode::UnknownError
-            }
+        // If the binary can't be found, we don't print anything out in human mode, but we want to
+        // return Exit Code 1 (ExitCode::UnknownError)
+        let exit_code = match path {
+            Some(_) => ExitCode::Success,
+            None => ExitCode::UnknownError,
         };
 
         session.add_event_end(ActivityKind::Which, exit_code);
         Ok(exit_code)
     }
 }
+
+/// Finds the binary that would run for `binary`, along with where it was found
+///
+/// 1. Start by checking if the user has a tool installed in the project or
+///    as a user default. If so, we're done.
+/// 2. Otherwise, use the platform image and/or the system environment to
+///    determine a lookup path to run `which` in.
+fn resolve_which(binary: &OsString, session: &mut Session) -> Fallible<(Source, Option<PathBuf>)> {
+    let default_tool = DefaultBinary::from_name(binary, session)?;
+    let project_bin_path = session
+        .project()?
+        .and_then(|project| project.find_bin(binary));
+
+    match (default_tool, project_bin_path) {
+        (Some(_), Some(bin_path)) => return Ok((Source::ProjectLocal, Some(bin_path))),
+        (Some(tool), _) => return Ok((Source::DefaultBinary, Some(tool.bin_path))),
+        _ => {}
+    }
+
+    // Treat any error with obtaining the current platform image as if the image doesn't exist
+    // However, errors in obtaining the current working directory or the System path should
+    // still be treated as errors.
+    let path = match Platform::current(session)
+        .unwrap_or(None)
+        .and_then(|platform| platform.checkout(session).ok())
+        .and_then(|image| image.path().ok())
+    {
+        Some(path) => path,
+        None => System::path()?,
+    };
+
+    let cwd = env::current_dir().with_context(|| ErrorKind::CurrentDirError)?;
+
+    // `which_in` will return an Err if it can't find the binary in the path
+    Ok(match which_in(binary, Some(path), cwd) {
+        Ok(result) => (Source::Path, Some(result)),
+        Err(_) => (Source::NotFound, None),
+    })
+}
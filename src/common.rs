@@ -2,6 +2,7 @@ use std::process::{Command, ExitStatus};
 
 use volta_core::error::{Context, ErrorKind, VoltaError};
 use volta_core::layout::{volta_home, volta_install};
+use volta_core::session::Session;
 
 pub enum Error {
     Volta(VoltaError),
@@ -23,6 +24,13 @@ pub fn ensure_layout() -> Result<(), Error> {
     Ok(())
 }
 
+/// Migrates the Volta directory if needed, then starts a session, which confirms that the
+/// directory is up to date
+pub fn init_session() -> Result<Session, Error> {
+    ensure_layout()?;
+    Session::init().map_err(Error::Volta)
+}
+
 pub trait IntoResult<T> {
     fn into_result(self) -> Result<T, Error>;
 }
@@ -2,14 +2,16 @@
 mod command;
 mod cli;
 
+use std::process::exit;
+
 use clap::Parser;
 
 use volta_core::error::report_error;
 use volta_core::log::{LogContext, LogVerbosity, Logger};
-use volta_core::session::{ActivityKind, Session};
+use volta_core::session::ActivityKind;
 
 mod common;
-use common::{ensure_layout, Error};
+use common::{init_session, Error};
 
 /// The entry point for the `volta` CLI.
 pub fn main() {
@@ -31,20 +33,22 @@ pub fn main() {
     Logger::init(LogContext::Volta, verbosity).expect("Only a single logger should be initialized");
     log::trace!("log level: {verbosity:?}");
 
-    let mut session = Session::init();
+    let mut session = match init_session() {
+        Ok(session) => session,
+        Err(Error::Tool(code)) => exit(code),
+        Err(Error::Volta(err)) => {
+            report_error(env!("CARGO_PKG_VERSION"), &err);
+            err.exit_code().exit();
+        }
+    };
     session.add_event_start(ActivityKind::Volta);
 
-    let result = ensure_layout().and_then(|()| volta.run(&mut session).map_err(Error::Volta));
-    match result {
+    match volta.run(&mut session) {
         Ok(exit_code) => {
             session.add_event_end(ActivityKind::Volta, exit_code);
             session.exit(exit_code);
         }
-        Err(Error::Tool(code)) => {
-            session.add_event_tool_end(ActivityKind::Volta, code);
-            session.exit_tool(code);
-        }
-        Err(Error::Volta(err)) => {
+        Err(err) => {
             report_error(env!("CARGO_PKG_VERSION"), &err);
             session.add_event_error(ActivityKind::Volta, &err);
             let code = err.exit_code();
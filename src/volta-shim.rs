@@ -1,10 +1,12 @@
 mod common;
 
-use common::{ensure_layout, Error, IntoResult};
+use std::process::exit;
+
+use common::{init_session, Error, IntoResult};
 use volta_core::error::{report_error, ExitCode};
 use volta_core::log::{LogContext, LogVerbosity, Logger};
 use volta_core::run::execute_shim;
-use volta_core::session::{ActivityKind, Session};
+use volta_core::session::ActivityKind;
 use volta_core::signal::setup_signal_handler;
 
 pub fn main() {
@@ -12,11 +14,17 @@ pub fn main() {
         .expect("Only a single Logger should be initialized");
     setup_signal_handler();
 
-    let mut session = Session::init();
+    let mut session = match init_session() {
+        Ok(session) => session,
+        Err(Error::Tool(code)) => exit(code),
+        Err(Error::Volta(err)) => {
+            report_error(env!("CARGO_PKG_VERSION"), &err);
+            ExitCode::ExecutionFailure.exit();
+        }
+    };
     session.add_event_start(ActivityKind::Tool);
 
-    let result = ensure_layout().and_then(|()| execute_shim(&mut session).into_result());
-    match result {
+    match execute_shim(&mut session).into_result() {
         Ok(()) => {
             session.add_event_end(ActivityKind::Tool, ExitCode::Success);
             session.exit(ExitCode::Success);
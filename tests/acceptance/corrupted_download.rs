@@ -1,4 +1,6 @@
-use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture, PnpmFixture, Yarn1Fixture};
+use crate::support::sandbox::{
+    sandbox, DistroMetadata, NodeFixture, PnpmFixture, Sandbox, Yarn1Fixture,
+};
 use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
 use node_semver::Version;
@@ -36,6 +38,32 @@ const PNPM_VERSION_INFO: &str = r#"
 }
 "#;
 
+const PNPM_VERSION_INFO_WITH_SHASUM: &str = r#"
+{
+    "name":"pnpm",
+    "dist-tags": { "latest":"7.7.1" },
+    "versions": {
+        "7.7.1": {
+            "version":"7.7.1",
+            "dist": { "shasum":"1a873cc397b01b1a39ed04288bcc85ba2aefda28", "tarball":"" }
+        }
+    }
+}
+"#;
+
+const PNPM_VERSION_INFO_WITH_WRONG_SHASUM: &str = r#"
+{
+    "name":"pnpm",
+    "dist-tags": { "latest":"7.7.1" },
+    "versions": {
+        "7.7.1": {
+            "version":"7.7.1",
+            "dist": { "shasum":"2aae6c35c94fcfb415dbe95f408b9ce91ee846ed", "tarball":"" }
+        }
+    }
+}
+"#;
+
 const PNPM_VERSION_FIXTURES: [DistroMetadata; 2] = [
     DistroMetadata {
         version: "0.0.1",
@@ -54,7 +82,7 @@ const YARN_1_VERSION_INFO: &str = r#"{
     "dist-tags": { "latest": "1.2.42" },
     "versions": {
         "0.0.1": { "version":"0.0.1", "dist": { "shasum":"", "tarball":"" }},
-        "1.2.42": { "version":"1.2.42", "dist": { "shasum:"", "tarball":"" }}
+        "1.2.42": { "version":"1.2.42", "dist": { "shasum":"", "tarball":"" }}
     }
 }"#;
 
@@ -101,6 +129,24 @@ fn install_valid_node_saves_to_inventory() {
     assert!(s.node_inventory_archive_exists(&Version::parse("10.99.1040").unwrap()));
 }
 
+#[test]
+fn install_node_without_archive_cache() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_NO_ARCHIVE_CACHE", "1")
+        .build();
+
+    assert_that!(
+        s.volta("install node@10.99.1040"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    // The image is installed, but the archive isn't kept in the inventory
+    assert!(Sandbox::path_exists(".volta/tools/image/node/10.99.1040"));
+    assert!(!s.node_inventory_archive_exists(&Version::parse("10.99.1040").unwrap()));
+}
+
 #[test]
 fn install_corrupted_pnpm_leaves_inventory_unchanged() {
     let s = sandbox()
@@ -138,6 +184,51 @@ fn install_valid_pnpm_saves_to_inventory() {
     assert!(s.pnpm_inventory_archive_exists("7.7.1"));
 }
 
+#[test]
+fn install_pnpm_matching_published_shasum() {
+    let s = sandbox()
+        .platform(r#"{ "node": { "runtime": "1.2.3", "npm": null }, "yarn": null }"#)
+        .pnpm_available_versions(PNPM_VERSION_INFO_WITH_SHASUM)
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .env("VOLTA_FEATURE_PNPM", "1")
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.volta("install pnpm@7.7.1"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_contains("[..]Verified the integrity of [..]/pnpm/-/pnpm-7.7.1.tgz")
+    );
+
+    assert!(s.pnpm_inventory_archive_exists("7.7.1"));
+
+    // The checksum computed while verifying the download is the one that is recorded
+    let provenance = s.pnpm_image_provenance("7.7.1").unwrap();
+    assert!(provenance.source.ends_with("/pnpm/-/pnpm-7.7.1.tgz"));
+    assert!(provenance.checksum.starts_with("sha512-"));
+}
+
+#[test]
+fn install_pnpm_mismatching_published_shasum_is_not_unpacked() {
+    let s = sandbox()
+        .platform(r#"{ "node": { "runtime": "1.2.3", "npm": null }, "yarn": null }"#)
+        .pnpm_available_versions(PNPM_VERSION_INFO_WITH_WRONG_SHASUM)
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .env("VOLTA_FEATURE_PNPM", "1")
+        .build();
+
+    assert_that!(
+        s.volta("install pnpm@7.7.1"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Integrity check failed for pnpm@7.7.1")
+    );
+
+    assert!(!s.pnpm_inventory_archive_exists("7.7.1"));
+    assert!(!Sandbox::path_exists(".volta/tools/image/pnpm/7.7.1"));
+}
+
 #[test]
 fn install_corrupted_yarn_leaves_inventory_unchanged() {
     let s = sandbox()
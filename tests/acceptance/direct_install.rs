@@ -114,13 +114,16 @@ cfg_if::cfg_if! {
     }
 }
 
-const YARN_1_VERSION_INFO: &str = r#"[
-{"tag_name":"v1.2.42","assets":[{"name":"yarn-v1.2.42.tar.gz"}]},
-{"tag_name":"v1.3.1","assets":[{"name":"yarn-v1.3.1.msi"}]},
-{"tag_name":"v1.4.159","assets":[{"name":"yarn-v1.4.159.tar.gz"}]},
-{"tag_name":"v1.7.71","assets":[{"name":"yarn-v1.7.71.tar.gz"}]},
-{"tag_name":"v1.12.99","assets":[{"name":"yarn-v1.12.99.tar.gz"}]}
-]"#;
+const YARN_1_VERSION_INFO: &str = r#"{
+    "name":"yarn",
+    "dist-tags": { "latest":"1.12.99" },
+    "versions": {
+        "1.2.42": { "version":"1.2.42", "dist": { "shasum":"", "tarball":"" }},
+        "1.4.159": { "version":"1.4.159", "dist": { "shasum":"", "tarball":"" }},
+        "1.7.71": { "version":"1.7.71", "dist": { "shasum":"", "tarball":"" }},
+        "1.12.99": { "version":"1.12.99", "dist": { "shasum":"", "tarball":"" }}
+    }
+}"#;
 
 const YARN_1_VERSION_FIXTURES: [DistroMetadata; 4] = [
     DistroMetadata {
@@ -227,7 +230,7 @@ fn npm_global_install_npm_intercepts() {
         execs()
             .with_status(ExitCode::Success as i32)
             .with_stdout_contains("[..]using Volta to install npm")
-            .with_stdout_contains("[..]installed and set npm@8.1.5 as default")
+            .with_stdout_contains("[..]installed and set npm@8.1.5 as default[..]")
     );
 }
 
@@ -247,7 +250,7 @@ fn yarn_global_add_npm_intercepts() {
         execs()
             .with_status(ExitCode::Success as i32)
             .with_stdout_contains("[..]using Volta to install npm")
-            .with_stdout_contains("[..]installed and set npm@4.5.6 as default")
+            .with_stdout_contains("[..]installed and set npm@4.5.6 as default[..]")
     );
 }
 
@@ -267,7 +270,7 @@ fn npm_global_install_yarn_intercepts() {
         execs()
             .with_status(ExitCode::Success as i32)
             .with_stdout_contains("[..]using Volta to install Yarn")
-            .with_stdout_contains("[..]installed and set yarn@1.12.99 as default")
+            .with_stdout_contains("[..]installed and set yarn@1.12.99 as default[..]")
     );
 }
 
@@ -287,7 +290,7 @@ fn yarn_global_add_yarn_intercepts() {
         execs()
             .with_status(ExitCode::Success as i32)
             .with_stdout_contains("[..]using Volta to install Yarn")
-            .with_stdout_contains("[..]installed and set yarn@1.7.71 as default")
+            .with_stdout_contains("[..]installed and set yarn@1.7.71 as default[..]")
     );
 }
 
@@ -310,9 +313,34 @@ fn npm_global_install_supports_multiples() {
             .with_status(ExitCode::Success as i32)
             .with_stdout_contains("[..]Volta is processing each package separately")
             .with_stdout_contains("[..]using Volta to install npm")
-            .with_stdout_contains("[..]installed and set npm@8.1.5 as default")
+            .with_stdout_contains("[..]installed and set npm@8.1.5 as default[..]")
             .with_stdout_contains("[..]using Volta to install Yarn")
-            .with_stdout_contains("[..]installed and set yarn@1.12.99 as default")
+            .with_stdout_contains("[..]installed and set yarn@1.12.99 as default[..]")
+    );
+}
+
+#[test]
+fn npm_global_install_multiples_reports_unattempted() {
+    let s = sandbox()
+        .platform(&platform_with_node("10.99.1040"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .npm_available_versions(NPM_VERSION_INFO)
+        .distro_mocks::<NpmFixture>(&NPM_VERSION_FIXTURES)
+        .yarn_1_available_versions(YARN_1_VERSION_INFO)
+        .distro_mocks::<Yarn1Fixture>(&YARN_1_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.npm("i -g npm@8.1.5 yarn@1.99.99 cowsay typescript"),
+        execs()
+            .with_status(ExitCode::ExecutionFailure as i32)
+            .with_stdout_contains("[..]installed and set npm@8.1.5 as default[..]")
+            .with_stderr_contains("[..]Stopped after yarn@1.99.99 failed.")
+            .with_stderr_contains("[..]Succeeded: npm@8.1.5")
+            .with_stderr_contains("[..]Failed: yarn@1.99.99")
+            .with_stderr_contains("[..]Not attempted: cowsay, typescript")
     );
 }
 
@@ -352,9 +380,9 @@ fn yarn_global_add_supports_multiples() {
             .with_status(ExitCode::Success as i32)
             .with_stdout_contains("[..]Volta is processing each package separately")
             .with_stdout_contains("[..]using Volta to install npm")
-            .with_stdout_contains("[..]installed and set npm@8.1.5 as default")
+            .with_stdout_contains("[..]installed and set npm@8.1.5 as default[..]")
             .with_stdout_contains("[..]using Volta to install Yarn")
-            .with_stdout_contains("[..]installed and set yarn@1.12.99 as default")
+            .with_stdout_contains("[..]installed and set yarn@1.12.99 as default[..]")
     );
 }
 
@@ -6,6 +6,8 @@ use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
 use test_support::matchers::execs;
 
+use volta_core::error::ExitCode;
+
 const PKG_CONFIG_BASIC: &str = r#"{
   "name": "cowsay",
   "version": "1.4.0",
@@ -345,3 +347,98 @@ fn project_local_binary_pnp() {
             .with_stdout_does_not_contain("Yarn version 1.23.483")
     );
 }
+
+const PACKAGE_JSON_WITH_ENV: &str = r#"{
+    "name": "with-env",
+    "volta": {
+        "node": "10.99.1040",
+        "env": {
+            "VOLTA_TEST_GREETING": "hello from the project"
+        }
+    }
+}"#;
+
+fn node_bin_printing_greeting() -> String {
+    cfg_if! {
+        if #[cfg(target_os = "windows")] {
+            String::from("@echo off\necho greeting: %VOLTA_TEST_GREETING%\n")
+        } else {
+            String::from("#!/bin/sh\necho \"greeting: $VOLTA_TEST_GREETING\"\n")
+        }
+    }
+}
+
+#[test]
+fn project_env_is_set_for_tools() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_ENV)
+        .shim("node")
+        .setup_node_binary("10.99.1040", "6.7.0", &node_bin_printing_greeting())
+        .add_dir_to_path(PathBuf::from("/bin"))
+        .build();
+
+    assert_that!(
+        s.exec_shim("node", ""),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("greeting: hello from the project")
+    );
+}
+
+#[test]
+fn project_env_does_not_override_user_env() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_ENV)
+        .shim("node")
+        .setup_node_binary("10.99.1040", "6.7.0", &node_bin_printing_greeting())
+        .add_dir_to_path(PathBuf::from("/bin"))
+        .env("VOLTA_TEST_GREETING", "hello from the user")
+        .build();
+
+    assert_that!(
+        s.exec_shim("node", ""),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("greeting: hello from the user")
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn run_captures_tool_output() {
+    let s = sandbox()
+        .platform(PLATFORM_NODE_NPM)
+        .setup_node_binary(
+            "11.10.1",
+            "6.7.0",
+            "#!/bin/sh\necho \"node args: $@\"\necho \"warning from node\" >&2\nexit 4\n",
+        )
+        .add_dir_to_path(PathBuf::from("/bin"))
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    // The output is still shown, and the exit code of the tool is unchanged
+    assert_that!(
+        s.volta("run --capture node hello"),
+        execs()
+            .with_status(ExitCode::ExecutionFailure as i32)
+            .with_stdout_contains("node args: hello")
+            .with_stderr_contains("warning from node")
+            .with_stderr_contains("[..]Captured output in [..]volta-run-[..]")
+    );
+
+    let capture_dir = s
+        .read_log_dir()
+        .expect("log directory exists")
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().contains("volta-run-"))
+        .expect("capture directory exists");
+    assert_eq!(
+        std::fs::read_to_string(capture_dir.join("stdout.log")).unwrap(),
+        "node args: hello\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(capture_dir.join("stderr.log")).unwrap(),
+        "warning from node\n"
+    );
+}
@@ -512,3 +512,31 @@ fn yarn_semver_with_hook_uses_configured_format() {
             .with_stderr_contains("[..]Could not download yarn@3.12.99")
     );
 }
+
+#[test]
+fn node_not_found_reports_index_hook_url() {
+    let hooks = format!(
+        r#"{{ "node": {{ "index": {{ "prefix": "{}/mirror/node/" }} }} }}"#,
+        mockito::server_url()
+    );
+    let s = sandbox().default_hooks(&hooks).build();
+    let _mock = mock("GET", "/mirror/node/index.json")
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(
+            r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]"#,
+        )
+        .create();
+
+    assert_that!(
+        s.volta("install node@12"),
+        execs()
+            .with_status(ExitCode::NoVersionMatch as i32)
+            .with_stderr_contains(format!(
+                "[..]Could not find Node version matching \"[..]\" in the version registry (searched {}/mirror/node/index.json).",
+                mockito::server_url()
+            ))
+    );
+}
@@ -15,9 +15,12 @@ cfg_if! {
         mod run_shim_directly;
         mod verbose_errors;
         mod volta_bypass;
+        mod volta_fetch;
         mod volta_install;
+        mod volta_list;
         mod volta_pin;
         mod volta_run;
         mod volta_uninstall;
+        mod volta_which;
     }
 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeSet;
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fs::{self, File};
@@ -11,7 +12,7 @@ use mockito::{self, mock, Matcher};
 use node_semver::Version;
 use test_support::{self, ok_or_panic, paths, paths::PathExt, process::ProcessBuilder};
 use volta_core::fs::{set_executable, symlink_file};
-use volta_core::tool::{Node, Pnpm, Yarn};
+use volta_core::tool::{read_provenance, Node, Pnpm, Provenance, Yarn};
 
 // version cache for node and yarn
 #[derive(PartialEq, Clone)]
@@ -153,12 +154,21 @@ pub struct SandboxBuilder {
     path_dirs: Vec<PathBuf>,
     shims: Vec<ShimBuilder>,
     has_exec_path: bool,
+    /// Registry documents that tests have mocked explicitly
+    mocked_documents: BTreeSet<&'static str>,
+    /// Registry documents for the mocked distros, which are needed to look up their integrity
+    distro_documents: BTreeSet<&'static str>,
 }
 
 pub trait DistroFixture: From<DistroMetadata> {
     fn server_path(&self) -> String;
     fn fixture_path(&self) -> String;
     fn metadata(&self) -> &DistroMetadata;
+
+    /// The path of the registry document for the package, for fixtures served by the npm registry
+    fn registry_document(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -248,6 +258,10 @@ impl DistroFixture for NpmFixture {
     fn metadata(&self) -> &DistroMetadata {
         &self.metadata
     }
+
+    fn registry_document(&self) -> Option<&'static str> {
+        Some("/npm")
+    }
 }
 
 impl DistroFixture for PnpmFixture {
@@ -262,6 +276,10 @@ impl DistroFixture for PnpmFixture {
     fn metadata(&self) -> &DistroMetadata {
         &self.metadata
     }
+
+    fn registry_document(&self) -> Option<&'static str> {
+        Some("/pnpm")
+    }
 }
 
 impl DistroFixture for Yarn1Fixture {
@@ -276,6 +294,10 @@ impl DistroFixture for Yarn1Fixture {
     fn metadata(&self) -> &DistroMetadata {
         &self.metadata
     }
+
+    fn registry_document(&self) -> Option<&'static str> {
+        Some("/yarn")
+    }
 }
 
 impl DistroFixture for YarnBerryFixture {
@@ -293,6 +315,10 @@ impl DistroFixture for YarnBerryFixture {
     fn metadata(&self) -> &DistroMetadata {
         &self.metadata
     }
+
+    fn registry_document(&self) -> Option<&'static str> {
+        Some("/@yarnpkg/cli-dist")
+    }
 }
 
 impl SandboxBuilder {
@@ -319,6 +345,8 @@ impl SandboxBuilder {
                 ShimBuilder::new("yarn".to_string()),
             ],
             has_exec_path: false,
+            mocked_documents: BTreeSet::new(),
+            distro_documents: BTreeSet::new(),
         }
     }
 
@@ -380,6 +408,7 @@ impl SandboxBuilder {
 
     /// Setup mock to return the available Yarn@1 versions (chainable)
     pub fn yarn_1_available_versions(mut self, body: &str) -> Self {
+        self.mocked_documents.insert("/yarn");
         let mock = mock("GET", "/yarn")
             .with_status(200)
             .with_header("content-type", "application/json")
@@ -391,6 +420,7 @@ impl SandboxBuilder {
 
     /// Setup mock to return the available Yarn@2+ versions (chainable)
     pub fn yarn_berry_available_versions(mut self, body: &str) -> Self {
+        self.mocked_documents.insert("/@yarnpkg/cli-dist");
         let mock = mock("GET", "/@yarnpkg/cli-dist")
             .with_status(200)
             .with_header("content-type", "application/json")
@@ -402,6 +432,7 @@ impl SandboxBuilder {
 
     /// Setup mock to return the available npm versions (chainable)
     pub fn npm_available_versions(mut self, body: &str) -> Self {
+        self.mocked_documents.insert("/npm");
         let mock = mock("GET", "/npm")
             .with_status(200)
             .with_header("content-type", "application/json")
@@ -414,6 +445,7 @@ impl SandboxBuilder {
 
     /// Setup mock to return the available pnpm versions (chainable)
     pub fn pnpm_available_versions(mut self, body: &str) -> Self {
+        self.mocked_documents.insert("/pnpm");
         let mock = mock("GET", "/pnpm")
             .with_status(200)
             .with_header("content-type", "application/json")
@@ -433,13 +465,33 @@ impl SandboxBuilder {
         self
     }
 
-    fn distro_mock<T: DistroFixture>(mut self, fx: &T) -> Self {
+    /// Setup mock to return the available node versions from the official server used by
+    /// `VOLTA_MIRROR_FALLBACK` (chainable)
+    pub fn node_fallback_versions(mut self, body: &str) -> Self {
+        let mock = mock("GET", "/node-official/index.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+        self.root.mocks.push(mock);
+
+        self
+    }
+
+    fn distro_mock<T: DistroFixture>(self, fx: &T) -> Self {
+        self.distro_mock_at("", fx)
+    }
+
+    fn distro_mock_at<T: DistroFixture>(mut self, root: &str, fx: &T) -> Self {
         // ISSUE(#145): this should actually use a real http server instead of these mocks
 
-        let server_path = fx.server_path();
+        let server_path = format!("{}{}", root, fx.server_path());
         let fixture_path = fx.fixture_path();
 
         let metadata = fx.metadata();
+        if let Some(document) = fx.registry_document() {
+            self.distro_documents.insert(document);
+        }
 
         if let Some(uncompressed_size) = metadata.uncompressed_size {
             // This can be abstracted when https://github.com/rust-lang/rust/issues/52963 lands.
@@ -475,6 +527,27 @@ impl SandboxBuilder {
         this
     }
 
+    /// Setup mocks to serve Node distros from the official server used by
+    /// `VOLTA_MIRROR_FALLBACK` (chainable)
+    pub fn node_fallback_distro_mocks(self, fixtures: &[DistroMetadata]) -> Self {
+        let mut this = self;
+        for fixture in fixtures {
+            this = this.distro_mock_at::<NodeFixture>("/node-official", &fixture.clone().into());
+        }
+        this
+    }
+
+    /// Setup a mock to serve the Node distro for another platform from the fixtures (chainable)
+    pub fn node_target_distro_mock(mut self, version: &str, filename: &str) -> Self {
+        let mock = mock("GET", &format!("/v{version}/{filename}")[..])
+            .with_header("Accept-Ranges", "bytes")
+            .with_body_from_file(format!("tests/fixtures/{filename}"))
+            .create();
+        self.root.mocks.push(mock);
+
+        self
+    }
+
     /// Add an arbitrary file to the sandbox (chainable)
     pub fn file(mut self, path: &str, contents: &str) -> Self {
         let file_name = sandbox_path(path);
@@ -698,6 +771,21 @@ impl SandboxBuilder {
         ok_or_panic! { fs::create_dir_all(yarn_inventory_dir()) };
         ok_or_panic! { fs::create_dir_all(volta_tmp_dir()) };
 
+        // serve an empty registry document for any distro whose document wasn't mocked, so that
+        // looking up its integrity finds no checksum to verify against
+        for document in self.distro_documents.difference(&self.mocked_documents) {
+            let body = format!(
+                r#"{{"name":"{}","dist-tags":{{}},"versions":{{}}}}"#,
+                document.trim_start_matches('/')
+            );
+            let mock = mock("GET", *document)
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(body)
+                .create();
+            self.root.mocks.push(mock);
+        }
+
         // write node and yarn caches
         for cache in self.caches.iter() {
             cache.build();
@@ -928,6 +1016,10 @@ impl Sandbox {
         volta_home().rm_rf();
     }
 
+    pub fn remove_node_image(&self, version: &str) {
+        node_image_dir(version).rm_rf();
+    }
+
     // check that files in the sandbox exist
 
     pub fn node_inventory_archive_exists(&self, version: &Version) -> bool {
@@ -936,6 +1028,22 @@ impl Sandbox {
             .exists()
     }
 
+    pub fn node_image_exists(&self, version: &str) -> bool {
+        node_image_dir(version).exists()
+    }
+
+    pub fn node_target_archive_exists(&self, target: &str, filename: &str) -> bool {
+        node_inventory_dir().join(target).join(filename).exists()
+    }
+
+    pub fn node_image_provenance(&self, version: &str) -> Option<Provenance> {
+        read_provenance(&node_image_dir(version)).unwrap()
+    }
+
+    pub fn pnpm_image_provenance(&self, version: &str) -> Option<Provenance> {
+        read_provenance(&pnpm_image_dir(version)).unwrap()
+    }
+
     pub fn pnpm_inventory_archive_exists(&self, version: &str) -> bool {
         pnpm_inventory_dir()
             .join(Pnpm::archive_filename(version))
@@ -964,6 +1072,9 @@ impl Sandbox {
         let package_img_dir = package_image_dir(name);
         package_img_dir.join("package.json").exists()
     }
+    pub fn read_node_index_cache() -> String {
+        read_file_to_string(node_index_file())
+    }
     pub fn read_default_platform() -> String {
         read_file_to_string(default_platform_file())
     }
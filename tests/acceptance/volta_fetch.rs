@@ -0,0 +1,74 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::error::ExitCode;
+
+const NODE_VERSION_INFO: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v9.27.6","npm":"5.6.17","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "windows")] {
+        // A target other than the one the tests are running on
+        const OTHER_TARGET: &str = "linux-arm64";
+        const OTHER_TARGET_ARCHIVE: &str = "node-v10.99.1040-linux-arm64.tar.gz";
+    } else {
+        const OTHER_TARGET: &str = "win-x64";
+        const OTHER_TARGET_ARCHIVE: &str = "node-v10.99.1040-win-x64.zip";
+    }
+}
+
+#[test]
+fn fetch_node_for_other_target() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .node_target_distro_mock("10.99.1040", OTHER_TARGET_ARCHIVE)
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta(&format!("fetch --target {} node@10", OTHER_TARGET)),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains(format!("[..]fetched node@10.99.1040 for {}", OTHER_TARGET))
+    );
+
+    assert!(s.node_target_archive_exists(OTHER_TARGET, OTHER_TARGET_ARCHIVE));
+    // Node for another platform can't be used here, so it isn't unpacked
+    assert!(!s.node_image_exists("10.99.1040"));
+}
+
+#[test]
+fn fetch_node_for_other_target_uses_inventory() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .node_target_distro_mock("10.99.1040", OTHER_TARGET_ARCHIVE)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    let command = format!("fetch --target {} node@10.99.1040", OTHER_TARGET);
+    assert_that!(s.volta(&command), execs().with_status(0));
+    assert_that!(
+        s.volta(&command),
+        execs().with_status(0).with_stderr_contains(format!(
+            "[..]Found node@10.99.1040 for {} in the inventory",
+            OTHER_TARGET
+        ))
+    );
+}
+
+#[test]
+fn fetch_with_invalid_target() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("fetch --target linux-amd64 node@10"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Invalid target platform 'linux-amd64'")
+    );
+}
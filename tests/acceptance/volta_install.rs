@@ -4,9 +4,12 @@ use crate::support::sandbox::{
 };
 use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
+use node_semver::Version;
 use test_support::matchers::execs;
+use test_support::paths;
 
 use volta_core::error::ExitCode;
+use volta_core::tool::Node;
 
 fn platform_with_node(node: &str) -> String {
     format!(
@@ -44,6 +47,13 @@ const NODE_VERSION_INFO: &str = r#"[
 ]
 "#;
 
+/// The Node index from a mirror that doesn't have the newest release yet
+const NODE_VERSION_INFO_WITHOUT_LATEST: &str = r#"[
+{"version":"v9.27.6","npm":"5.6.17","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v8.9.10","npm":"5.6.7","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
 cfg_if::cfg_if! {
     if #[cfg(target_os = "macos")] {
         const NODE_VERSION_FIXTURES: [DistroMetadata; 4] = [
@@ -119,13 +129,16 @@ cfg_if::cfg_if! {
     }
 }
 
-const YARN_1_VERSION_INFO: &str = r#"[
-{"tag_name":"v1.2.42","assets":[{"name":"yarn-v1.2.42.tar.gz"}]},
-{"tag_name":"v1.3.1","assets":[{"name":"yarn-v1.3.1.msi"}]},
-{"tag_name":"v1.4.159","assets":[{"name":"yarn-v1.4.159.tar.gz"}]},
-{"tag_name":"v1.7.71","assets":[{"name":"yarn-v1.7.71.tar.gz"}]},
-{"tag_name":"v1.12.99","assets":[{"name":"yarn-v1.12.99.tar.gz"}]}
-]"#;
+const YARN_1_VERSION_INFO: &str = r#"{
+    "name":"yarn",
+    "dist-tags": { "latest":"1.12.99" },
+    "versions": {
+        "1.2.42": { "version":"1.2.42", "dist": { "shasum":"", "tarball":"" }},
+        "1.4.159": { "version":"1.4.159", "dist": { "shasum":"", "tarball":"" }},
+        "1.7.71": { "version":"1.7.71", "dist": { "shasum":"", "tarball":"" }},
+        "1.12.99": { "version":"1.12.99", "dist": { "shasum":"", "tarball":"" }}
+    }
+}"#;
 
 const YARN_1_VERSION_FIXTURES: [DistroMetadata; 4] = [
     DistroMetadata {
@@ -406,3 +419,362 @@ fn install_node_with_shadowed_binary() {
             .with_stdout_contains("[..]is shadowed by another binary of the same name at [..]")
     );
 }
+
+#[test]
+fn install_node_traces_resolution() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.volta("install node@9"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_contains("[..]Resolving node '>=9.0.0 <10.0.0-0'")
+            .with_stderr_contains("    index: [..]/node-dist/index.json (from default index)")
+            .with_stderr_contains("    entries considered: 4")
+            .with_stderr_contains("    filter: satisfies '>=9.0.0 <10.0.0-0'")
+            .with_stderr_contains("    chosen: 9.27.6")
+    );
+}
+
+#[test]
+fn install_node_system_runs_node_from_path() {
+    #[cfg(windows)]
+    const SCRIPT_FILENAME: &str = "node.bat";
+    #[cfg(not(windows))]
+    const SCRIPT_FILENAME: &str = "node";
+
+    let s = sandbox()
+        .env("VOLTA_LOGLEVEL", "info")
+        .executable_file(SCRIPT_FILENAME, "echo system node")
+        .shim("node")
+        .build();
+
+    assert_that!(
+        s.volta("install node@system"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]installed and set node@system as default")
+    );
+    assert!(Sandbox::read_default_platform().contains(r#""runtime": "system""#));
+
+    assert_that!(
+        s.exec_shim("node", "--version"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("system node")
+    );
+}
+
+#[test]
+fn install_node_system_without_system_node_errors() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("install node@system"),
+        execs()
+            .with_status(ExitCode::ExecutableNotFound as i32)
+            .with_stderr_contains("[..]Could not find a system Node install.")
+    );
+}
+
+#[test]
+fn install_node_records_provenance() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("install node@10.99.1040"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    let provenance = s.node_image_provenance("10.99.1040").unwrap();
+    assert!(provenance.source.contains("/v10.99.1040/node-v10.99.1040-"));
+    assert!(provenance.checksum.starts_with("sha512-"));
+}
+
+#[test]
+fn install_node_falls_back_to_official_server() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO_WITHOUT_LATEST)
+        .node_fallback_versions(NODE_VERSION_INFO)
+        .node_fallback_distro_mocks(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_MIRROR_FALLBACK", "1")
+        .build();
+
+    assert_that!(
+        s.volta("install node@10"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    let provenance = s.node_image_provenance("10.99.1040").unwrap();
+    assert!(provenance
+        .source
+        .contains("/node-official/v10.99.1040/node-v10.99.1040-"));
+
+    // The official index isn't cached, so the mirror's cached index is kept
+    let cache = Sandbox::read_node_index_cache();
+    let header = cache.lines().next().unwrap();
+    assert!(header.contains("/node-dist/index.json"));
+    assert!(!header.contains("/node-official/"));
+}
+
+#[test]
+fn install_node_without_fallback_uses_mirror_only() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO_WITHOUT_LATEST)
+        .node_fallback_versions(NODE_VERSION_INFO)
+        .node_fallback_distro_mocks(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("install node@10"),
+        execs()
+            .with_status(ExitCode::NoVersionMatch as i32)
+            .with_stderr_contains("[..]Could not find Node version matching[..]")
+    );
+}
+
+#[test]
+fn install_missing_exact_node_suggests_nearest_versions() {
+    let s = sandbox()
+        .mock_not_found()
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("install node@9.99.0"),
+        execs()
+            .with_status(ExitCode::NoVersionMatch as i32)
+            .with_stderr_contains("[..]The closest available versions are: 9.27.6, 10.99.1040[..]")
+    );
+}
+
+#[test]
+fn install_node_already_default_does_not_rewrite_platform() {
+    // Deliberately not formatted the way Volta writes it, so that any save would be visible
+    let platform = r#"{"node":{"runtime":"10.99.1040","npm":null},"pnpm":null,"yarn":null}"#;
+    let s = sandbox()
+        .platform(platform)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta("install node@10.99.1040"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]node@10.99.1040 is already installed and set as default")
+            .with_stdout_does_not_contain("[..]installed and set node@10.99.1040[..]")
+    );
+
+    assert_eq!(Sandbox::read_default_platform(), platform);
+}
+
+#[test]
+fn install_node_summary_reports_download_or_cache() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta("install node@10.99.1040"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains(
+                "[..]installed and set node@10.99.1040 (with npm@6.2.26) as default (downloaded [..] KiB in [..]s)"
+            )
+    );
+    assert_that!(
+        s.volta("install node@9.27.6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    // The archive is still in the inventory, so installing again doesn't download it
+    s.remove_node_image("10.99.1040");
+    assert_that!(
+        s.volta("install node@10.99.1040"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains(
+                "[..]installed and set node@10.99.1040[..] as default (from cache)"
+            )
+            .with_stdout_does_not_contain("Downloading[..]")
+    );
+}
+
+#[test]
+fn install_node_with_job_tmp_and_inventory() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+    let job_dir = paths::home().join("ci-job-1");
+    let archive = Node::archive_filename(&Version::parse("10.99.1040").unwrap());
+
+    assert_that!(
+        s.volta("install node@10.99.1040")
+            .env("VOLTA_TMP", job_dir.join("tmp"))
+            .env("VOLTA_INVENTORY", job_dir.join("inventory")),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    // The archive is cached in the job's own inventory and staged in its own tmp directory,
+    // while the image is published to the shared Volta home
+    assert!(job_dir
+        .join("inventory")
+        .join("node")
+        .join(archive)
+        .exists());
+    assert!(job_dir
+        .join("inventory")
+        .join("node")
+        .join("node-v10.99.1040-npm")
+        .exists());
+    assert!(job_dir.join("tmp").is_dir());
+    assert!(!s.node_inventory_archive_exists(&Version::parse("10.99.1040").unwrap()));
+    assert!(s.node_image_exists("10.99.1040"));
+}
+
+#[test]
+fn install_selected_bins_requires_single_package() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("install --bin tsc node@10.99.1040"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains(
+                "[..]Executables can only be selected when installing a single package"
+            )
+    );
+
+    assert_that!(
+        s.volta("install --bin tsc typescript eslint"),
+        execs().with_status(ExitCode::InvalidArguments as i32)
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn setup_installs_default_node() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("setup --install node@lts"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert!(s.node_image_exists("10.99.1040"));
+    assert!(Sandbox::read_default_platform().contains(r#""runtime": "10.99.1040""#));
+}
+
+#[test]
+#[cfg(unix)]
+fn setup_keeps_existing_default() {
+    let s = sandbox()
+        .platform(&platform_with_node("8.9.10"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_SETUP_TOOLS", "node@lts")
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta("setup"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains(
+                "[..]Default Node 8.9.10 is already set, skipping the default tools."
+            )
+    );
+
+    assert!(!s.node_image_exists("10.99.1040"));
+    assert!(Sandbox::read_default_platform().contains(r#""runtime": "8.9.10""#));
+}
+
+#[test]
+fn install_node_in_project_with_unfetched_node_version_file() {
+    let s = sandbox()
+        .package_json(r#"{ "name": "node-version-file" }"#)
+        .project_file(".node-version", "v10\n")
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    // The partial version in the file doesn't need to be fetched for the project to load
+    assert_that!(
+        s.volta("install node@10"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]installed and set node@10.99.1040[..]")
+    );
+
+    assert!(s.node_image_exists("10.99.1040"));
+}
+
+#[test]
+fn install_node_fails_over_to_next_mirror_index() {
+    let mirrors = format!(
+        "{0}/unavailable-mirror, {0}/node-dist",
+        mockito::server_url()
+    );
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("ENV_NODE_MIRROR", &mirrors)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.volta("install node@10"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_contains(
+                "[..]unavailable-mirror/index.json failed, trying the next mirror[..]"
+            )
+            .with_stderr_contains("[..]Downloaded successfully from [..]/node-dist/index.json")
+    );
+
+    assert!(s.node_image_exists("10.99.1040"));
+}
+
+#[test]
+fn install_node_dry_run_shows_plan() {
+    let image_root = paths::home().join("images");
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_IMAGE_ROOT", &image_root.to_string_lossy())
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta("install --dry-run node@10.99.1040"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("install node@10.99.1040")
+            .with_stdout_contains("    download from [..]/v10.99.1040/node-v10.99.1040-[..]")
+            .with_stdout_contains(format!(
+                "    unpack into '{}'",
+                image_root.join("node").join("10.99.1040").display()
+            ))
+            .with_stdout_contains("    set as default")
+    );
+
+    // Nothing was installed
+    assert!(!image_root.join("node").join("10.99.1040").exists());
+    assert!(Sandbox::read_default_platform().is_empty());
+}
@@ -0,0 +1,36 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::error::ExitCode;
+
+const RANGED_PACKAGE_JSON: &str = r#"{
+  "name": "ranged-project",
+  "volta": {
+    "node": "^10"
+  }
+}"#;
+
+#[test]
+fn list_node_highlights_versions_satisfying_pinned_range() {
+    let s = sandbox()
+        .package_json(RANGED_PACKAGE_JSON)
+        .setup_node_binary("10.99.1040", "6.2.26", "")
+        .setup_node_binary("10.1.0", "5.6.0", "")
+        .setup_node_binary("9.27.6", "5.6.17", "")
+        .build();
+
+    // The range resolves to the newest fetched match, and other matches are marked as satisfying it
+    assert_that!(
+        s.volta("list node --format plain"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("runtime node@10.99.1040 (current @ [..]package.json)")
+            .with_stdout_contains(
+                "runtime node@10.1.0 (satisfies >=10.0.0 <11.0.0-0 @ [..]package.json)"
+            )
+            .with_stdout_contains("runtime node@9.27.6")
+            .with_stdout_does_not_contain("runtime node@9.27.6 ([..]")
+    );
+}
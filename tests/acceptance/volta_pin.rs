@@ -3,6 +3,7 @@ use crate::support::sandbox::{
 };
 use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
+use node_semver::Version;
 use test_support::matchers::execs;
 
 use volta_core::error::ExitCode;
@@ -338,7 +339,12 @@ fn pin_node() {
     assert_eq!(
         s.read_package_json(),
         package_json_with_pinned_node("6.19.62"),
-    )
+    );
+    // The first pin creates the lockfile
+    assert_eq!(
+        std::fs::read_to_string(s.root().join("volta.lock.json")).unwrap(),
+        "{\n  \"node\": \"6.19.62\"\n}\n"
+    );
 }
 
 #[test]
@@ -396,6 +402,27 @@ fn pin_node_no_version() {
     )
 }
 
+#[test]
+fn pin_node_no_version_uses_default() {
+    let s = sandbox()
+        .platform(r#"{"node":{"runtime":"8.9.10","npm":null},"pnpm":null,"yarn":null}"#)
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin node"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    // The default version is pinned exactly, rather than the latest LTS (10.99.1040)
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("8.9.10"),
+    )
+}
+
 #[test]
 fn pin_node_informs_newer_npm() {
     let s = sandbox()
@@ -700,6 +727,27 @@ fn pin_yarn_3_missing_release_v2() {
     )
 }
 
+#[test]
+fn pin_yarn_prefetches_pinned_node() {
+    let s = sandbox()
+        .package_json(&package_json_with_pinned_node("10.99.1040"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .yarn_1_available_versions(YARN_1_VERSION_INFO)
+        .yarn_berry_available_versions(YARN_BERRY_VERSION_INFO)
+        .distro_mocks::<Yarn1Fixture>(&YARN_1_VERSION_FIXTURES)
+        .env("VOLTA_PREFETCH_ON_PIN", "1")
+        .build();
+
+    assert_that!(
+        s.volta("pin yarn@1.4"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert!(s.node_inventory_archive_exists(&Version::parse("10.99.1040").unwrap()));
+    assert!(s.yarn_inventory_archive_exists("1.4.159"));
+}
+
 #[test]
 fn pin_yarn_leaves_npm() {
     let s = sandbox()
@@ -1109,3 +1157,42 @@ fn pin_pnpm_leaves_npm() {
         package_json_with_pinned_node_npm_pnpm("1.2.3", "3.4.5", "6.34.0"),
     )
 }
+
+#[test]
+fn pin_frozen_keeps_lock() {
+    let lockfile = "{\n  \"node\": \"9.27.6\"\n}\n";
+    let s = sandbox()
+        .package_json(&package_json_with_pinned_node("9.27.6"))
+        .project_file("volta.lock.json", lockfile)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_FROZEN", "1")
+        .build();
+
+    assert_that!(
+        s.volta("pin node@10"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("The lockfile has node@9.27.6, but node@[..] was requested.[..]")
+    );
+    assert_that!(
+        s.volta("pin --update-lock node@9"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("Updating the lockfile would resolve its versions again.[..]")
+    );
+    // A requirement that the locked version satisfies is resolved to it
+    assert_that!(
+        s.volta("pin node@9"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("9.27.6")
+    );
+    assert_eq!(
+        std::fs::read_to_string(s.root().join("volta.lock.json")).unwrap(),
+        lockfile
+    );
+}
@@ -281,6 +281,25 @@ fn command_line_node() {
     );
 }
 
+#[test]
+fn command_line_node_with_corrupt_default() {
+    let s = sandbox()
+        .layout_file("v4")
+        .platform(r#"{ "node": { "runtime": "#)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env(VOLTA_LOGLEVEL, "debug")
+        .build();
+
+    assert_that!(
+        s.volta("run --node 10.99.1040 node --version"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_contains("[..]Ignoring the default platform[..]")
+            .with_stderr_contains("[..]Node: 10.99.1040 from command-line configuration")
+    );
+}
+
 #[test]
 fn inherited_node() {
     let s = sandbox()
@@ -515,3 +534,179 @@ fn force_no_pnpm() {
             .with_stderr_contains("[..]No pnpm version found in this project.")
     );
 }
+
+#[test]
+fn frozen_with_outdated_lock() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node("9.27.6"))
+        .project_file("volta.lock.json", r#"{ "node": "8.9.10" }"#)
+        .build();
+
+    assert_that!(
+        s.volta("--frozen run node --version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Could not use the frozen project lockfile")
+            .with_stderr_contains("The lockfile has node@8.9.10, but node@9.27.6 is needed.[..]")
+    );
+
+    // The lockfile is only checked when it is frozen
+    assert_that!(
+        s.volta("run node --version"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+}
+
+#[test]
+fn frozen_without_lock() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node("9.27.6"))
+        .build();
+
+    assert_that!(
+        s.volta("--frozen run node --version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Could not use the frozen project lockfile")
+            .with_stderr_contains("The project doesn't have a lockfile.[..]")
+    );
+}
+
+#[test]
+fn frozen_does_not_resolve_ranges_remotely() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node("^10"))
+        .project_file("volta.lock.json", r#"{ "node": "9.27.6" }"#)
+        .env(VOLTA_LOGLEVEL, "debug")
+        .build();
+
+    assert_that!(
+        s.volta("--frozen run node --version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "The lockfile doesn't have a version of node matching '>=10.0.0 <11.0.0-0'.[..]"
+            )
+            .with_stderr_does_not_contain("[..]Fetching public registry[..]")
+    );
+}
+
+#[test]
+fn unreleased_pinned_node_fails_by_default() {
+    let s = sandbox()
+        .platform(r#"{ "node": { "runtime": "8.9.10", "npm": null } }"#)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node("21.99.0"))
+        .build();
+
+    assert_that!(
+        s.volta("run node --version"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Could not download node@21.99.0[..]")
+    );
+}
+
+#[test]
+fn unreleased_pinned_node_falls_back_when_lenient() {
+    let s = sandbox()
+        .platform(r#"{ "node": { "runtime": "8.9.10", "npm": null } }"#)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node("21.99.0"))
+        .env("VOLTA_STRICT_PIN", "0")
+        .env(VOLTA_LOGLEVEL, "debug")
+        .build();
+
+    assert_that!(
+        s.volta("run node --version"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains(
+                "[..]could not fetch node@21.99.0, which is pinned by the project, so the default node@8.9.10 is used.[..]"
+            )
+            .with_stderr_contains("[..]Node: 8.9.10 from default configuration")
+    );
+}
+
+#[test]
+fn npx_requires_npm_with_npx() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .npm_available_versions(NPM_VERSION_INFO)
+        .distro_mocks::<NpmFixture>(&NPM_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node_npm("9.27.6", "4.5.6"))
+        .build();
+
+    assert_that!(
+        s.volta("run npx cowsay"),
+        execs()
+            .with_status(ExitCode::ExecutableNotFound as i32)
+            .with_stderr_contains("[..]'npx' is only available with npm >= 5.2.0")
+            .with_stderr_contains("[..]The active version of npm is 4.5.6.")
+    );
+}
+
+#[test]
+fn npm_exec_requires_npm_7() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node("9.27.6"))
+        .build();
+
+    // The bundled npm provides npx, but not `npm exec`
+    assert_that!(
+        s.volta("run npm exec cowsay"),
+        execs()
+            .with_status(ExitCode::ExecutableNotFound as i32)
+            .with_stderr_contains("[..]'npm exec' is only available with npm >= 7.0.0")
+            .with_stderr_contains(
+                "[..]The active version of npm is 5.6.17, which is bundled with Node 9.27.6."
+            )
+    );
+
+    // Other npm commands aren't affected
+    assert_that!(
+        s.volta("run npm --version"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+}
+
+#[test]
+fn profile_reports_phases() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .package_json(&package_json_with_pinned_node("9.27.6"))
+        .env("VOLTA_PROFILE", "1")
+        .build();
+
+    assert_that!(
+        s.volta("run --node 10 node --version"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_contains("Volta profile:")
+            .with_stderr_contains("    project load[..]ms")
+            .with_stderr_contains("    resolve[..]ms")
+            .with_stderr_contains("    fetch[..]ms")
+            .with_stderr_contains("    unpack[..]ms")
+            .with_stderr_contains("    spawn[..]ms")
+    );
+
+    // Nothing is printed unless profiling is enabled
+    assert_that!(
+        s.volta("run node --version").env_remove("VOLTA_PROFILE"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_does_not_contain("Volta profile:")
+    );
+}
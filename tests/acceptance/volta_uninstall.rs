@@ -215,3 +215,56 @@ fn uninstall_runtime() {
             .with_stderr_contains("[..]error: Uninstalling node is not supported yet.")
     )
 }
+
+const PLATFORM_NODE_16: &str = r#"{
+  "node": {
+    "runtime": "16.20.2",
+    "npm": null
+  },
+  "yarn": null
+}"#;
+
+#[test]
+fn uninstall_node_range() {
+    let s = sandbox()
+        .platform(PLATFORM_NODE_16)
+        .setup_node_binary("14.21.3", "6.14.18", "")
+        .setup_node_binary("16.20.2", "8.19.4", "")
+        .setup_node_binary("17.9.1", "8.11.0", "")
+        .setup_node_binary("20.5.1", "9.8.0", "")
+        .env(VOLTA_LOGLEVEL, "info")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall node <18"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("[..]removed Node versions: 14.21.3, 17.9.1")
+    );
+
+    // The default Node is kept, even though it matches the range
+    assert!(!s.node_image_exists("14.21.3"));
+    assert!(s.node_image_exists("16.20.2"));
+    assert!(!s.node_image_exists("17.9.1"));
+    assert!(s.node_image_exists("20.5.1"));
+}
+
+#[test]
+fn uninstall_node_range_without_matches() {
+    let s = sandbox()
+        .platform(PLATFORM_NODE_16)
+        .setup_node_binary("16.20.2", "8.19.4", "")
+        .setup_node_binary("20.5.1", "9.8.0", "")
+        .env(VOLTA_LOGLEVEL, "info")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall node@<16"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("No matching versions of Node to remove")
+    );
+
+    assert!(s.node_image_exists("16.20.2"));
+    assert!(s.node_image_exists("20.5.1"));
+}
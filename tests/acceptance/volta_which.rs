@@ -0,0 +1,114 @@
+use crate::support::sandbox::{sandbox, PackageBinInfo};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+const PLATFORM_NODE_NPM: &str = r#"{
+    "node":{
+        "runtime":"11.10.1",
+        "npm":"6.7.0"
+    }
+}"#;
+
+const PKG_CONFIG_TYPESCRIPT: &str = r#"{
+  "name": "typescript",
+  "version": "5.1.6",
+  "platform": {
+    "node": "11.10.1",
+    "npm": "6.7.0",
+    "yarn": null
+  },
+  "bins": [
+    "tsc"
+  ],
+  "manager": "Npm"
+}"#;
+
+const BIN_CONFIG_TSC: &str = r#"{
+  "name": "tsc",
+  "package": "typescript",
+  "version": "5.1.6",
+  "platform": {
+    "node": "11.10.1",
+    "npm": "6.7.0",
+    "yarn": null
+  },
+  "manager": "Npm"
+}"#;
+
+const PACKAGE_JSON_WITH_TYPESCRIPT: &str = r#"{
+    "name": "with-typescript",
+    "dependencies": {
+        "typescript": "5.2.2"
+    },
+    "volta": {
+        "node": "11.10.1"
+    }
+}"#;
+
+fn tsc_bin_info() -> Vec<PackageBinInfo> {
+    vec![PackageBinInfo {
+        name: "tsc".to_string(),
+        contents: "tsc".to_string(),
+    }]
+}
+
+#[test]
+fn which_json_default_binary() {
+    let s = sandbox()
+        .platform(PLATFORM_NODE_NPM)
+        .package_config("typescript", PKG_CONFIG_TYPESCRIPT)
+        .binary_config("tsc", BIN_CONFIG_TSC)
+        .package_image("typescript", "5.1.6", Some(tsc_bin_info()))
+        .build();
+
+    assert_that!(
+        s.volta("which --json tsc"),
+        execs().with_status(0).with_json(
+            r#"{
+                "tool": "tsc",
+                "path": "[..]/tools/image/packages/typescript/[..]tsc",
+                "source": "default-binary"
+            }"#
+        )
+    );
+}
+
+#[test]
+fn which_json_project_local_binary() {
+    let s = sandbox()
+        .platform(PLATFORM_NODE_NPM)
+        .package_json(PACKAGE_JSON_WITH_TYPESCRIPT)
+        .package_config("typescript", PKG_CONFIG_TYPESCRIPT)
+        .binary_config("tsc", BIN_CONFIG_TSC)
+        .package_image("typescript", "5.1.6", Some(tsc_bin_info()))
+        .project_bins(tsc_bin_info())
+        .build();
+
+    assert_that!(
+        s.volta("which --json tsc"),
+        execs().with_status(0).with_json(
+            r#"{
+                "tool": "tsc",
+                "path": "[..]/node_modules/.bin/tsc",
+                "source": "project-local"
+            }"#
+        )
+    );
+}
+
+#[test]
+fn which_json_unknown_tool() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("which --json volta-unknown-tool"),
+        execs().with_status(1).with_json(
+            r#"{
+                "tool": "volta-unknown-tool",
+                "path": null,
+                "source": "not-found"
+            }"#
+        )
+    );
+}
@@ -224,3 +224,43 @@ fn install_package_tag_version() {
         execs().with_status(0).with_stdout_contains("0.19.0")
     );
 }
+
+#[test]
+fn install_package_selected_bins() {
+    let p = temp_project().build();
+
+    // have to install node first, because we need npm
+    assert_that!(p.volta("install node@14.15.0"), execs().with_status(0));
+
+    assert_that!(
+        p.volta("install --bin tsc typescript@4.1.2"),
+        execs().with_status(0)
+    );
+    assert!(p.package_is_installed("typescript"));
+    assert!(p.shim_exists("tsc"));
+    assert!(!p.shim_exists("tsserver"));
+
+    assert_that!(
+        p.exec_shim("tsc", "--version"),
+        execs().with_status(0).with_stdout_contains("4.1.2")
+    );
+}
+
+#[test]
+fn reinstall_package_with_selected_bins() {
+    let p = temp_project().build();
+
+    // have to install node first, because we need npm
+    assert_that!(p.volta("install node@14.15.0"), execs().with_status(0));
+
+    assert_that!(p.volta("install typescript@4.1.2"), execs().with_status(0));
+    assert!(p.shim_exists("tsc"));
+    assert!(p.shim_exists("tsserver"));
+
+    assert_that!(
+        p.volta("install --bin tsc typescript@4.1.2"),
+        execs().with_status(0)
+    );
+    assert!(p.shim_exists("tsc"));
+    assert!(!p.shim_exists("tsserver"));
+}